@@ -1,3 +1,4 @@
+use super::tuning::{MidiTuningMethod, Tuning};
 use anyhow::Result;
 
 #[derive(Debug, Clone, Copy)]
@@ -23,4 +24,15 @@ pub trait AudioBackend {
     fn play_chord(&mut self, notes: &[u8], velocity: u8) -> Result<()>;
     fn stop_all(&mut self) -> Result<()>;
     fn name(&self) -> &'static str;
+
+    /// Strike a General MIDI drum; backends without a drum channel play it
+    /// as an ordinary note
+    fn play_drum(&mut self, note: u8, velocity: u8) -> Result<()> {
+        self.play_note(note, velocity)
+    }
+
+    /// Apply a tuning; backends that can't retune ignore it
+    fn set_tuning(&mut self, _tuning: &Tuning, _method: MidiTuningMethod) -> Result<()> {
+        Ok(())
+    }
 }
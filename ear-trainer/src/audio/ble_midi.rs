@@ -1,4 +1,5 @@
 use super::backend::AudioBackend;
+use super::tuning::{MidiTuner, MidiTuningMethod, Tuning};
 use anyhow::{anyhow, Result};
 use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter, WriteType};
 use btleplug::platform::{Adapter, Manager, Peripheral};
@@ -158,6 +159,7 @@ pub struct BleMidiBackend {
     should_reconnect: Arc<AtomicBool>,
     connected_device_name: Arc<TokioMutex<Option<String>>>,
     preferred_device_address: Arc<TokioMutex<Option<String>>>,
+    tuner: MidiTuner,
 }
 
 impl BleMidiBackend {
@@ -175,6 +177,8 @@ impl BleMidiBackend {
             should_reconnect: Arc::new(AtomicBool::new(true)),
             connected_device_name: Arc::new(TokioMutex::new(None)),
             preferred_device_address: Arc::new(TokioMutex::new(None)),
+            // Two notes per MTS message keep sysex within a 20 byte BLE packet
+            tuner: MidiTuner::new(0, 2),
         };
 
         // Initialize adapter
@@ -198,6 +202,8 @@ impl BleMidiBackend {
             should_reconnect: Arc::new(AtomicBool::new(false)),
             connected_device_name: Arc::new(TokioMutex::new(None)),
             preferred_device_address: Arc::new(TokioMutex::new(None)),
+            // Two notes per MTS message keep sysex within a 20 byte BLE packet
+            tuner: MidiTuner::new(0, 2),
         }
     }
 
@@ -551,6 +557,10 @@ impl BleMidiBackend {
 
         let mut packet = vec![timestamp_high, timestamp_low];
         packet.extend_from_slice(data);
+        // System exclusive end needs its own timestamp
+        if data.first() == Some(&0xF0) && packet.last() == Some(&0xF7) {
+            packet.insert(packet.len() - 1, timestamp_low);
+        }
 
         self.runtime.block_on(async {
             let peripheral_guard = peripheral.lock().await;
@@ -629,18 +639,20 @@ impl AudioBackend for BleMidiBackend {
         if !self.is_connected() {
             return Ok(());
         }
-        // Note On: 0x90 = channel 1
-        let data = [0x90, note, velocity];
-        self.send_midi(&data)
+        for message in self.tuner.note_on(note, velocity) {
+            self.send_midi(&message)?;
+        }
+        Ok(())
     }
 
     fn stop_note(&mut self, note: u8) -> Result<()> {
         if !self.is_connected() {
             return Ok(());
         }
-        // Note Off: 0x80 = channel 1
-        let data = [0x80, note, 0];
-        self.send_midi(&data)
+        for message in self.tuner.note_off(note) {
+            self.send_midi(&message)?;
+        }
+        Ok(())
     }
 
     fn play_chord(&mut self, notes: &[u8], velocity: u8) -> Result<()> {
@@ -650,10 +662,25 @@ impl AudioBackend for BleMidiBackend {
         Ok(())
     }
 
+    /// Drums go to the GM percussion channel so pitch bend tuning leaves
+    /// them alone
+    fn play_drum(&mut self, note: u8, velocity: u8) -> Result<()> {
+        if !self.is_connected() {
+            return Ok(());
+        }
+        for message in self.tuner.drum_hit(note, velocity) {
+            self.send_midi(&message)?;
+        }
+        Ok(())
+    }
+
     fn stop_all(&mut self) -> Result<()> {
         if !self.is_connected() {
             return Ok(());
         }
+        for message in self.tuner.release_all() {
+            let _ = self.send_midi(&message);
+        }
         // All Notes Off on channel 1
         let data = [0xB0, 123, 0];
         self.send_midi(&data)
@@ -662,6 +689,17 @@ impl AudioBackend for BleMidiBackend {
     fn name(&self) -> &'static str {
         "BLE MIDI"
     }
+
+    fn set_tuning(&mut self, tuning: &Tuning, method: MidiTuningMethod) -> Result<()> {
+        let messages = self.tuner.retune(tuning, method);
+        if !self.is_connected() {
+            return Ok(());
+        }
+        for message in messages {
+            self.send_midi(&message)?;
+        }
+        Ok(())
+    }
 }
 
 impl Default for BleMidiBackend {
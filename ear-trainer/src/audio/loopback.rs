@@ -0,0 +1,82 @@
+//! MIDI round-trip latency measurement
+//!
+//! With the MIDI backend's output port cabled (or routed) back into the
+//! first MIDI input, a note sent out comes straight back in. Half the round
+//! trip is taken as the output latency.
+
+use super::midi::SharedConnection;
+use anyhow::{anyhow, Context, Result};
+use midir::MidiInput;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for each ping to come back
+const PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Send `pings` notes over the MIDI backend's connection and return the
+/// median round trip
+pub fn measure_round_trip(output: &SharedConnection, pings: u8) -> Result<Duration> {
+    let midi_in = MidiInput::new("Ear Trainer Loopback In")
+        .context("Failed to create MIDI input")?;
+    let in_port = midi_in
+        .ports()
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No MIDI input ports available"))?;
+
+    let (tx, rx) = mpsc::channel();
+    let _input = midi_in
+        .connect(
+            &in_port,
+            "ear-trainer-loopback",
+            move |_stamp, message, _| {
+                // Note on with non-zero velocity
+                if message.len() == 3 && message[0] & 0xF0 == 0x90 && message[2] > 0 {
+                    let _ = tx.send((message[1], Instant::now()));
+                }
+            },
+            (),
+        )
+        .map_err(|e| anyhow!("Failed to open MIDI input: {}", e))?;
+
+    let send = |message: &[u8]| -> Result<()> {
+        let mut connection = output.lock().unwrap();
+        let connection = connection.as_mut().ok_or_else(|| anyhow!("MIDI output is disconnected"))?;
+        connection.send(message).context("Failed to send MIDI message")
+    };
+
+    let mut samples = Vec::new();
+    for i in 0..pings {
+        let note = 60 + i;
+        let sent = Instant::now();
+        send(&[0x90, note, 1])?;
+
+        let deadline = sent + PING_TIMEOUT;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match rx.recv_timeout(remaining) {
+                Ok((received, at)) if received == note => {
+                    samples.push(at.duration_since(sent));
+                    break;
+                }
+                Ok(_) => continue,
+                Err(_) => break,
+            }
+        }
+
+        let _ = send(&[0x80, note, 0]);
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    median(&mut samples).ok_or_else(|| {
+        anyhow!("No notes came back - connect MIDI out to MIDI in and try again")
+    })
+}
+
+fn median(samples: &mut [Duration]) -> Option<Duration> {
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_unstable();
+    Some(samples[samples.len() / 2])
+}
@@ -1,11 +1,13 @@
 use super::backend::AudioBackend;
 use super::ble_midi::{BleConnectionState, BleEvent, BleMidiBackend};
-use super::midi::MidiBackend;
+use super::midi::{MidiBackend, SharedConnection};
 use super::synth::SynthBackend;
+use super::tuning::{Temperament, Tuning, TuningConfig, REFERENCE_PITCHES};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ActiveBackend {
@@ -39,11 +41,43 @@ pub struct KnownDevice {
     pub last_connected: u64,
 }
 
+/// Output latency of each backend in milliseconds, as found by calibration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyOffsets {
+    pub midi_ms: f32,
+    pub synth_ms: f32,
+    pub ble_midi_ms: f32,
+}
+
+impl LatencyOffsets {
+    pub fn get(&self, backend: ActiveBackend) -> f32 {
+        match backend {
+            ActiveBackend::Midi => self.midi_ms,
+            ActiveBackend::Synth => self.synth_ms,
+            ActiveBackend::BleMidi => self.ble_midi_ms,
+        }
+    }
+
+    pub fn set(&mut self, backend: ActiveBackend, ms: f32) {
+        let ms = ms.max(0.0);
+        match backend {
+            ActiveBackend::Midi => self.midi_ms = ms,
+            ActiveBackend::Synth => self.synth_ms = ms,
+            ActiveBackend::BleMidi => self.ble_midi_ms = ms,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AudioConfig {
     pub known_ble_devices: Vec<KnownDevice>,
     pub preferred_ble_device: Option<String>,
     pub last_backend: Option<ActiveBackend>,
+    #[serde(default)]
+    pub latency: LatencyOffsets,
+    #[serde(default)]
+    pub tuning: TuningConfig,
 }
 
 impl AudioConfig {
@@ -120,13 +154,16 @@ impl Default for BleStatus {
 }
 
 pub struct AudioManager {
-    midi_backend: MidiBackend,
-    synth_backend: SynthBackend,
+    midi_backend: Option<MidiBackend>,
+    synth_backend: Option<SynthBackend>,
     ble_midi_backend: BleMidiBackend,
+    /// Replaces the hardware backends entirely when set (headless/testing)
+    override_backend: Option<Box<dyn AudioBackend>>,
     active_backend: ActiveBackend,
     config: AudioConfig,
     ble_status: BleStatus,
     auto_scan_started: bool,
+    tuning: Tuning,
 }
 
 impl AudioManager {
@@ -141,16 +178,51 @@ impl AudioManager {
         }
 
         let active_backend = config.last_backend.unwrap_or(ActiveBackend::Synth);
+        let tuning = load_tuning(&config.tuning);
 
-        Ok(Self {
-            midi_backend: MidiBackend::new()?,
-            synth_backend: SynthBackend::new()?,
+        let mut manager = Self {
+            midi_backend: Some(MidiBackend::new()?),
+            synth_backend: Some(SynthBackend::new()?),
             ble_midi_backend,
+            override_backend: None,
             active_backend,
             config,
             ble_status: BleStatus::default(),
             auto_scan_started: false,
-        })
+            tuning,
+        };
+        manager.apply_tuning();
+        Ok(manager)
+    }
+
+    /// Create a manager that sends everything to the given backend
+    ///
+    /// No audio, MIDI or Bluetooth hardware is touched and no config is
+    /// loaded, so this works on headless machines (e.g. for tests).
+    pub fn with_backend(backend: Box<dyn AudioBackend>) -> Self {
+        Self {
+            midi_backend: None,
+            synth_backend: None,
+            ble_midi_backend: BleMidiBackend::new_dummy(),
+            override_backend: Some(backend),
+            active_backend: ActiveBackend::Synth,
+            config: AudioConfig::default(),
+            ble_status: BleStatus::default(),
+            auto_scan_started: true,
+            tuning: Tuning::equal(),
+        }
+    }
+
+    /// The backend that currently receives notes, if it is available
+    fn output(&mut self) -> Option<&mut dyn AudioBackend> {
+        if let Some(backend) = self.override_backend.as_mut() {
+            return Some(backend.as_mut());
+        }
+        match self.active_backend {
+            ActiveBackend::Midi => self.midi_backend.as_mut().map(|b| b as &mut dyn AudioBackend),
+            ActiveBackend::Synth => self.synth_backend.as_mut().map(|b| b as &mut dyn AudioBackend),
+            ActiveBackend::BleMidi => Some(&mut self.ble_midi_backend),
+        }
     }
 
     pub fn start_ble_scan(&mut self) {
@@ -171,6 +243,100 @@ impl AudioManager {
         self.config.save();
     }
 
+    /// How long after sending a note it is heard on the active backend
+    pub fn output_latency(&self) -> Duration {
+        let ms = self.config.latency.get(self.active_backend);
+        Duration::from_micros((ms * 1000.0) as u64)
+    }
+
+    /// Output connection of the MIDI backend, if it is connected
+    pub fn midi_connection(&self) -> Option<SharedConnection> {
+        self.midi_backend.as_ref()?.shared_connection()
+    }
+
+    pub fn latency_offsets(&self) -> LatencyOffsets {
+        self.config.latency
+    }
+
+    /// Store a calibrated latency for a backend
+    pub fn set_latency_ms(&mut self, backend: ActiveBackend, ms: f32) {
+        self.config.latency.set(backend, ms);
+        // Headless managers never write the user's config
+        if self.override_backend.is_none() {
+            self.config.save();
+        }
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    /// Load and apply new tuning settings
+    ///
+    /// Scala files are read here; on error the current tuning is kept.
+    pub fn set_tuning_config(&mut self, config: TuningConfig) -> Result<()> {
+        let mut tuning = Tuning::from_config(&config)?;
+        tuning.tonic = self.tuning.tonic;
+        self.tuning = tuning;
+        self.config.tuning = config;
+        // Headless managers never write the user's config
+        if self.override_backend.is_none() {
+            self.config.save();
+        }
+        self.apply_tuning();
+        Ok(())
+    }
+
+    /// Build the fixed temperaments on a new tonic (pitch class, 0 = C)
+    pub fn set_tuning_tonic(&mut self, tonic: u8) {
+        if self.tuning.tonic != tonic % 12 {
+            self.tuning.tonic = tonic % 12;
+            self.apply_tuning();
+        }
+    }
+
+    /// Cycle temperament, skipping Scala when no scale file is configured
+    pub fn cycle_temperament(&mut self) {
+        let mut config = self.config.tuning.clone();
+        config.temperament = config.temperament.next();
+        if config.temperament == Temperament::Scala && config.scala_file.is_none() {
+            config.temperament = config.temperament.next();
+        }
+        if self.set_tuning_config(config.clone()).is_err() {
+            // Unreadable Scala file
+            config.temperament = config.temperament.next();
+            let _ = self.set_tuning_config(config);
+        }
+    }
+
+    /// Cycle through the common reference pitches for A4
+    pub fn cycle_reference_pitch(&mut self) {
+        let mut config = self.config.tuning.clone();
+        let current = REFERENCE_PITCHES
+            .iter()
+            .position(|&hz| hz == config.reference_hz)
+            .unwrap_or(REFERENCE_PITCHES.len() - 1);
+        config.reference_hz = REFERENCE_PITCHES[(current + 1) % REFERENCE_PITCHES.len()];
+        let _ = self.set_tuning_config(config);
+    }
+
+    /// Send the current tuning to every backend
+    fn apply_tuning(&mut self) {
+        let tuning = self.tuning.clone();
+        let method = self.config.tuning.midi_method;
+        if let Some(backend) = self.override_backend.as_mut() {
+            let _ = backend.set_tuning(&tuning, method);
+            return;
+        }
+        if let Some(midi) = self.midi_backend.as_mut() {
+            let _ = midi.set_tuning(&tuning, method);
+        }
+        if let Some(synth) = self.synth_backend.as_mut() {
+            let _ = synth.set_tuning(&tuning, method);
+        }
+        let _ = self.ble_midi_backend.set_tuning(&tuning, method);
+    }
+
     pub fn toggle_backend(&mut self) {
         self.set_active_backend(self.active_backend.next());
     }
@@ -193,6 +359,11 @@ impl AudioManager {
                     // Clear prerequisites on successful connection
                     self.ble_status.prerequisites.clear();
 
+                    // A fresh connection has lost any MTS tuning
+                    let _ = self
+                        .ble_midi_backend
+                        .set_tuning(&self.tuning, self.config.tuning.midi_method);
+
                     // Save to known devices
                     // Note: We'd need the address here, but for now just save by name
                     // In a real implementation, we'd pass the address with the event
@@ -243,32 +414,44 @@ impl AudioManager {
     }
 
     pub fn play_note(&mut self, note: u8, velocity: u8) -> Result<()> {
-        match self.active_backend {
-            ActiveBackend::Midi => self.midi_backend.play_note(note, velocity),
-            ActiveBackend::Synth => self.synth_backend.play_note(note, velocity),
-            ActiveBackend::BleMidi => self.ble_midi_backend.play_note(note, velocity),
+        match self.output() {
+            Some(backend) => backend.play_note(note, velocity),
+            None => Ok(()),
         }
     }
 
     pub fn stop_note(&mut self, note: u8) -> Result<()> {
-        match self.active_backend {
-            ActiveBackend::Midi => self.midi_backend.stop_note(note),
-            ActiveBackend::Synth => self.synth_backend.stop_note(note),
-            ActiveBackend::BleMidi => self.ble_midi_backend.stop_note(note),
+        match self.output() {
+            Some(backend) => backend.stop_note(note),
+            None => Ok(()),
+        }
+    }
+
+    pub fn play_drum(&mut self, note: u8, velocity: u8) -> Result<()> {
+        match self.output() {
+            Some(backend) => backend.play_drum(note, velocity),
+            None => Ok(()),
         }
     }
 
     pub fn play_chord(&mut self, notes: &[u8], velocity: u8) -> Result<()> {
-        match self.active_backend {
-            ActiveBackend::Midi => self.midi_backend.play_chord(notes, velocity),
-            ActiveBackend::Synth => self.synth_backend.play_chord(notes, velocity),
-            ActiveBackend::BleMidi => self.ble_midi_backend.play_chord(notes, velocity),
+        match self.output() {
+            Some(backend) => backend.play_chord(notes, velocity),
+            None => Ok(()),
         }
     }
 
     pub fn stop_all(&mut self) {
-        let _ = self.midi_backend.stop_all();
-        let _ = self.synth_backend.stop_all();
+        if let Some(backend) = self.override_backend.as_mut() {
+            let _ = backend.stop_all();
+            return;
+        }
+        if let Some(midi) = self.midi_backend.as_mut() {
+            let _ = midi.stop_all();
+        }
+        if let Some(synth) = self.synth_backend.as_mut() {
+            let _ = synth.stop_all();
+        }
         let _ = self.ble_midi_backend.stop_all();
     }
 
@@ -277,6 +460,9 @@ impl AudioManager {
     }
 
     pub fn get_status_line(&self) -> String {
+        if let Some(backend) = self.override_backend.as_ref() {
+            return backend.name().to_string();
+        }
         match self.active_backend {
             ActiveBackend::Midi => "MIDI".to_string(),
             ActiveBackend::Synth => "Synth".to_string(),
@@ -313,20 +499,58 @@ impl Default for AudioManager {
                 eprintln!("Warning: Failed to create audio manager: {}. Using fallback.", e);
                 // Create a minimal fallback with synth only
                 let config = AudioConfig::load();
-                Self {
-                    midi_backend: MidiBackend::default(),
-                    synth_backend: SynthBackend::new().expect("Synth backend required"),
+                let tuning = load_tuning(&config.tuning);
+                let mut manager = Self {
+                    midi_backend: Some(MidiBackend::default()),
+                    synth_backend: SynthBackend::new().ok(),
                     ble_midi_backend: BleMidiBackend::new().unwrap_or_else(|_| {
                         // Create a dummy BLE backend that won't do anything
                         eprintln!("BLE MIDI unavailable");
                         BleMidiBackend::new_dummy()
                     }),
+                    override_backend: None,
                     active_backend: ActiveBackend::Synth,
                     config,
                     ble_status: BleStatus::default(),
                     auto_scan_started: false,
-                }
+                    tuning,
+                };
+                manager.apply_tuning();
+                manager
             }
         }
     }
 }
+
+/// Tuning from saved settings, keeping the reference pitch if a Scala file fails
+fn load_tuning(config: &TuningConfig) -> Tuning {
+    Tuning::from_config(config).unwrap_or_else(|e| {
+        eprintln!("Warning: {:#}. Using equal temperament.", e);
+        let mut tuning = Tuning::equal();
+        tuning.reference_hz = config.reference_hz;
+        tuning
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_without_latency_loads() {
+        let json = r#"{"known_ble_devices":[],"preferred_ble_device":null,"last_backend":"Midi"}"#;
+        let config: AudioConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.last_backend, Some(ActiveBackend::Midi));
+        assert_eq!(config.latency, LatencyOffsets::default());
+    }
+
+    #[test]
+    fn test_latency_per_backend() {
+        let mut latency = LatencyOffsets::default();
+        latency.set(ActiveBackend::BleMidi, 35.0);
+        latency.set(ActiveBackend::Synth, -5.0);
+        assert_eq!(latency.get(ActiveBackend::BleMidi), 35.0);
+        assert_eq!(latency.get(ActiveBackend::Synth), 0.0);
+        assert_eq!(latency.get(ActiveBackend::Midi), 0.0);
+    }
+}
@@ -1,11 +1,16 @@
 use super::backend::AudioBackend;
+use super::tuning::{MidiTuner, MidiTuningMethod, Tuning};
 use anyhow::{Context, Result};
 use midir::{MidiOutput, MidiOutputConnection};
 use std::sync::{Arc, Mutex};
 
+/// Output connection shared between the backend and latency measurement
+pub type SharedConnection = Arc<Mutex<Option<MidiOutputConnection>>>;
+
 pub struct MidiBackend {
-    connection: Arc<Mutex<Option<MidiOutputConnection>>>,
+    connection: SharedConnection,
     channel: u8,
+    tuner: MidiTuner,
 }
 
 impl MidiBackend {
@@ -35,26 +40,39 @@ impl MidiBackend {
         Ok(Self {
             connection: Arc::new(Mutex::new(connection)),
             channel: 0,
+            tuner: MidiTuner::new(0, 64),
         })
     }
 
+    /// The connection notes are sent on, if connected
+    pub fn shared_connection(&self) -> Option<SharedConnection> {
+        self.connection.lock().unwrap().is_some().then(|| Arc::clone(&self.connection))
+    }
+
     fn send_message(&mut self, message: &[u8]) -> Result<()> {
         if let Some(conn) = self.connection.lock().unwrap().as_mut() {
             conn.send(message).context("Failed to send MIDI message")?;
         }
         Ok(())
     }
+
+    fn send_messages(&mut self, messages: Vec<Vec<u8>>) -> Result<()> {
+        for message in messages {
+            self.send_message(&message)?;
+        }
+        Ok(())
+    }
 }
 
 impl AudioBackend for MidiBackend {
     fn play_note(&mut self, note: u8, velocity: u8) -> Result<()> {
-        let msg = [0x90 | self.channel, note, velocity];
-        self.send_message(&msg)
+        let messages = self.tuner.note_on(note, velocity);
+        self.send_messages(messages)
     }
 
     fn stop_note(&mut self, note: u8) -> Result<()> {
-        let msg = [0x80 | self.channel, note, 0];
-        self.send_message(&msg)
+        let messages = self.tuner.note_off(note);
+        self.send_messages(messages)
     }
 
     fn play_chord(&mut self, notes: &[u8], velocity: u8) -> Result<()> {
@@ -65,6 +83,8 @@ impl AudioBackend for MidiBackend {
     }
 
     fn stop_all(&mut self) -> Result<()> {
+        let held = self.tuner.release_all();
+        let _ = self.send_messages(held);
         for note in 0..128 {
             let msg = [0x80 | self.channel, note, 0];
             let _ = self.send_message(&msg);
@@ -75,6 +95,18 @@ impl AudioBackend for MidiBackend {
     fn name(&self) -> &'static str {
         "MIDI"
     }
+
+    /// Drums go to the GM percussion channel so pitch bend tuning leaves
+    /// them alone
+    fn play_drum(&mut self, note: u8, velocity: u8) -> Result<()> {
+        let messages = self.tuner.drum_hit(note, velocity);
+        self.send_messages(messages)
+    }
+
+    fn set_tuning(&mut self, tuning: &Tuning, method: MidiTuningMethod) -> Result<()> {
+        let messages = self.tuner.retune(tuning, method);
+        self.send_messages(messages)
+    }
 }
 
 impl Default for MidiBackend {
@@ -82,6 +114,7 @@ impl Default for MidiBackend {
         Self::new().unwrap_or_else(|_| Self {
             connection: Arc::new(Mutex::new(None)),
             channel: 0,
+            tuner: MidiTuner::new(0, 64),
         })
     }
 }
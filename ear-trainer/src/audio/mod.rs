@@ -1,8 +1,11 @@
 pub mod backend;
 pub mod ble_midi;
+pub mod loopback;
 pub mod manager;
 pub mod midi;
+pub mod recording;
 pub mod synth;
+pub mod tuning;
 
 pub use ble_midi::{check_ble_prerequisites, BleConnectionState, BlePrerequisites};
 pub use manager::{ActiveBackend, AudioManager, BleStatus, PrerequisiteStatus};
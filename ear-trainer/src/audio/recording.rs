@@ -0,0 +1,139 @@
+//! Recording backend for headless testing
+//!
+//! Captures every note event with a timestamp instead of producing sound,
+//! so the output of the playback scheduler can be asserted on exactly.
+
+use super::backend::AudioBackend;
+use crate::clock::Clock;
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What happened at a recorded instant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordedKind {
+    NoteOn { note: u8, velocity: u8 },
+    NoteOff { note: u8 },
+    /// A GM drum struck on the percussion channel
+    DrumHit { note: u8, velocity: u8 },
+    AllNotesOff,
+}
+
+/// A single captured event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedEvent {
+    /// Time since the backend was created
+    pub at: Duration,
+    pub kind: RecordedKind,
+}
+
+impl RecordedEvent {
+    /// Time in whole milliseconds (convenient for assertions)
+    #[cfg(test)]
+    pub fn at_ms(&self) -> u64 {
+        self.at.as_millis() as u64
+    }
+}
+
+/// Backend that records events instead of playing them
+///
+/// Clones share the same event log, so a test can keep one copy while the
+/// other is handed to the `AudioManager`.
+#[derive(Clone)]
+pub struct RecordingBackend {
+    clock: Arc<dyn Clock + Send + Sync>,
+    origin: Instant,
+    events: Arc<Mutex<Vec<RecordedEvent>>>,
+}
+
+impl RecordingBackend {
+    pub fn new(clock: Arc<dyn Clock + Send + Sync>) -> Self {
+        let origin = clock.now();
+        Self {
+            clock,
+            origin,
+            events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn push(&self, kind: RecordedKind) {
+        let at = self.clock.now().saturating_duration_since(self.origin);
+        self.events.lock().unwrap().push(RecordedEvent { at, kind });
+    }
+
+    /// All events recorded so far
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Only the note-on events, drum hits included, as (time_ms, note, velocity)
+    #[cfg(test)]
+    pub fn note_ons(&self) -> Vec<(u64, u8, u8)> {
+        self.events()
+            .into_iter()
+            .filter_map(|e| match e.kind {
+                RecordedKind::NoteOn { note, velocity } | RecordedKind::DrumHit { note, velocity } => {
+                    Some((e.at_ms(), note, velocity))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl AudioBackend for RecordingBackend {
+    fn play_note(&mut self, note: u8, velocity: u8) -> Result<()> {
+        self.push(RecordedKind::NoteOn { note, velocity });
+        Ok(())
+    }
+
+    fn stop_note(&mut self, note: u8) -> Result<()> {
+        self.push(RecordedKind::NoteOff { note });
+        Ok(())
+    }
+
+    fn play_chord(&mut self, notes: &[u8], velocity: u8) -> Result<()> {
+        for &note in notes {
+            self.play_note(note, velocity)?;
+        }
+        Ok(())
+    }
+
+    fn play_drum(&mut self, note: u8, velocity: u8) -> Result<()> {
+        self.push(RecordedKind::DrumHit { note, velocity });
+        Ok(())
+    }
+
+    fn stop_all(&mut self) -> Result<()> {
+        self.push(RecordedKind::AllNotesOff);
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "Recording"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::VirtualClock;
+
+    #[test]
+    fn test_records_timestamps() {
+        let clock = VirtualClock::new();
+        let mut backend = RecordingBackend::new(Arc::new(clock.clone()));
+
+        backend.play_chord(&[60, 64, 67], 100).unwrap();
+        clock.advance(Duration::from_millis(500));
+        backend.stop_all().unwrap();
+
+        assert_eq!(
+            backend.note_ons(),
+            vec![(0, 60, 100), (0, 64, 100), (0, 67, 100)]
+        );
+        let last = backend.events().last().copied().unwrap();
+        assert_eq!(last.at_ms(), 500);
+        assert_eq!(last.kind, RecordedKind::AllNotesOff);
+    }
+}
@@ -1,4 +1,5 @@
 use super::backend::AudioBackend;
+use super::tuning::{MidiTuningMethod, Tuning};
 use anyhow::Result;
 use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
 use std::sync::{Arc, Mutex};
@@ -11,6 +12,7 @@ pub struct SynthBackend {
     stream_handle: OutputStreamHandle,
     sinks: Arc<Mutex<Vec<Sink>>>,
     active_notes: Arc<Mutex<Vec<u8>>>,
+    tuning: Tuning,
 }
 
 impl SynthBackend {
@@ -22,17 +24,14 @@ impl SynthBackend {
             stream_handle,
             sinks: Arc::new(Mutex::new(Vec::new())),
             active_notes: Arc::new(Mutex::new(Vec::new())),
+            tuning: Tuning::equal(),
         })
     }
-
-    fn midi_to_frequency(note: u8) -> f32 {
-        440.0 * 2f32.powf((note as f32 - 69.0) / 12.0)
-    }
 }
 
 impl AudioBackend for SynthBackend {
     fn play_note(&mut self, note: u8, velocity: u8) -> Result<()> {
-        let frequency = Self::midi_to_frequency(note);
+        let frequency = self.tuning.frequency(note) as f32;
         // Reduce amplitude to prevent clipping when multiple notes play
         let amplitude = (velocity as f32 / 127.0) * 0.15;
 
@@ -82,6 +81,11 @@ impl AudioBackend for SynthBackend {
     fn name(&self) -> &'static str {
         "Synthesis"
     }
+
+    fn set_tuning(&mut self, tuning: &Tuning, _method: MidiTuningMethod) -> Result<()> {
+        self.tuning = tuning.clone();
+        Ok(())
+    }
 }
 
 impl Default for SynthBackend {
@@ -0,0 +1,695 @@
+//! Reference Pitch and Tuning Systems
+//!
+//! Maps MIDI note numbers to frequencies for the synth, and to MIDI Tuning
+//! Standard or pitch bend messages for external instruments. Besides 12-tone
+//! equal temperament the fixed temperaments (just, Pythagorean, meantone) are
+//! built on the tonic of the current key, and arbitrary scales can be loaded
+//! from Scala `.scl` files with optional `.kbm` keyboard mappings.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Concert pitch
+pub const STANDARD_A4: f32 = 440.0;
+
+/// Reference pitches offered when cycling (modern, orchestral, Baroque, "Verdi")
+pub const REFERENCE_PITCHES: [f32; 4] = [440.0, 442.0, 415.0, 432.0];
+
+/// MIDI channel reserved for drums (GM), never used for pitch bend notes
+const DRUM_CHANNEL: u8 = 9;
+
+/// Pitch bend range set on every channel in pitch bend mode (semitones)
+const BEND_RANGE: f64 = 2.0;
+
+/// How notes are laid out within the octave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Temperament {
+    /// 12-tone equal temperament
+    #[default]
+    Equal,
+    /// 5-limit just intonation on the tonic of the key
+    Just,
+    /// Pure fifths from the tonic
+    Pythagorean,
+    /// Quarter-comma meantone: pure major thirds, narrow fifths
+    Meantone,
+    /// Scale loaded from a Scala file
+    Scala,
+}
+
+impl Temperament {
+    /// Cycle to next temperament
+    pub fn next(&self) -> Self {
+        match self {
+            Temperament::Equal => Temperament::Just,
+            Temperament::Just => Temperament::Pythagorean,
+            Temperament::Pythagorean => Temperament::Meantone,
+            Temperament::Meantone => Temperament::Scala,
+            Temperament::Scala => Temperament::Equal,
+        }
+    }
+
+    /// Get display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Temperament::Equal => "Equal",
+            Temperament::Just => "Just",
+            Temperament::Pythagorean => "Pythagorean",
+            Temperament::Meantone => "Meantone",
+            Temperament::Scala => "Scala",
+        }
+    }
+
+    /// Cents above the tonic for each of the 12 chromatic steps
+    fn chromatic_cents(&self) -> Option<[f64; 12]> {
+        match self {
+            Temperament::Just => {
+                let ratios = [
+                    (1, 1), (16, 15), (9, 8), (6, 5), (5, 4), (4, 3),
+                    (45, 32), (3, 2), (8, 5), (5, 3), (9, 5), (15, 8),
+                ];
+                Some(ratios.map(|(n, d)| ratio_to_cents(n as f64 / d as f64)))
+            }
+            // Chain of fifths from the minor second to the augmented fourth
+            Temperament::Pythagorean => Some(chain_of_fifths(ratio_to_cents(1.5), -5)),
+            // Chain of fifths from the minor third to the augmented fifth
+            Temperament::Meantone => Some(chain_of_fifths(ratio_to_cents(1.25) / 4.0 + 600.0, -3)),
+            Temperament::Equal | Temperament::Scala => None,
+        }
+    }
+}
+
+/// How tuning is sent to MIDI and BLE MIDI instruments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum MidiTuningMethod {
+    /// MIDI Tuning Standard single note tuning changes (retunes all 128 keys)
+    #[default]
+    Mts,
+    /// Each note on its own channel with a pitch bend
+    PitchBend,
+}
+
+impl MidiTuningMethod {
+    /// Get display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            MidiTuningMethod::Mts => "MTS",
+            MidiTuningMethod::PitchBend => "Pitch Bend",
+        }
+    }
+}
+
+/// Persisted tuning settings (part of the audio config)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TuningConfig {
+    /// Frequency of A4 in Hz
+    pub reference_hz: f32,
+    pub temperament: Temperament,
+    /// Scala scale used by `Temperament::Scala`
+    pub scala_file: Option<PathBuf>,
+    /// Optional keyboard mapping for the Scala scale
+    pub kbm_file: Option<PathBuf>,
+    pub midi_method: MidiTuningMethod,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        Self {
+            reference_hz: STANDARD_A4,
+            temperament: Temperament::Equal,
+            scala_file: None,
+            kbm_file: None,
+            midi_method: MidiTuningMethod::Mts,
+        }
+    }
+}
+
+/// A scale read from a Scala `.scl` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalaScale {
+    pub description: String,
+    /// Pitches in cents above 1/1; the last one is the period (usually 1200)
+    pub degrees: Vec<f64>,
+}
+
+impl ScalaScale {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid Scala file {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        // The description may be empty, so only comments are skipped before it
+        let mut lines = text.lines().filter(|line| !line.starts_with('!'));
+        let description = lines.next().ok_or_else(|| anyhow!("missing description"))?.trim().to_string();
+        let count: usize = lines
+            .next()
+            .and_then(|line| line.split_whitespace().next())
+            .ok_or_else(|| anyhow!("missing note count"))?
+            .parse()
+            .map_err(|_| anyhow!("invalid note count"))?;
+
+        let degrees = lines
+            .filter_map(|line| line.split_whitespace().next())
+            .take(count)
+            .map(parse_pitch)
+            .collect::<Result<Vec<_>>>()?;
+        if degrees.len() != count {
+            bail!("expected {} pitches, found {}", count, degrees.len());
+        }
+        if count == 0 {
+            bail!("scale has no pitches");
+        }
+        Ok(Self { description, degrees })
+    }
+
+    fn period(&self) -> f64 {
+        *self.degrees.last().unwrap_or(&1200.0)
+    }
+
+    /// Cents of a scale degree, continuing into further periods
+    fn degree_cents(&self, degree: i64) -> f64 {
+        let len = self.degrees.len() as i64;
+        let period = degree.div_euclid(len) as f64 * self.period();
+        match degree.rem_euclid(len) {
+            0 => period,
+            step => period + self.degrees[step as usize - 1],
+        }
+    }
+}
+
+/// A keyboard mapping read from a Scala `.kbm` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardMapping {
+    /// Keys in one repetition of the mapping (0 = map keys to degrees in order)
+    pub size: usize,
+    /// Key where scale degree 0 is mapped
+    pub middle_note: u8,
+    /// Key tuned to `reference_hz`
+    pub reference_note: u8,
+    pub reference_hz: f64,
+    /// Scale degree that counts as the formal octave
+    pub octave_degree: usize,
+    /// Scale degree for each key of the repetition (None = unmapped)
+    pub map: Vec<Option<usize>>,
+}
+
+impl KeyboardMapping {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid keyboard mapping {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut values = text
+            .lines()
+            .filter(|line| !line.starts_with('!'))
+            .filter_map(|line| line.split_whitespace().next());
+        let mut next = |what: &str| values.next().ok_or_else(|| anyhow!("missing {}", what));
+
+        let size: usize = next("map size")?.parse().map_err(|_| anyhow!("invalid map size"))?;
+        // First and last mapped keys; every key is mapped here
+        next("first note")?;
+        next("last note")?;
+        let middle_note = next("middle note")?.parse().map_err(|_| anyhow!("invalid middle note"))?;
+        let reference_note = next("reference note")?.parse().map_err(|_| anyhow!("invalid reference note"))?;
+        let reference_hz = next("reference frequency")?
+            .parse()
+            .map_err(|_| anyhow!("invalid reference frequency"))?;
+        let octave_degree = next("octave degree")?.parse().map_err(|_| anyhow!("invalid octave degree"))?;
+
+        let mut map = Vec::with_capacity(size);
+        for _ in 0..size {
+            // Short mappings are allowed; missing keys are unmapped
+            match values.next() {
+                Some("x") | None => map.push(None),
+                Some(value) => map.push(Some(
+                    value.parse().map_err(|_| anyhow!("invalid mapping entry '{}'", value))?,
+                )),
+            }
+        }
+
+        Ok(Self { size, middle_note, reference_note, reference_hz, octave_degree, map })
+    }
+}
+
+/// The active tuning: maps MIDI notes to frequencies
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    /// Frequency of A4 in Hz
+    pub reference_hz: f32,
+    pub temperament: Temperament,
+    /// Pitch class (0 = C) the fixed temperaments are built on
+    pub tonic: u8,
+    scala: Option<(ScalaScale, Option<KeyboardMapping>)>,
+}
+
+impl Tuning {
+    /// Standard 12-TET at A4 = 440 Hz
+    pub fn equal() -> Self {
+        Self {
+            reference_hz: STANDARD_A4,
+            temperament: Temperament::Equal,
+            tonic: 0,
+            scala: None,
+        }
+    }
+
+    /// Build a tuning from saved settings, loading any Scala files
+    pub fn from_config(config: &TuningConfig) -> Result<Self> {
+        let mut tuning = Self {
+            reference_hz: config.reference_hz,
+            temperament: config.temperament,
+            tonic: 0,
+            scala: None,
+        };
+        if let Some(scl) = &config.scala_file {
+            let scale = ScalaScale::load(scl)?;
+            let mapping = config.kbm_file.as_deref().map(KeyboardMapping::load).transpose()?;
+            tuning = tuning.with_scala(scale, mapping);
+        }
+        Ok(tuning)
+    }
+
+    /// Attach a Scala scale (and optional keyboard mapping) for `Temperament::Scala`
+    pub fn with_scala(mut self, scale: ScalaScale, mapping: Option<KeyboardMapping>) -> Self {
+        self.scala = Some((scale, mapping));
+        self
+    }
+
+    /// Description of the loaded Scala scale, if any
+    pub fn scala_description(&self) -> Option<&str> {
+        self.scala.as_ref().map(|(scale, _)| scale.description.as_str())
+    }
+
+    /// Whether this is plain 12-TET at A4 = 440 Hz
+    pub fn is_standard(&self) -> bool {
+        self.reference_hz == STANDARD_A4 && self.effective_temperament() == Temperament::Equal
+    }
+
+    /// Scala without a loaded scale falls back to equal temperament
+    fn effective_temperament(&self) -> Temperament {
+        match self.temperament {
+            Temperament::Scala if self.scala.is_none() => Temperament::Equal,
+            temperament => temperament,
+        }
+    }
+
+    /// Short label for the status bar, e.g. "Just A415"
+    pub fn label(&self) -> String {
+        format!("{} A{}", self.effective_temperament().name(), self.reference_hz)
+    }
+
+    /// Frequency of a MIDI note in Hz
+    pub fn frequency(&self, note: u8) -> f64 {
+        if self.effective_temperament() == Temperament::Scala {
+            if let Some(freq) = self.scala_frequency(note) {
+                return freq;
+            }
+        }
+        equal_frequency(note as f64 + self.cents_from_equal_temperament(note) / 100.0, self.reference_hz as f64)
+    }
+
+    /// Deviation of a note from 12-TET at A4 = 440 Hz, in cents
+    pub fn cents_from_standard(&self, note: u8) -> f64 {
+        ratio_to_cents(self.frequency(note) / equal_frequency(note as f64, STANDARD_A4 as f64))
+    }
+
+    /// Deviation of a fixed temperament from 12-TET at the same reference
+    ///
+    /// The tonic keeps its equal tempered pitch and the other notes move
+    /// around it.
+    fn cents_from_equal_temperament(&self, note: u8) -> f64 {
+        let Some(table) = self.effective_temperament().chromatic_cents() else {
+            return 0.0;
+        };
+        let step = (note as i32 - self.tonic as i32).rem_euclid(12) as usize;
+        table[step] - step as f64 * 100.0
+    }
+
+    fn scala_frequency(&self, note: u8) -> Option<f64> {
+        let (scale, mapping) = self.scala.as_ref()?;
+        match mapping {
+            Some(mapping) if mapping.size > 0 => {
+                let key_cents = |key: u8| -> Option<f64> {
+                    let offset = key as i64 - mapping.middle_note as i64;
+                    let size = mapping.size as i64;
+                    let degree = (*mapping.map.get(offset.rem_euclid(size) as usize)?)? as i64;
+                    let octave = scale.degree_cents(mapping.octave_degree as i64);
+                    Some(offset.div_euclid(size) as f64 * octave + scale.degree_cents(degree))
+                };
+                let cents = key_cents(note)? - key_cents(mapping.reference_note)?;
+                Some(mapping.reference_hz * cents_to_ratio(cents))
+            }
+            Some(mapping) => {
+                // Linear mapping: one key per scale degree from the middle note
+                let cents = scale.degree_cents(note as i64 - mapping.middle_note as i64)
+                    - scale.degree_cents(mapping.reference_note as i64 - mapping.middle_note as i64);
+                Some(mapping.reference_hz * cents_to_ratio(cents))
+            }
+            None => {
+                // Without a mapping, degree 0 is the tonic nearest middle C at
+                // its equal tempered pitch
+                let base = 60 + self.tonic as i64;
+                let cents = scale.degree_cents(note as i64 - base);
+                Some(equal_frequency(base as f64, self.reference_hz as f64) * cents_to_ratio(cents))
+            }
+        }
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::equal()
+    }
+}
+
+/// Converts note events into MIDI messages that carry the active tuning
+///
+/// With `Mts` the instrument is retuned once per tuning change and notes are
+/// sent unchanged. With `PitchBend` every sounding note gets a channel of its
+/// own, bent from the nearest key to the exact pitch.
+#[derive(Debug, Clone)]
+pub struct MidiTuner {
+    pub method: MidiTuningMethod,
+    /// Channel for untuned and MTS notes
+    channel: u8,
+    tuning: Tuning,
+    /// Notes per MTS message (BLE packets are small)
+    mts_chunk: usize,
+    /// Whether the instrument may hold a non-standard tuning from us
+    retuned: bool,
+    /// Held pitch bend notes as (requested note, channel, sent key)
+    held: Vec<(u8, u8, u8)>,
+    next_channel: u8,
+}
+
+impl MidiTuner {
+    pub fn new(channel: u8, mts_chunk: usize) -> Self {
+        Self {
+            method: MidiTuningMethod::Mts,
+            channel,
+            tuning: Tuning::equal(),
+            mts_chunk: mts_chunk.clamp(1, 127),
+            retuned: false,
+            held: Vec::new(),
+            next_channel: 0,
+        }
+    }
+
+    /// Switch tuning and method; returns messages that prepare the instrument
+    pub fn retune(&mut self, tuning: &Tuning, method: MidiTuningMethod) -> Vec<Vec<u8>> {
+        let mut messages = self.release_all();
+        self.tuning = tuning.clone();
+        self.method = method;
+
+        if tuning.is_standard() && !self.retuned {
+            return messages;
+        }
+        match method {
+            MidiTuningMethod::Mts => {
+                messages.extend(mts_messages(tuning, self.mts_chunk));
+                self.retuned = !tuning.is_standard();
+            }
+            MidiTuningMethod::PitchBend => {
+                // Reset any MTS tuning left behind, then set the bend range
+                if self.retuned {
+                    messages.extend(mts_messages(&Tuning::equal(), self.mts_chunk));
+                    self.retuned = false;
+                }
+                for channel in bend_channels() {
+                    messages.extend(bend_range_messages(channel));
+                }
+            }
+        }
+        messages
+    }
+
+    /// Messages that start a note
+    pub fn note_on(&mut self, note: u8, velocity: u8) -> Vec<Vec<u8>> {
+        if self.method == MidiTuningMethod::Mts || self.tuning.is_standard() {
+            return vec![vec![0x90 | self.channel, note, velocity]];
+        }
+
+        let mut messages = self.note_off(note);
+        let exact = note as f64 + self.tuning.cents_from_standard(note) / 100.0;
+        let key = exact.round().clamp(0.0, 127.0);
+        let bend = pitch_bend_value((exact - key) * 100.0);
+
+        let channel = self.take_channel();
+        messages.push(vec![0xE0 | channel, (bend & 0x7F) as u8, (bend >> 7) as u8]);
+        messages.push(vec![0x90 | channel, key as u8, velocity]);
+        self.held.push((note, channel, key as u8));
+        messages
+    }
+
+    /// Messages that end a note
+    pub fn note_off(&mut self, note: u8) -> Vec<Vec<u8>> {
+        if let Some(pos) = self.held.iter().position(|&(n, _, _)| n == note) {
+            let (_, channel, key) = self.held.remove(pos);
+            return vec![vec![0x80 | channel, key, 0]];
+        }
+        if self.method == MidiTuningMethod::Mts || self.tuning.is_standard() {
+            return vec![vec![0x80 | self.channel, note, 0]];
+        }
+        Vec::new()
+    }
+
+    /// Messages that strike a drum on the GM percussion channel, where
+    /// nothing is bent; the note off follows at once since drum sounds play
+    /// out on their own
+    pub fn drum_hit(&self, note: u8, velocity: u8) -> Vec<Vec<u8>> {
+        vec![vec![0x90 | DRUM_CHANNEL, note, velocity], vec![0x80 | DRUM_CHANNEL, note, 0]]
+    }
+
+    /// Note offs for everything held on pitch bend channels
+    pub fn release_all(&mut self) -> Vec<Vec<u8>> {
+        self.held
+            .drain(..)
+            .map(|(_, channel, key)| vec![0x80 | channel, key, 0])
+            .collect()
+    }
+
+    /// Next pitch bend channel, preferring ones without a held note
+    fn take_channel(&mut self) -> u8 {
+        let channels: Vec<u8> = bend_channels().collect();
+        for _ in 0..channels.len() {
+            let channel = channels[self.next_channel as usize % channels.len()];
+            self.next_channel = self.next_channel.wrapping_add(1);
+            if !self.held.iter().any(|&(_, c, _)| c == channel) {
+                return channel;
+            }
+        }
+        // Every channel is busy; steal the oldest
+        channels[self.next_channel as usize % channels.len()]
+    }
+}
+
+/// Channels used for pitch bend notes
+fn bend_channels() -> impl Iterator<Item = u8> {
+    (0..16).filter(|&c| c != DRUM_CHANNEL)
+}
+
+/// RPN 0 (pitch bend sensitivity) set to `BEND_RANGE` semitones
+fn bend_range_messages(channel: u8) -> Vec<Vec<u8>> {
+    let cc = 0xB0 | channel;
+    vec![
+        vec![cc, 101, 0],
+        vec![cc, 100, 0],
+        vec![cc, 6, BEND_RANGE as u8],
+        vec![cc, 38, 0],
+        vec![cc, 101, 127],
+        vec![cc, 100, 127],
+    ]
+}
+
+/// 14-bit pitch bend for a deviation in cents (8192 = no bend)
+pub fn pitch_bend_value(cents: f64) -> u16 {
+    let value = 8192.0 + cents / (BEND_RANGE * 100.0) * 8192.0;
+    value.round().clamp(0.0, 16383.0) as u16
+}
+
+/// MIDI Tuning Standard real-time single note tuning changes for all keys
+pub fn mts_messages(tuning: &Tuning, notes_per_message: usize) -> Vec<Vec<u8>> {
+    let keys: Vec<u8> = (0..128).collect();
+    keys.chunks(notes_per_message.clamp(1, 127))
+        .map(|chunk| {
+            let mut message = vec![0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, chunk.len() as u8];
+            for &key in chunk {
+                message.push(key);
+                message.extend_from_slice(&mts_frequency(key as f64 + tuning.cents_from_standard(key) / 100.0));
+            }
+            message.push(0xF7);
+            message
+        })
+        .collect()
+}
+
+/// MTS frequency data: semitone and 14-bit fraction of a semitone
+fn mts_frequency(pitch: f64) -> [u8; 3] {
+    let semitone = pitch.floor().clamp(0.0, 127.0);
+    // 7F 7F 7F means "no change", so stay just below it
+    let max_fraction = if semitone >= 127.0 { 16382.0 } else { 16383.0 };
+    let fraction = ((pitch - semitone) * 16384.0).round().clamp(0.0, max_fraction) as u16;
+    [semitone as u8, (fraction >> 7) as u8, (fraction & 0x7F) as u8]
+}
+
+/// One line of a Scala file: cents if it has a dot, otherwise a ratio
+fn parse_pitch(value: &str) -> Result<f64> {
+    if value.contains('.') {
+        return value.parse().map_err(|_| anyhow!("invalid cents value '{}'", value));
+    }
+    let (num, den) = value.split_once('/').unwrap_or((value, "1"));
+    let num: f64 = num.parse().map_err(|_| anyhow!("invalid ratio '{}'", value))?;
+    let den: f64 = den.parse().map_err(|_| anyhow!("invalid ratio '{}'", value))?;
+    if num <= 0.0 || den <= 0.0 {
+        bail!("invalid ratio '{}'", value);
+    }
+    Ok(ratio_to_cents(num / den))
+}
+
+/// Pitch classes reached by stacking fifths of the given size, starting
+/// `lowest` fifths below the tonic, as cents within one octave
+fn chain_of_fifths(fifth: f64, lowest: i32) -> [f64; 12] {
+    let mut table = [0.0; 12];
+    for k in lowest..lowest + 12 {
+        let step = (k * 7).rem_euclid(12) as usize;
+        table[step] = (k as f64 * fifth).rem_euclid(1200.0);
+    }
+    table
+}
+
+/// Frequency of a (possibly fractional) MIDI pitch in 12-TET
+fn equal_frequency(pitch: f64, reference_hz: f64) -> f64 {
+    reference_hz * cents_to_ratio((pitch - 69.0) * 100.0)
+}
+
+fn ratio_to_cents(ratio: f64) -> f64 {
+    1200.0 * ratio.log2()
+}
+
+fn cents_to_ratio(cents: f64) -> f64 {
+    2f64.powf(cents / 1200.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn test_reference_pitch() {
+        let mut tuning = Tuning::equal();
+        assert!(close(tuning.frequency(69), 440.0));
+        assert!(close(tuning.frequency(81), 880.0));
+        assert!(close(tuning.frequency(60), 261.63));
+
+        tuning.reference_hz = 415.0;
+        assert!(close(tuning.frequency(69), 415.0));
+        assert!(!tuning.is_standard());
+        assert!((tuning.cents_from_standard(60) + 101.27).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_fixed_temperaments() {
+        // Just intonation in D: F# is a pure major third above D
+        let tuning = Tuning { temperament: Temperament::Just, tonic: 2, ..Tuning::equal() };
+        assert!(close(tuning.frequency(66) / tuning.frequency(62), 1.25));
+        assert!(close(tuning.frequency(69) / tuning.frequency(62), 1.5));
+        assert!(close(tuning.frequency(62), Tuning::equal().frequency(62)));
+
+        let pythagorean = Tuning { temperament: Temperament::Pythagorean, ..Tuning::equal() };
+        assert!(close(pythagorean.frequency(67) / pythagorean.frequency(60), 1.5));
+        assert!(close(pythagorean.frequency(64) / pythagorean.frequency(60), 81.0 / 64.0));
+
+        let meantone = Tuning { temperament: Temperament::Meantone, ..Tuning::equal() };
+        assert!(close(meantone.frequency(64) / meantone.frequency(60), 1.25));
+        assert!(close(meantone.frequency(68) / meantone.frequency(64), 1.25));
+        for note in 60..72 {
+            assert!(meantone.frequency(note + 1) > meantone.frequency(note));
+        }
+    }
+
+    #[test]
+    fn test_scala_files() {
+        let scl = "! pelog.scl\n!\nSeven note test scale\n 7\n!\n 120.0 cents\n 5/4\n 4/3\n 3/2\n 800.0\n 1000.\n 2\n";
+        let scale = ScalaScale::parse(scl).unwrap();
+        assert_eq!(scale.description, "Seven note test scale");
+        assert_eq!(scale.degrees.len(), 7);
+        assert!(close(scale.degrees[1], 386.31));
+        assert!(close(scale.period(), 1200.0));
+        assert!(ScalaScale::parse("Bad\n 3\n 100.0\n").is_err());
+
+        // Without a mapping, degree 0 is middle C
+        let scala = Tuning { temperament: Temperament::Scala, ..Tuning::equal() };
+        let tuning = scala.clone().with_scala(scale.clone(), None);
+        assert!(close(tuning.frequency(60), 261.63));
+        assert!(close(tuning.frequency(62) / tuning.frequency(60), 1.25));
+        assert!(close(tuning.frequency(67), 2.0 * 261.6256));
+
+        // Map the scale onto the white keys with A = 432 Hz
+        let kbm = "! white keys\n12\n0\n127\n60\n69\n432.0\n7\n! mapping\n0\nx\n1\nx\n2\n3\nx\n4\nx\n5\nx\n6\n";
+        let mapping = KeyboardMapping::parse(kbm).unwrap();
+        assert_eq!(mapping.map[1], None);
+        let tuning = scala.with_scala(scale, Some(mapping));
+        assert!(close(tuning.frequency(69), 432.0));
+        assert!(close(tuning.frequency(81), 864.0));
+        assert!(close(tuning.frequency(64) / tuning.frequency(60), 1.25));
+    }
+
+    #[test]
+    fn test_mts_encoding() {
+        assert_eq!(mts_frequency(69.0), [69, 0, 0]);
+        assert_eq!(mts_frequency(60.5), [60, 0x40, 0]);
+        assert_eq!(mts_frequency(127.9999), [127, 0x7F, 0x7E]);
+
+        let messages = mts_messages(&Tuning::equal(), 64);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(&messages[0][..7], &[0xF0, 0x7F, 0x7F, 0x08, 0x02, 0x00, 64]);
+        assert_eq!(messages[0].len(), 7 + 64 * 4 + 1);
+        assert_eq!(&messages[1][7..11], &[64, 64, 0, 0]);
+        assert_eq!(messages[1].last(), Some(&0xF7));
+    }
+
+    #[test]
+    fn test_midi_tuner() {
+        let mut tuner = MidiTuner::new(0, 64);
+        // Standard tuning passes notes through and sends nothing up front
+        assert!(tuner.retune(&Tuning::equal(), MidiTuningMethod::Mts).is_empty());
+        assert_eq!(tuner.note_on(60, 100), vec![vec![0x90, 60, 100]]);
+
+        let baroque = Tuning { reference_hz: 415.0, ..Tuning::equal() };
+        assert_eq!(tuner.retune(&baroque, MidiTuningMethod::Mts).len(), 2);
+        assert_eq!(tuner.note_on(69, 100), vec![vec![0x90, 69, 100]]);
+
+        // Pitch bend: A415 is a semitone down, so the key moves and the
+        // remaining cent is bent
+        let setup = tuner.retune(&baroque, MidiTuningMethod::PitchBend);
+        assert_eq!(setup.len(), 2 + 15 * 6);
+        let on = tuner.note_on(69, 100);
+        assert_eq!(on.len(), 2);
+        assert_eq!(on[0][0] & 0xF0, 0xE0);
+        assert_eq!(on[1][1], 68);
+        let bend = on[0][1] as u16 | (on[0][2] as u16) << 7;
+        assert_eq!(bend, 8140);
+        let channel = on[1][0] & 0x0F;
+        assert_ne!(channel, DRUM_CHANNEL);
+
+        // A second note gets its own channel, and note off uses the bent key
+        let other = tuner.note_on(72, 100);
+        assert_ne!(other[1][0] & 0x0F, channel);
+        assert_eq!(tuner.note_off(69), vec![vec![0x80 | channel, 68, 0]]);
+        assert_eq!(tuner.release_all().len(), 1);
+
+        // Drums stay on their own channel, unbent
+        assert_eq!(tuner.drum_hit(42, 90), vec![vec![0x99, 42, 90], vec![0x89, 42, 0]]);
+    }
+}
@@ -0,0 +1,1029 @@
+//! Headless command-line interface
+//!
+//! `ear-trainer <command> ...` prints results from the theory engine as text
+//! or JSON instead of starting the TUI, so it can be used from scripts.
+
+use crate::audio::manager::AudioConfig;
+use crate::audio::tuning::{MidiTuningMethod, Temperament, Tuning};
+use crate::export::{self, ExportOptions};
+use crate::music::{
+    BassStyle, Brick, BrickAnalyzer, BrickLibrary, Chord, ChordRecognizer, ChordScaleMatcher, DrumStyle, FunctionalAnalysis,
+    GripStyle, GuitarTuning, GuitarVoicer, HumanizeAmount, MusicData, Note, Progression, ProgressionLibrary, Reharmonizer, RhythmStyle, RomanNumeral, ScaleChoice, SpelledPitch, Standard,
+    StandardsLibrary, Substitution, TuneBuilder, VoiceLeading, VoicingType,
+};
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: ear-trainer [COMMAND] [OPTIONS]
+
+Without a command the interactive trainer starts.
+
+Commands:
+  list [progressions|bricks|standards]   List the built-in library
+  analyze <CHORDS>                       Analyze chords, e.g. \"Dm7 G7 Cmaj7\"
+  identify <NOTES> [--bass NOTE]         Name the chord in a voicing, e.g. \"E3 G3 B3 D4\"
+  brick <NAME> [--key KEY]               Show a LEGO brick in a key (default C)
+  standard <NAME>                        Show the brick breakdown of a standard
+  lego <PROGRESSION|CHORDS>              Find the bricks and joins in any progression
+  tune <SECTIONS|STANDARD> [--form F]    Chain bricks and joins into a tune, e.g.
+                                         \"A: Launcher, Sad Launcher | B: High Jump,
+                                         Launcher\" --form AABA, or rebuild a standard
+  reharm <PROGRESSION|CHORDS> [--sub S]  Reharmonize and show what changed
+  grips <CHORD> [--grip STYLE]           Guitar grips for a chord, lowest position first
+  export-midi <PROGRESSION> -o <FILE>    Write a progression to a MIDI file
+  tuning                                 Show or change the playback tuning
+  data                                   Check user bricks, joins and standards
+  help                                   Show this message
+
+Options:
+  --json               Print JSON instead of text
+  --key KEY            Key for brick, or key context for analyze and lego
+  --genre GENRE        Only list progressions in this genre
+  --form FORM          Section order for tune, e.g. AABA or ABAC
+  --sub NAMES          Substitutions for reharm, comma separated: Tritone Sub,
+                       ii-V Insertion, Backdoor, Diminished Passing, Coltrane,
+                       Relative Swap (default Tritone Sub)
+  --grip STYLE         Drop 2, Drop 3, Shell (grips, default Drop 2)
+  --guitar TUNING      Standard, Drop D, Open G, DADGAD, All Fourths (grips)
+  -o, --output FILE    Output file for export-midi
+  --voicing NAME       Full, Shell, Rootless A, Rootless B, Drop 2, Drop 3, Drop 2&4,
+                       Quartal, So What, Upper Structure, Spread
+  --rhythm NAME        Whole, Quarter, Jazz, Swing, Bossa, Funk
+  --bass NAME          Off, Root, Root-5th, Walking, Latin, Funk
+                       (identify: bass note under a rootless voicing)
+  --drums NAME         Off, Click, Jazz, Brushes, Bossa, Funk, Rock
+  --swing RATIO        Swing ratio of the jazz styles, 0.5 (straight) to 1.0;
+                       default 0.67
+  --humanize AMOUNT    Off, Tight, Natural, Loose
+  --seed N             Seed for humanize variation
+  --tempo BPM          Override the progression tempo
+  --loops N            Times to play through the progression (default 1)
+  --temperament NAME   Equal, Just, Pythagorean, Meantone, Scala (tuning)
+  --reference HZ       Frequency of A4, e.g. 415 or 442 (tuning)
+  --scala FILE         Scala .scl scale, or \"none\" (tuning)
+  --kbm FILE           Scala .kbm keyboard mapping, or \"none\" (tuning)
+  --midi-tuning NAME   MTS or \"Pitch Bend\" for MIDI instruments (tuning)";
+
+/// Options that take a value
+const VALUE_OPTIONS: &[&str] = &[
+    "key", "genre", "form", "sub", "grip", "guitar", "output", "voicing", "rhythm", "bass", "drums", "swing", "tempo", "loops",
+    "humanize", "seed", "temperament", "reference", "scala", "kbm", "midi-tuning",
+];
+
+/// Parsed command line
+#[derive(Debug, Default)]
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    json: bool,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut parsed = Args::default();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if arg == "--json" {
+                parsed.json = true;
+            } else if arg == "-h" || arg == "--help" {
+                parsed.command = "help".to_string();
+            } else if let Some(option) = arg.strip_prefix("--").or(if arg == "-o" { Some("output") } else { None }) {
+                let (name, inline_value) = match option.split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (option, None),
+                };
+                if !VALUE_OPTIONS.contains(&name) {
+                    bail!("unknown option --{}\n\n{}", name, USAGE);
+                }
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| anyhow!("--{} needs a value", name))?,
+                };
+                parsed.options.push((name.to_string(), value));
+            } else if parsed.command.is_empty() {
+                parsed.command = arg.clone();
+            } else {
+                parsed.positional.push(arg.clone());
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All positional arguments joined, for names containing spaces
+    fn name(&self, what: &str) -> Result<String> {
+        if self.positional.is_empty() {
+            bail!("missing {} name\n\n{}", what, USAGE);
+        }
+        Ok(self.positional.join(" "))
+    }
+}
+
+/// Run a command; `args` excludes the program name
+pub fn run(args: &[String]) -> Result<()> {
+    let args = Args::parse(args)?;
+
+    match args.command.as_str() {
+        "list" => list(&args),
+        "analyze" => analyze(&args),
+        "identify" => identify(&args),
+        "brick" => brick(&args),
+        "standard" => standard(&args),
+        "lego" => lego(&args),
+        "tune" => tune(&args),
+        "reharm" => reharm(&args),
+        "grips" => grips(&args),
+        "export-midi" => export_midi(&args),
+        "tuning" => tuning(&args),
+        "data" => data(&args),
+        "help" | "" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => bail!("unknown command '{}'\n\n{}", other, USAGE),
+    }
+}
+
+fn print_json(value: &Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Bricks, joins and standards with the user's data files, warning about
+/// any entries that were left out
+fn music_data() -> MusicData {
+    let data = MusicData::load();
+    for issue in &data.issues {
+        eprintln!("warning: {}", issue);
+    }
+    data
+}
+
+fn chord_list(changes: impl IntoIterator<Item = String>) -> String {
+    changes.into_iter().collect::<Vec<_>>().join(" ")
+}
+
+fn list(args: &Args) -> Result<()> {
+    let what = args.positional.first().map(|s| s.as_str());
+    if let Some(what) = what {
+        if !["progressions", "bricks", "standards"].contains(&what) {
+            bail!("can only list progressions, bricks or standards");
+        }
+    }
+    let show = |section: &str| what.is_none() || what == Some(section);
+
+    let data = music_data();
+    let progressions = ProgressionLibrary::with_bricks(&data.bricks);
+    let mut genres = progressions.all_genres();
+    genres.sort();
+    if let Some(genre) = args.option("genre") {
+        genres.retain(|g| g.eq_ignore_ascii_case(genre));
+        if genres.is_empty() {
+            bail!("unknown genre '{}'", genre);
+        }
+    }
+
+    let mut brick_list = data.bricks.all();
+    brick_list.sort_by(|a, b| a.name.cmp(&b.name));
+    let standards = &data.standards;
+
+    if args.json {
+        let mut out = serde_json::Map::new();
+        if show("progressions") {
+            let progs: Vec<&Progression> = genres
+                .iter()
+                .filter_map(|g| progressions.get_by_genre(g))
+                .flatten()
+                .collect();
+            out.insert("progressions".to_string(), serde_json::to_value(progs)?);
+        }
+        if show("bricks") {
+            out.insert("bricks".to_string(), serde_json::to_value(&brick_list)?);
+        }
+        if show("standards") {
+            out.insert("standards".to_string(), serde_json::to_value(standards.all())?);
+        }
+        return print_json(&Value::Object(out));
+    }
+
+    if show("progressions") {
+        println!("Progressions");
+        for genre in &genres {
+            println!("  {}", genre);
+            for prog in progressions.get_by_genre(genre).into_iter().flatten() {
+                println!(
+                    "    {:<32} {:>3.0} bpm  {}",
+                    prog.name,
+                    prog.tempo,
+                    chord_list(prog.changes.iter().map(|c| c.chord.name()))
+                );
+            }
+        }
+    }
+    if show("bricks") {
+        println!("Bricks");
+        for brick in &brick_list {
+            println!("  {:<16} {:<11} {}", brick.name, brick.category.name(), brick.analysis());
+        }
+    }
+    if show("standards") {
+        println!("Standards");
+        for standard in standards.all() {
+            println!(
+                "  {:<24} {:<20} {} bars, {}",
+                standard.name, standard.composer, standard.total_bars, standard.form
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Split a chord list on whitespace, bar lines and commas
+fn parse_chords(input: &str) -> Result<Vec<Chord>> {
+    input
+        .split(|c: char| c.is_whitespace() || c == '|' || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse())
+        .collect()
+}
+
+/// Everything `analyze` reports about one chord
+#[derive(Debug, Serialize)]
+struct ChordReport {
+    chord: String,
+    /// Roman numeral in the `--key`, e.g. "V7/ii"
+    numeral: Option<String>,
+    function: Option<&'static str>,
+    role: Option<String>,
+    notes: Vec<String>,
+    guide_tones: Vec<String>,
+    scale: String,
+    /// Why the scale fits here: the key, or where the chord resolves
+    scale_reason: String,
+    scale_notes: Vec<String>,
+    alternate_scales: Vec<String>,
+    extensions: Vec<String>,
+    /// Guide tone movement into the next chord
+    voice_leading: Vec<GuideToneMove>,
+}
+
+#[derive(Debug, Serialize)]
+struct GuideToneMove {
+    note: String,
+    semitones: i8,
+}
+
+impl ChordReport {
+    fn new(chord: &Chord, next: Option<&Chord>, analysis: Option<&RomanNumeral>, choice: ScaleChoice) -> Self {
+        let names = |notes: Vec<SpelledPitch>| notes.iter().map(|n| n.name()).collect();
+        let scale = choice.scale.clone();
+        let guide_tones = chord.guide_tones().into_iter().map(|n| chord.spell(n)).collect();
+        Self {
+            chord: chord.name(),
+            numeral: analysis.map(|a| a.numeral.clone()),
+            function: analysis.map(|a| a.function.name()),
+            role: analysis.map(|a| a.role.name()),
+            notes: names(chord.notes()),
+            guide_tones: names(guide_tones),
+            scale: scale.name(),
+            scale_reason: choice.reason.clone(),
+            scale_notes: names(scale.notes()),
+            alternate_scales: ChordScaleMatcher::scale_options(chord, &choice)
+                .iter()
+                .skip(1)
+                .map(|s| s.name())
+                .collect(),
+            extensions: scale
+                .available_extensions(chord)
+                .iter()
+                .map(|(n, label)| format!("{} ({})", n.name(), label))
+                .collect(),
+            voice_leading: next
+                .map(|next| {
+                    VoiceLeading::analyze(chord, next)
+                        .iter()
+                        .map(|gt| GuideToneMove {
+                            note: gt.note.name(),
+                            semitones: gt.movement,
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn analyze(args: &Args) -> Result<()> {
+    let chords = parse_chords(&args.positional.join(" "))?;
+    if chords.is_empty() {
+        bail!("no chords given, e.g. ear-trainer analyze \"Dm7 G7 Cmaj7\"");
+    }
+    let key = args.option("key").map(|k| k.parse::<Note>()).transpose()?;
+    let analysis = key.map(|k| FunctionalAnalysis::analyze_chords(&chords, k)).unwrap_or_default();
+    // Without a key, scales follow only where each chord resolves
+    let scales = match key {
+        Some(k) => ChordScaleMatcher::scales_in_key(&chords, k),
+        None => chords
+            .iter()
+            .enumerate()
+            .map(|(i, chord)| ChordScaleMatcher::get_contextual_scale(chord, chords.get(i + 1), None))
+            .collect(),
+    };
+
+    let reports: Vec<ChordReport> = chords
+        .iter()
+        .zip(scales)
+        .enumerate()
+        .map(|(i, (chord, choice))| ChordReport::new(chord, chords.get(i + 1), analysis.get(i), choice))
+        .collect();
+
+    if args.json {
+        return print_json(&serde_json::to_value(&reports)?);
+    }
+
+    for (i, report) in reports.iter().enumerate() {
+        println!("{}", report.chord);
+        if let (Some(numeral), Some(function), Some(role)) = (&report.numeral, report.function, &report.role) {
+            println!("  Function:    {} ({}, {})", numeral, function, role);
+        }
+        println!("  Notes:       {}", report.notes.join(" "));
+        println!("  Guide tones: {}", report.guide_tones.join(" "));
+        println!("  Scale:       {} ({})", report.scale, report.scale_notes.join(" "));
+        println!("               {}", report.scale_reason);
+        if !report.alternate_scales.is_empty() {
+            println!("  Also:        {}", report.alternate_scales.join(", "));
+        }
+        if !report.extensions.is_empty() {
+            println!("  Tensions:    {}", report.extensions.join(", "));
+        }
+        if let Some(next) = reports.get(i + 1) {
+            let moves: Vec<String> = report
+                .voice_leading
+                .iter()
+                .map(|m| format!("{} {:+}", m.note, m.semitones))
+                .collect();
+            if !moves.is_empty() {
+                println!("  To {}: {}", next.chord, moves.join(", "));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a MIDI note number or a name with octave like "C4" or "F#3"
+fn parse_midi_note(s: &str) -> Result<u8> {
+    if let Ok(midi) = s.parse::<u8>() {
+        return Ok(midi);
+    }
+    let split = s
+        .find(|c: char| c.is_ascii_digit() || c == '-')
+        .ok_or_else(|| anyhow!("note '{}' needs an octave, e.g. C4", s))?;
+    let (name, octave) = s.split_at(split);
+    let pitch: SpelledPitch = name.parse()?;
+    let octave: i16 = octave.parse().map_err(|_| anyhow!("invalid octave in '{}'", s))?;
+    let midi = (octave + 1) * 12 + pitch.letter.natural() as i16 + pitch.accidental as i16;
+    u8::try_from(midi)
+        .ok()
+        .filter(|&m| m <= 127)
+        .ok_or_else(|| anyhow!("note '{}' is out of MIDI range", s))
+}
+
+fn identify(args: &Args) -> Result<()> {
+    let notes = args
+        .positional
+        .iter()
+        .flat_map(|arg| arg.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|s| !s.is_empty())
+        .map(parse_midi_note)
+        .collect::<Result<Vec<u8>>>()?;
+    if notes.is_empty() {
+        bail!("no notes given, e.g. ear-trainer identify \"E3 G3 B3 D4\"");
+    }
+
+    let candidates = match args.option("bass") {
+        Some(bass) => ChordRecognizer::recognize_with_bass(&notes, parse_midi_note(bass)?),
+        None => ChordRecognizer::recognize(&notes),
+    };
+    let candidates: Vec<_> = candidates.into_iter().take(5).collect();
+
+    if args.json {
+        let value: Vec<Value> = candidates
+            .iter()
+            .map(|c| {
+                json!({
+                    "chord": c.chord.name(),
+                    "score": c.score,
+                    "omitted": c.omitted,
+                    "added": c.added,
+                    "rootless": c.rootless,
+                })
+            })
+            .collect();
+        return print_json(&Value::Array(value));
+    }
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        let rootless = if candidate.rootless { "  rootless" } else { "" };
+        println!("{}. {:<24} {:>3}{}", i + 1, candidate.describe(), candidate.score, rootless);
+    }
+    Ok(())
+}
+
+fn find_brick<'a>(bricks: &'a BrickLibrary, name: &str) -> Result<&'a Brick> {
+    bricks
+        .all()
+        .into_iter()
+        .find(|b| b.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow!("unknown brick '{}' (see `ear-trainer list bricks`)", name))
+}
+
+fn brick(args: &Args) -> Result<()> {
+    let data = music_data();
+    let brick = find_brick(&data.bricks, &args.name("brick")?)?;
+    let key: Note = args.option("key").unwrap_or("C").parse()?;
+    let changes = brick.transpose(key);
+    let used_in: Vec<&str> = data
+        .standards
+        .using_brick(&brick.name)
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+
+    if args.json {
+        return print_json(&json!({
+            "name": brick.name,
+            "category": brick.category.name(),
+            "description": brick.description,
+            "key": key.name(),
+            "analysis": brick.analysis(),
+            "changes": changes,
+            "examples": brick.examples,
+            "standards": used_in,
+        }));
+    }
+
+    println!("{} in {} ({})", brick.name, key, brick.category.name());
+    println!("  {}", brick.description);
+    println!("  Analysis: {}", brick.analysis());
+    println!(
+        "  Changes:  {}",
+        changes
+            .iter()
+            .map(|c| format!("{} ({})", c.chord.name(), c.duration))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    );
+    if !brick.examples.is_empty() {
+        println!("  Examples: {}", brick.examples.join(", "));
+    }
+    if !used_in.is_empty() {
+        println!("  In standards: {}", used_in.join(", "));
+    }
+    Ok(())
+}
+
+fn find_standard<'a>(standards: &'a StandardsLibrary, name: &str) -> Result<&'a Standard> {
+    standards
+        .get(name)
+        .or_else(|| standards.all().iter().find(|s| s.name.eq_ignore_ascii_case(name)))
+        .ok_or_else(|| anyhow!("unknown standard '{}' (see `ear-trainer list standards`)", name))
+}
+
+fn standard(args: &Args) -> Result<()> {
+    let data = music_data();
+    let standard = find_standard(&data.standards, &args.name("standard")?)?;
+    let bricks = &data.bricks;
+
+    // Chords of a brick reference, if the brick is in the library
+    let brick_chords = |name: &str, key: Note| -> Option<String> {
+        bricks
+            .get(name)
+            .map(|b| chord_list(b.transpose(key).iter().map(|c| c.chord.name())))
+    };
+
+    if args.json {
+        let mut value = serde_json::to_value(standard)?;
+        for section in value["sections"].as_array_mut().into_iter().flatten() {
+            for brick_ref in section["bricks"].as_array_mut().into_iter().flatten() {
+                let name = brick_ref["brick_name"].as_str().unwrap_or_default().to_string();
+                let key: Option<Note> = serde_json::from_value(brick_ref["key"].clone()).ok();
+                brick_ref["chords"] = json!(key.and_then(|k| brick_chords(&name, k)));
+            }
+        }
+        return print_json(&value);
+    }
+
+    let year = standard.year.map(|y| format!(" ({})", y)).unwrap_or_default();
+    println!("{} - {}{}", standard.name, standard.composer, year);
+    println!(
+        "  {} bars, {} form, key of {}, {}",
+        standard.total_bars, standard.form, standard.key, standard.style
+    );
+    for section in &standard.sections {
+        println!("  [{}] {} bars in {}", section.label, section.bars, section.key);
+        for brick_ref in &section.bricks {
+            println!(
+                "    bar {:>2}  {:<16} in {:<2}  {}",
+                brick_ref.start_bar,
+                brick_ref.brick_name,
+                brick_ref.key.name(),
+                brick_chords(&brick_ref.brick_name, brick_ref.key).unwrap_or_default()
+            );
+        }
+    }
+    if !standard.joins_used.is_empty() {
+        println!("  Joins: {}", standard.joins_used.join(", "));
+    }
+    Ok(())
+}
+
+/// A library progression by name, or a chord list of four beats per chord
+fn progression_or_chords(args: &Args, bricks: &BrickLibrary) -> Result<Progression> {
+    let name = args.name("progression")?;
+    let library = ProgressionLibrary::with_bricks(bricks);
+    if let Some(prog) = library
+        .all_progressions()
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(&name))
+    {
+        return Ok(prog.clone());
+    }
+
+    let chords = parse_chords(&name)
+        .ok()
+        .filter(|chords| !chords.is_empty())
+        .ok_or_else(|| anyhow!("'{}' is neither a progression nor a chord list", name))?;
+    let mut prog = Progression::new("Chords".to_string(), String::new(), Note::C, 120.0);
+    for chord in chords {
+        prog.add_chord(chord, 4.0);
+    }
+    prog.key = match args.option("key") {
+        Some(k) => k.parse()?,
+        // The key of the first brick, else the first chord
+        None => BrickAnalyzer::new()
+            .analyze(&prog)
+            .iter()
+            .find(|s| s.brick.is_some())
+            .map(|s| s.key)
+            .unwrap_or(prog.changes[0].chord.root),
+    };
+    Ok(prog)
+}
+
+fn lego(args: &Args) -> Result<()> {
+    let data = music_data();
+    let progression = progression_or_chords(args, &data.bricks)?;
+    let segments = BrickAnalyzer::with_libraries(data.bricks, data.joins).analyze(&progression);
+    let joins: Vec<&str> = segments.iter().filter_map(|s| s.join.as_deref()).collect();
+
+    if args.json {
+        return print_json(&json!({
+            "progression": progression.name,
+            "key": progression.spelled_key().name(),
+            "bricks": segments,
+            "joins": joins,
+        }));
+    }
+
+    println!("{} in {}", progression.name, progression.spelled_key());
+    for segment in &segments {
+        match segment.to_brick_ref(progression.meter)? {
+            Some(brick_ref) => {
+                if let Some(join) = &segment.join {
+                    println!("    {:>6}  {}", "", join);
+                }
+                println!(
+                    "    bar {:>2}  {:<16} in {:<2}  {}",
+                    brick_ref.start_bar,
+                    brick_ref.brick_name,
+                    brick_ref.key.name(),
+                    segment.chords.join(" ")
+                );
+                if let Some(notes) = brick_ref.notes {
+                    println!("    {:>6}  ({})", "", notes);
+                }
+            }
+            None => println!(
+                "    bar {:>2}  {:<22}  {}",
+                segment.start_bar(progression.meter),
+                "(no brick)",
+                segment.chords.join(" ")
+            ),
+        }
+    }
+    if !joins.is_empty() {
+        println!("  Joins: {}", joins.join(", "));
+    }
+    Ok(())
+}
+
+/// Build a tune from a standard's name or a section list
+///
+/// Sections are separated by `|` and may start with a label (`A:`); parts
+/// are comma separated, and any part that names a join moves the key.
+fn tune(args: &Args) -> Result<()> {
+    let spec = args.name("tune")?;
+    let data = music_data();
+    let (bricks, joins) = (&data.bricks, &data.joins);
+    let key: Note = args.option("key").unwrap_or("C").parse()?;
+
+    let mut builder = match find_standard(&data.standards, &spec) {
+        Ok(standard) => TuneBuilder::from_standard(standard),
+        Err(_) => {
+            let mut builder = TuneBuilder::new("Tune", key, 140.0);
+            for section in spec.split('|') {
+                // Unlabelled sections take the next letter
+                let (label, parts) = match section.split_once(':') {
+                    Some((label, parts)) => (label.trim().to_string(), parts),
+                    None => (builder.next_label(), section),
+                };
+                builder = builder.section(label);
+                for part in parts.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+                    match joins.all().into_iter().find(|j| j.name.eq_ignore_ascii_case(part)) {
+                        Some(join) => builder = builder.join(&join.name),
+                        None => builder = builder.brick(&find_brick(bricks, part)?.name),
+                    }
+                }
+            }
+            builder
+        }
+    };
+    if let Some(form) = args.option("form") {
+        builder = builder.form(&form.to_uppercase());
+    }
+    if let Some(v) = args.option("tempo") {
+        builder.tempo = v.parse().map_err(|_| anyhow!("invalid tempo '{}'", v))?;
+    }
+    let tune = builder.build(bricks, joins)?;
+    let standard = &tune.standard;
+
+    if args.json {
+        return print_json(&json!({
+            "standard": standard,
+            "progression": tune.progression,
+        }));
+    }
+
+    println!(
+        "{} - {} bars, {} form, key of {}",
+        standard.name, standard.total_bars, standard.form, standard.key
+    );
+    for section in &standard.sections {
+        println!("  [{}] {} bars in {}", section.label, section.bars, section.key);
+        for brick_ref in &section.bricks {
+            println!(
+                "    bar {:>2}  {:<16} in {:<2}",
+                brick_ref.start_bar,
+                brick_ref.brick_name,
+                brick_ref.key.name()
+            );
+        }
+    }
+    if !standard.joins_used.is_empty() {
+        println!("  Joins: {}", standard.joins_used.join(", "));
+    }
+    println!(
+        "  Changes: {}",
+        chord_list(tune.progression.changes.iter().map(|c| c.chord.name()))
+    );
+    Ok(())
+}
+
+fn reharm(args: &Args) -> Result<()> {
+    let progression = progression_or_chords(args, &music_data().bricks)?;
+    let substitutions = args
+        .option("sub")
+        .unwrap_or("Tritone Sub")
+        .split(',')
+        .map(|name| parse_style(name, Substitution::default(), |s| s.next(), |s| s.name()))
+        .collect::<Result<Vec<_>>>()?;
+    let reharm = Reharmonizer::apply(&progression, &substitutions);
+
+    if args.json {
+        return print_json(&serde_json::to_value(&reharm)?);
+    }
+
+    let changes = |prog: &Progression| {
+        chord_list(prog.changes.iter().map(|c| format!("{} ({})", c.chord.name(), c.duration)))
+    };
+    println!("{}", reharm.progression.name);
+    println!("  Before: {}", changes(&progression));
+    println!("  After:  {}", changes(&reharm.progression));
+    if reharm.changes.is_empty() {
+        println!("  Nothing to substitute");
+    }
+    for change in &reharm.changes {
+        println!(
+            "  bar {:>2}  {:<18} {}",
+            change.beat as u32 / progression.meter.beats.max(1) as u32 + 1,
+            change.substitution.name(),
+            change.describe()
+        );
+    }
+    Ok(())
+}
+
+fn grips(args: &Args) -> Result<()> {
+    let chord: Chord = args.name("chord")?.parse()?;
+    let style = match args.option("grip") {
+        Some(g) => parse_style(g, GripStyle::default(), |s| s.next(), |s| s.name())?,
+        None => GripStyle::default(),
+    };
+    let tuning = match args.option("guitar") {
+        Some(t) => parse_style(t, GuitarTuning::default(), |s| s.next(), |s| s.name())?,
+        None => GuitarTuning::default(),
+    };
+    let grips = GuitarVoicer::new(tuning).grips(&chord, style);
+
+    if args.json {
+        let value: Vec<Value> = grips
+            .iter()
+            .map(|g| json!({ "diagram": g.diagram(), "frets": g.frets, "notes": g.notes }))
+            .collect();
+        return print_json(&Value::Array(value));
+    }
+
+    println!("{} · {} · {} tuning", chord.name(), style.name(), tuning.name());
+    if grips.is_empty() {
+        println!("  No {} grip fits within four frets", style.name());
+    }
+    for grip in &grips {
+        let notes: Vec<String> = grip
+            .notes
+            .iter()
+            .map(|&n| format!("{}{}", chord.spell(Note::from_midi(n)).name(), n as i16 / 12 - 1))
+            .collect();
+        println!("  {:<18} {}", grip.diagram(), notes.join(" "));
+    }
+    Ok(())
+}
+
+/// Match a style by display or variant name, ignoring case and punctuation
+fn parse_style<T: Copy + PartialEq + std::fmt::Debug>(
+    input: &str,
+    first: T,
+    next: impl Fn(T) -> T,
+    name: impl Fn(T) -> &'static str,
+) -> Result<T> {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase()
+    };
+    let wanted = normalize(input);
+
+    let mut style = first;
+    let mut names = Vec::new();
+    loop {
+        if normalize(name(style)) == wanted || normalize(&format!("{:?}", style)) == wanted {
+            return Ok(style);
+        }
+        names.push(name(style));
+        style = next(style);
+        if style == first {
+            bail!("unknown style '{}', expected one of: {}", input, names.join(", "));
+        }
+    }
+}
+
+fn export_midi(args: &Args) -> Result<()> {
+    let name = args.name("progression")?;
+    let library = ProgressionLibrary::with_bricks(&music_data().bricks);
+    let mut progression = library
+        .all_progressions()
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(&name))
+        .cloned()
+        .ok_or_else(|| anyhow!("unknown progression '{}' (see `ear-trainer list progressions`)", name))?;
+    let output = PathBuf::from(
+        args.option("output")
+            .ok_or_else(|| anyhow!("export-midi needs an output file (-o FILE)"))?,
+    );
+
+    let mut options = ExportOptions::default();
+    if let Some(v) = args.option("voicing") {
+        options.voicing = parse_style(v, VoicingType::default(), |s| s.next(), |s| s.name())?;
+    }
+    if let Some(v) = args.option("rhythm") {
+        options.rhythm = parse_style(v, RhythmStyle::default(), |s| s.next(), |s| s.name())?;
+    }
+    if let Some(v) = args.option("bass") {
+        options.bass = parse_style(v, BassStyle::default(), |s| s.next(), |s| s.name())?;
+    }
+    if let Some(v) = args.option("drums") {
+        options.drums = parse_style(v, DrumStyle::default(), |s| s.next(), |s| s.name())?;
+    }
+    if let Some(v) = args.option("swing") {
+        let ratio: f32 = v.parse().map_err(|_| anyhow!("invalid swing ratio '{}'", v))?;
+        if !(0.5..1.0).contains(&ratio) {
+            bail!("swing ratio must be between 0.5 and 1.0");
+        }
+        options.swing = Some(ratio);
+    }
+    if let Some(v) = args.option("humanize") {
+        options.humanize = parse_style(v, HumanizeAmount::default(), |s| s.next(), |s| s.name())?;
+    }
+    if let Some(v) = args.option("seed") {
+        options.seed = v.parse().map_err(|_| anyhow!("invalid seed '{}'", v))?;
+    }
+    if let Some(v) = args.option("loops") {
+        options.loops = v.parse().map_err(|_| anyhow!("invalid loop count '{}'", v))?;
+    }
+    if let Some(v) = args.option("tempo") {
+        let tempo: f32 = v.parse().map_err(|_| anyhow!("invalid tempo '{}'", v))?;
+        if !(20.0..=400.0).contains(&tempo) {
+            bail!("tempo must be between 20 and 400 bpm");
+        }
+        progression.tempo = tempo;
+    }
+
+    export::write_midi_file(&progression, &options, &output)?;
+
+    if args.json {
+        return print_json(&json!({
+            "progression": progression.name,
+            "output": output.display().to_string(),
+            "tempo": progression.tempo,
+            "loops": options.loops,
+        }));
+    }
+    println!("Wrote {} to {}", progression.name, output.display());
+    Ok(())
+}
+
+/// Report where user data files are read from and what was wrong with them
+fn data(args: &Args) -> Result<()> {
+    let dir = crate::music::data::user_data_dir();
+    let data = MusicData::load();
+
+    if args.json {
+        let issues: Vec<String> = data.issues.iter().map(|i| i.to_string()).collect();
+        print_json(&json!({
+            "directory": dir.as_ref().map(|d| d.display().to_string()),
+            "bricks": data.bricks.all().len(),
+            "joins": data.joins.all().len(),
+            "standards": data.standards.all().len(),
+            "issues": issues,
+        }))?;
+    } else {
+        match &dir {
+            Some(dir) => println!("User data in {}/{{bricks,joins,standards}}/*.json", dir.display()),
+            None => println!("No config directory for user data"),
+        }
+        println!(
+            "  {} bricks, {} joins, {} standards",
+            data.bricks.all().len(),
+            data.joins.all().len(),
+            data.standards.all().len()
+        );
+        for issue in &data.issues {
+            println!("  {}", issue);
+        }
+    }
+    if !data.issues.is_empty() {
+        bail!("{} entries were left out", data.issues.len());
+    }
+    Ok(())
+}
+
+/// Show the saved tuning, changing it first if any tuning options are given
+fn tuning(args: &Args) -> Result<()> {
+    let mut config = AudioConfig::load();
+    let mut settings = config.tuning.clone();
+
+    if let Some(v) = args.option("temperament") {
+        settings.temperament = parse_style(v, Temperament::default(), |s| s.next(), |s| s.name())?;
+    }
+    if let Some(v) = args.option("reference") {
+        let hz: f32 = v.parse().map_err(|_| anyhow!("invalid reference pitch '{}'", v))?;
+        if !(380.0..=480.0).contains(&hz) {
+            bail!("reference pitch must be between 380 and 480 Hz");
+        }
+        settings.reference_hz = hz;
+    }
+    let path_option = |name: &str| -> Option<Option<PathBuf>> {
+        args.option(name)
+            .map(|v| if v.eq_ignore_ascii_case("none") { None } else { Some(PathBuf::from(v)) })
+    };
+    if let Some(path) = path_option("scala") {
+        settings.scala_file = path;
+    }
+    if let Some(path) = path_option("kbm") {
+        settings.kbm_file = path;
+    }
+    if let Some(v) = args.option("midi-tuning") {
+        settings.midi_method = parse_style(v, MidiTuningMethod::default(), |s| match s {
+            MidiTuningMethod::Mts => MidiTuningMethod::PitchBend,
+            MidiTuningMethod::PitchBend => MidiTuningMethod::Mts,
+        }, |s| s.name())?;
+    }
+    if settings.temperament == Temperament::Scala && settings.scala_file.is_none() {
+        bail!("the Scala temperament needs a scale file (--scala FILE)");
+    }
+
+    // Check that any Scala files load before saving them
+    let mut tuning = Tuning::from_config(&settings)?;
+    if settings != config.tuning {
+        config.tuning = settings.clone();
+        config.save();
+    }
+
+    let key: Note = args.option("key").unwrap_or("C").parse()?;
+    tuning.tonic = key as u8;
+    let notes: Vec<(u8, f64, f64)> = (0..=12)
+        .map(|step| {
+            let midi = key.to_midi(4) + step;
+            (midi, tuning.frequency(midi), tuning.cents_from_standard(midi))
+        })
+        .collect();
+
+    if args.json {
+        return print_json(&json!({
+            "temperament": settings.temperament.name(),
+            "reference_hz": settings.reference_hz,
+            "scala_file": settings.scala_file,
+            "kbm_file": settings.kbm_file,
+            "scala_description": tuning.scala_description(),
+            "midi_tuning": settings.midi_method.name(),
+            "key": key.name(),
+            "notes": notes
+                .iter()
+                .map(|&(midi, hz, cents)| json!({
+                    "midi": midi,
+                    "name": Note::from_midi(midi).name(),
+                    "hz": hz,
+                    "cents_from_a440": cents,
+                }))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    println!("{} tuning, A4 = {} Hz, MIDI via {}", settings.temperament.name(), settings.reference_hz, settings.midi_method.name());
+    if let Some(description) = tuning.scala_description() {
+        println!("  Scale: {}", description);
+    }
+    println!("  Octave from {}4:", key);
+    for (midi, hz, cents) in notes {
+        println!("  {:<3} {:>3}  {:>8.2} Hz  {:>+7.2} cents", Note::from_midi(midi).name(), midi, hz, cents);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Args {
+        let owned: Vec<String> = list.iter().map(|s| s.to_string()).collect();
+        Args::parse(&owned).unwrap()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&["brick", "Sad", "Cadence", "--key", "Eb", "--json"]);
+        assert_eq!(parsed.command, "brick");
+        assert_eq!(parsed.name("brick").unwrap(), "Sad Cadence");
+        assert_eq!(parsed.option("key"), Some("Eb"));
+        assert!(parsed.json);
+
+        let parsed = args(&["export-midi", "ii-V-I (C)", "-o", "out.mid", "--loops=2"]);
+        assert_eq!(parsed.option("output"), Some("out.mid"));
+        assert_eq!(parsed.option("loops"), Some("2"));
+
+        let owned = vec!["list".to_string(), "--bogus".to_string()];
+        assert!(Args::parse(&owned).is_err());
+    }
+
+    #[test]
+    fn test_parse_chord_list() {
+        let chords = parse_chords("| Dm7 G7 | Cmaj7, A7 |").unwrap();
+        let names: Vec<String> = chords.iter().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["Dm7", "G7", "Cmaj7", "A7"]);
+    }
+
+    #[test]
+    fn test_parse_style_names() {
+        let rootless = parse_style("rootless-b", VoicingType::default(), |s| s.next(), |s| s.name());
+        assert_eq!(rootless.unwrap(), VoicingType::RootlessB);
+        let bossa = parse_style("BossaNova", RhythmStyle::default(), |s| s.next(), |s| s.name());
+        assert_eq!(bossa.unwrap(), RhythmStyle::BossaNova);
+        assert!(parse_style("polka", BassStyle::default(), |s| s.next(), |s| s.name()).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! Time sources for playback scheduling
+//!
+//! `App::update` asks a `Clock` for the current time instead of calling
+//! `Instant::now()` directly, so tests can drive playback with a virtual
+//! clock and get the exact same note stream on every run.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A source of monotonic time
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Wall clock backed by `Instant::now()`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Manually advanced clock for deterministic playback
+///
+/// Clones share the same time, so a test can hand one copy to the `App`,
+/// another to a `RecordingBackend`, and advance both at once.
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    origin: Instant,
+    elapsed_us: Arc<AtomicU64>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            elapsed_us: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Move time forward
+    pub fn advance(&self, by: Duration) {
+        self.elapsed_us
+            .fetch_add(by.as_micros() as u64, Ordering::SeqCst);
+    }
+
+    /// Time elapsed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_us.load(Ordering::SeqCst))
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_clock_shared() {
+        let clock = VirtualClock::new();
+        let other = clock.clone();
+        let start = clock.now();
+
+        other.advance(Duration::from_millis(250));
+        assert_eq!(clock.now() - start, Duration::from_millis(250));
+        assert_eq!(clock.elapsed(), Duration::from_millis(250));
+    }
+}
@@ -1,12 +1,25 @@
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub default_tempo: f32,
     pub default_velocity: u8,
     pub midi_channel: u8,
     pub min_note: u8,
     pub max_note: u8,
+    /// Extra interval sets, offered after the built-in ones
+    pub interval_sets: Vec<CustomIntervalSet>,
+}
+
+/// An interval set from the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomIntervalSet {
+    pub name: String,
+    /// Sizes in semitones, 1 to 24
+    pub semitones: Vec<u8>,
 }
 
 impl Default for Config {
@@ -17,16 +30,42 @@ impl Default for Config {
             midi_channel: 0,
             min_note: 48,  // C3
             max_note: 84,  // C6
+            interval_sets: Vec::new(),
         }
     }
 }
 
 impl Config {
+    fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join("ear-trainer").join("config.json"))
+    }
+
+    /// Read `config.json` from the config directory; anything missing or
+    /// unreadable falls back to the defaults
     pub fn load() -> Self {
-        Self::default()
+        Self::config_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn save(&self) -> anyhow::Result<()> {
-        Ok(())
+    #[test]
+    fn test_partial_config_loads() {
+        let json = r#"{"max_note": 96, "interval_sets": [{"name": "Tritones", "semitones": [6, 18]}]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.max_note, 96);
+        assert_eq!(config.min_note, Config::default().min_note);
+        assert_eq!(
+            config.interval_sets,
+            vec![CustomIntervalSet {
+                name: "Tritones".to_string(),
+                semitones: vec![6, 18],
+            }]
+        );
     }
 }
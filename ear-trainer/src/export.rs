@@ -0,0 +1,496 @@
+//! Standard MIDI File export
+//!
+//! Progressions are rendered by running the normal playback scheduler
+//! (`App::update`) against a `VirtualClock` and a `RecordingBackend`, so an
+//! exported file contains exactly what the app would play live. Each chord
+//! change is marked with its name and Roman numeral.
+
+use crate::audio::recording::{RecordedEvent, RecordedKind, RecordingBackend};
+use crate::audio::AudioManager;
+use crate::clock::VirtualClock;
+use crate::music::{BassStyle, DrumStyle, FunctionalAnalysis, HumanizeAmount, Meter, Progression, RhythmStyle, VoicingType};
+use crate::music::humanize::DEFAULT_SEED;
+use crate::ui::App;
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Ticks per quarter note in exported files
+pub const PPQ: u16 = 480;
+
+/// Length written for a drum hit, a 32nd note
+const DRUM_TICKS: u64 = PPQ as u64 / 8;
+
+/// Accompaniment settings used for rendering
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub voicing: VoicingType,
+    pub rhythm: RhythmStyle,
+    pub bass: BassStyle,
+    pub drums: DrumStyle,
+    /// Swing ratio, or None for the app's default hard swing
+    pub swing: Option<f32>,
+    /// Timing and velocity variation of the backing parts
+    pub humanize: HumanizeAmount,
+    /// Seed for the humanize variation
+    pub seed: u64,
+    /// How many times to play through the progression
+    pub loops: u32,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            voicing: VoicingType::default(),
+            rhythm: RhythmStyle::default(),
+            bass: BassStyle::default(),
+            drums: DrumStyle::default(),
+            swing: None,
+            humanize: HumanizeAmount::Off,
+            seed: DEFAULT_SEED,
+            loops: 1,
+        }
+    }
+}
+
+/// Beat length as the scheduler computes it (whole milliseconds)
+fn beat_duration_ms(tempo: f32) -> u64 {
+    (60000.0 / tempo) as u64
+}
+
+/// The progression without changes too short to schedule
+///
+/// The player moves on once a change's whole milliseconds have passed, so a
+/// change that rounds down to none would never end.
+fn playable(progression: &Progression) -> Progression {
+    let beat_ms = beat_duration_ms(progression.tempo) as f32;
+    let mut progression = progression.clone();
+    progression.changes.retain(|change| (change.duration * beat_ms) as u64 > 0);
+    progression
+}
+
+/// Play a progression headlessly and return everything that was sent to the backend
+///
+/// The result ends with the `AllNotesOff` of the final chord change.
+/// Zero-length changes are skipped.
+pub fn render(progression: &Progression, options: &ExportOptions) -> Vec<RecordedEvent> {
+    let progression = &playable(progression);
+    let clock = VirtualClock::new();
+    let recorder = RecordingBackend::new(Arc::new(clock.clone()));
+    let audio = AudioManager::with_backend(Box::new(recorder.clone()));
+    let mut app = App::with_audio(audio, Box::new(clock.clone()));
+
+    app.load_progression(progression.clone());
+    app.current_voicing = options.voicing;
+    app.rhythm_state.set_style(options.rhythm);
+    app.bass_state.set_style(options.bass);
+    app.drum_state.set_style(options.drums);
+    if let Some(ratio) = options.swing {
+        app.swing_enabled = true;
+        app.swing_ratio = ratio;
+    }
+    app.humanizer.set_amount(options.humanize);
+    app.humanizer.set_seed(options.seed);
+    app.refresh_timeline();
+
+    let total_changes = progression.changes.len() * options.loops as usize;
+    if total_changes == 0 {
+        return Vec::new();
+    }
+
+    // Tick once per MIDI tick so humanized timing survives in the file
+    let tick = Duration::from_micros(beat_duration_ms(progression.tempo) * 1000 / PPQ as u64);
+
+    app.play();
+    let mut changes = 0;
+    let mut last_idx = app.current_chord_idx;
+    while changes < total_changes {
+        clock.advance(tick);
+        app.update();
+        if app.current_chord_idx != last_idx {
+            last_idx = app.current_chord_idx;
+            changes += 1;
+        }
+    }
+
+    // Drop the downbeat that started the next pass
+    let mut events = recorder.events();
+    if let Some(end) = events
+        .iter()
+        .rposition(|e| e.kind == RecordedKind::AllNotesOff)
+    {
+        events.truncate(end + 1);
+    }
+    events
+}
+
+/// Render a progression and write it as a MIDI file
+pub fn write_midi_file(progression: &Progression, options: &ExportOptions, path: &Path) -> Result<()> {
+    let events = render(progression, options);
+    let markers = chord_markers(progression, options.loops);
+    let bytes = to_smf(&events, progression.tempo, progression.meter, &progression.name, &markers);
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Marker text ("Dm7 ii7") and tick for every chord change over all loops
+pub fn chord_markers(progression: &Progression, loops: u32) -> Vec<(u64, String)> {
+    let progression = &playable(progression);
+    let analysis = FunctionalAnalysis::analyze(progression);
+    let mut beats = 0.0;
+    let mut markers = Vec::new();
+    for _ in 0..loops {
+        for (change, numeral) in progression.changes.iter().zip(&analysis) {
+            let tick = (beats * beat_ticks(progression.meter) as f32).round() as u64;
+            markers.push((tick, format!("{} {}", change.chord.name(), numeral.numeral)));
+            beats += change.duration;
+        }
+    }
+    markers
+}
+
+/// Ticks per beat of the meter: a quarter in x/4, an eighth in x/8
+fn beat_ticks(meter: Meter) -> u64 {
+    PPQ as u64 * 4 / meter.unit.max(1) as u64
+}
+
+/// Encode recorded events as a format 0 Standard MIDI File
+///
+/// `tempo` counts beats of the `meter`. `markers` are (tick, text) pairs
+/// written as marker meta events, ahead of any notes on the same tick.
+/// Pitched notes go on channel 1 and drum hits on the GM percussion
+/// channel 10.
+pub fn to_smf(events: &[RecordedEvent], tempo: f32, meter: Meter, name: &str, markers: &[(u64, String)]) -> Vec<u8> {
+    let beat_us = beat_duration_ms(tempo) * 1000;
+    let beat_ticks = beat_ticks(meter);
+    let to_ticks = |at: Duration| -> u64 {
+        (at.as_micros() as u64 * beat_ticks + beat_us / 2) / beat_us.max(1)
+    };
+
+    // (tick, status, data1, data2)
+    let mut messages: Vec<(u64, u8, u8, u8)> = Vec::new();
+    let mut sounding: Vec<u8> = Vec::new();
+    for event in events {
+        let tick = to_ticks(event.at);
+        match event.kind {
+            RecordedKind::NoteOn { note, velocity } => {
+                // Retriggering a held note: end the old one first
+                if let Some(pos) = sounding.iter().position(|&n| n == note) {
+                    sounding.remove(pos);
+                    messages.push((tick, 0x80, note, 64));
+                }
+                sounding.push(note);
+                messages.push((tick, 0x90, note, velocity));
+            }
+            RecordedKind::NoteOff { note } => {
+                if let Some(pos) = sounding.iter().position(|&n| n == note) {
+                    sounding.remove(pos);
+                    messages.push((tick, 0x80, note, 64));
+                }
+            }
+            RecordedKind::DrumHit { note, velocity } => {
+                messages.push((tick, 0x99, note, velocity));
+                messages.push((tick + DRUM_TICKS, 0x89, note, 64));
+            }
+            RecordedKind::AllNotesOff => {
+                for note in sounding.drain(..) {
+                    messages.push((tick, 0x80, note, 64));
+                }
+            }
+        }
+    }
+    // Drum note offs were written ahead of time; the sort is stable, so
+    // events on the same tick keep their order
+    messages.sort_by_key(|m| m.0);
+    let end_tick = messages.last().map(|m| m.0).unwrap_or(0);
+    for note in sounding.drain(..) {
+        messages.push((end_tick, 0x80, note, 64));
+    }
+
+    let mut track = Vec::new();
+
+    // Track name
+    track.push(0);
+    track.extend_from_slice(&[0xFF, 0x03]);
+    write_vlq(&mut track, name.len() as u64);
+    track.extend_from_slice(name.as_bytes());
+
+    // Tempo (microseconds per quarter note)
+    let quarter_us = beat_us * PPQ as u64 / beat_ticks;
+    track.extend_from_slice(&[0x00, 0xFF, 0x51, 0x03]);
+    track.extend_from_slice(&(quarter_us as u32).to_be_bytes()[1..]);
+
+    // Time signature, clicking on quarters (dotted quarters in compound
+    // meters), 8 32nds per quarter
+    let denominator = meter.unit.max(1).trailing_zeros() as u8;
+    let clocks = if meter.is_compound() { 36 } else { 24 };
+    track.extend_from_slice(&[0x00, 0xFF, 0x58, 0x04, meter.beats, denominator, clocks, 0x08]);
+
+    let mut last_tick = 0;
+    let mut markers = markers.iter().peekable();
+    for (tick, status, data1, data2) in messages {
+        while let Some((marker_tick, text)) = markers.next_if(|m| m.0 <= tick) {
+            write_vlq(&mut track, marker_tick - last_tick);
+            track.extend_from_slice(&[0xFF, 0x06]);
+            write_vlq(&mut track, text.len() as u64);
+            track.extend_from_slice(text.as_bytes());
+            last_tick = *marker_tick;
+        }
+        write_vlq(&mut track, tick - last_tick);
+        track.extend_from_slice(&[status, data1, data2]);
+        last_tick = tick;
+    }
+
+    // End of track
+    track.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+
+    let mut smf = Vec::with_capacity(track.len() + 22);
+    smf.extend_from_slice(b"MThd");
+    smf.extend_from_slice(&6u32.to_be_bytes());
+    smf.extend_from_slice(&0u16.to_be_bytes()); // format 0
+    smf.extend_from_slice(&1u16.to_be_bytes()); // one track
+    smf.extend_from_slice(&PPQ.to_be_bytes());
+    smf.extend_from_slice(b"MTrk");
+    smf.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    smf.extend_from_slice(&track);
+    smf
+}
+
+/// Write a MIDI variable-length quantity
+fn write_vlq(out: &mut Vec<u8>, mut value: u64) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.reverse();
+    out.extend_from_slice(&bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::drums::gm_drums;
+    use crate::music::{Chord, ChordQuality, Note, ProgressionLibrary};
+
+    #[test]
+    fn test_vlq() {
+        let encode = |value| {
+            let mut out = Vec::new();
+            write_vlq(&mut out, value);
+            out
+        };
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(0x7F), vec![0x7F]);
+        assert_eq!(encode(0x80), vec![0x81, 0x00]);
+        assert_eq!(encode(480), vec![0x83, 0x60]);
+        assert_eq!(encode(0x0FFF_FFFF), vec![0xFF, 0xFF, 0xFF, 0x7F]);
+    }
+
+    #[test]
+    fn test_render_ii_v_i() {
+        let library = ProgressionLibrary::new();
+        let prog = library.get_by_name("ii-V-I (C)").unwrap();
+        let events = render(prog, &ExportOptions::default());
+        let note_ons: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e.kind, RecordedKind::NoteOn { .. }))
+            .collect();
+
+        // Three five-note chords, Cmaj7 struck in both of its bars, ending
+        // where the loop would restart
+        assert_eq!(note_ons.len(), 4 * 5);
+        assert_eq!(note_ons.last().unwrap().at_ms(), 6_000);
+        assert_eq!(events.last().unwrap().at_ms(), 8_000);
+        assert_eq!(events.last().unwrap().kind, RecordedKind::AllNotesOff);
+    }
+
+    #[test]
+    fn test_patterns_repeat_each_bar_of_a_long_chord() {
+        for meter in [Meter::WALTZ, Meter::TWELVE_EIGHT] {
+            let bar = meter.bar_beats();
+            let mut prog = Progression::new("Long chord".to_string(), "Test".to_string(), Note::C, 120.0)
+                .with_meter(meter);
+            prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 2.0 * bar);
+            prog.add_chord(Chord::new(Note::F, ChordQuality::Dominant7), bar);
+            let options = ExportOptions {
+                rhythm: RhythmStyle::JazzBasic,
+                drums: DrumStyle::JazzRide,
+                ..ExportOptions::default()
+            };
+            let events = render(&prog, &options);
+
+            // Everything before the change to F7, two bars in
+            let change_ms = (2.0 * bar * 500.0) as u64;
+            let first: Vec<&RecordedEvent> = events.iter().filter(|e| e.at_ms() < change_ms).collect();
+            let mut comp_onsets: Vec<u64> = first
+                .iter()
+                .filter(|e| matches!(e.kind, RecordedKind::NoteOn { .. }))
+                .map(|e| e.at_ms())
+                .collect();
+            comp_onsets.dedup();
+            let drum_hits: Vec<(u64, u8)> = first
+                .iter()
+                .filter_map(|e| match e.kind {
+                    RecordedKind::DrumHit { note, .. } => Some((e.at_ms(), note)),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(comp_onsets.len(), 2 * RhythmStyle::JazzBasic.pattern(meter).len(), "{}", meter.name());
+            assert_eq!(drum_hits.len(), 2 * DrumStyle::JazzRide.pattern(meter).len(), "{}", meter.name());
+            // No hit is struck twice at once
+            let mut unique = drum_hits.clone();
+            unique.sort_unstable();
+            unique.dedup();
+            assert_eq!(unique.len(), drum_hits.len(), "{}", meter.name());
+        }
+    }
+
+    #[test]
+    fn test_render_skips_zero_length_changes() {
+        let mut prog = Progression::new("Gap".to_string(), "Test".to_string(), Note::C, 120.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Minor7), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Dominant7), 0.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
+
+        let events = render(&prog, &ExportOptions::default());
+        assert_eq!(events.last().unwrap().at_ms(), 4_000);
+        let markers = chord_markers(&prog, 1);
+        assert_eq!(markers.iter().map(|m| m.0).collect::<Vec<_>>(), vec![0, 4 * PPQ as u64]);
+
+        // Nothing left to play
+        prog.changes.retain(|change| change.duration == 0.0);
+        assert!(render(&prog, &ExportOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_render_humanized() {
+        let library = ProgressionLibrary::new();
+        let prog = library.get_by_name("ii-V-I (C)").unwrap();
+        let options = ExportOptions {
+            bass: BassStyle::Walking,
+            humanize: HumanizeAmount::Natural,
+            seed: 11,
+            ..ExportOptions::default()
+        };
+        let events = render(prog, &options);
+        assert_eq!(events, render(prog, &options));
+
+        // Every low note stays within the comping (8ms ± 10ms) and bass
+        // (5ms ± 6ms) feel of where it sits on the grid
+        let low_onsets = |events: &[RecordedEvent], note: u8| -> Vec<i64> {
+            events
+                .iter()
+                .filter(|e| matches!(e.kind, RecordedKind::NoteOn { note: n, .. } if n == note))
+                .map(|e| e.at.as_micros() as i64)
+                .collect()
+        };
+        let grid = render(prog, &ExportOptions { humanize: HumanizeAmount::Off, ..options.clone() });
+        let mut moved = 0;
+        for note in 24..60 {
+            let played = low_onsets(&events, note);
+            let expected = low_onsets(&grid, note);
+            assert_eq!(played.len(), expected.len(), "note {}", note);
+            for (at, on_grid) in played.iter().zip(&expected) {
+                let shift = at - on_grid;
+                assert!((-2_500..=18_500).contains(&shift), "note {} moved {}us", note, shift);
+                if shift != 0 {
+                    moved += 1;
+                }
+            }
+        }
+        assert!(moved > 0);
+
+        let reseeded = ExportOptions { seed: 12, ..options };
+        assert_ne!(events, render(prog, &reseeded));
+    }
+
+    #[test]
+    fn test_smf_layout() {
+        let events = vec![
+            RecordedEvent {
+                at: Duration::ZERO,
+                kind: RecordedKind::NoteOn { note: 60, velocity: 100 },
+            },
+            RecordedEvent {
+                at: Duration::from_millis(500),
+                kind: RecordedKind::AllNotesOff,
+            },
+        ];
+        let smf = to_smf(&events, 120.0, Meter::COMMON, "", &[]);
+
+        assert_eq!(&smf[0..4], b"MThd");
+        assert_eq!(&smf[12..14], &PPQ.to_be_bytes());
+        assert_eq!(&smf[14..18], b"MTrk");
+        let track_len = u32::from_be_bytes([smf[18], smf[19], smf[20], smf[21]]) as usize;
+        assert_eq!(smf.len(), 22 + track_len);
+        // Tempo of 500000us per quarter
+        assert!(smf.windows(6).any(|w| w == [0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20]));
+        // Note on at 0, note off one quarter later, end of track
+        assert!(smf.ends_with(&[
+            0x00, 0x90, 60, 100, 0x83, 0x60, 0x80, 60, 64, 0x00, 0xFF, 0x2F, 0x00
+        ]));
+        assert!(smf.windows(7).any(|w| w == [0xFF, 0x58, 0x04, 0x04, 0x02, 0x18, 0x08]));
+
+        // In 6/8 the beat is an eighth: 120 eighths a minute is a quarter
+        // every second, and the note off lands an eighth (240 ticks) in
+        let smf = to_smf(&events, 120.0, Meter::SIX_EIGHT, "", &[]);
+        assert!(smf.windows(6).any(|w| w == [0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40]));
+        assert!(smf.windows(7).any(|w| w == [0xFF, 0x58, 0x04, 0x06, 0x03, 0x24, 0x08]));
+        assert!(smf.ends_with(&[0x81, 0x70, 0x80, 60, 64, 0x00, 0xFF, 0x2F, 0x00]));
+    }
+
+    #[test]
+    fn test_drums_on_channel_ten() {
+        let library = ProgressionLibrary::new();
+        let prog = library.get_by_name("ii-V-I (C)").unwrap();
+        let options = ExportOptions {
+            drums: DrumStyle::JazzRide,
+            ..ExportOptions::default()
+        };
+        let events = render(prog, &options);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e.kind, RecordedKind::DrumHit { note, .. } if note == gm_drums::RIDE)));
+
+        let smf = to_smf(&events, prog.tempo, prog.meter, "", &[]);
+        let statuses: Vec<u8> = smf
+            .windows(3)
+            .filter(|w| w[1] == gm_drums::RIDE && matches!(w[0], 0x99 | 0x89 | 0x90 | 0x80))
+            .map(|w| w[0])
+            .collect();
+        assert!(statuses.contains(&0x99) && statuses.contains(&0x89));
+
+        // A drum hit is struck on channel 10 and released a 32nd later
+        let hit = vec![RecordedEvent {
+            at: Duration::ZERO,
+            kind: RecordedKind::DrumHit { note: gm_drums::KICK, velocity: 90 },
+        }];
+        let smf = to_smf(&hit, 120.0, Meter::COMMON, "", &[]);
+        assert!(smf.ends_with(&[
+            0x00, 0x99, gm_drums::KICK, 90, 0x3C, 0x89, gm_drums::KICK, 64, 0x00, 0xFF, 0x2F, 0x00
+        ]));
+    }
+
+    #[test]
+    fn test_chord_markers() {
+        let library = ProgressionLibrary::new();
+        let prog = library.get_by_name("ii-V-I (C)").unwrap();
+        let markers = chord_markers(prog, 2);
+        let texts: Vec<&str> = markers.iter().map(|(_, t)| t.as_str()).collect();
+        assert_eq!(texts, vec!["Dm7 ii7", "G7 V7", "Cmaj7 Imaj7", "Dm7 ii7", "G7 V7", "Cmaj7 Imaj7"]);
+        assert_eq!(markers[0].0, 0);
+        assert_eq!(markers[1].0, 4 * PPQ as u64);
+
+        let events = vec![RecordedEvent {
+            at: Duration::ZERO,
+            kind: RecordedKind::NoteOn { note: 62, velocity: 100 },
+        }];
+        let smf = to_smf(&events, 120.0, Meter::COMMON, "", &markers[..1]);
+        let marker = [&[0x00, 0xFF, 0x06, 0x07][..], b"Dm7 ii7", &[0x00, 0x90, 62, 100]].concat();
+        assert!(smf.windows(marker.len()).any(|w| w == marker));
+    }
+}
@@ -1,5 +1,8 @@
 mod audio;
+mod cli;
+mod clock;
 mod config;
+mod export;
 mod music;
 mod storage;
 mod ui;
@@ -20,9 +23,18 @@ use ratatui::{
 };
 use std::io;
 use std::time::{Duration, Instant};
+use audio::ActiveBackend;
+use music::TunePart;
+use ui::quiz::{Question, Quiz, QuizScore};
 use ui::{App, AppMode};
 
 fn main() -> Result<()> {
+    // Any arguments select a headless subcommand instead of the TUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -94,6 +106,22 @@ fn ui(f: &mut Frame, app: &App) {
             render_lego_quiz(f, app);
             return;
         }
+        AppMode::LegoBuild => {
+            render_lego_build(f, app);
+            return;
+        }
+        AppMode::Calibrate => {
+            render_calibration(f, app);
+            return;
+        }
+        AppMode::IntervalQuiz => {
+            render_interval_quiz(f, app);
+            return;
+        }
+        AppMode::ScaleQuiz => {
+            render_scale_quiz(f, app);
+            return;
+        }
         _ => {}
     }
 
@@ -136,22 +164,34 @@ fn ui(f: &mut Frame, app: &App) {
 }
 
 fn render_piano_roll_panel(f: &mut Frame, app: &App, area: Rect) {
-    // Split the panel: timeline piano roll on top, keyboard reference on bottom
+    // Split the panel: timeline piano roll on top, keyboard or fretboard reference on bottom
+    let reference_height = if app.show_fretboard { 9 } else { 5 };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(6),      // Timeline piano roll (takes most space)
-            Constraint::Length(5),   // Horizontal keyboard reference
+            Constraint::Min(6),                      // Timeline piano roll (takes most space)
+            Constraint::Length(reference_height),    // Keyboard or fretboard reference
         ])
         .split(area);
 
     // Render enhanced piano roll with timeline
     let enhanced_roll = ui::EnhancedPianoRoll::new(&app.timeline_state)
-        .with_voice_leading(app.show_voice_leading);
+        .with_voice_leading(app.show_voice_leading)
+        .with_line(app.soloist && app.mode == AppMode::Listen);
     f.render_widget(enhanced_roll, chunks[0]);
 
-    // Render horizontal keyboard reference
+    // Render fretboard or horizontal keyboard reference
     if let (Some(chord), Some(scale)) = (app.current_chord(), app.current_scale()) {
+        if app.show_fretboard {
+            let mut fretboard = ui::Fretboard::new(chord, &scale).with_tuning(app.guitar_tuning);
+            if let Some(grip) = app.guitar_grip() {
+                // Slide up the neck when the grip sits past the 12th fret
+                let start = if grip.position() + 3 > 12 { grip.position() - 1 } else { 0 };
+                fretboard = fretboard.with_frets(start, 13).with_grip(grip);
+            }
+            f.render_widget(fretboard, chunks[1]);
+            return;
+        }
         let keyboard = ui::HorizontalKeyboard::new(chord, &scale)
             .with_range(48, 2); // 2 octaves starting from C3
         f.render_widget(keyboard, chunks[1]);
@@ -190,6 +230,12 @@ fn render_header(f: &mut Frame, app: &App, area: Rect) {
             Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
         ),
         Span::raw("  |  "),
+        Span::styled("Key: ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            app.key_name().unwrap_or_default(),
+            Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  |  "),
         Span::styled("Audio: ", Style::default().fg(Color::Gray)),
         Span::styled(audio_text, Style::default().fg(audio_color)),
         Span::raw("  |  Press 'h' for help"),
@@ -217,12 +263,13 @@ fn render_main_content(f: &mut Frame, app: &App, area: Rect) {
 
 fn render_progression_list(f: &mut Frame, app: &App, area: Rect) {
     if let Some(progression) = app.current_progression() {
-        let items: Vec<ListItem> = progression
+        let mut items: Vec<ListItem> = progression
             .changes
             .iter()
             .enumerate()
             .map(|(i, change)| {
-                let is_current = i == app.current_chord_idx;
+                // Follow the playhead, which allows for output latency
+                let is_current = i == app.timeline_state.current_chord_idx;
                 let prefix = if is_current { "► " } else { "  " };
 
                 let style = if is_current {
@@ -244,6 +291,23 @@ fn render_progression_list(f: &mut Frame, app: &App, area: Rect) {
             })
             .collect();
 
+        // What the reharmonization changed
+        if let Some((substitution, reharm)) = &app.reharm {
+            items.push(ListItem::new(""));
+            if reharm.changes.is_empty() {
+                items.push(
+                    ListItem::new(format!("  No {} applies here", substitution.name()))
+                        .style(Style::default().fg(Color::DarkGray)),
+                );
+            }
+            for change in &reharm.changes {
+                items.push(
+                    ListItem::new(format!("  bar {}: {}", change.beat as u32 / progression.meter.beats.max(1) as u32 + 1, change.describe()))
+                        .style(Style::default().fg(Color::Magenta)),
+                );
+            }
+        }
+
         let title = format!("{} - {}", progression.name, progression.genre);
         let list = List::new(items)
             .block(
@@ -269,7 +333,17 @@ fn render_chord_analysis(f: &mut Frame, app: &App, area: Rect) {
             None
         };
 
-        let mut notation = ui::render_notation(chord, &scale);
+        let mut notation = ui::render_notation(chord, &scale)
+            .with_voicing(app.current_voicing, app.voice(app.current_chord_idx, chord));
+        if let Some(analysis) = app.analysis.get(app.current_chord_idx) {
+            notation = notation.with_analysis(analysis.clone());
+        }
+        if let Some(brick) = app.current_brick() {
+            notation = notation.with_brick(brick);
+        }
+        if let Some(reason) = app.scale_reason() {
+            notation = notation.with_scale_reason(reason);
+        }
         if let Some(next) = next_chord {
             notation = notation.with_next_chord(next);
         }
@@ -302,6 +376,10 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
         AppMode::Quiz => "Quiz Mode",
         AppMode::LegoListen => "LEGO Listen",
         AppMode::LegoQuiz => "LEGO Quiz",
+        AppMode::LegoBuild => "LEGO Build",
+        AppMode::Calibrate => "Calibration",
+        AppMode::IntervalQuiz => "Interval Trainer",
+        AppMode::ScaleQuiz => "Scale Trainer",
     };
 
     // Build status line - show BLE issues if any, otherwise normal status
@@ -359,6 +437,8 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
         let rhythm_text = app.rhythm_name();
         let bass_text = app.bass_name();
         let drums_text = app.drums_name();
+        let humanize_text = app.humanize_name();
+        let tuning = app.audio_manager.tuning();
 
         let tempo_line = Line::from(vec![
             Span::styled(
@@ -367,12 +447,17 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
             ),
             Span::raw(" "),
             Span::styled(
-                prog.key.name(),
+                prog.spelled_key().name(),
                 Style::default().fg(Color::Magenta),
             ),
             Span::raw(" "),
             Span::styled(
-                format!("V:{}", format!("{:?}", app.current_voicing).chars().take(4).collect::<String>()),
+                prog.meter.name(),
+                Style::default().fg(Color::Magenta),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!("V:{}", app.current_voicing.name()),
                 Style::default().fg(Color::Cyan),
             ),
             Span::raw(" "),
@@ -391,10 +476,25 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Color::LightYellow),
             ),
             Span::raw(" "),
+            Span::styled(
+                if app.soloist { "Solo" } else { "----" },
+                Style::default().fg(if app.soloist { Color::LightMagenta } else { Color::DarkGray }),
+            ),
+            Span::raw(" "),
             Span::styled(
                 format!("D:{}", drums_text),
                 Style::default().fg(Color::LightRed),
             ),
+            Span::raw(" "),
+            Span::styled(
+                format!("H:{}", humanize_text),
+                Style::default().fg(Color::LightGreen),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!("T:{}", tuning.label()),
+                Style::default().fg(if tuning.is_standard() { Color::DarkGray } else { Color::LightMagenta }),
+            ),
         ]);
 
         f.render_widget(Paragraph::new(tempo_line), chunks[0]);
@@ -423,6 +523,7 @@ fn render_help(f: &mut Frame, app: &App) {
         Line::from("  n          - Next progression"),
         Line::from("  p          - Previous progression"),
         Line::from("  +/-        - Increase/Decrease tempo"),
+        Line::from("  k/K        - Transpose up/down a semitone"),
         Line::from(""),
         Line::from(vec![
             Span::styled("Navigation:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
@@ -434,6 +535,9 @@ fn render_help(f: &mut Frame, app: &App) {
         Line::from("  3          - Quiz mode"),
         Line::from("  4          - LEGO Bricks Listen mode"),
         Line::from("  5          - LEGO Bricks Quiz mode"),
+        Line::from("  6          - LEGO Tune Builder"),
+        Line::from("  7          - Interval trainer"),
+        Line::from("  8          - Scale and mode trainer"),
         Line::from(""),
         Line::from(vec![
             Span::styled("LEGO Mode Controls:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
@@ -442,22 +546,54 @@ fn render_help(f: &mut Frame, app: &App) {
         Line::from("  k/K        - Next/Previous key (Listen)"),
         Line::from("  d          - Cycle difficulty"),
         Line::from("  1-4        - Answer quiz question (Quiz)"),
+        Line::from("  a / c      - Add brick / join to the tune (Build)"),
+        Line::from("  j/J        - Next/Previous join (Build)"),
+        Line::from("  s / f      - New section / cycle form (Build)"),
+        Line::from("  u / x      - Undo / clear the tune (Build)"),
+        Line::from("  ENTER      - Open the tune in Listen mode (Build)"),
         Line::from("  ESC        - Exit LEGO mode"),
         Line::from(""),
+        Line::from(vec![
+            Span::styled("Interval Trainer:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from("  SPACE      - Replay the interval, or next once answered"),
+        Line::from("  1-4        - Answer"),
+        Line::from("  ENTER      - Skip to the next interval"),
+        Line::from("  s          - Cycle interval set (perfect, 3rds & 6ths, compound...)"),
+        Line::from("  d          - Cycle direction: ascending/descending/harmonic/mixed"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Scale Trainer:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from("  SPACE      - Replay the scale, or next once answered"),
+        Line::from("  1-4        - Answer"),
+        Line::from("  ENTER      - Skip to the next scale"),
+        Line::from("  d          - Cycle tier: major modes/melodic minor modes/symmetric/everything"),
+        Line::from("  b          - Cycle backing: none/drone/chord"),
+        Line::from(""),
         Line::from(vec![
             Span::styled("Display Options:", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
         ]),
         Line::from("  s          - Toggle scale display"),
+        Line::from("  S          - Cycle scale for the current chord"),
         Line::from("  v          - Toggle voice leading arrows"),
         Line::from("  V          - Cycle voicing type"),
+        Line::from("  f          - Toggle guitar fretboard"),
+        Line::from("  F          - Cycle guitar tuning"),
         Line::from("  w          - Toggle swing feel"),
         Line::from("  W          - Cycle swing ratio"),
+        Line::from("  x          - Cycle reharmonization (tritone sub, ii-V, backdoor...)"),
         Line::from("  r          - Cycle rhythm style (chord comping)"),
         Line::from("  B          - Cycle bass style (walking bass)"),
         Line::from("  D          - Cycle drum style"),
-        Line::from("  [/]        - Scroll timeline left/right"),
+        Line::from("  i          - Toggle improvised solo line (Listen)"),
+        Line::from("  H          - Cycle humanize amount (timing/velocity feel)"),
+        Line::from("  t          - Cycle tuning: Equal/Just/Pythagorean/Meantone/Scala"),
+        Line::from("  A          - Cycle reference pitch: A440/442/415/432"),
+        Line::from("  [/]        - Scroll timeline a bar left/right"),
         Line::from("  m          - Cycle audio: MIDI -> Synth -> BLE MIDI"),
         Line::from("  b          - Force BLE MIDI rescan"),
+        Line::from("  L          - Calibrate latency of current audio output"),
         Line::from("  h          - Toggle this help screen"),
         Line::from(""),
         Line::from(vec![
@@ -493,6 +629,16 @@ fn render_help(f: &mut Frame, app: &App) {
         }
     }
 
+    if !app.lego_state.data_issues.is_empty() {
+        all_lines.push(Line::from(""));
+        all_lines.push(Line::from(vec![
+            Span::styled("Left Out of Your Data Files:", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+        ]));
+        for issue in &app.lego_state.data_issues {
+            all_lines.push(Line::from(format!("  {}", issue)));
+        }
+    }
+
     all_lines.push(Line::from(""));
     all_lines.push(Line::from("Press 'h' to close this help screen"));
 
@@ -507,8 +653,64 @@ fn render_help(f: &mut Frame, app: &App) {
     f.render_widget(paragraph, area);
 }
 
+// ==== Quiz Rendering ====
+
+/// Score, accuracy and streak for a quiz session
+fn score_spans(score: &QuizScore) -> Vec<Span<'static>> {
+    vec![
+        Span::styled("Score: ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("{}/{}", score.correct, score.total),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  |  "),
+        Span::styled("Accuracy: ", Style::default().fg(Color::Gray)),
+        Span::styled(format!("{:.0}%", score.accuracy()), Style::default().fg(Color::Cyan)),
+        Span::raw("  |  "),
+        Span::styled("Streak: ", Style::default().fg(Color::Gray)),
+        Span::styled(
+            format!("{} (best: {})", score.streak, score.best_streak),
+            Style::default().fg(Color::Yellow),
+        ),
+    ]
+}
+
+/// A quiz's numbered answers, marked right and wrong once revealed
+fn option_lines<T: Question>(quiz: &Quiz<T>, label: impl Fn(&T::Answer) -> String) -> Vec<Line<'static>> {
+    quiz.options
+        .iter()
+        .enumerate()
+        .map(|(i, option)| {
+            let (prefix, style) = if !quiz.revealed {
+                ("  ", Style::default().fg(Color::White))
+            } else if i == quiz.correct_idx {
+                ("✓ ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            } else if quiz.user_answer == Some(i) {
+                ("✗ ", Style::default().fg(Color::Red))
+            } else {
+                ("  ", Style::default().fg(Color::DarkGray))
+            };
+            Line::from(Span::styled(format!("{}[{}] {}", prefix, i + 1, label(option)), style))
+        })
+        .collect()
+}
+
 // ==== LEGO Mode Rendering ====
 
+/// Header warning when user bricks, joins or standards were left out
+fn data_issue_spans(app: &App) -> Vec<Span<'static>> {
+    match app.lego_state.data_issues.len() {
+        0 => Vec::new(),
+        n => vec![
+            Span::raw("  |  "),
+            Span::styled(
+                format!("⚠ {} data file issues (h for details)", n),
+                Style::default().fg(Color::Red),
+            ),
+        ],
+    }
+}
+
 fn render_lego_listen(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -521,7 +723,7 @@ fn render_lego_listen(f: &mut Frame, app: &App) {
         .split(f.size());
 
     // Header
-    let header_text = vec![
+    let mut header_text = vec![
         Span::styled(
             "🧱 LEGO BRICKS - Listen Mode",
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
@@ -537,6 +739,7 @@ fn render_lego_listen(f: &mut Frame, app: &App) {
             Style::default().fg(Color::Green),
         ),
     ];
+    header_text.extend(data_issue_spans(app));
     let header = Paragraph::new(Line::from(header_text))
         .block(Block::default().borders(Borders::ALL))
         .style(Style::default());
@@ -604,8 +807,9 @@ fn render_lego_listen(f: &mut Frame, app: &App) {
             ]),
             Line::from(""),
         ];
+        let tonic = brick.tonic(app.lego_state.current_key);
         for chord_def in &brick.template {
-            let chord_name = chord_def.to_chord(app.lego_state.current_key).name();
+            let chord_name = chord_def.to_chord(tonic).name();
             lines.push(Line::from(vec![
                 Span::styled(
                     format!("  {:?} → ", chord_def.degree),
@@ -693,29 +897,7 @@ fn render_lego_quiz(f: &mut Frame, app: &App) {
     f.render_widget(header, chunks[0]);
 
     // Score panel
-    let score = &app.lego_state.session_score;
-    let score_lines = vec![
-        Line::from(vec![
-            Span::styled("Score: ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                format!("{}/{}", score.correct, score.total),
-                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
-            ),
-            Span::raw("  |  "),
-            Span::styled("Accuracy: ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                format!("{:.0}%", score.accuracy()),
-                Style::default().fg(Color::Cyan),
-            ),
-            Span::raw("  |  "),
-            Span::styled("Streak: ", Style::default().fg(Color::Gray)),
-            Span::styled(
-                format!("{} (best: {})", score.streak, score.best_streak),
-                Style::default().fg(Color::Yellow),
-            ),
-        ]),
-    ];
-    let score_block = Paragraph::new(score_lines)
+    let score_block = Paragraph::new(Line::from(score_spans(&app.lego_state.quiz.score)))
         .block(
             Block::default()
                 .title("Session Score")
@@ -725,7 +907,7 @@ fn render_lego_quiz(f: &mut Frame, app: &App) {
     f.render_widget(score_block, chunks[1]);
 
     // Quiz content
-    if let Some(quiz) = &app.lego_state.current_quiz {
+    if let Some(quiz) = &app.lego_state.quiz.current {
         let playback_hint = if app.is_playing {
             Span::styled("▶ PLAYING - Listen carefully!", Style::default().fg(Color::Green))
         } else {
@@ -738,6 +920,10 @@ fn render_lego_quiz(f: &mut Frame, app: &App) {
                     "Which brick is playing?",
                     Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(
+                    format!("  (in {})", quiz.question.brick.tonic(quiz.question.key)),
+                    Style::default().fg(Color::Gray),
+                ),
             ]),
             Line::from(""),
             Line::from(vec![playback_hint]),
@@ -745,27 +931,7 @@ fn render_lego_quiz(f: &mut Frame, app: &App) {
         ];
 
         // Answer options
-        for (i, option) in quiz.options.iter().enumerate() {
-            let is_selected = quiz.user_answer == Some(i);
-            let is_correct_answer = i == quiz.correct_idx;
-
-            let (prefix, style) = if quiz.revealed {
-                if is_correct_answer {
-                    ("✓ ", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
-                } else if is_selected {
-                    ("✗ ", Style::default().fg(Color::Red))
-                } else {
-                    ("  ", Style::default().fg(Color::DarkGray))
-                }
-            } else {
-                ("  ", Style::default().fg(Color::White))
-            };
-
-            quiz_lines.push(Line::from(vec![
-                Span::styled(format!("{}[{}] ", prefix, i + 1), style),
-                Span::styled(option, style),
-            ]));
-        }
+        quiz_lines.extend(option_lines(quiz, |name| name.clone()));
 
         if quiz.revealed {
             quiz_lines.push(Line::from(""));
@@ -821,3 +987,573 @@ fn render_lego_quiz(f: &mut Frame, app: &App) {
         .style(Style::default());
     f.render_widget(controls_block, chunks[3]);
 }
+
+fn render_lego_build(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),   // Header
+            Constraint::Min(10),     // Main content
+            Constraint::Length(4),   // Controls hint
+        ])
+        .split(f.size());
+
+    let lego = &app.lego_state;
+    let builder = &lego.builder;
+    let form = if builder.form.is_empty() {
+        "as written".to_string()
+    } else {
+        builder.form.clone()
+    };
+
+    // Header
+    let mut header_text = vec![
+        Span::styled(
+            "🧱 LEGO BRICKS - Tune Builder",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  |  "),
+        Span::styled(
+            format!("Key: {}", builder.key.name()),
+            Style::default().fg(Color::Green),
+        ),
+        Span::raw("  |  "),
+        Span::styled(format!("Form: {}", form), Style::default().fg(Color::Yellow)),
+    ];
+    header_text.extend(data_issue_spans(app));
+    let header = Paragraph::new(Line::from(header_text))
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default());
+    f.render_widget(header, chunks[0]);
+
+    let main_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(chunks[1]);
+
+    // Left: what the next a/c would add
+    let brick_name = lego.current_brick_name.as_deref().unwrap_or("-");
+    let brick_analysis = lego
+        .get_current_brick()
+        .map(|b| b.analysis())
+        .unwrap_or_default();
+    let (join_name, join_desc) = lego
+        .selected_join()
+        .map(|j| (j.name.clone(), j.description.clone()))
+        .unwrap_or_default();
+    let picker = vec![
+        Line::from(vec![
+            Span::styled("Brick: ", Style::default().fg(Color::Gray)),
+            Span::styled(brick_name, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(Span::styled(brick_analysis, Style::default().fg(Color::White))),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Join: ", Style::default().fg(Color::Gray)),
+            Span::styled(join_name, Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(Span::styled(join_desc, Style::default().fg(Color::White))),
+    ];
+    let picker_block = Paragraph::new(picker)
+        .block(
+            Block::default()
+                .title("Parts")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(picker_block, main_chunks[0]);
+
+    // Right: the sections as written, then the built form's breakdown
+    let mut lines = Vec::new();
+    for section in &builder.sections {
+        let parts: Vec<String> = section
+            .parts
+            .iter()
+            .map(|part| match part {
+                TunePart::Brick { name, .. } => name.clone(),
+                TunePart::Join(name) => format!("~{}~", name),
+            })
+            .collect();
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{}: ", section.label),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(parts.join(" → ")),
+        ]));
+    }
+    if builder.sections.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Press 'a' to add the selected brick",
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    lines.push(Line::from(""));
+    match builder.build(&lego.brick_library, &lego.join_library) {
+        Ok(tune) => {
+            for section in &tune.standard.sections {
+                let bricks: Vec<String> = section
+                    .bricks
+                    .iter()
+                    .map(|b| format!("{} {} ({})", b.start_bar, b.brick_name, b.key.name()))
+                    .collect();
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<3} ", section.label), Style::default().fg(Color::Cyan)),
+                    Span::raw(bricks.join(", ")),
+                ]));
+            }
+            lines.push(Line::from(Span::styled(
+                format!("{} bars, {} chords", tune.standard.total_bars, tune.progression.changes.len()),
+                Style::default().fg(Color::Gray),
+            )));
+        }
+        Err(e) if !builder.sections.is_empty() => {
+            lines.push(Line::from(Span::styled(e.to_string(), Style::default().fg(Color::Red))));
+        }
+        Err(_) => {}
+    }
+    let tune_block = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Tune")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Green)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(tune_block, main_chunks[1]);
+
+    // Controls hint
+    let key = |k: &'static str| Span::styled(k, Style::default().fg(Color::Yellow));
+    let controls = vec![
+        Line::from(vec![
+            key("SPACE"), Span::raw(" Play  "),
+            key("ENTER"), Span::raw(" Open in Listen  "),
+            key("n/p"), Span::raw(" Brick  "),
+            key("a"), Span::raw(" Add brick  "),
+            key("j/J"), Span::raw(" Join  "),
+            key("c"), Span::raw(" Add join  "),
+        ]),
+        Line::from(vec![
+            key("s"), Span::raw(" New section  "),
+            key("f"), Span::raw(" Form  "),
+            key("k/K"), Span::raw(" Key  "),
+            key("u"), Span::raw(" Undo  "),
+            key("x"), Span::raw(" Clear  "),
+            key("ESC"), Span::raw(" Back"),
+        ]),
+    ];
+    let controls_block = Paragraph::new(controls)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default());
+    f.render_widget(controls_block, chunks[2]);
+}
+
+// ==== Latency Calibration ====
+
+fn midi_name(midi: u8) -> String {
+    format!("{}{}", music::Note::from_midi(midi).name(), midi as i32 / 12 - 1)
+}
+
+fn render_interval_quiz(f: &mut Frame, app: &App) {
+    let state = &app.interval_state;
+    let set = state.set();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),   // Header
+            Constraint::Length(3),   // Score
+            Constraint::Min(8),      // Quiz and stats
+            Constraint::Length(3),   // Controls
+        ])
+        .split(f.size());
+
+    let header = Paragraph::new(Line::from(vec![
+        Span::styled(
+            "🎵 INTERVAL TRAINER",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  |  "),
+        Span::styled(format!("Set: {}", set.name), Style::default().fg(Color::Yellow)),
+        Span::raw("  |  "),
+        Span::styled(state.direction.name(), Style::default().fg(Color::Magenta)),
+        Span::raw("  |  "),
+        Span::styled(
+            format!("Range: {}-{}", midi_name(state.min_note), midi_name(state.max_note)),
+            Style::default().fg(Color::Gray),
+        ),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let score_line = Paragraph::new(Line::from(score_spans(&state.quiz.score)))
+    .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Yellow)));
+    f.render_widget(score_line, chunks[1]);
+
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[2]);
+
+    let quiz_lines = if let Some(quiz) = &state.quiz.current {
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("Which interval is this? ({})", quiz.question.direction.name()),
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+        lines.extend(option_lines(quiz, |&semitones| {
+            format!(
+                "{} ({})",
+                music::interval::interval_name(semitones),
+                music::interval::interval_symbol(semitones)
+            )
+        }));
+        if quiz.revealed {
+            let q = quiz.question;
+            lines.push(Line::from(""));
+            lines.push(Line::from(if quiz.is_correct() {
+                Span::styled("Correct!", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(
+                    format!("It was: {}", music::interval::interval_name(q.semitones)),
+                    Style::default().fg(Color::Red),
+                )
+            }));
+            lines.push(Line::from(Span::styled(
+                format!("{} to {}", midi_name(q.low), midi_name(q.high())),
+                Style::default().fg(Color::Gray),
+            )));
+            lines.push(Line::from(Span::styled(
+                "Press SPACE for the next interval",
+                Style::default().fg(Color::Gray),
+            )));
+        }
+        lines
+    } else {
+        vec![Line::from(Span::styled(
+            format!("No interval in \"{}\" fits the note range; press s for another set", set.name),
+            Style::default().fg(Color::Red),
+        ))]
+    };
+    let quiz_block = Paragraph::new(quiz_lines)
+        .block(
+            Block::default()
+                .title("Quiz")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+    f.render_widget(quiz_block, body[0]);
+
+    // All-time accuracy for each interval in the set
+    let stats: Vec<ListItem> = set
+        .semitones
+        .iter()
+        .map(|&semitones| {
+            let name = music::interval::interval_name(semitones);
+            let (text, color) = match app.progress.interval_stats.get(name) {
+                Some(stats) if stats.times_seen > 0 => (
+                    format!("{:>4}  {:>3.0}% of {}", music::interval::interval_symbol(semitones), stats.accuracy(), stats.times_seen),
+                    if stats.is_mastered() { Color::Green } else { Color::White },
+                ),
+                _ => (format!("{:>4}  -", music::interval::interval_symbol(semitones)), Color::DarkGray),
+            };
+            ListItem::new(Span::styled(text, Style::default().fg(color)))
+        })
+        .collect();
+    let stats_block = List::new(stats).block(
+        Block::default()
+            .title("All-time accuracy")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    f.render_widget(stats_block, body[1]);
+
+    let controls = Paragraph::new(Line::from(vec![
+        Span::styled("1-4", Style::default().fg(Color::Yellow)),
+        Span::raw(" Answer  "),
+        Span::styled("SPACE", Style::default().fg(Color::Yellow)),
+        Span::raw(" Replay/Next  "),
+        Span::styled("s", Style::default().fg(Color::Yellow)),
+        Span::raw(" Set  "),
+        Span::styled("d", Style::default().fg(Color::Yellow)),
+        Span::raw(" Direction  "),
+        Span::styled("ESC", Style::default().fg(Color::Yellow)),
+        Span::raw(" Back  "),
+        Span::styled("h", Style::default().fg(Color::Yellow)),
+        Span::raw(" Help"),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(controls, chunks[3]);
+}
+
+fn render_scale_quiz(f: &mut Frame, app: &App) {
+    let state = &app.scale_state;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),   // Header
+            Constraint::Length(3),   // Score
+            Constraint::Min(8),      // Quiz and stats
+            Constraint::Length(3),   // Controls
+        ])
+        .split(f.size());
+
+    let header = Paragraph::new(Line::from(vec![
+        Span::styled(
+            "🎼 SCALE TRAINER",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  |  "),
+        Span::styled(format!("Tier: {}", state.tier.name()), Style::default().fg(Color::Yellow)),
+        Span::raw("  |  "),
+        Span::styled(state.backing.name(), Style::default().fg(Color::Magenta)),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let score_line = Paragraph::new(Line::from(score_spans(&state.quiz.score)))
+    .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Yellow)));
+    f.render_widget(score_line, chunks[1]);
+
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[2]);
+
+    let quiz_lines = if let Some(quiz) = &state.quiz.current {
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "Which scale is this?",
+                Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+        ];
+        lines.extend(option_lines(quiz, |scale_type| scale_type.name().to_string()));
+        if quiz.revealed {
+            let scale = quiz.question.scale();
+            let notes: Vec<String> = scale.notes().iter().map(|n| n.name()).collect();
+            lines.push(Line::from(""));
+            lines.push(Line::from(if quiz.is_correct() {
+                Span::styled("Correct!", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(format!("It was: {}", quiz.correct_answer().name()), Style::default().fg(Color::Red))
+            }));
+            lines.push(Line::from(Span::styled(
+                format!("{}: {}", scale.name(), notes.join(" ")),
+                Style::default().fg(Color::Gray),
+            )));
+            if let Some(chord) = music::ChordScaleMatcher::chord_for_scale(&scale) {
+                lines.push(Line::from(Span::styled(
+                    format!("Played over {}", chord.name()),
+                    Style::default().fg(Color::Gray),
+                )));
+            }
+            lines.push(Line::from(Span::styled(
+                "Press SPACE for the next scale",
+                Style::default().fg(Color::Gray),
+            )));
+        }
+        lines
+    } else {
+        vec![Line::from(Span::styled(
+            "The note range is too small for a scale; widen min_note/max_note",
+            Style::default().fg(Color::Red),
+        ))]
+    };
+    let quiz_block = Paragraph::new(quiz_lines)
+        .block(
+            Block::default()
+                .title("Quiz")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+    f.render_widget(quiz_block, body[0]);
+
+    // All-time accuracy for each scale in the tier
+    let stats: Vec<ListItem> = state
+        .tier
+        .scale_types()
+        .iter()
+        .map(|scale_type| {
+            let name = scale_type.name();
+            let (text, color) = match app.progress.scale_stats.get(name) {
+                Some(stats) if stats.times_seen > 0 => (
+                    format!("{:<20} {:>3.0}% of {}", name, stats.accuracy(), stats.times_seen),
+                    if stats.is_mastered() { Color::Green } else { Color::White },
+                ),
+                _ => (format!("{:<20}   -", name), Color::DarkGray),
+            };
+            ListItem::new(Span::styled(text, Style::default().fg(color)))
+        })
+        .collect();
+    let stats_block = List::new(stats).block(
+        Block::default()
+            .title("All-time accuracy")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    f.render_widget(stats_block, body[1]);
+
+    let controls = Paragraph::new(Line::from(vec![
+        Span::styled("1-4", Style::default().fg(Color::Yellow)),
+        Span::raw(" Answer  "),
+        Span::styled("SPACE", Style::default().fg(Color::Yellow)),
+        Span::raw(" Replay/Next  "),
+        Span::styled("d", Style::default().fg(Color::Yellow)),
+        Span::raw(" Tier  "),
+        Span::styled("b", Style::default().fg(Color::Yellow)),
+        Span::raw(" Backing  "),
+        Span::styled("ESC", Style::default().fg(Color::Yellow)),
+        Span::raw(" Back  "),
+        Span::styled("h", Style::default().fg(Color::Yellow)),
+        Span::raw(" Help"),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(controls, chunks[3]);
+}
+
+fn render_calibration(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),   // Header
+            Constraint::Min(10),     // Main content
+            Constraint::Length(3),   // Controls
+        ])
+        .split(f.size());
+
+    let Some(calibration) = app.calibration.as_ref() else {
+        return;
+    };
+
+    // Header
+    let header_text = vec![
+        Span::styled(
+            "⏱ LATENCY CALIBRATION",
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw("  |  "),
+        Span::styled(
+            format!("Output: {}", calibration.backend.display_name()),
+            Style::default().fg(Color::Yellow),
+        ),
+    ];
+    let header = Paragraph::new(Line::from(header_text))
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default());
+    f.render_widget(header, chunks[0]);
+
+    let main_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+
+    // Left: instructions and progress
+    let progress = format!(
+        "{}/{}",
+        calibration.clicks_played(),
+        ui::calibration::TOTAL_CLICKS
+    );
+    let mut lines = vec![
+        Line::from("Tap SPACE in time with the clicks you hear."),
+        Line::from("The first bar is a count-in and isn't measured."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Clicks: ", Style::default().fg(Color::Gray)),
+            Span::styled(progress, Style::default().fg(Color::Cyan)),
+            Span::raw("   "),
+            Span::styled("Taps: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                calibration.tap_count().to_string(),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]),
+        Line::from(""),
+    ];
+    let estimate = match calibration.estimate_ms() {
+        Some(ms) => Span::styled(
+            format!("{:.0} ms", ms),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        ),
+        None => Span::styled("tap along...", Style::default().fg(Color::DarkGray)),
+    };
+    lines.push(Line::from(vec![
+        Span::styled("Measured latency: ", Style::default().fg(Color::Gray)),
+        estimate,
+    ]));
+    if let Some(status) = &calibration.loopback_status {
+        lines.push(Line::from(vec![
+            Span::styled("MIDI loopback: ", Style::default().fg(Color::Gray)),
+            Span::styled(status.as_str(), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+    if calibration.is_finished() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            "Done. ENTER saves, SPACE runs again.",
+            Style::default().fg(Color::Gray),
+        )]));
+    }
+
+    let info_block = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Tap Along")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .wrap(Wrap { trim: true });
+    f.render_widget(info_block, main_chunks[0]);
+
+    // Right: saved offsets per backend
+    let offsets = app.audio_manager.latency_offsets();
+    let saved_lines: Vec<Line> = [ActiveBackend::Midi, ActiveBackend::Synth, ActiveBackend::BleMidi]
+        .iter()
+        .map(|&backend| {
+            Line::from(vec![
+                Span::styled(
+                    format!("  {:<10}", backend.display_name()),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    format!("{:.0} ms", offsets.get(backend)),
+                    Style::default().fg(Color::Green),
+                ),
+            ])
+        })
+        .collect();
+    let saved_block = Paragraph::new(saved_lines).block(
+        Block::default()
+            .title("Saved Offsets")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green)),
+    );
+    f.render_widget(saved_block, main_chunks[1]);
+
+    // Controls; loopback only measures MIDI output
+    let mut controls = vec![
+        Span::styled("SPACE", Style::default().fg(Color::Yellow)),
+        Span::raw(" Tap  "),
+    ];
+    if calibration.backend == ActiveBackend::Midi {
+        controls.push(Span::styled("l", Style::default().fg(Color::Yellow)));
+        controls.push(Span::raw(" MIDI Loopback  "));
+    }
+    controls.extend([
+        Span::styled("ENTER", Style::default().fg(Color::Yellow)),
+        Span::raw(" Save  "),
+        Span::styled("ESC", Style::default().fg(Color::Yellow)),
+        Span::raw(" Cancel"),
+    ]);
+    let controls = vec![Line::from(controls)];
+    let controls_block = Paragraph::new(controls)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default());
+    f.render_widget(controls_block, chunks[2]);
+}
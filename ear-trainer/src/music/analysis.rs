@@ -0,0 +1,320 @@
+//! Roman Numeral and Functional Analysis
+//!
+//! Labels each chord of a progression relative to its key: a Roman numeral
+//! (ii7, V7/vi, subV7, bVImaj7), its harmonic function and how it relates to
+//! the key. Chords made only of key tones are diatonic; dominant chords that
+//! point at another degree are secondary dominants or tritone substitutes;
+//! chords that fit the parallel major or minor are borrowed.
+
+use super::chord::{Chord, ChordQuality, Note};
+use super::progression::Progression;
+use serde::Serialize;
+
+/// Numerals for each semitone above the tonic
+const NUMERALS: [&str; 12] = ["I", "bII", "II", "bIII", "III", "IV", "#IV", "V", "bVI", "VI", "bVII", "VII"];
+
+pub(super) const MAJOR: [i8; 7] = [0, 2, 4, 5, 7, 9, 11];
+pub(super) const NATURAL_MINOR: [i8; 7] = [0, 2, 3, 5, 7, 8, 10];
+
+/// Tonic, subdominant or dominant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HarmonicFunction {
+    Tonic,
+    Subdominant,
+    Dominant,
+}
+
+impl HarmonicFunction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HarmonicFunction::Tonic => "Tonic",
+            HarmonicFunction::Subdominant => "Subdominant",
+            HarmonicFunction::Dominant => "Dominant",
+        }
+    }
+}
+
+/// How a chord relates to the key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ChordRole {
+    /// Built from the key's own scale
+    Diatonic,
+    /// V7 of another degree, e.g. A7 as V7/ii in C
+    SecondaryDominant { target: String },
+    /// Dominant a tritone away from the usual one, resolving down a half step
+    TritoneSub { target: String },
+    /// Taken from the parallel major or minor
+    Borrowed,
+    /// None of the above
+    Chromatic,
+}
+
+impl ChordRole {
+    pub fn name(&self) -> String {
+        match self {
+            ChordRole::Diatonic => "Diatonic".to_string(),
+            ChordRole::SecondaryDominant { target } => format!("Secondary dominant of {}", target),
+            ChordRole::TritoneSub { target } => format!("Tritone sub for V of {}", target),
+            ChordRole::Borrowed => "Borrowed".to_string(),
+            ChordRole::Chromatic => "Chromatic".to_string(),
+        }
+    }
+}
+
+/// Analysis of one chord
+#[derive(Debug, Clone, Serialize)]
+pub struct RomanNumeral {
+    /// Numeral with chord suffix, e.g. "ii7", "V7/vi", "bVIImaj7"
+    pub numeral: String,
+    pub function: HarmonicFunction,
+    pub role: ChordRole,
+}
+
+pub struct FunctionalAnalysis;
+
+impl FunctionalAnalysis {
+    /// Analyze every chord change of a progression in its key
+    pub fn analyze(progression: &Progression) -> Vec<RomanNumeral> {
+        let chords: Vec<Chord> = progression.changes.iter().map(|c| c.chord.clone()).collect();
+        Self::analyze_chords(&chords, progression.key)
+    }
+
+    /// Analyze a chord sequence in a key
+    ///
+    /// The key is read as minor as `is_minor_key` decides, so "Dm7b5 G7 Cm"
+    /// in C is iiø7 V7 i.
+    pub fn analyze_chords(chords: &[Chord], key: Note) -> Vec<RomanNumeral> {
+        let minor = Self::is_minor_key(chords, key);
+
+        chords
+            .iter()
+            .enumerate()
+            .map(|(i, chord)| {
+                // Wrap around so the last chord resolves into the first, as when looping
+                let next = chords.get(i + 1).or(chords.first());
+                analyze_chord(chord, next, key, minor)
+            })
+            .collect()
+    }
+
+    /// Whether a chord sequence is in the minor key on `key`
+    ///
+    /// A tonic chord at either end decides: the key is minor if one of them
+    /// is a minor chord. Tunes that start and end away from the tonic (on a
+    /// turnaround, say) are minor when their ii-V cadences into the key are
+    /// mostly minor ones (iiø7 V7) rather than major ones (ii7 V7).
+    pub fn is_minor_key(chords: &[Chord], key: Note) -> bool {
+        let ends: Vec<&Chord> = [chords.first(), chords.last()]
+            .into_iter()
+            .flatten()
+            .filter(|c| c.root == key)
+            .collect();
+        if !ends.is_empty() {
+            return ends.iter().any(|c| c.quality.is_minor());
+        }
+
+        let (mut minor, mut major) = (0, 0);
+        for pair in chords.windows(2) {
+            let is_ii_v = semitones_above(pair[0].root, key) == 2
+                && semitones_above(pair[1].root, key) == 7
+                && pair[1].quality.is_dominant();
+            match pair[0].quality {
+                ChordQuality::HalfDiminished if is_ii_v => minor += 1,
+                quality if is_ii_v && quality.is_minor() => major += 1,
+                _ => {}
+            }
+        }
+        minor > major
+    }
+}
+
+fn semitones_above(note: Note, key: Note) -> i8 {
+    (note as i8 - key as i8).rem_euclid(12)
+}
+
+/// Chord tones within the octave, leaving out added tensions
+fn core_tones(chord: &Chord, key: Note) -> Vec<i8> {
+    chord
+        .quality
+        .intervals()
+        .iter()
+        .filter(|&&i| i < 12)
+        .map(|&i| semitones_above(chord.root.transpose(i), key))
+        .collect()
+}
+
+fn fits(tones: &[i8], scale: &[i8]) -> bool {
+    tones.iter().all(|t| scale.contains(t))
+}
+
+/// Numeral for a degree, lower case for minor chords, with the chord suffix
+fn numeral(degree: i8, quality: ChordQuality) -> String {
+    let base = NUMERALS[degree as usize];
+    let base = if quality.is_minor() {
+        base.to_lowercase()
+    } else {
+        base.to_string()
+    };
+    format!("{}{}", base, numeral_suffix(quality))
+}
+
+fn numeral_suffix(quality: ChordQuality) -> String {
+    match quality {
+        ChordQuality::Minor => String::new(),
+        ChordQuality::Diminished => "°".to_string(),
+        ChordQuality::Augmented => "+".to_string(),
+        ChordQuality::HalfDiminished => "ø7".to_string(),
+        ChordQuality::Diminished7 => "°7".to_string(),
+        // Lower case already says minor
+        q if q.is_minor() => q.symbol().trim_start_matches('m').to_string(),
+        q => q.symbol().to_string(),
+    }
+}
+
+/// Plain numeral of the diatonic chord on a degree, used as a target ("ii", "V")
+fn target_numeral(degree: i8, minor: bool) -> Option<String> {
+    let (scale, minor_degrees): ([i8; 7], &[i8]) = if minor {
+        (NATURAL_MINOR, &[0, 5])
+    } else {
+        (MAJOR, &[2, 4, 9])
+    };
+    if !scale.contains(&degree) {
+        return None;
+    }
+    let base = NUMERALS[degree as usize];
+    Some(if minor_degrees.contains(&degree) {
+        base.to_lowercase()
+    } else {
+        base.to_string()
+    })
+}
+
+fn degree_function(degree: i8) -> HarmonicFunction {
+    match degree {
+        0 | 3 | 4 | 9 => HarmonicFunction::Tonic,
+        7 | 11 => HarmonicFunction::Dominant,
+        _ => HarmonicFunction::Subdominant,
+    }
+}
+
+fn analyze_chord(chord: &Chord, next: Option<&Chord>, key: Note, minor: bool) -> RomanNumeral {
+    let degree = semitones_above(chord.root, key);
+    let tones = core_tones(chord, key);
+    let (home, parallel): (&[i8], &[i8]) = if minor {
+        (&NATURAL_MINOR, &MAJOR)
+    } else {
+        (&MAJOR, &NATURAL_MINOR)
+    };
+    let numeral_text = numeral(degree, chord.quality);
+
+    if chord.quality.is_dominant() {
+        // V of the key, including the raised-leading-tone V of minor
+        if degree == 7 {
+            return RomanNumeral {
+                numeral: numeral_text,
+                function: HarmonicFunction::Dominant,
+                role: ChordRole::Diatonic,
+            };
+        }
+
+        let resolves_down = next.is_some_and(|n| semitones_above(n.root, chord.root) == 11);
+        let sub_target = (degree - 1).rem_euclid(12);
+        if resolves_down {
+            if let Some(target) = target_numeral(sub_target, minor) {
+                let symbol = format!("subV{}", numeral_suffix(chord.quality));
+                return RomanNumeral {
+                    numeral: if sub_target == 0 { symbol } else { format!("{}/{}", symbol, target) },
+                    function: HarmonicFunction::Dominant,
+                    role: ChordRole::TritoneSub { target },
+                };
+            }
+        }
+
+        let target_degree = (degree + 5).rem_euclid(12);
+        if !fits(&tones, home) || degree == 0 {
+            if let Some(target) = target_numeral(target_degree, minor) {
+                return RomanNumeral {
+                    numeral: format!("V{}/{}", numeral_suffix(chord.quality), target),
+                    function: HarmonicFunction::Dominant,
+                    role: ChordRole::SecondaryDominant { target },
+                };
+            }
+        }
+    }
+
+    let minor_dominant = minor && matches!(degree, 7 | 11) && fits(&tones, &[0, 2, 3, 5, 7, 8, 11]);
+    let role = if fits(&tones, home) || minor_dominant {
+        ChordRole::Diatonic
+    } else if fits(&tones, parallel) {
+        ChordRole::Borrowed
+    } else {
+        ChordRole::Chromatic
+    };
+
+    RomanNumeral {
+        numeral: numeral_text,
+        function: degree_function(degree),
+        role,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyze(chords: &str, key: Note) -> Vec<RomanNumeral> {
+        let chords: Vec<Chord> = chords.split_whitespace().map(|c| c.parse().unwrap()).collect();
+        FunctionalAnalysis::analyze_chords(&chords, key)
+    }
+
+    fn numerals(analysis: &[RomanNumeral]) -> Vec<&str> {
+        analysis.iter().map(|a| a.numeral.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diatonic_numerals() {
+        let analysis = analyze("Cmaj7 Am7 Dm7 G7 Em7 Fmaj7 Bm7b5", Note::C);
+        assert_eq!(numerals(&analysis), vec!["Imaj7", "vi7", "ii7", "V7", "iii7", "IVmaj7", "viiø7"]);
+        assert!(analysis.iter().all(|a| a.role == ChordRole::Diatonic));
+        let functions: Vec<_> = analysis.iter().map(|a| a.function).collect();
+        use HarmonicFunction::*;
+        assert_eq!(functions, vec![Tonic, Tonic, Subdominant, Dominant, Tonic, Subdominant, Dominant]);
+    }
+
+    #[test]
+    fn test_secondary_dominants_and_subs() {
+        let analysis = analyze("Cmaj7 A7 Dm7 D7 G7 C7 Fmaj7 Db7 Cmaj7", Note::C);
+        assert_eq!(
+            numerals(&analysis),
+            vec!["Imaj7", "V7/ii", "ii7", "V7/V", "V7", "V7/IV", "IVmaj7", "subV7", "Imaj7"]
+        );
+        assert_eq!(analysis[1].role, ChordRole::SecondaryDominant { target: "ii".to_string() });
+        assert_eq!(analysis[7].role, ChordRole::TritoneSub { target: "I".to_string() });
+        assert_eq!(analysis[7].function, HarmonicFunction::Dominant);
+
+        // Eb7 to Dm7 stands in for A7
+        let analysis = analyze("Eb7 Dm7 G7 Cmaj7", Note::C);
+        assert_eq!(analysis[0].numeral, "subV7/ii");
+    }
+
+    #[test]
+    fn test_borrowed_and_minor_keys() {
+        let analysis = analyze("Cmaj7 Fm7 Abmaj7 Bb7 Cmaj7", Note::C);
+        assert_eq!(numerals(&analysis), vec!["Imaj7", "iv7", "bVImaj7", "bVII7", "Imaj7"]);
+        assert_eq!(analysis[1].role, ChordRole::Borrowed);
+        assert_eq!(analysis[2].role, ChordRole::Borrowed);
+        assert_eq!(analysis[3].role, ChordRole::Borrowed);
+
+        let analysis = analyze("Dm7b5 G7b9 Cm7", Note::C);
+        assert_eq!(numerals(&analysis), vec!["iiø7", "V7b9", "i7"]);
+        assert!(analysis.iter().all(|a| a.role == ChordRole::Diatonic));
+
+        // Away from the tonic at both ends, the minor ii-Vs decide
+        let minor_turnaround = analyze("Dm7b5 G7 Ebmaj7 Abmaj7 Dm7b5 G7", Note::C);
+        assert_eq!(minor_turnaround[2].numeral, "bIIImaj7");
+        assert_eq!(minor_turnaround[2].role, ChordRole::Diatonic);
+        let major_turnaround = analyze("Dm7 G7 Em7 A7 Dm7 G7", Note::C);
+        assert_eq!(major_turnaround[0].numeral, "ii7");
+        assert_eq!(major_turnaround[3].role, ChordRole::SecondaryDominant { target: "ii".to_string() });
+    }
+}
@@ -4,6 +4,10 @@
 //! with different styles for jazz, latin, and funk.
 
 use super::chord::{Chord, ChordQuality, Note};
+use super::humanize::{Humanizer, Part};
+use super::meter::Meter;
+use super::progression::ChordChange;
+use super::theory::ChordScaleMatcher;
 use serde::{Deserialize, Serialize};
 
 /// A single bass note in a pattern
@@ -17,6 +21,9 @@ pub struct BassNote {
     pub velocity: f32,
     /// Whether this is a chromatic approach note
     pub chromatic: bool,
+    /// Exact semitones from the chord root, overriding `degree`; used by
+    /// walking lines for approach tones aimed at the next chord
+    pub semitones: Option<i8>,
 }
 
 impl BassNote {
@@ -26,6 +33,7 @@ impl BassNote {
             degree,
             velocity,
             chromatic: false,
+            semitones: None,
         }
     }
 
@@ -35,8 +43,121 @@ impl BassNote {
             degree,
             velocity,
             chromatic: true,
+            semitones: None,
         }
     }
+
+    /// A note at an exact distance from the chord root
+    pub fn at(beat: f32, semitones: i8, velocity: f32, chromatic: bool) -> Self {
+        Self {
+            beat,
+            degree: 0,
+            velocity,
+            chromatic,
+            semitones: Some(semitones),
+        }
+    }
+
+    /// Semitones above the chord root
+    fn offset(&self, quality: ChordQuality) -> i8 {
+        self.semitones
+            .unwrap_or_else(|| degree_to_semitones(self.degree, quality))
+    }
+}
+
+/// How the last beat of a walking line leads into the next chord
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approach {
+    /// A half step below the next root
+    ChromaticBelow,
+    /// A half step above the next root
+    ChromaticAbove,
+    /// A step of the current chord's scale into the next root
+    ScaleStep,
+    /// The dominant (fifth) of the next root
+    Dominant,
+}
+
+impl Approach {
+    pub const ALL: [Approach; 4] = [
+        Approach::ChromaticBelow,
+        Approach::Dominant,
+        Approach::ChromaticAbove,
+        Approach::ScaleStep,
+    ];
+
+    /// Semitones from the next root, given the scale of the chord it leaves
+    fn offset(&self, chord: &Chord, target: Note) -> i8 {
+        match self {
+            Approach::ChromaticBelow => -1,
+            Approach::ChromaticAbove => 1,
+            Approach::Dominant => -5,
+            Approach::ScaleStep => {
+                // A whole step below if the scale has it, else from above
+                let scale = ChordScaleMatcher::get_primary_scale(chord);
+                [-2, 2, -1, 1]
+                    .into_iter()
+                    .find(|&step| scale.contains(target.transpose(step)))
+                    .unwrap_or(-1)
+            }
+        }
+    }
+}
+
+/// Middle-of-the-bar shapes for walking lines, as scale degrees (7 = octave,
+/// negative below the root); index 3 lands on the second bar's downbeat
+const WALK_UP: [i8; 6] = [2, 4, 6, 7, 6, 4];
+const WALK_SCALE: [i8; 6] = [1, 2, 3, 4, 5, 4];
+const WALK_DOWN: [i8; 6] = [-1, -3, -5, -7, -5, -3];
+
+/// Degrees for a bar of walking without a next chord to aim at
+const WALK_BAR: [i8; 6] = [0, 2, 4, 6, 4, 2];
+
+/// One chord's walking line: root on the downbeat, a shape through the
+/// chord, and the last pulse approaching the next root
+///
+/// The line walks on the meter's pulse: quarter notes, or dotted quarters
+/// in 6/8 and 12/8. `variation` picks the shape and approach, so the same
+/// changes walk differently from chorus to chorus.
+pub fn walking_line(change: &ChordChange, next: Option<&ChordChange>, variation: usize, meter: Meter) -> Vec<BassNote> {
+    let chord = &change.chord;
+    let pulses = meter.pulses_in(change.duration.round().max(1.0));
+    let root = chord.root.to_midi(2) as i8;
+    let mut line = vec![BassNote::new(0.0, 0, 0.9)];
+
+    // The next chord's downbeat is its root in the same register
+    let target = next.map(|n| n.chord.root.to_midi(2) as i8 - root);
+    let shape = match (target.is_some_and(|t| t < 0), variation.is_multiple_of(2)) {
+        (true, true) => &WALK_DOWN,
+        (false, true) => &WALK_UP,
+        _ => &WALK_SCALE,
+    };
+
+    let middle = pulses.len().saturating_sub(if target.is_some() { 2 } else { 1 });
+    for (step, &beat) in pulses.iter().enumerate().skip(1).take(middle) {
+        let degree = shape[(step - 1) % shape.len()];
+        let semitones = match degree {
+            d if d < 0 => degree_to_semitones(d + 7, chord.quality) - 12,
+            d => degree_to_semitones(d, chord.quality),
+        };
+        let velocity = if step.is_multiple_of(2) { 0.75 } else { 0.7 };
+        line.push(BassNote::at(beat, semitones, velocity, false));
+    }
+
+    if let (Some(target), Some(next), Some(&last)) = (target, next, pulses.get(1).and(pulses.last())) {
+        let approach = Approach::ALL[variation % Approach::ALL.len()];
+        let mut semitones = target + approach.offset(chord, next.chord.root);
+        // The dominant can leap either way; take the octave nearer the line
+        if approach == Approach::Dominant {
+            let previous = line.last().map(|n| n.offset(chord.quality)).unwrap_or(0);
+            if (semitones + 12 - previous).abs() < (semitones - previous).abs() {
+                semitones += 12;
+            }
+        }
+        let chromatic = matches!(approach, Approach::ChromaticBelow | Approach::ChromaticAbove);
+        line.push(BassNote::at(last, semitones, 0.7, chromatic));
+    }
+    line
 }
 
 /// Bass pattern style
@@ -58,9 +179,73 @@ pub enum BassStyle {
 }
 
 impl BassStyle {
-    /// Get the pattern for one bar (4 beats)
+    /// Get the pattern for one bar of the meter
     /// Returns scale degrees relative to chord root
-    pub fn pattern(&self) -> Vec<BassNote> {
+    pub fn pattern(&self, meter: Meter) -> Vec<BassNote> {
+        if meter == Meter::COMMON {
+            return self.common_time();
+        }
+        let bar = meter.bar_beats();
+        let groups = meter.group_spans();
+        // An eighth note is half a beat in x/4 and a whole beat in x/8
+        let eighth = if meter.unit == 8 { 1.0 } else { 0.5 };
+
+        let mut notes = match self {
+            BassStyle::Off => vec![],
+
+            BassStyle::RootOnly => vec![BassNote::new(0.0, 0, 0.9)],
+
+            // Fifth on each later group; the waltz takes it on beat three
+            BassStyle::RootFifth => {
+                let mut notes = vec![BassNote::new(0.0, 0, 0.9)];
+                notes.extend(groups[1..].iter().map(|&(start, _)| BassNote::new(start, 4, 0.7)));
+                if groups.len() == 1 {
+                    notes.push(BassNote::new(bar - 1.0, 4, 0.7));
+                }
+                notes
+            }
+
+            // Up through the chord and back down, one note per pulse
+            BassStyle::Walking => meter
+                .pulses()
+                .into_iter()
+                .enumerate()
+                .map(|(i, beat)| BassNote::new(beat, WALK_BAR[i % WALK_BAR.len()], 0.9 * meter.accent(beat)))
+                .collect(),
+
+            // Root and fifth on alternate groups, with a root pickup into
+            // the next bar
+            BassStyle::Latin => {
+                let mut notes: Vec<BassNote> = groups
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(start, _))| {
+                        let degree = if i.is_multiple_of(2) { 0 } else { 4 };
+                        BassNote::new(start, degree, 0.85 * meter.accent(start))
+                    })
+                    .collect();
+                notes.push(BassNote::new(bar - eighth, 0, 0.5));
+                notes
+            }
+
+            // Root on each group, a ghost note and a fifth pickup
+            BassStyle::Funk => groups
+                .iter()
+                .flat_map(|&(start, len)| {
+                    [
+                        BassNote::new(start, 0, 0.95 * meter.accent(start)),
+                        BassNote::new(start + 1.5 * eighth, 0, 0.5),
+                        BassNote::new(start + len - eighth, 4, 0.7),
+                    ]
+                })
+                .collect(),
+        };
+        notes.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        notes
+    }
+
+    /// The 4/4 patterns
+    fn common_time(&self) -> Vec<BassNote> {
         match self {
             BassStyle::Off => vec![],
 
@@ -113,6 +298,12 @@ impl BassStyle {
         }
     }
 
+    /// Whether the style is played with a swing feel; the others stay
+    /// straight whatever the swing setting
+    pub fn swings(&self) -> bool {
+        matches!(self, BassStyle::Walking)
+    }
+
     /// Get display name
     pub fn name(&self) -> &'static str {
         match self {
@@ -134,16 +325,28 @@ fn degree_to_semitones(degree: i8, quality: ChordQuality) -> i8 {
         2 => match quality {
             ChordQuality::Minor7 | ChordQuality::Minor6 | ChordQuality::Minor9 |
             ChordQuality::MinorMajor7 | ChordQuality::MinorMajor9 |
-            ChordQuality::Diminished7 | ChordQuality::HalfDiminished => 3,  // Minor third
+            ChordQuality::Diminished7 | ChordQuality::HalfDiminished |
+            ChordQuality::Minor | ChordQuality::Diminished | ChordQuality::Minor11 => 3,  // Minor third
+            // Sus chords have no third; walk through the fourth instead
+            ChordQuality::Sus2 | ChordQuality::Sus4 | ChordQuality::Dominant7sus4 |
+            ChordQuality::Dominant11 | ChordQuality::Dominant13sus4 => 5,
             _ => 4,  // Major third
         },
         3 => 5,   // Fourth
-        4 => 7,   // Fifth (or diminished fifth for dim chords)
+        4 => match quality {
+            ChordQuality::Diminished | ChordQuality::Diminished7 |
+            ChordQuality::HalfDiminished | ChordQuality::Dominant7b5 => 6,  // Diminished fifth
+            ChordQuality::Augmented | ChordQuality::Dominant7sharp5 | ChordQuality::Major7sharp5 => 8,  // Augmented fifth
+            _ => 7,  // Fifth
+        },
         5 => 9,   // Sixth (major sixth for walking bass)
         6 => match quality {
             ChordQuality::Major7 | ChordQuality::MinorMajor7 |
             ChordQuality::Major9 | ChordQuality::MinorMajor9 |
-            ChordQuality::Major7sharp11 => 11,  // Major seventh
+            ChordQuality::Major7sharp11 | ChordQuality::Major7sharp5 |
+            // Plain major chords lead up to the octave, not down to a b7
+            ChordQuality::Major | ChordQuality::Add9 | ChordQuality::Major69 |
+            ChordQuality::Augmented | ChordQuality::Sus2 | ChordQuality::Sus4 => 11,  // Major seventh
             _ => 10,  // Minor seventh
         },
         7 => 12,  // Octave
@@ -156,7 +359,13 @@ fn degree_to_semitones(degree: i8, quality: ChordQuality) -> i8 {
 pub struct BassState {
     /// Current bass style
     pub style: BassStyle,
-    /// Current pattern
+    /// Time signature the pattern is written for
+    meter: Meter,
+    /// Swing ratio applied to off-beat eighths (0.5 = straight)
+    swing: f32,
+    /// Pattern on the beat grid, for the current chord when walking
+    base: Vec<BassNote>,
+    /// Current pattern, with humanized timing
     pattern: Vec<BassNote>,
     /// Index of next note to play
     next_note_idx: usize,
@@ -169,9 +378,13 @@ pub struct BassState {
 impl BassState {
     pub fn new() -> Self {
         let style = BassStyle::default();
+        let meter = Meter::default();
         Self {
-            pattern: style.pattern(),
+            base: style.pattern(meter),
+            pattern: style.pattern(meter),
             style,
+            meter,
+            swing: 0.5,
             next_note_idx: 0,
             last_beat: -1.0,
             bass_octave: 36, // C2
@@ -181,10 +394,46 @@ impl BassState {
     /// Set the bass style
     pub fn set_style(&mut self, style: BassStyle) {
         self.style = style;
-        self.pattern = style.pattern();
+        self.base = style.pattern(self.meter);
+        self.pattern = self.base.clone();
+        self.reset();
+    }
+
+    /// Switch to the patterns for another time signature
+    pub fn set_meter(&mut self, meter: Meter) {
+        if meter != self.meter {
+            self.meter = meter;
+            self.set_style(self.style);
+        }
+    }
+
+    /// Get ready for a chord change; the pattern is written out for the
+    /// change's length, and walking lines lead into `next`
+    ///
+    /// `chorus` counts passes through the progression and `position` the
+    /// change within it, so the line varies each time round.
+    pub fn prepare(&mut self, change: &ChordChange, next: Option<&ChordChange>, position: usize, chorus: u32) {
+        self.base = match self.style {
+            BassStyle::Walking => walking_line(change, next, position + chorus as usize * 3, self.meter),
+            style => {
+                // Repeat the bar for as long as the chord lasts
+                let bar = self.meter.bar_beats();
+                let pattern = style.pattern(self.meter);
+                (0..(change.duration / bar).ceil().max(1.0) as usize)
+                    .flat_map(|n| pattern.iter().map(move |note| BassNote { beat: note.beat + n as f32 * bar, ..*note }))
+                    .filter(|note| note.beat < change.duration)
+                    .collect()
+            }
+        };
+        self.pattern = self.base.clone();
         self.reset();
     }
 
+    /// Swing the off-beat eighths of swing styles by a ratio (0.5 = straight)
+    pub fn set_swing(&mut self, ratio: f32) {
+        self.swing = ratio;
+    }
+
     /// Cycle to next style
     pub fn cycle_style(&mut self) {
         self.set_style(self.style.next());
@@ -196,6 +445,16 @@ impl BassState {
         self.last_beat = -1.0;
     }
 
+    /// Re-roll the pattern's timing and velocities from the style's grid
+    pub fn humanize(&mut self, humanizer: &mut Humanizer, beat_ms: f32) {
+        self.pattern = self.base.clone();
+        for note in &mut self.pattern {
+            note.beat = humanizer.shift_beat(Part::Bass, note.beat, beat_ms);
+            note.velocity = humanizer.velocity(Part::Bass, note.velocity);
+        }
+        self.pattern.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+    }
+
     /// Check if we should play a bass note at the current beat position
     /// Returns Some((midi_note, velocity)) if we should play, None otherwise
     pub fn check_note(&mut self, current_beat: f32, beats_in_bar: f32, chord: &Chord) -> Option<(u8, f32)> {
@@ -206,11 +465,13 @@ impl BassState {
         // Wrap beat position to bar
         let beat_in_bar = current_beat % beats_in_bar;
 
-        // Don't trigger on same beat twice
-        if (beat_in_bar - self.last_beat).abs() < 0.05 {
-            return None;
+        // Start the pattern over when a new bar begins
+        if beat_in_bar < self.last_beat {
+            self.reset();
         }
 
+        let swing = if self.style.swings() { self.swing } else { 0.5 };
+
         // Find if any note should trigger
         for (i, bass_note) in self.pattern.iter().enumerate() {
             // Skip already played notes
@@ -218,26 +479,21 @@ impl BassState {
                 continue;
             }
 
-            // Check if we've passed this note's beat
-            let note_beat = bass_note.beat % beats_in_bar;
+            // Check if we've passed this note's beat, once swung
+            let note_beat = self.meter.swing(bass_note.beat, swing) % beats_in_bar;
             if beat_in_bar >= note_beat && self.last_beat < note_beat {
                 self.next_note_idx = i + 1;
                 self.last_beat = beat_in_bar;
 
                 // Calculate MIDI note
                 let root_midi = chord.root.to_midi(2); // Bass register (octave 2)
-                let semitones = degree_to_semitones(bass_note.degree, chord.quality);
+                let semitones = bass_note.offset(chord.quality);
                 let midi_note = (root_midi as i8 + semitones).clamp(24, 60) as u8;
 
                 return Some((midi_note, bass_note.velocity));
             }
         }
 
-        // Reset if we've wrapped around
-        if beat_in_bar < self.last_beat {
-            self.next_note_idx = 0;
-        }
-
         self.last_beat = beat_in_bar;
         None
     }
@@ -263,28 +519,82 @@ mod tests {
             BassStyle::Latin,
             BassStyle::Funk,
         ] {
-            let pattern = style.pattern();
-
-            if style == BassStyle::Off {
-                assert!(pattern.is_empty());
-                continue;
+            for meter in Meter::ALL {
+                let pattern = style.pattern(meter);
+
+                if style == BassStyle::Off {
+                    assert!(pattern.is_empty());
+                    continue;
+                }
+
+                // All beats should be within the bar, starting on the downbeat root
+                assert_eq!((pattern[0].beat, pattern[0].degree), (0.0, 0), "{:?} in {}", style, meter);
+                for note in &pattern {
+                    assert!(
+                        note.beat >= 0.0 && note.beat < meter.bar_beats(),
+                        "{:?} in {} note beat {} out of range",
+                        style,
+                        meter,
+                        note.beat
+                    );
+                    assert!(
+                        note.velocity > 0.0 && note.velocity <= 1.0,
+                        "{:?} note velocity {} out of range",
+                        style,
+                        note.velocity
+                    );
+                }
             }
+        }
+    }
 
-            // All beats should be within 0-4
-            for note in &pattern {
-                assert!(
-                    note.beat >= 0.0 && note.beat < 4.0,
-                    "{:?} note beat {} out of range",
-                    style,
-                    note.beat
-                );
-                assert!(
-                    note.velocity > 0.0 && note.velocity <= 1.0,
-                    "{:?} note velocity {} out of range",
-                    style,
-                    note.velocity
-                );
-            }
+    #[test]
+    fn test_meter_patterns() {
+        let beats = |style: BassStyle, meter| -> Vec<f32> {
+            style.pattern(meter).iter().map(|note| note.beat).collect()
+        };
+        assert_eq!(beats(BassStyle::RootFifth, Meter::WALTZ), vec![0.0, 2.0]);
+        assert_eq!(beats(BassStyle::RootFifth, Meter::FIVE_FOUR), vec![0.0, 3.0]);
+        assert_eq!(beats(BassStyle::Walking, Meter::SEVEN_FOUR).len(), 7);
+        assert_eq!(beats(BassStyle::Walking, Meter::SIX_EIGHT), vec![0.0, 3.0]);
+        assert_eq!(beats(BassStyle::Latin, Meter::SIX_EIGHT), vec![0.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn test_pattern_fills_the_change() {
+        let mut bass = BassState::new();
+        bass.set_meter(Meter::WALTZ);
+        bass.set_style(BassStyle::RootFifth);
+        bass.prepare(&change("Cmaj7", 6.0), None, 0, 0);
+        let beats: Vec<f32> = bass.base.iter().map(|n| n.beat).collect();
+        assert_eq!(beats, vec![0.0, 2.0, 3.0, 5.0]);
+    }
+
+    #[test]
+    fn test_degrees_follow_quality() {
+        // Third, fifth and seventh for each kind of chord
+        let cases = [
+            (ChordQuality::Major, [4, 7, 11]),
+            (ChordQuality::Minor, [3, 7, 10]),
+            (ChordQuality::Diminished, [3, 6, 10]),
+            (ChordQuality::Augmented, [4, 8, 11]),
+            (ChordQuality::Sus2, [5, 7, 11]),
+            (ChordQuality::Sus4, [5, 7, 11]),
+            (ChordQuality::Add9, [4, 7, 11]),
+            (ChordQuality::Major69, [4, 7, 11]),
+            (ChordQuality::Dominant11, [5, 7, 10]),
+            (ChordQuality::Minor11, [3, 7, 10]),
+            (ChordQuality::Dominant13, [4, 7, 10]),
+            (ChordQuality::Dominant7sharp11, [4, 7, 10]),
+            (ChordQuality::Dominant7sharp5, [4, 8, 10]),
+            (ChordQuality::Dominant7b5, [4, 6, 10]),
+            (ChordQuality::Dominant13sus4, [5, 7, 10]),
+            (ChordQuality::Major7sharp5, [4, 8, 11]),
+        ];
+        for (quality, [third, fifth, seventh]) in cases {
+            assert_eq!(degree_to_semitones(2, quality), third, "{:?}", quality);
+            assert_eq!(degree_to_semitones(4, quality), fifth, "{:?}", quality);
+            assert_eq!(degree_to_semitones(6, quality), seventh, "{:?}", quality);
         }
     }
 
@@ -301,4 +611,67 @@ mod tests {
         // Should return to start
         assert_eq!(style, start);
     }
+
+    fn change(chord: &str, duration: f32) -> ChordChange {
+        ChordChange {
+            chord: chord.parse().unwrap(),
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_walking_line_approaches_next_chord() {
+        let dm7 = change("Dm7", 4.0);
+        let g7 = change("G7", 4.0);
+        let g = Note::G.to_midi(2) as i8 - Note::D.to_midi(2) as i8;
+
+        // Every approach lands next to the G on the last beat
+        let mut approaches = Vec::new();
+        for variation in 0..Approach::ALL.len() {
+            let line = walking_line(&dm7, Some(&g7), variation, Meter::COMMON);
+            assert_eq!(line.len(), 4);
+            assert_eq!(line[0].offset(ChordQuality::Minor7), 0);
+            let last = line[3];
+            assert_eq!(last.beat, 3.0);
+            approaches.push(last.offset(ChordQuality::Minor7) - g);
+        }
+        approaches.sort_unstable();
+        // Chromatic below and above, the dominant D and a scale step (F to G)
+        assert_eq!(approaches, vec![-5, -2, -1, 1]);
+    }
+
+    #[test]
+    fn test_walking_line_fits_the_change() {
+        let g7 = change("G7", 4.0);
+
+        // Two beats: root then the approach
+        let short = walking_line(&change("Dm7", 2.0), Some(&g7), 0, Meter::COMMON);
+        assert_eq!(short.iter().map(|n| n.beat).collect::<Vec<_>>(), vec![0.0, 1.0]);
+        assert!(short[1].chromatic);
+
+        // Eight beats walk on through the second bar, the octave on its downbeat
+        let long = walking_line(&change("Cmaj7", 8.0), Some(&change("Dm7", 4.0)), 0, Meter::COMMON);
+        assert_eq!(long.len(), 8);
+        assert_eq!(long[4].offset(ChordQuality::Major7), 12);
+
+        // 12/8 walks in dotted quarters, approaching on the last one
+        let compound = walking_line(&change("Cmaj7", 12.0), Some(&g7), 0, Meter::TWELVE_EIGHT);
+        assert_eq!(compound.iter().map(|n| n.beat).collect::<Vec<_>>(), vec![0.0, 3.0, 6.0, 9.0]);
+        assert!(compound[3].chromatic);
+    }
+
+    #[test]
+    fn test_walking_varies_by_chorus() {
+        let dm7 = change("Dm7", 4.0);
+        let g7 = change("G7", 4.0);
+        let mut bass = BassState::new();
+        bass.set_style(BassStyle::Walking);
+
+        let mut lines = Vec::new();
+        for chorus in 0..2 {
+            bass.prepare(&dm7, Some(&g7), 0, chorus);
+            lines.push(bass.base.iter().map(|n| n.offset(ChordQuality::Minor7)).collect::<Vec<_>>());
+        }
+        assert_ne!(lines[0], lines[1]);
+    }
 }
@@ -4,7 +4,9 @@
 //! They can be transposed to any key and combined with "joins" to create longer progressions.
 
 use super::chord::{Chord, ChordQuality, Note};
+use super::meter::Meter;
 use super::progression::{ChordChange, Progression};
+use super::spelling::SpelledPitch;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -45,6 +47,21 @@ impl ScaleDegree {
         key.transpose(semitones)
     }
 
+    /// Spell the degree in a key, keeping its flat (Db for bII in C, Gb for bV)
+    pub fn spell(&self, key: SpelledPitch) -> SpelledPitch {
+        let steps = match self {
+            ScaleDegree::I => 0,
+            ScaleDegree::bII | ScaleDegree::II => 1,
+            ScaleDegree::bIII | ScaleDegree::III => 2,
+            ScaleDegree::IV => 3,
+            ScaleDegree::bV | ScaleDegree::V => 4,
+            ScaleDegree::bVI | ScaleDegree::VI => 5,
+            ScaleDegree::bVII | ScaleDegree::VII => 6,
+        };
+        let semitones = (self.to_note(key.note()) as i8 - key.note() as i8).rem_euclid(12);
+        key.up(steps, semitones)
+    }
+
     /// Get Roman numeral representation
     pub fn symbol(&self) -> &'static str {
         match self {
@@ -106,18 +123,9 @@ pub struct BrickChord {
 }
 
 impl BrickChord {
-    pub fn new(degree: ScaleDegree, quality: ChordQuality, duration: f32) -> Self {
-        Self {
-            degree,
-            quality,
-            duration,
-        }
-    }
-
-    /// Convert to concrete Chord in a given key
-    pub fn to_chord(&self, key: Note) -> Chord {
-        let root = self.degree.to_note(key);
-        Chord::new(root, self.quality)
+    /// Convert to concrete Chord in a key, given by its spelled tonic
+    pub fn to_chord(&self, key: SpelledPitch) -> Chord {
+        Chord::spelled(self.degree.spell(key), self.quality)
     }
 }
 
@@ -130,43 +138,42 @@ pub struct Brick {
     pub description: String,
     /// Chord changes relative to key (scale degrees)
     pub template: Vec<BrickChord>,
-    /// Total duration in beats (typically 16 for 4 bars)
+    /// Total duration in beats (typically 16 for 4 bars); summed from the
+    /// template when a data file leaves it out
+    #[serde(default)]
     pub duration_beats: f32,
     /// Category for organization
     pub category: BrickCategory,
     /// Example songs using this brick
+    #[serde(default)]
     pub examples: Vec<String>,
+    /// Time signature the durations are counted in
+    #[serde(default)]
+    pub meter: Meter,
 }
 
 impl Brick {
-    pub fn new(
-        name: impl Into<String>,
-        description: impl Into<String>,
-        template: Vec<BrickChord>,
-        category: BrickCategory,
-    ) -> Self {
-        let duration_beats = template.iter().map(|c| c.duration).sum();
-        Self {
-            name: name.into(),
-            description: description.into(),
-            template,
-            duration_beats,
-            category,
-            examples: Vec::new(),
-        }
+    /// Whether the brick resolves to a minor tonic, like the Sad Cadence
+    pub fn is_minor(&self) -> bool {
+        self.template
+            .iter()
+            .find(|bc| bc.degree == ScaleDegree::I)
+            .is_some_and(|bc| bc.quality.is_minor())
     }
 
-    pub fn with_examples(mut self, examples: Vec<&str>) -> Self {
-        self.examples = examples.into_iter().map(String::from).collect();
-        self
+    /// The tonic of a key as this brick spells it: C# for a minor brick,
+    /// Db for a major one
+    pub fn tonic(&self, key: Note) -> SpelledPitch {
+        SpelledPitch::tonic(key, self.is_minor())
     }
 
     /// Transpose this brick to a specific key, returning concrete ChordChanges
     pub fn transpose(&self, key: Note) -> Vec<ChordChange> {
+        let tonic = self.tonic(key);
         self.template
             .iter()
             .map(|bc| ChordChange {
-                chord: bc.to_chord(key),
+                chord: bc.to_chord(tonic),
                 duration: bc.duration,
             })
             .collect()
@@ -174,15 +181,18 @@ impl Brick {
 
     /// Convert this brick to a playable Progression in the given key
     pub fn to_progression(&self, key: Note, tempo: f32) -> Progression {
+        let tonic = self.tonic(key);
         let mut prog = Progression::new(
-            format!("{} ({})", self.name, key.name()),
+            format!("{} ({})", self.name, tonic),
             "LEGO Bricks".to_string(),
             key,
             tempo,
-        );
+        )
+        .with_meter(self.meter);
+        prog.minor = self.is_minor();
 
         for bc in &self.template {
-            prog.add_chord(bc.to_chord(key), bc.duration);
+            prog.add_chord(bc.to_chord(tonic), bc.duration);
         }
 
         prog
@@ -198,270 +208,38 @@ impl Brick {
     }
 }
 
+/// Bricks shipped in `data/bricks.json`
+const BUNDLED_BRICKS: &str = include_str!("../../data/bricks.json");
+
 /// Library of all Cork bricks
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BrickLibrary {
     bricks: HashMap<String, Brick>,
 }
 
 impl BrickLibrary {
+    /// The bricks bundled with the app
     pub fn new() -> Self {
-        let mut library = Self {
-            bricks: HashMap::new(),
-        };
-        library.populate_cadences();
-        library.populate_turnarounds();
-        library.populate_deceptive();
-        library.populate_modal();
+        let bricks: Vec<Brick> =
+            serde_json::from_str(BUNDLED_BRICKS).expect("bundled bricks.json is valid");
+        let mut library = Self::empty();
+        for brick in bricks {
+            library.add(brick);
+        }
         library
     }
 
-    fn add(&mut self, brick: Brick) {
-        self.bricks.insert(brick.name.clone(), brick);
-    }
-
-    fn populate_cadences(&mut self) {
-        // Launcher - Classic major ii-V-I
-        self.add(
-            Brick::new(
-                "Launcher",
-                "Classic major ii-V-I cadence - the most fundamental jazz progression",
-                vec![
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 8.0),
-                ],
-                BrickCategory::Cadence,
-            )
-            .with_examples(vec!["All The Things You Are", "Autumn Leaves", "Satin Doll"]),
-        );
-
-        // Sad Launcher - Minor ii-V-i
-        self.add(
-            Brick::new(
-                "Sad Launcher",
-                "Minor ii-V-i cadence with half-diminished ii chord",
-                vec![
-                    BrickChord::new(ScaleDegree::II, ChordQuality::HalfDiminished, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7b9, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Minor7, 8.0),
-                ],
-                BrickCategory::Cadence,
-            )
-            .with_examples(vec!["Blue Bossa", "Softly As In A Morning Sunrise"]),
-        );
-
-        // Dizzy - Tritone substitution
-        self.add(
-            Brick::new(
-                "Dizzy",
-                "ii-V-I with tritone substitution (bII7 for V7)",
-                vec![
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::bII, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 8.0),
-                ],
-                BrickCategory::Cadence,
-            )
-            .with_examples(vec!["The Girl From Ipanema", "Lady Bird"]),
-        );
-
-        // Overrun - Extended resolution
-        self.add(
-            Brick::new(
-                "Overrun",
-                "Extended tonic resolution - dwelling on the I chord",
-                vec![
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                ],
-                BrickCategory::Extension,
-            )
-            .with_examples(vec![]),
-        );
-
-        // Sad Cadence - ii-V to minor
-        self.add(
-            Brick::new(
-                "Sad Cadence",
-                "Secondary dominant resolving to minor chord",
-                vec![
-                    BrickChord::new(ScaleDegree::VI, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Minor7, 8.0),
-                ],
-                BrickCategory::Cadence,
-            )
-            .with_examples(vec!["Autumn Leaves (B section)"]),
-        );
-
-        // Pennies - Minor to major pivot
-        self.add(
-            Brick::new(
-                "Pennies",
-                "Minor ii-V resolving to major ii-V (pivot modulation)",
-                vec![
-                    BrickChord::new(ScaleDegree::III, ChordQuality::HalfDiminished, 4.0),
-                    BrickChord::new(ScaleDegree::VI, ChordQuality::Dominant7b9, 4.0),
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 4.0),
-                ],
-                BrickCategory::Cadence,
-            )
-            .with_examples(vec!["Pennies From Heaven"]),
-        );
-
-        // Raindrop - Chromatic descent
-        self.add(
-            Brick::new(
-                "Raindrop",
-                "Chromatic descending bass line with modal interchange",
-                vec![
-                    BrickChord::new(ScaleDegree::IV, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::IV, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::III, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::bIII, ChordQuality::Dominant7, 4.0),
-                ],
-                BrickCategory::Cadence,
-            )
-            .with_examples(vec!["My Funny Valentine", "In A Sentimental Mood"]),
-        );
-    }
-
-    fn populate_turnarounds(&mut self) {
-        // Long Approach - iii-VI-ii-V
-        self.add(
-            Brick::new(
-                "Long Approach",
-                "Extended approach: iii-VI-ii-V turnaround",
-                vec![
-                    BrickChord::new(ScaleDegree::III, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::VI, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 4.0),
-                ],
-                BrickCategory::Turnaround,
-            )
-            .with_examples(vec!["Have You Met Miss Jones", "I Got Rhythm"]),
-        );
-
-        // Honeysuckle - IV minor turnaround
-        self.add(
-            Brick::new(
-                "Honeysuckle",
-                "ii-V to IV with minor IV (backdoor progression)",
-                vec![
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::IV, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::IV, ChordQuality::Minor7, 4.0),
-                ],
-                BrickCategory::Turnaround,
-            )
-            .with_examples(vec!["Honeysuckle Rose", "Stompin' At The Savoy"]),
-        );
-
-        // Rhythm - Basic rhythm changes turnaround
-        self.add(
-            Brick::new(
-                "Rhythm",
-                "Classic rhythm changes turnaround: I-vi-ii-V",
-                vec![
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::VI, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 4.0),
-                ],
-                BrickCategory::Turnaround,
-            )
-            .with_examples(vec!["I Got Rhythm", "Oleo", "Anthropology"]),
-        );
-    }
-
-    fn populate_deceptive(&mut self) {
-        // Nowhere - Deceptive to iii-VI
-        self.add(
-            Brick::new(
-                "Nowhere",
-                "Deceptive resolution: ii-V resolves to iii-VI instead of I",
-                vec![
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::III, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::VI, ChordQuality::Dominant7, 4.0),
-                ],
-                BrickCategory::Deceptive,
-            )
-            .with_examples(vec!["Out Of Nowhere", "Satin Doll (bridge)"]),
-        );
-
-        // Surprise - To flat VI
-        self.add(
-            Brick::new(
-                "Surprise",
-                "Deceptive cadence to bVI major",
-                vec![
-                    BrickChord::new(ScaleDegree::II, ChordQuality::Minor7, 4.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 4.0),
-                    BrickChord::new(ScaleDegree::bVI, ChordQuality::Major7, 8.0),
-                ],
-                BrickCategory::Deceptive,
-            )
-            .with_examples(vec![]),
-        );
+    pub fn empty() -> Self {
+        Self {
+            bricks: HashMap::new(),
+        }
     }
 
-    fn populate_modal(&mut self) {
-        // Starlight - Giant Steps pattern
-        self.add(
-            Brick::new(
-                "Starlight",
-                "Major third cycle (Giant Steps pattern)",
-                vec![
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::bIII, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::bVI, ChordQuality::Major7, 4.0),
-                    BrickChord::new(ScaleDegree::bII, ChordQuality::Major7, 4.0),
-                ],
-                BrickCategory::Modal,
-            )
-            .with_examples(vec!["Giant Steps", "Central Park West"]),
-        );
-
-        // Countdown - Coltrane changes with dominants
-        self.add(
-            Brick::new(
-                "Countdown",
-                "Coltrane changes: major third cycle with V7 approaches",
-                vec![
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 2.0),
-                    BrickChord::new(ScaleDegree::bIII, ChordQuality::Dominant7, 2.0),
-                    BrickChord::new(ScaleDegree::bVI, ChordQuality::Major7, 2.0),
-                    BrickChord::new(ScaleDegree::VII, ChordQuality::Dominant7, 2.0),
-                    BrickChord::new(ScaleDegree::bV, ChordQuality::Major7, 2.0),
-                    BrickChord::new(ScaleDegree::V, ChordQuality::Dominant7, 2.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Major7, 4.0),
-                ],
-                BrickCategory::Modal,
-            )
-            .with_examples(vec!["Countdown", "Giant Steps"]),
-        );
-
-        // So What - Modal vamp
-        self.add(
-            Brick::new(
-                "So What",
-                "Modal vamp - Dorian minor 7th chord",
-                vec![
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Minor7, 8.0),
-                    BrickChord::new(ScaleDegree::I, ChordQuality::Minor7, 8.0),
-                ],
-                BrickCategory::Modal,
-            )
-            .with_examples(vec!["So What", "Impressions"]),
-        );
+    /// Add a brick, replacing any with the same name; its duration is
+    /// always the sum of its chords
+    pub fn add(&mut self, mut brick: Brick) {
+        brick.duration_beats = brick.template.iter().map(|c| c.duration).sum();
+        self.bricks.insert(brick.name.clone(), brick);
     }
 
     /// Get a brick by name
@@ -540,14 +318,6 @@ pub enum QuizDifficulty {
 }
 
 impl QuizDifficulty {
-    pub fn name(&self) -> &'static str {
-        match self {
-            QuizDifficulty::Beginner => "Beginner",
-            QuizDifficulty::Intermediate => "Intermediate",
-            QuizDifficulty::Advanced => "Advanced",
-        }
-    }
-
     pub fn next(self) -> Self {
         match self {
             QuizDifficulty::Beginner => QuizDifficulty::Intermediate,
@@ -589,6 +359,14 @@ mod tests {
         assert_eq!(f_changes[0].chord.root, Note::G);
         assert_eq!(f_changes[1].chord.root, Note::C);
         assert_eq!(f_changes[2].chord.root, Note::F);
+
+        // Sharp and minor keys spell with sharps: F# major, C# minor
+        let names = |changes: Vec<ChordChange>| -> Vec<String> { changes.iter().map(|c| c.chord.name()).collect() };
+        assert_eq!(names(launcher.transpose(Note::Gb)), ["G#m7", "C#7", "F#maj7"]);
+        let sad = library.get("Sad Launcher").unwrap();
+        assert!(sad.is_minor() && !launcher.is_minor());
+        assert_eq!(names(sad.transpose(Note::Db)), ["D#m7b5", "G#7b9", "C#m7"]);
+        assert_eq!(sad.to_progression(Note::Db, 120.0).name, "Sad Launcher (C#)");
     }
 
     #[test]
@@ -1,5 +1,8 @@
+use super::spelling::SpelledPitch;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Note {
@@ -86,6 +89,38 @@ impl fmt::Display for Note {
     }
 }
 
+impl FromStr for Note {
+    type Err = anyhow::Error;
+
+    /// Parse a note name like "C", "F#", "Bb" or "Ebb", with up to two
+    /// accidentals
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.trim().chars();
+        let letter = chars.next().ok_or_else(|| anyhow!("empty note name"))?;
+        let mut pitch: i8 = match letter.to_ascii_uppercase() {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return Err(anyhow!("invalid note name: {}", s)),
+        };
+        for (i, accidental) in chars.enumerate() {
+            if i == 2 {
+                return Err(anyhow!("too many accidentals in note name: {}", s));
+            }
+            match accidental {
+                '#' | '♯' => pitch += 1,
+                'b' | '♭' => pitch -= 1,
+                _ => return Err(anyhow!("invalid note name: {}", s)),
+            }
+        }
+        Ok(Note::from_midi(pitch.rem_euclid(12) as u8))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChordQuality {
     Major7,           // Cmaj7
@@ -106,9 +141,102 @@ pub enum ChordQuality {
     Altered,          // C7alt (b9, #9, b13)
     MinorMajor9,      // Cm(maj9)
     Major7sharp11,    // Cmaj7#11
+    Major,            // C
+    Minor,            // Cm
+    Diminished,       // Cdim
+    Augmented,        // Caug
+    Sus2,             // Csus2
+    Sus4,             // Csus4
+    Add9,             // Cadd9
+    Major69,          // C6/9
+    Dominant11,       // C11 (no 3rd)
+    Minor11,          // Cm11
+    Dominant13,       // C13
+    Dominant7sharp11, // C7#11
+    Dominant7sharp5,  // C7#5
+    Dominant7b5,      // C7b5
+    Dominant13sus4,   // C13sus4
+    Major7sharp5,     // Cmaj7#5
 }
 
 impl ChordQuality {
+    /// All qualities, in declaration order
+    pub const ALL: [ChordQuality; 34] = [
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+        ChordQuality::Dominant7,
+        ChordQuality::HalfDiminished,
+        ChordQuality::Diminished7,
+        ChordQuality::MinorMajor7,
+        ChordQuality::Major6,
+        ChordQuality::Minor6,
+        ChordQuality::Dominant7sus4,
+        ChordQuality::Major9,
+        ChordQuality::Minor9,
+        ChordQuality::Dominant9,
+        ChordQuality::Dominant7b9,
+        ChordQuality::Dominant7sharp9,
+        ChordQuality::Dominant7b13,
+        ChordQuality::Altered,
+        ChordQuality::MinorMajor9,
+        ChordQuality::Major7sharp11,
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::Sus2,
+        ChordQuality::Sus4,
+        ChordQuality::Add9,
+        ChordQuality::Major69,
+        ChordQuality::Dominant11,
+        ChordQuality::Minor11,
+        ChordQuality::Dominant13,
+        ChordQuality::Dominant7sharp11,
+        ChordQuality::Dominant7sharp5,
+        ChordQuality::Dominant7b5,
+        ChordQuality::Dominant13sus4,
+        ChordQuality::Major7sharp5,
+    ];
+
+    /// Look up a quality from a chord symbol suffix
+    ///
+    /// Accepts the symbols produced by `symbol()` plus common alternatives
+    /// ("M7", "-7", "ø", "o7", ...).
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        if let Some(quality) = Self::ALL.iter().find(|q| q.symbol() == symbol) {
+            return Some(*quality);
+        }
+        let quality = match symbol {
+            "M7" | "Maj7" | "ma7" | "Δ" | "Δ7" => ChordQuality::Major7,
+            "min7" | "mi7" | "-7" => ChordQuality::Minor7,
+            "ø" | "ø7" | "-7b5" | "min7b5" => ChordQuality::HalfDiminished,
+            "o7" | "°7" => ChordQuality::Diminished7,
+            "mM7" | "m(M7)" | "mmaj7" | "-maj7" => ChordQuality::MinorMajor7,
+            "-6" | "min6" => ChordQuality::Minor6,
+            "7sus" | "9sus" | "9sus4" => ChordQuality::Dominant7sus4,
+            "M" | "maj" => ChordQuality::Major,
+            "-" | "min" | "mi" => ChordQuality::Minor,
+            "o" | "°" => ChordQuality::Diminished,
+            "+" => ChordQuality::Augmented,
+            "sus" => ChordQuality::Sus4,
+            "2" => ChordQuality::Add9,
+            "69" | "6add9" => ChordQuality::Major69,
+            "-11" | "min11" => ChordQuality::Minor11,
+            "7+11" => ChordQuality::Dominant7sharp11,
+            "7+5" | "7+" | "+7" | "aug7" => ChordQuality::Dominant7sharp5,
+            "7-5" => ChordQuality::Dominant7b5,
+            "13sus" => ChordQuality::Dominant13sus4,
+            "M9" | "Maj9" | "Δ9" => ChordQuality::Major9,
+            "-9" | "min9" => ChordQuality::Minor9,
+            "alt" => ChordQuality::Altered,
+            "mM9" | "m(M9)" | "mmaj9" => ChordQuality::MinorMajor9,
+            "M7#11" | "Δ#11" => ChordQuality::Major7sharp11,
+            "M7#5" | "Δ#5" | "maj7+5" | "+maj7" | "Δ+" => ChordQuality::Major7sharp5,
+            _ => return None,
+        };
+        Some(quality)
+    }
+
     pub fn intervals(&self) -> Vec<i8> {
         match self {
             ChordQuality::Major7 => vec![0, 4, 7, 11],
@@ -129,6 +257,22 @@ impl ChordQuality {
             ChordQuality::Altered => vec![0, 4, 7, 10, 13, 15, 20],
             ChordQuality::MinorMajor9 => vec![0, 3, 7, 11, 14],
             ChordQuality::Major7sharp11 => vec![0, 4, 7, 11, 18],
+            ChordQuality::Major => vec![0, 4, 7],
+            ChordQuality::Minor => vec![0, 3, 7],
+            ChordQuality::Diminished => vec![0, 3, 6],
+            ChordQuality::Augmented => vec![0, 4, 8],
+            ChordQuality::Sus2 => vec![0, 2, 7],
+            ChordQuality::Sus4 => vec![0, 5, 7],
+            ChordQuality::Add9 => vec![0, 4, 7, 14],
+            ChordQuality::Major69 => vec![0, 4, 7, 9, 14],
+            ChordQuality::Dominant11 => vec![0, 7, 10, 14, 17],
+            ChordQuality::Minor11 => vec![0, 3, 7, 10, 14, 17],
+            ChordQuality::Dominant13 => vec![0, 4, 7, 10, 14, 21],
+            ChordQuality::Dominant7sharp11 => vec![0, 4, 7, 10, 18],
+            ChordQuality::Dominant7sharp5 => vec![0, 4, 8, 10],
+            ChordQuality::Dominant7b5 => vec![0, 4, 6, 10],
+            ChordQuality::Dominant13sus4 => vec![0, 5, 7, 10, 14, 21],
+            ChordQuality::Major7sharp5 => vec![0, 4, 8, 11],
         }
     }
 
@@ -152,8 +296,54 @@ impl ChordQuality {
             ChordQuality::Altered => "7alt",
             ChordQuality::MinorMajor9 => "m(maj9)",
             ChordQuality::Major7sharp11 => "maj7#11",
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::Sus2 => "sus2",
+            ChordQuality::Sus4 => "sus4",
+            ChordQuality::Add9 => "add9",
+            ChordQuality::Major69 => "6/9",
+            ChordQuality::Dominant11 => "11",
+            ChordQuality::Minor11 => "m11",
+            ChordQuality::Dominant13 => "13",
+            ChordQuality::Dominant7sharp11 => "7#11",
+            ChordQuality::Dominant7sharp5 => "7#5",
+            ChordQuality::Dominant7b5 => "7b5",
+            ChordQuality::Dominant13sus4 => "13sus4",
+            ChordQuality::Major7sharp5 => "maj7#5",
         }
     }
+
+    /// Has a minor third and no major third (m7, m7b5, dim7, ...)
+    pub fn is_minor(&self) -> bool {
+        let intervals = self.intervals();
+        intervals.contains(&3) && !intervals.contains(&4)
+    }
+
+    /// Has a minor seventh over a major or suspended third (7, 9, 7sus4, 7alt, ...)
+    pub fn is_dominant(&self) -> bool {
+        let intervals = self.intervals();
+        intervals.contains(&10) && !intervals.contains(&3)
+    }
+
+    /// Letters above the root for each interval (a third is 2, a seventh 6)
+    pub fn letter_steps(&self) -> Vec<u8> {
+        self.intervals()
+            .iter()
+            .map(|&interval| match interval {
+                0 => 0,
+                1..=2 | 13..=15 => 1,
+                3 | 4 => 2,
+                5 | 17 | 18 => 3,
+                6..=8 => 4,
+                // The diminished seventh is a bb7, not a 6
+                9 if *self == ChordQuality::Diminished7 => 6,
+                9 | 20 | 21 => 5,
+                _ => 6,
+            })
+            .collect()
+    }
 }
 
 impl fmt::Display for ChordQuality {
@@ -178,6 +368,12 @@ pub struct Chord {
     pub root: Note,
     pub quality: ChordQuality,
     pub bass: Option<Note>,
+    /// How the root is spelled, when known from a symbol or key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_spelling: Option<SpelledPitch>,
+    /// How the bass note is spelled, when known from a symbol or key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bass_spelling: Option<SpelledPitch>,
 }
 
 impl Chord {
@@ -186,6 +382,8 @@ impl Chord {
             root,
             quality,
             bass: None,
+            root_spelling: None,
+            bass_spelling: None,
         }
     }
 
@@ -194,10 +392,93 @@ impl Chord {
             root,
             quality,
             bass: Some(bass),
+            root_spelling: None,
+            bass_spelling: None,
         }
     }
 
-    pub fn notes(&self) -> Vec<Note> {
+    /// Same chord with the root spelled explicitly
+    pub fn spelled(root: SpelledPitch, quality: ChordQuality) -> Self {
+        let mut chord = Self::new(root.note(), quality);
+        chord.root_spelling = Some(root);
+        chord
+    }
+
+    /// Spell the root (and bass) as chromatic degrees of a key, given by
+    /// its spelled tonic
+    pub fn in_key(mut self, key: SpelledPitch) -> Self {
+        self.root_spelling = Some(SpelledPitch::in_key(self.root, key));
+        self.bass_spelling = self.bass.map(|bass| {
+            if self.pitch_classes().contains(&bass) {
+                self.spell(bass)
+            } else {
+                SpelledPitch::in_key(bass, key)
+            }
+        });
+        self
+    }
+
+    /// Same chord moved by a number of semitones, slash bass included
+    ///
+    /// Spellings are dropped; respell with `in_key` for the new key.
+    pub fn transpose(&self, semitones: i8) -> Self {
+        Self {
+            root: self.root.transpose(semitones),
+            quality: self.quality,
+            bass: self.bass.map(|bass| bass.transpose(semitones)),
+            root_spelling: None,
+            bass_spelling: None,
+        }
+    }
+
+    pub fn spelled_root(&self) -> SpelledPitch {
+        self.root_spelling
+            .filter(|spelling| *spelling == self.root)
+            .unwrap_or_else(|| SpelledPitch::from_note(self.root))
+    }
+
+    pub fn spelled_bass(&self) -> Option<SpelledPitch> {
+        let bass = self.bass?;
+        Some(
+            self.bass_spelling
+                .filter(|spelling| *spelling == bass)
+                .unwrap_or_else(|| self.spell(bass)),
+        )
+    }
+
+    /// Spell any pitch class relative to the chord root
+    ///
+    /// Chord tones take the letter of their chord degree (E# in C#7);
+    /// other notes are spelled as chromatic degrees above the root.
+    pub fn spell(&self, note: Note) -> SpelledPitch {
+        let root = self.spelled_root();
+        let semitones = (note as i8 - self.root as i8).rem_euclid(12);
+        let chord_step = self
+            .quality
+            .intervals()
+            .iter()
+            .zip(self.quality.letter_steps())
+            .find(|(&interval, _)| interval.rem_euclid(12) == semitones)
+            .map(|(_, steps)| steps);
+        match chord_step {
+            Some(steps) => root.up(steps, semitones),
+            None => SpelledPitch::in_key(note, root),
+        }
+    }
+
+    /// Chord tones spelled from the root
+    pub fn notes(&self) -> Vec<SpelledPitch> {
+        let root = self.spelled_root();
+        self.quality
+            .intervals()
+            .iter()
+            .zip(self.quality.letter_steps())
+            .map(|(&interval, steps)| root.up(steps, interval))
+            .collect()
+    }
+
+    /// Chord tones as pitch classes
+    pub fn pitch_classes(&self) -> Vec<Note> {
         self.quality
             .intervals()
             .iter()
@@ -267,10 +548,10 @@ impl Chord {
     }
 
     pub fn name(&self) -> String {
-        if let Some(bass) = self.bass {
-            format!("{}{}/{}", self.root, self.quality.symbol(), bass)
+        if let Some(bass) = self.spelled_bass() {
+            format!("{}{}/{}", self.spelled_root(), self.quality.symbol(), bass)
         } else {
-            format!("{}{}", self.root, self.quality.symbol())
+            format!("{}{}", self.spelled_root(), self.quality.symbol())
         }
     }
 }
@@ -281,6 +562,41 @@ impl fmt::Display for Chord {
     }
 }
 
+impl FromStr for Chord {
+    type Err = anyhow::Error;
+
+    /// Parse a chord symbol like "Dm7", "Bbmaj7#11", "G7/B" or "C6/9/E"
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        // The slash in "6/9" is part of the quality, not a bass note
+        let (symbol, bass) = match s.rsplit_once('/') {
+            Some((symbol, bass)) if bass != "9" => (symbol, Some(bass.parse::<SpelledPitch>()?)),
+            _ => (s, None),
+        };
+
+        // Root is a letter plus any accidentals
+        let root_len = symbol
+            .char_indices()
+            .skip(1)
+            .find(|&(_, c)| !matches!(c, '#' | 'b' | '♯' | '♭'))
+            .map(|(i, _)| i)
+            .unwrap_or(symbol.len());
+        let (root, suffix) = symbol.split_at(root_len);
+        let root: SpelledPitch = root.parse()?;
+
+        let quality = ChordQuality::from_symbol(suffix)
+            .ok_or_else(|| anyhow!("unknown chord quality '{}' in {}", suffix, s))?;
+
+        Ok(Self {
+            root: root.note(),
+            quality,
+            bass: bass.map(|bass| bass.note()),
+            root_spelling: Some(root),
+            bass_spelling: bass,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,10 +608,114 @@ mod tests {
         assert_eq!(notes, vec![Note::C, Note::E, Note::G, Note::B]);
     }
 
+    #[test]
+    fn test_extended_qualities() {
+        let spell = |symbol: &str| -> String {
+            let chord: Chord = symbol.parse().unwrap();
+            chord.notes().iter().map(|n| n.name()).collect::<Vec<_>>().join(" ")
+        };
+
+        assert_eq!(spell("C"), "C E G");
+        assert_eq!(spell("Cm"), "C Eb G");
+        assert_eq!(spell("Bdim"), "B D F");
+        assert_eq!(spell("Eaug"), "E G# B#");
+        assert_eq!(spell("Dsus2"), "D E A");
+        assert_eq!(spell("Gsus4"), "G C D");
+        assert_eq!(spell("Fadd9"), "F A C G");
+        assert_eq!(spell("C6/9"), "C E G A D");
+        assert_eq!(spell("F11"), "F C Eb G Bb");
+        assert_eq!(spell("Am11"), "A C E G B D");
+        assert_eq!(spell("G13"), "G B D F A E");
+        assert_eq!(spell("D7#11"), "D F# A C G#");
+        assert_eq!(spell("A7#5"), "A C# E# G");
+        assert_eq!(spell("Bb7b5"), "Bb D Fb Ab");
+        assert_eq!(spell("Eb13sus4"), "Eb Ab Bb Db F C");
+
+        // Triads have no seventh to guide with, sus chords no third
+        let c: Chord = "C".parse().unwrap();
+        assert_eq!(c.guide_tones(), vec![Note::E]);
+        let g11: Chord = "G11".parse().unwrap();
+        assert_eq!(g11.guide_tones(), vec![Note::F]);
+    }
+
     #[test]
     fn test_guide_tones() {
         let cmaj7 = Chord::new(Note::C, ChordQuality::Major7);
         let guide_tones = cmaj7.guide_tones();
         assert_eq!(guide_tones, vec![Note::E, Note::B]);
     }
+
+    #[test]
+    fn test_parse_chords() {
+        let chord: Chord = "Dm7".parse().unwrap();
+        assert_eq!(chord.root, Note::D);
+        assert_eq!(chord.quality, ChordQuality::Minor7);
+
+        let chord: Chord = "Bbmaj7#11".parse().unwrap();
+        assert_eq!(chord.root, Note::Bb);
+        assert_eq!(chord.quality, ChordQuality::Major7sharp11);
+
+        let chord: Chord = "C6/9/E".parse().unwrap();
+        assert_eq!(chord.quality, ChordQuality::Major69);
+        assert_eq!(chord.bass, Some(Note::E));
+        let chord: Chord = "Abm".parse().unwrap();
+        assert_eq!(chord.quality, ChordQuality::Minor);
+        let chord: Chord = "G".parse().unwrap();
+        assert_eq!(chord.quality, ChordQuality::Major);
+        assert_eq!(chord.name(), "G");
+
+        let chord: Chord = "F#ø/C".parse().unwrap();
+        assert_eq!(chord.root, Note::Gb);
+        assert_eq!(chord.quality, ChordQuality::HalfDiminished);
+        assert_eq!(chord.bass, Some(Note::C));
+
+        // Every symbol we print parses back to the same quality
+        for quality in ChordQuality::ALL {
+            let chord: Chord = format!("Eb{}", quality.symbol()).parse().unwrap();
+            assert_eq!(chord.quality, quality);
+        }
+
+        assert!("H7".parse::<Chord>().is_err());
+        assert_eq!("Ebb".parse::<Note>().unwrap(), Note::D);
+        assert_eq!("B##".parse::<Note>().unwrap(), Note::Db);
+        assert!("C###".parse::<Note>().is_err());
+        assert!(format!("C{}", "#".repeat(200)).parse::<Note>().is_err());
+        assert!("Cfoo".parse::<Chord>().is_err());
+    }
+
+    #[test]
+    fn test_chord_spelling() {
+        let names = |chord: &Chord| -> Vec<String> {
+            chord.notes().iter().map(|n| n.name()).collect()
+        };
+
+        let chord: Chord = "C#7".parse().unwrap();
+        assert_eq!(names(&chord), vec!["C#", "E#", "G#", "B"]);
+        assert_eq!(chord.name(), "C#7");
+        assert_eq!(chord.spell(Note::C).name(), "B#");
+
+        let chord: Chord = "C#maj7".parse().unwrap();
+        assert_eq!(names(&chord)[3], "B#");
+
+        let chord: Chord = "F#ø/C".parse().unwrap();
+        assert_eq!(chord.name(), "F#m7b5/C");
+
+        let chord = Chord::new(Note::C, ChordQuality::Diminished7);
+        assert_eq!(names(&chord), vec!["C", "Eb", "Gb", "Bbb"]);
+
+        // Roots follow the key: the V of B major is F#, the bII of C is Db
+        let chord = Chord::new(Note::Gb, ChordQuality::Dominant7).in_key(SpelledPitch::tonic(Note::B, false));
+        assert_eq!(chord.name(), "F#7");
+        assert_eq!(names(&chord), vec!["F#", "A#", "C#", "E"]);
+        let chord = Chord::new(Note::Db, ChordQuality::Dominant7).in_key(SpelledPitch::tonic(Note::C, false));
+        assert_eq!(chord.name(), "Db7");
+
+        // The V of C# minor is G#, not Ab
+        let chord = Chord::new(Note::Ab, ChordQuality::Dominant7).in_key(SpelledPitch::tonic(Note::Db, true));
+        assert_eq!(chord.name(), "G#7");
+
+        // Slash basses that are chord tones take the chord's spelling
+        let chord = Chord::with_bass(Note::E, ChordQuality::Dominant7, Note::Ab).in_key(SpelledPitch::tonic(Note::A, false));
+        assert_eq!(chord.name(), "E7/G#");
+    }
 }
@@ -0,0 +1,314 @@
+//! Brick, Join and Standard Data Files
+//!
+//! The libraries ship as JSON files in `data/`, bundled into the binary.
+//! Users can add their own entries, or replace bundled ones by name, with
+//! JSON files in `<config dir>/ear-trainer/{bricks,joins,standards}/`;
+//! each file holds a single entry or a list of them, in the same shape as
+//! the bundled files. Everything is checked once loaded: bricks need a
+//! template, joins a shift within a tritone, and standards may only use
+//! known bricks and joins, with every brick inside its section's bars.
+//! Entries that fail are left out and reported.
+
+use super::bricks::{Brick, BrickLibrary};
+use super::joins::{Join, JoinLibrary};
+use super::standards::{Standard, StandardsLibrary};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a problem was found and what it is
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataIssue {
+    /// File the entry came from, or "bundled"
+    pub source: String,
+    pub message: String,
+}
+
+impl fmt::Display for DataIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+/// A data file holds one entry or a list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+/// The loaded libraries and anything that was left out
+#[derive(Debug)]
+pub struct MusicData {
+    pub bricks: BrickLibrary,
+    pub joins: JoinLibrary,
+    pub standards: StandardsLibrary,
+    pub issues: Vec<DataIssue>,
+}
+
+/// The user's data directory, `<config dir>/ear-trainer`
+pub fn user_data_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("ear-trainer"))
+}
+
+impl MusicData {
+    /// Bundled data with the user's additions
+    pub fn load() -> Self {
+        Self::load_from(user_data_dir().as_deref())
+    }
+
+    /// Bundled data, with additions from `dir` if given
+    pub fn load_from(dir: Option<&Path>) -> Self {
+        let mut issues = Vec::new();
+        let mut bricks = BrickLibrary::new();
+        let mut joins = JoinLibrary::new();
+        let mut standards: Vec<(String, Standard)> = StandardsLibrary::new()
+            .all()
+            .iter()
+            .map(|s| ("bundled".to_string(), s.clone()))
+            .collect();
+
+        if let Some(dir) = dir {
+            for (source, brick) in read_entries::<Brick>(&dir.join("bricks"), &mut issues) {
+                match check_brick(&brick) {
+                    Ok(()) => bricks.add(brick),
+                    Err(message) => issues.push(DataIssue { source, message }),
+                }
+            }
+            for (source, join) in read_entries::<Join>(&dir.join("joins"), &mut issues) {
+                match check_join(&join) {
+                    Ok(()) => joins.add(join),
+                    Err(message) => issues.push(DataIssue { source, message }),
+                }
+            }
+            for (source, standard) in read_entries::<Standard>(&dir.join("standards"), &mut issues) {
+                standards.retain(|(_, s)| s.name != standard.name);
+                standards.push((source, standard));
+            }
+        }
+
+        // Standards are checked last, against the final bricks and joins
+        let mut library = StandardsLibrary::empty();
+        for (source, standard) in standards {
+            let problems = check_standard(&standard, &bricks, &joins);
+            if problems.is_empty() {
+                library.add(standard);
+            } else {
+                issues.extend(problems.into_iter().map(|message| DataIssue {
+                    source: source.clone(),
+                    message,
+                }));
+            }
+        }
+
+        Self {
+            bricks,
+            joins,
+            standards: library,
+            issues,
+        }
+    }
+}
+
+/// Every entry in the JSON files of a directory, by file name, with where
+/// each came from
+fn read_entries<T: DeserializeOwned>(dir: &Path, issues: &mut Vec<DataIssue>) -> Vec<(String, T)> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut entries = Vec::new();
+    for path in paths {
+        let source = path.display().to_string();
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<OneOrMany<T>>(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(OneOrMany::Many(items)) => entries.extend(items.into_iter().map(|item| (source.clone(), item))),
+            Ok(OneOrMany::One(item)) => entries.push((source, item)),
+            Err(e) => issues.push(DataIssue {
+                source,
+                message: format!("not a valid entry or list: {}", e),
+            }),
+        }
+    }
+    entries
+}
+
+fn check_brick(brick: &Brick) -> Result<(), String> {
+    if brick.template.is_empty() {
+        return Err(format!("brick \"{}\" has no chords", brick.name));
+    }
+    if brick.template.iter().any(|c| c.duration <= 0.0) {
+        return Err(format!("brick \"{}\" has a chord without a duration", brick.name));
+    }
+    // Left out, the duration reads as 0 and is summed from the chords
+    let sum: f32 = brick.template.iter().map(|c| c.duration).sum();
+    if brick.duration_beats != 0.0 && (brick.duration_beats - sum).abs() > 1e-3 {
+        return Err(format!(
+            "brick \"{}\" lasts {} beats but its chords add up to {}",
+            brick.name, brick.duration_beats, sum
+        ));
+    }
+    Ok(())
+}
+
+fn check_join(join: &Join) -> Result<(), String> {
+    if !(-6..=6).contains(&join.key_shift) {
+        return Err(format!(
+            "join \"{}\" shifts the key by {} semitones; use -6 to 6",
+            join.name, join.key_shift
+        ));
+    }
+    Ok(())
+}
+
+/// Everything wrong with a standard, one message each
+pub fn check_standard(standard: &Standard, bricks: &BrickLibrary, joins: &JoinLibrary) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut section_start = 1u32;
+    for section in &standard.sections {
+        let section_end = section_start + section.bars as u32 - 1;
+        for brick_ref in &section.bricks {
+            if bricks.get(&brick_ref.brick_name).is_none() {
+                problems.push(format!(
+                    "\"{}\" section {}: unknown brick \"{}\"",
+                    standard.name, section.label, brick_ref.brick_name
+                ));
+            }
+            let start = brick_ref.start_bar as u32;
+            let end = start + (brick_ref.duration_bars as u32).max(1) - 1;
+            if brick_ref.duration_bars == 0 || start < section_start || end > section_end {
+                problems.push(format!(
+                    "\"{}\" section {}: {} at bars {}-{} doesn't fit the section's bars {}-{}",
+                    standard.name, section.label, brick_ref.brick_name, start, end, section_start, section_end
+                ));
+            }
+        }
+        section_start = section_end + 1;
+    }
+    for used in &standard.joins_used {
+        // Entries read "Name (from -> to)"
+        let name = used.split(" (").next().unwrap_or(used).trim();
+        if joins.get(name).is_none() {
+            problems.push(format!("\"{}\": unknown join \"{}\"", standard.name, name));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ear-trainer-data-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for kind in ["bricks", "joins", "standards"] {
+            fs::create_dir_all(dir.join(kind)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_bundled_data_is_valid() {
+        let data = MusicData::load_from(None);
+        assert!(data.issues.is_empty(), "{:?}", data.issues);
+        assert_eq!(data.bricks.all().len(), 15);
+        assert_eq!(data.joins.all().len(), 8);
+        assert_eq!(data.standards.all().len(), StandardsLibrary::new().all().len());
+        assert_eq!(data.bricks.get("Launcher").unwrap().duration_beats, 16.0);
+        for brick in data.bricks.all() {
+            assert_eq!(check_brick(brick), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_user_files_add_and_override() {
+        let dir = scratch_dir("override");
+        // A single brick, without its summed duration
+        fs::write(
+            dir.join("bricks/minor.json"),
+            r#"{"name": "Minor Plagal", "description": "iv-I", "category": "Cadence",
+                "template": [{"degree": "IV", "quality": "Minor7", "duration": 8.0},
+                             {"degree": "I", "quality": "Major7", "duration": 8.0}]}"#,
+        )
+        .unwrap();
+        // A list replacing a bundled join
+        fs::write(
+            dir.join("joins/mine.json"),
+            r#"[{"name": "Back Door", "key_shift": -2, "description": "Mine", "examples": ["Mine"]}]"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("standards/tune.json"),
+            r#"{"name": "Plagal Blues", "composer": "Me", "year": null, "total_bars": 8,
+                "form": "A", "key": "F", "tempo_range": [100, 140], "style": "Ballad",
+                "sections": [{"label": "A", "bars": 8, "key": "F", "bricks": [
+                    {"brick_name": "Minor Plagal", "start_bar": 1, "duration_bars": 4, "key": "F", "notes": null},
+                    {"brick_name": "Launcher", "start_bar": 5, "duration_bars": 4, "key": "F", "notes": null}]}],
+                "joins_used": ["Back Door (F -> Eb)"]}"#,
+        )
+        .unwrap();
+
+        let data = MusicData::load_from(Some(&dir));
+        assert!(data.issues.is_empty(), "{:?}", data.issues);
+        assert_eq!(data.bricks.get("Minor Plagal").unwrap().duration_beats, 16.0);
+        assert_eq!(data.joins.get("Back Door").unwrap().description, "Mine");
+        assert_eq!(data.joins.all().len(), 8);
+        assert!(data.standards.get("Plagal Blues").is_some());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_invalid_entries_are_reported() {
+        let dir = scratch_dir("invalid");
+        fs::write(dir.join("bricks/broken.json"), "{ not json").unwrap();
+        fs::write(
+            dir.join("bricks/lengths.json"),
+            r#"[{"name": "Long", "description": "", "category": "Cadence", "duration_beats": 12.0,
+                 "template": [{"degree": "I", "quality": "Major7", "duration": 8.0}]},
+                {"name": "Backwards", "description": "", "category": "Cadence", "duration_beats": -8.0,
+                 "template": [{"degree": "I", "quality": "Major7", "duration": 8.0}]}]"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("joins/far.json"),
+            r#"{"name": "Far", "key_shift": 9, "description": "Too far"}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("standards/bad.json"),
+            r#"{"name": "Bad Tune", "composer": "Me", "year": null, "total_bars": 8,
+                "form": "A", "key": "C", "tempo_range": [100, 140], "style": "Swing",
+                "sections": [{"label": "A", "bars": 8, "key": "C", "bricks": [
+                    {"brick_name": "Lancher", "start_bar": 1, "duration_bars": 4, "key": "C", "notes": null},
+                    {"brick_name": "Launcher", "start_bar": 7, "duration_bars": 4, "key": "C", "notes": null}]}],
+                "joins_used": ["Far (C -> A)"]}"#,
+        )
+        .unwrap();
+
+        let data = MusicData::load_from(Some(&dir));
+        let messages: Vec<String> = data.issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(messages.len(), 7, "{:#?}", messages);
+        assert!(messages[0].contains("broken.json: not a valid entry"));
+        assert!(messages[1].ends_with("brick \"Long\" lasts 12 beats but its chords add up to 8"));
+        assert!(messages[2].ends_with("brick \"Backwards\" lasts -8 beats but its chords add up to 8"));
+        assert!(messages[3].contains("join \"Far\" shifts the key by 9"));
+        assert!(messages[4].ends_with("\"Bad Tune\" section A: unknown brick \"Lancher\""));
+        assert!(messages[5].ends_with("Launcher at bars 7-10 doesn't fit the section's bars 1-8"));
+        assert!(messages[6].ends_with("\"Bad Tune\": unknown join \"Far\""));
+        assert!(data.bricks.get("Long").is_none() && data.bricks.get("Backwards").is_none());
+        assert!(data.standards.get("Bad Tune").is_none());
+        assert!(data.joins.get("Far").is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -3,6 +3,9 @@
 //! Provides drum patterns for different styles (jazz, latin, funk, etc.)
 //! Uses General MIDI drum note numbers.
 
+use super::humanize::{Humanizer, Part};
+use super::meter::Meter;
+use super::rhythm::BELL;
 use serde::{Deserialize, Serialize};
 
 /// General MIDI Drum Note Numbers
@@ -62,8 +65,132 @@ pub enum DrumStyle {
 }
 
 impl DrumStyle {
-    /// Get the pattern for one bar (4 beats)
-    pub fn pattern(&self) -> Vec<DrumHit> {
+    /// Get the pattern for one bar of the meter
+    pub fn pattern(&self, meter: Meter) -> Vec<DrumHit> {
+        use gm_drums::*;
+
+        if meter == Meter::COMMON {
+            return self.common_time();
+        }
+        let bar = meter.bar_beats();
+        let groups = meter.group_spans();
+        let compound = meter.is_compound();
+        let accent = |beat: f32| meter.accent(beat);
+        // Evenly spaced notes across the bar
+        let every = |step: f32| (0..(bar / step) as usize).map(move |i| i as f32 * step);
+        // An eighth note is half a beat in x/4 and a whole beat in x/8
+        let eighth = if meter.unit == 8 { 1.0 } else { 0.5 };
+        // Kick on alternate groups in compound meters, answered by the snare
+        let kick_snare = |i: usize, start: f32, velocity: f32| {
+            let note = if i.is_multiple_of(2) { KICK } else { SNARE };
+            DrumHit::new(start, note, velocity)
+        };
+
+        match self {
+            DrumStyle::Off => vec![],
+
+            // Hi-hat on every beat, accenting the groups
+            DrumStyle::Metronome => every(1.0)
+                .map(|beat| DrumHit::new(beat, CLOSED_HH, 0.8 * accent(beat)))
+                .collect(),
+
+            // Ride on the pulse with the skip note before each group's end,
+            // hi-hat closing each group
+            DrumStyle::JazzRide => {
+                let mut hits: Vec<DrumHit> = meter
+                    .pulses()
+                    .into_iter()
+                    .map(|beat| DrumHit::new(beat, RIDE, 0.85 * accent(beat)))
+                    .collect();
+                for &(start, len) in &groups {
+                    let (skip, pedal) = if compound {
+                        (start + 2.0, start + len)
+                    } else {
+                        (start + len - 0.5, start + len - 1.0)
+                    };
+                    hits.push(DrumHit::new(skip, RIDE, 0.5));
+                    if pedal < bar && (compound || len >= 2.0) {
+                        hits.push(DrumHit::new(pedal, PEDAL_HH, 0.6));
+                    }
+                }
+                hits
+            }
+
+            // Side stick on the pulse, light hi-hat at the end of each group
+            DrumStyle::JazzBrushes => {
+                let mut hits: Vec<DrumHit> = meter
+                    .pulses()
+                    .into_iter()
+                    .map(|beat| DrumHit::new(beat, SIDE_STICK, 0.55 * accent(beat)))
+                    .collect();
+                hits.extend(
+                    groups
+                        .iter()
+                        .filter(|&&(_, len)| len >= 2.0)
+                        .map(|&(start, len)| DrumHit::new(start + len - 1.0, CLOSED_HH, 0.3)),
+                );
+                hits
+            }
+
+            // Simple meters: cross-stick on each group and the "and" before
+            // the next, over hi-hat eighths. Compound meters: the 6/8 bell
+            // on cowbell over a shaker, kick on the dotted quarters
+            DrumStyle::BossaNova => {
+                let mut hits: Vec<DrumHit> = groups
+                    .iter()
+                    .map(|&(start, _)| DrumHit::new(start, KICK, 0.7 * accent(start)))
+                    .collect();
+                if compound {
+                    hits.extend(BELL.iter().map(|&b| b as f32).filter(|&b| b < bar).map(|b| DrumHit::new(b, COWBELL, 0.6 * accent(b))));
+                    hits.extend(every(eighth).map(|b| DrumHit::new(b, SHAKER, 0.35)));
+                } else {
+                    for &(start, len) in &groups {
+                        hits.push(DrumHit::new(start, SIDE_STICK, 0.6 * accent(start)));
+                        hits.push(DrumHit::new(start + len - eighth, SIDE_STICK, 0.5));
+                    }
+                    hits.extend(every(eighth).map(|b| DrumHit::new(b, CLOSED_HH, 0.45 * accent(b))));
+                }
+                hits
+            }
+
+            // Kick and backbeat snare around sixteenth-note hi-hats
+            DrumStyle::Funk => {
+                let mut hits = Vec::new();
+                for (i, &(start, len)) in groups.iter().enumerate() {
+                    if compound {
+                        hits.push(kick_snare(i, start, 0.9));
+                    } else {
+                        hits.push(DrumHit::new(start, KICK, 0.95 * accent(start)));
+                        hits.push(DrumHit::new(start + len - 1.0, SNARE, 0.9));
+                    }
+                    // Ghost note before the next group
+                    hits.push(DrumHit::new(start + len - eighth, SNARE, 0.3));
+                }
+                hits.push(DrumHit::new(1.5 * eighth, KICK, 0.7));
+                hits.extend(every(eighth / 2.0).map(|b| DrumHit::new(b, CLOSED_HH, 0.6 * accent(b))));
+                hits
+            }
+
+            // Kick on each group with snare on the beats between (or on
+            // alternate dotted quarters), hi-hat eighths
+            DrumStyle::Rock => {
+                let mut hits = Vec::new();
+                for (i, &(start, len)) in groups.iter().enumerate() {
+                    if compound {
+                        hits.push(kick_snare(i, start, 0.9));
+                    } else {
+                        hits.push(DrumHit::new(start, KICK, 0.9));
+                        hits.extend((1..len as usize).map(|b| DrumHit::new(start + b as f32, SNARE, 0.85)));
+                    }
+                }
+                hits.extend(every(eighth).map(|b| DrumHit::new(b, CLOSED_HH, 0.7 * accent(b))));
+                hits
+            }
+        }
+    }
+
+    /// The 4/4 patterns
+    fn common_time(&self) -> Vec<DrumHit> {
         use gm_drums::*;
 
         match self {
@@ -78,14 +205,14 @@ impl DrumStyle {
             ],
 
             DrumStyle::JazzRide => vec![
-                // Classic jazz ride pattern with swing feel
-                // "spang-a-lang" pattern
+                // Classic jazz ride "spang-a-lang" pattern, written
+                // straight; the skip notes swing with the swing ratio
                 DrumHit::new(0.0, RIDE, 0.85),        // Beat 1
                 DrumHit::new(1.0, RIDE, 0.6),         // Beat 2
-                DrumHit::new(1.67, RIDE, 0.5),        // Swung skip note
+                DrumHit::new(1.5, RIDE, 0.5),         // Skip note on the "and"
                 DrumHit::new(2.0, RIDE, 0.75),        // Beat 3
                 DrumHit::new(3.0, RIDE, 0.6),         // Beat 4
-                DrumHit::new(3.67, RIDE, 0.5),        // Swung skip note
+                DrumHit::new(3.5, RIDE, 0.5),         // Skip note on the "and"
                 // Hi-hat on 2 and 4
                 DrumHit::new(1.0, PEDAL_HH, 0.6),
                 DrumHit::new(3.0, PEDAL_HH, 0.6),
@@ -188,6 +315,12 @@ impl DrumStyle {
         }
     }
 
+    /// Whether the style is played with a swing feel; the others stay
+    /// straight whatever the swing setting
+    pub fn swings(&self) -> bool {
+        matches!(self, DrumStyle::JazzRide | DrumStyle::JazzBrushes)
+    }
+
     /// Get display name
     pub fn name(&self) -> &'static str {
         match self {
@@ -207,6 +340,10 @@ impl DrumStyle {
 pub struct DrumState {
     /// Current drum style
     pub style: DrumStyle,
+    /// Time signature the pattern is written for
+    meter: Meter,
+    /// Swing ratio applied to off-beat eighths (0.5 = straight)
+    swing: f32,
     /// Current pattern
     pattern: Vec<DrumHit>,
     /// Indices of notes that have been played this bar
@@ -218,11 +355,14 @@ pub struct DrumState {
 impl DrumState {
     pub fn new() -> Self {
         let style = DrumStyle::default();
-        let pattern = style.pattern();
+        let meter = Meter::default();
+        let pattern = style.pattern(meter);
         let played_notes = vec![false; pattern.len()];
         Self {
             pattern,
             style,
+            meter,
+            swing: 0.5,
             played_notes,
             last_beat: -1.0,
         }
@@ -231,10 +371,23 @@ impl DrumState {
     /// Set the drum style
     pub fn set_style(&mut self, style: DrumStyle) {
         self.style = style;
-        self.pattern = style.pattern();
+        self.pattern = style.pattern(self.meter);
         self.reset();
     }
 
+    /// Switch to the patterns for another time signature
+    pub fn set_meter(&mut self, meter: Meter) {
+        if meter != self.meter {
+            self.meter = meter;
+            self.set_style(self.style);
+        }
+    }
+
+    /// Swing the off-beat eighths of swing styles by a ratio (0.5 = straight)
+    pub fn set_swing(&mut self, ratio: f32) {
+        self.swing = ratio;
+    }
+
     /// Cycle to next style
     pub fn cycle_style(&mut self) {
         self.set_style(self.style.next());
@@ -246,6 +399,16 @@ impl DrumState {
         self.last_beat = -1.0;
     }
 
+    /// Re-roll the pattern's timing and velocities from the style's grid
+    pub fn humanize(&mut self, humanizer: &mut Humanizer, beat_ms: f32) {
+        self.pattern = self.style.pattern(self.meter);
+        for hit in &mut self.pattern {
+            hit.beat = humanizer.shift_beat(Part::Drums, hit.beat, beat_ms);
+            hit.velocity = humanizer.velocity(Part::Drums, hit.velocity);
+        }
+        self.played_notes = vec![false; self.pattern.len()];
+    }
+
     /// Check if we should play drum hits at the current beat position
     /// Returns a Vec of (midi_note, velocity) for all hits that should play
     pub fn check_hits(&mut self, current_beat: f32, beats_in_bar: f32) -> Vec<(u8, f32)> {
@@ -261,6 +424,8 @@ impl DrumState {
             self.reset();
         }
 
+        let swing = if self.style.swings() { self.swing } else { 0.5 };
+
         let mut hits = vec![];
 
         // Find all hits that should trigger
@@ -270,8 +435,8 @@ impl DrumState {
                 continue;
             }
 
-            // Check if we've passed this hit's beat
-            let hit_beat = drum_hit.beat % beats_in_bar;
+            // Check if we've passed this hit's beat, once swung
+            let hit_beat = self.meter.swing(drum_hit.beat, swing) % beats_in_bar;
             if beat_in_bar >= hit_beat && self.last_beat < hit_beat {
                 if let Some(played) = self.played_notes.get_mut(i) {
                     *played = true;
@@ -306,38 +471,63 @@ mod tests {
             DrumStyle::Funk,
             DrumStyle::Rock,
         ] {
-            let pattern = style.pattern();
+            for meter in Meter::ALL {
+                let pattern = style.pattern(meter);
 
-            if style == DrumStyle::Off {
-                assert!(pattern.is_empty());
-                continue;
-            }
+                if style == DrumStyle::Off {
+                    assert!(pattern.is_empty());
+                    continue;
+                }
 
-            // All beats should be within 0-4
-            for hit in &pattern {
-                assert!(
-                    hit.beat >= 0.0 && hit.beat < 4.0,
-                    "{:?} hit beat {} out of range",
-                    style,
-                    hit.beat
-                );
-                assert!(
-                    hit.velocity > 0.0 && hit.velocity <= 1.0,
-                    "{:?} hit velocity {} out of range",
-                    style,
-                    hit.velocity
-                );
-                // Verify valid GM drum notes (35-81)
-                assert!(
-                    hit.note >= 35 && hit.note <= 81,
-                    "{:?} hit note {} out of GM drum range",
-                    style,
-                    hit.note
-                );
+                // All beats should be within the bar
+                for hit in &pattern {
+                    assert!(
+                        hit.beat >= 0.0 && hit.beat < meter.bar_beats(),
+                        "{:?} in {} hit beat {} out of range",
+                        style,
+                        meter,
+                        hit.beat
+                    );
+                    assert!(
+                        hit.velocity > 0.0 && hit.velocity <= 1.0,
+                        "{:?} hit velocity {} out of range",
+                        style,
+                        hit.velocity
+                    );
+                    // Verify valid GM drum notes (35-81)
+                    assert!(
+                        hit.note >= 35 && hit.note <= 81,
+                        "{:?} hit note {} out of GM drum range",
+                        style,
+                        hit.note
+                    );
+                }
+                assert!(pattern.iter().any(|hit| hit.beat == 0.0), "{:?} in {} has no downbeat", style, meter);
             }
         }
     }
 
+    #[test]
+    fn test_meter_patterns() {
+        use gm_drums::*;
+        let beats = |style: DrumStyle, meter, note| -> Vec<f32> {
+            let mut beats: Vec<f32> = style.pattern(meter).iter().filter(|hit| hit.note == note).map(|hit| hit.beat).collect();
+            beats.sort_by(f32::total_cmp);
+            beats
+        };
+        // Jazz waltz: ride "ding, ding-a, ding" with the hi-hat on three
+        assert_eq!(beats(DrumStyle::JazzRide, Meter::WALTZ, RIDE), vec![0.0, 1.0, 2.0, 2.5]);
+        assert_eq!(beats(DrumStyle::JazzRide, Meter::WALTZ, PEDAL_HH), vec![2.0]);
+        // 5/4 closes each group of 3+2 with the hi-hat
+        assert_eq!(beats(DrumStyle::JazzRide, Meter::FIVE_FOUR, PEDAL_HH), vec![2.0, 4.0]);
+        // 6/8 bell on cowbell, kick on the dotted quarters
+        assert_eq!(beats(DrumStyle::BossaNova, Meter::SIX_EIGHT, COWBELL), vec![0.0, 2.0, 4.0, 5.0]);
+        assert_eq!(beats(DrumStyle::BossaNova, Meter::SIX_EIGHT, KICK), vec![0.0, 3.0]);
+        // 12/8 rock backbeat on the second and fourth dotted quarters
+        assert_eq!(beats(DrumStyle::Rock, Meter::TWELVE_EIGHT, SNARE), vec![3.0, 9.0]);
+        assert_eq!(beats(DrumStyle::Rock, Meter::TWELVE_EIGHT, CLOSED_HH).len(), 12);
+    }
+
     #[test]
     fn test_style_cycle() {
         let mut style = DrumStyle::Off;
@@ -0,0 +1,261 @@
+//! Guitar Voicings
+//!
+//! Playable guitar grips for a chord: drop 2 and drop 3 voicings on their
+//! usual string sets, and three-note shells, for any six-string tuning.
+
+use super::chord::Chord;
+use super::voicings::{fifth, four_part, seventh, third};
+use serde::{Deserialize, Serialize};
+
+/// Widest stretch of a grip, in frets between the lowest and highest fretted note
+const MAX_STRETCH: u8 = 3;
+
+/// Six-string tunings, open strings listed low to high
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum GuitarTuning {
+    #[default]
+    Standard,
+    DropD,
+    OpenG,
+    Dadgad,
+    AllFourths,
+}
+
+impl GuitarTuning {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GuitarTuning::Standard => "Standard",
+            GuitarTuning::DropD => "Drop D",
+            GuitarTuning::OpenG => "Open G",
+            GuitarTuning::Dadgad => "DADGAD",
+            GuitarTuning::AllFourths => "All Fourths",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GuitarTuning::Standard => GuitarTuning::DropD,
+            GuitarTuning::DropD => GuitarTuning::OpenG,
+            GuitarTuning::OpenG => GuitarTuning::Dadgad,
+            GuitarTuning::Dadgad => GuitarTuning::AllFourths,
+            GuitarTuning::AllFourths => GuitarTuning::Standard,
+        }
+    }
+
+    /// MIDI notes of the open strings, lowest string first
+    pub fn strings(&self) -> [u8; 6] {
+        match self {
+            GuitarTuning::Standard => [40, 45, 50, 55, 59, 64],   // E A D G B E
+            GuitarTuning::DropD => [38, 45, 50, 55, 59, 64],      // D A D G B E
+            GuitarTuning::OpenG => [38, 43, 50, 55, 59, 62],      // D G D G B D
+            GuitarTuning::Dadgad => [38, 45, 50, 55, 57, 62],     // D A D G A D
+            GuitarTuning::AllFourths => [40, 45, 50, 55, 60, 65], // E A D G C F
+        }
+    }
+}
+
+/// Kinds of grip the guitar voicer produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum GripStyle {
+    /// Drop 2 on four adjacent strings
+    #[default]
+    Drop2,
+    /// Drop 3 with the bass on the 6th or 5th string, skipping the next one
+    Drop3,
+    /// Root, 3rd and 7th on three strings
+    Shell,
+}
+
+impl GripStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GripStyle::Drop2 => "Drop 2",
+            GripStyle::Drop3 => "Drop 3",
+            GripStyle::Shell => "Shell",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GripStyle::Drop2 => GripStyle::Drop3,
+            GripStyle::Drop3 => GripStyle::Shell,
+            GripStyle::Shell => GripStyle::Drop2,
+        }
+    }
+
+    /// The grip closest to a keyboard voicing
+    pub fn for_voicing(voicing: super::VoicingType) -> Self {
+        match voicing {
+            super::VoicingType::Shell => GripStyle::Shell,
+            super::VoicingType::Drop3 => GripStyle::Drop3,
+            _ => GripStyle::Drop2,
+        }
+    }
+
+    /// String sets (indices, lowest string first) this style is played on
+    fn string_sets(&self) -> &'static [&'static [usize]] {
+        match self {
+            GripStyle::Drop2 => &[&[0, 1, 2, 3], &[1, 2, 3, 4], &[2, 3, 4, 5]],
+            GripStyle::Drop3 => &[&[0, 2, 3, 4], &[1, 3, 4, 5]],
+            GripStyle::Shell => &[&[0, 1, 2], &[0, 2, 3], &[1, 2, 3], &[1, 3, 4]],
+        }
+    }
+
+    /// Orders of chord tones (intervals from the root, lowest voice first)
+    fn voice_orders(&self, chord: &Chord) -> Vec<Vec<i8>> {
+        let intervals = chord.quality.intervals();
+        match self {
+            GripStyle::Drop2 | GripStyle::Drop3 => {
+                let close = four_part(&intervals);
+                (0..close.len())
+                    .map(|r| {
+                        let mut v = close.clone();
+                        v.rotate_left(r);
+                        match self {
+                            GripStyle::Drop2 => vec![v[2], v[0], v[1], v[3]],
+                            _ => vec![v[1], v[0], v[2], v[3]],
+                        }
+                    })
+                    .collect()
+            }
+            GripStyle::Shell => {
+                // Triads use the 5th in place of the 7th, as keyboard shells do
+                let third = third(&intervals).unwrap_or(4);
+                let top = seventh(&intervals).or_else(|| fifth(&intervals)).unwrap_or(7);
+                vec![vec![0, third, top], vec![0, top, third]]
+            }
+        }
+    }
+}
+
+/// A chord shape on the guitar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GuitarGrip {
+    /// Fret per string, lowest string first; None for muted strings
+    pub frets: Vec<Option<u8>>,
+    /// Sounding MIDI notes, low to high
+    pub notes: Vec<u8>,
+}
+
+impl GuitarGrip {
+    /// Fret diagram in chord-chart order, e.g. "x 3 5 4 5 x"
+    pub fn diagram(&self) -> String {
+        self.frets
+            .iter()
+            .map(|f| f.map(|f| f.to_string()).unwrap_or_else(|| "x".to_string()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Lowest fretted position, ignoring open strings
+    pub fn position(&self) -> u8 {
+        self.frets.iter().flatten().copied().filter(|&f| f > 0).min().unwrap_or(0)
+    }
+}
+
+/// Finds playable grips for chords in a guitar tuning
+#[derive(Debug, Clone, Copy)]
+pub struct GuitarVoicer {
+    pub tuning: GuitarTuning,
+    /// Highest fret a grip may use
+    pub max_fret: u8,
+}
+
+impl GuitarVoicer {
+    pub fn new(tuning: GuitarTuning) -> Self {
+        Self { tuning, max_fret: 15 }
+    }
+
+    /// Every grip of the chord in the given style, lowest position first
+    pub fn grips(&self, chord: &Chord, style: GripStyle) -> Vec<GuitarGrip> {
+        let mut grips: Vec<GuitarGrip> = Vec::new();
+        for order in style.voice_orders(chord) {
+            for set in style.string_sets() {
+                for position in 0..=self.max_fret {
+                    if let Some(grip) = self.place(chord, set, &order, position) {
+                        if !grips.contains(&grip) {
+                            grips.push(grip);
+                        }
+                    }
+                }
+            }
+        }
+        grips.sort_by_key(|g| (g.position(), g.notes.first().copied()));
+        grips
+    }
+
+    /// Fit the voices onto the strings within a four-fret window starting at `position`
+    fn place(&self, chord: &Chord, set: &[usize], order: &[i8], position: u8) -> Option<GuitarGrip> {
+        let open = self.tuning.strings();
+        let top = (position + MAX_STRETCH).min(self.max_fret);
+        let mut frets = vec![None; open.len()];
+        let mut notes: Vec<u8> = Vec::with_capacity(order.len());
+
+        for (&string, &interval) in set.iter().zip(order) {
+            let pitch_class = chord.root.transpose(interval) as u8;
+            let fret = (position..=top).find(|f| (open[string] + f) % 12 == pitch_class)?;
+            let midi = open[string] + fret;
+            if notes.last().is_some_and(|&prev| midi <= prev) {
+                return None;
+            }
+            frets[string] = Some(fret);
+            notes.push(midi);
+        }
+
+        // Anchor each shape at its own lowest fret so it's only found once
+        let fretted = frets.iter().flatten().copied().filter(|&f| f > 0).min();
+        if position > 0 && fretted != Some(position) {
+            return None;
+        }
+        Some(GuitarGrip { frets, notes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::chord::{ChordQuality, Note};
+
+    fn has_grip(grips: &[GuitarGrip], diagram: &str) -> bool {
+        grips.iter().any(|g| g.diagram() == diagram)
+    }
+
+    #[test]
+    fn test_standard_grips() {
+        let voicer = GuitarVoicer::new(GuitarTuning::Standard);
+        let cmaj7 = Chord::new(Note::C, ChordQuality::Major7);
+
+        // The drop 2 on the middle strings and drop 3 off the 6th string
+        assert!(has_grip(&voicer.grips(&cmaj7, GripStyle::Drop2), "x 3 5 4 5 x"));
+        assert!(has_grip(&voicer.grips(&cmaj7, GripStyle::Drop3), "8 x 9 9 8 x"));
+
+        // Both G7 shells off the 6th string
+        let g7 = Chord::new(Note::G, ChordQuality::Dominant7);
+        let shells = voicer.grips(&g7, GripStyle::Shell);
+        assert!(has_grip(&shells, "3 x 3 4 x x"));
+        assert!(has_grip(&shells, "3 2 3 x x x"));
+    }
+
+    #[test]
+    fn test_grips_are_playable() {
+        for tuning in [GuitarTuning::Standard, GuitarTuning::DropD, GuitarTuning::Dadgad] {
+            let voicer = GuitarVoicer::new(tuning);
+            for quality in ChordQuality::ALL {
+                let chord = Chord::new(Note::Bb, quality);
+                for style in [GripStyle::Drop2, GripStyle::Drop3, GripStyle::Shell] {
+                    let grips = voicer.grips(&chord, style);
+                    // Shells are a seventh-chord device; some triads don't fit in four frets
+                    if style != GripStyle::Shell || seventh(&quality.intervals()).is_some() {
+                        assert!(!grips.is_empty(), "{:?} {:?} {}", tuning, quality, style.name());
+                    }
+                    for grip in grips {
+                        let fretted: Vec<u8> = grip.frets.iter().flatten().copied().filter(|&f| f > 0).collect();
+                        let stretch = fretted.iter().max().unwrap_or(&0) - fretted.iter().min().unwrap_or(&0);
+                        assert!(stretch <= MAX_STRETCH, "{}", grip.diagram());
+                        assert!(grip.notes.iter().all(|&n| chord.pitch_classes().contains(&Note::from_midi(n))));
+                    }
+                }
+            }
+        }
+    }
+}
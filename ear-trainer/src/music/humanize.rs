@@ -0,0 +1,230 @@
+//! Humanized Timing and Dynamics
+//!
+//! Nudges the comping, bass and drum patterns away from the grid so the band
+//! sounds less mechanical. Each part has its own feel: random timing jitter,
+//! random velocity variation and a constant offset (positive plays behind the
+//! beat, negative pushes ahead of it). Patterns are re-rolled for every chord
+//! from a seeded RNG, so the same seed always plays the same performance.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Seed used unless another one is chosen
+pub const DEFAULT_SEED: u64 = 0x5EED;
+
+/// Backing part being humanized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Comping,
+    Bass,
+    Drums,
+}
+
+/// How one part deviates from the grid
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub struct PartFeel {
+    /// Maximum random timing deviation in milliseconds
+    pub timing_ms: f32,
+    /// Maximum random velocity change as a fraction (0.1 = ±10%)
+    pub velocity: f32,
+    /// Constant offset in milliseconds (positive = laid back, negative = pushed)
+    pub offset_ms: f32,
+}
+
+impl PartFeel {
+    pub fn new(timing_ms: f32, velocity: f32, offset_ms: f32) -> Self {
+        Self { timing_ms, velocity, offset_ms }
+    }
+}
+
+/// Preset humanization amounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum HumanizeAmount {
+    /// Exactly on the grid
+    #[default]
+    Off,
+    /// Barely noticeable, like a studio rhythm section
+    Tight,
+    /// Comping and bass sit a little behind a slightly pushing drummer
+    Natural,
+    /// Loose jam session feel
+    Loose,
+}
+
+impl HumanizeAmount {
+    /// Preset feel for a part
+    pub fn feel(&self, part: Part) -> PartFeel {
+        match (self, part) {
+            (HumanizeAmount::Off, _) => PartFeel::default(),
+
+            (HumanizeAmount::Tight, Part::Comping) => PartFeel::new(4.0, 0.05, 2.0),
+            (HumanizeAmount::Tight, Part::Bass) => PartFeel::new(3.0, 0.05, 3.0),
+            (HumanizeAmount::Tight, Part::Drums) => PartFeel::new(2.0, 0.04, -2.0),
+
+            (HumanizeAmount::Natural, Part::Comping) => PartFeel::new(10.0, 0.10, 8.0),
+            (HumanizeAmount::Natural, Part::Bass) => PartFeel::new(6.0, 0.08, 5.0),
+            (HumanizeAmount::Natural, Part::Drums) => PartFeel::new(4.0, 0.08, -3.0),
+
+            (HumanizeAmount::Loose, Part::Comping) => PartFeel::new(20.0, 0.15, 15.0),
+            (HumanizeAmount::Loose, Part::Bass) => PartFeel::new(12.0, 0.12, 10.0),
+            (HumanizeAmount::Loose, Part::Drums) => PartFeel::new(8.0, 0.12, -5.0),
+        }
+    }
+
+    /// Cycle to next amount
+    pub fn next(&self) -> Self {
+        match self {
+            HumanizeAmount::Off => HumanizeAmount::Tight,
+            HumanizeAmount::Tight => HumanizeAmount::Natural,
+            HumanizeAmount::Natural => HumanizeAmount::Loose,
+            HumanizeAmount::Loose => HumanizeAmount::Off,
+        }
+    }
+
+    /// Get display name
+    pub fn name(&self) -> &'static str {
+        match self {
+            HumanizeAmount::Off => "Off",
+            HumanizeAmount::Tight => "Tight",
+            HumanizeAmount::Natural => "Natural",
+            HumanizeAmount::Loose => "Loose",
+        }
+    }
+}
+
+/// Per-part feel settings with a reproducible random source
+#[derive(Debug, Clone)]
+pub struct Humanizer {
+    /// Preset the part feels were last set from
+    pub amount: HumanizeAmount,
+    pub comping: PartFeel,
+    pub bass: PartFeel,
+    pub drums: PartFeel,
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Humanizer {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            amount: HumanizeAmount::Off,
+            comping: PartFeel::default(),
+            bass: PartFeel::default(),
+            drums: PartFeel::default(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Set all part feels from a preset
+    pub fn set_amount(&mut self, amount: HumanizeAmount) {
+        self.amount = amount;
+        self.comping = amount.feel(Part::Comping);
+        self.bass = amount.feel(Part::Bass);
+        self.drums = amount.feel(Part::Drums);
+    }
+
+    /// Cycle to next preset
+    pub fn cycle_amount(&mut self) {
+        self.set_amount(self.amount.next());
+    }
+
+    /// Use a new seed, starting its sequence from the beginning
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.restart();
+    }
+
+    /// Rewind the random sequence so playback repeats exactly
+    pub fn restart(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+
+    pub fn feel(&self, part: Part) -> PartFeel {
+        match part {
+            Part::Comping => self.comping,
+            Part::Bass => self.bass,
+            Part::Drums => self.drums,
+        }
+    }
+
+    /// Move a pattern position off the grid
+    ///
+    /// Positions never move before the start of the chord, so a pushed
+    /// downbeat still lands on the chord change.
+    pub fn shift_beat(&mut self, part: Part, beat: f32, beat_ms: f32) -> f32 {
+        let feel = self.feel(part);
+        // Difference of two uniforms: small deviations are more likely than large ones
+        let jitter = (self.rng.gen::<f32>() - self.rng.gen::<f32>()) * feel.timing_ms;
+        let shift_ms = feel.offset_ms + jitter;
+        if beat_ms <= 0.0 {
+            return beat;
+        }
+        (beat + shift_ms / beat_ms).max(0.0)
+    }
+
+    /// Vary a velocity (0.0-1.0)
+    pub fn velocity(&mut self, part: Part, velocity: f32) -> f32 {
+        let feel = self.feel(part);
+        let change = self.rng.gen_range(-1.0..=1.0) * feel.velocity;
+        (velocity * (1.0 + change)).clamp(0.05, 1.0)
+    }
+}
+
+impl Default for Humanizer {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roll(humanizer: &mut Humanizer) -> Vec<(f32, f32)> {
+        (0..16)
+            .map(|i| {
+                let beat = humanizer.shift_beat(Part::Bass, i as f32 * 0.5, 500.0);
+                let velocity = humanizer.velocity(Part::Drums, 0.8);
+                (beat, velocity)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_off_stays_on_grid() {
+        let mut humanizer = Humanizer::new(7);
+        for (i, (beat, velocity)) in roll(&mut humanizer).into_iter().enumerate() {
+            assert_eq!(beat, i as f32 * 0.5);
+            assert_eq!(velocity, 0.8);
+        }
+    }
+
+    #[test]
+    fn test_seeded_and_bounded() {
+        let mut a = Humanizer::new(42);
+        let mut b = Humanizer::new(42);
+        a.set_amount(HumanizeAmount::Loose);
+        b.set_amount(HumanizeAmount::Loose);
+        let first = roll(&mut a);
+        assert_eq!(first, roll(&mut b));
+
+        // Restarting replays the same sequence
+        a.restart();
+        assert_eq!(roll(&mut a), first);
+
+        // Bass is laid back by 10ms ± 12ms at 500ms per beat
+        let feel = a.feel(Part::Bass);
+        for (i, (beat, velocity)) in first.iter().enumerate() {
+            let shift_ms = (beat - i as f32 * 0.5) * 500.0;
+            assert!(shift_ms >= -feel.timing_ms + feel.offset_ms - 0.01 || *beat == 0.0);
+            assert!(shift_ms <= feel.timing_ms + feel.offset_ms + 0.01);
+            assert!((velocity - 0.8).abs() <= 0.8 * 0.12 + 1e-6);
+        }
+        assert!(first.iter().any(|(_, v)| *v != 0.8));
+
+        a.set_seed(43);
+        assert_ne!(roll(&mut a), first);
+    }
+}
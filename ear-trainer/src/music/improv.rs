@@ -0,0 +1,398 @@
+//! Improvised Lines
+//!
+//! Bebop-style eighth-note lines over a progression. Guide tones (3rds and
+//! 7ths) land on the strong beats, other chord tones on the weak ones, and
+//! the off-beats pass between them through the chord's contextual scale,
+//! the one the analysis panel shows. Changes
+//! are often approached with an enclosure: a scale step above the target, a
+//! half step below it, then the target on the downbeat. Lines come from a
+//! seeded RNG, so the same seed always plays the same solo.
+
+use super::chord::{Chord, Note};
+use super::progression::Progression;
+use super::scale::Scale;
+use super::theory::ChordScaleMatcher;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Chance of enclosing the first note of the next change
+const ENCLOSURE_CHANCE: f64 = 0.6;
+
+/// Chance of turning the line round on a strong beat
+const TURN_CHANCE: f64 = 0.25;
+
+/// Why a note was chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineRole {
+    /// 3rd or 7th of the chord
+    GuideTone,
+    /// Root, 5th or another chord tone
+    ChordTone,
+    /// Passing or neighbour tone from the chord's scale
+    Scale,
+    /// Half step outside the scale
+    Chromatic,
+}
+
+/// One eighth note of an improvised line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineNote {
+    /// Index of the change the note is played over
+    pub change: usize,
+    /// Beat position within the change
+    pub beat: f32,
+    pub midi: u8,
+    /// Velocity (0.0-1.0)
+    pub velocity: f32,
+    pub role: LineRole,
+}
+
+/// Change index, beat and (once chosen) the note for one eighth of a line
+type Slot = (usize, f32, Option<(u8, LineRole)>);
+
+/// Writes improvised lines over chord changes
+#[derive(Debug, Clone, Copy)]
+pub struct Improviser {
+    pub seed: u64,
+    /// Lowest and highest MIDI note of the line
+    pub range: (u8, u8),
+}
+
+impl Improviser {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, range: (60, 84) } // C4 to C6
+    }
+
+    /// An eighth-note line over the whole progression
+    pub fn line(&self, progression: &Progression) -> Vec<LineNote> {
+        let changes = &progression.changes;
+        let Some(first) = changes.first() else {
+            return Vec::new();
+        };
+
+        let scales: Vec<Scale> = ChordScaleMatcher::progression_scales(progression)
+            .into_iter()
+            .map(|choice| choice.scale)
+            .collect();
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let (low, high) = self.range;
+        let mut pitch = nearest(&guide_tones(&first.chord), (low + high) / 2, -1, self.range);
+        let mut direction: i8 = -1;
+        // Downbeat an enclosure is aiming at
+        let mut target: Option<u8> = None;
+
+        // The notes on the beat and the enclosures come first; the off-beats
+        // in between are filled in once both ends are known
+        let mut slots: Vec<Slot> = Vec::new();
+        for (idx, change) in changes.iter().enumerate() {
+            let chord = &change.chord;
+            let eighths = ((change.duration * 2.0).round() as usize).max(1);
+            let enclose = idx + 1 < changes.len()
+                && eighths >= 4
+                && eighths.is_multiple_of(2)
+                && rng.gen_bool(ENCLOSURE_CHANCE);
+
+            for eighth in 0..eighths {
+                let beat = eighth as f32 / 2.0;
+                if eighth % 2 == 1 {
+                    if slots.last().is_none_or(|s| s.0 != idx || s.1 != beat) {
+                        slots.push((idx, beat, None));
+                    }
+                    continue;
+                }
+
+                if eighth % 4 == 0 {
+                    if pitch + 4 >= high {
+                        direction = -1;
+                    } else if pitch <= low + 4 {
+                        direction = 1;
+                    } else if rng.gen_bool(TURN_CHANCE) {
+                        direction = -direction;
+                    }
+                }
+
+                if enclose && eighth + 2 == eighths {
+                    let next = &changes[idx + 1].chord;
+                    let goal = nearest(&guide_tones(next), pitch, direction, (low + 1, high - 2));
+                    let above = scale_step(goal, 1, &scales[idx + 1]);
+                    slots.push((idx, beat, Some((above, LineRole::Scale))));
+                    slots.push((idx, beat + 0.5, Some((goal - 1, LineRole::Chromatic))));
+                    pitch = goal - 1;
+                    target = Some(goal);
+                    continue;
+                }
+
+                let (midi, role) = match target.take().filter(|_| eighth == 0) {
+                    Some(goal) => (goal, LineRole::GuideTone),
+                    None if eighth % 4 == 0 => {
+                        (nearest(&guide_tones(chord), pitch, direction, self.range), LineRole::GuideTone)
+                    }
+                    None => (next_tone(&chord.pitch_classes(), pitch, direction, self.range), LineRole::ChordTone),
+                };
+                slots.push((idx, beat, Some((midi, role))));
+                pitch = midi;
+            }
+        }
+
+        // Pass between the notes either side of each off-beat; the last one
+        // leads back to the top of the progression
+        let filled: Vec<Option<(u8, LineRole)>> = slots.iter().map(|s| s.2).collect();
+        slots
+            .iter()
+            .enumerate()
+            .map(|(i, &(change, beat, note))| {
+                let (midi, role) = note.unwrap_or_else(|| {
+                    let before = filled[..i].iter().rev().flatten().next();
+                    let after = filled[i + 1..].iter().chain(&filled[..i]).flatten().next();
+                    match (before, after) {
+                        (Some(&(from, _)), Some(&(to, _))) => {
+                            passing_tone(from, to, &scales[change], self.range)
+                        }
+                        _ => (pitch, LineRole::ChordTone),
+                    }
+                });
+                let velocity = match role {
+                    LineRole::GuideTone if beat.fract() == 0.0 => 0.8,
+                    _ if beat.fract() == 0.0 => 0.7,
+                    _ => 0.6,
+                };
+                LineNote { change, beat, midi, velocity, role }
+            })
+            .collect()
+    }
+}
+
+/// 3rd and 7th, or every chord tone when the chord has neither
+fn guide_tones(chord: &Chord) -> Vec<Note> {
+    let tones = chord.guide_tones();
+    if tones.is_empty() {
+        chord.pitch_classes()
+    } else {
+        tones
+    }
+}
+
+/// The closest note of `notes` to `from` within the range, preferring to
+/// keep moving in `direction` and never repeating `from`
+fn nearest(notes: &[Note], from: u8, direction: i8, range: (u8, u8)) -> u8 {
+    (range.0..=range.1)
+        .filter(|&midi| midi != from && notes.contains(&Note::from_midi(midi)))
+        .min_by_key(|&midi| {
+            let distance = (midi as i16 - from as i16).abs() * 2;
+            let against = (midi > from) != (direction > 0);
+            distance + against as i16
+        })
+        .unwrap_or(from)
+}
+
+/// The next note of `notes` moving from `from` in `direction`, turning round
+/// at the edge of the range
+fn next_tone(notes: &[Note], from: u8, direction: i8, range: (u8, u8)) -> u8 {
+    (1..=12)
+        .map(|step| from as i16 + step * direction as i16)
+        .filter(|&midi| midi >= range.0 as i16 && midi <= range.1 as i16)
+        .map(|midi| midi as u8)
+        .find(|&midi| notes.contains(&Note::from_midi(midi)))
+        .unwrap_or_else(|| nearest(notes, from, -direction, range))
+}
+
+/// The next scale tone from `from` in `direction`
+fn scale_step(from: u8, direction: i8, scale: &Scale) -> u8 {
+    (1..=12)
+        .map(|step| (from as i16 + step * direction as i16) as u8)
+        .find(|&midi| scale.contains(Note::from_midi(midi)))
+        .unwrap_or(from)
+}
+
+/// Off-beat note between two on-beat notes
+///
+/// Wide gaps are bridged by a scale tone, a whole step by the note between
+/// (chromatic if it's outside the scale), and anything closer by a scale
+/// neighbour of the note being approached.
+fn passing_tone(from: u8, to: u8, scale: &Scale, range: (u8, u8)) -> (u8, LineRole) {
+    let gap = to as i16 - from as i16;
+    let middle = (from as i16 + to as i16) / 2;
+    match gap.abs() {
+        3.. => {
+            let (low, high) = (from.min(to) + 1, from.max(to) - 1);
+            (low..=high)
+                .filter(|&midi| scale.contains(Note::from_midi(midi)))
+                .min_by_key(|&midi| (midi as i16 - middle).abs())
+                .map(|midi| (midi, LineRole::Scale))
+                .unwrap_or(((from as i16 + gap.signum()) as u8, LineRole::Chromatic))
+        }
+        2 => {
+            let midi = middle as u8;
+            if scale.contains(Note::from_midi(midi)) {
+                (midi, LineRole::Scale)
+            } else {
+                (midi, LineRole::Chromatic)
+            }
+        }
+        _ => {
+            let above = scale_step(to, 1, scale);
+            if above <= range.1 {
+                (above, LineRole::Scale)
+            } else {
+                (scale_step(to, -1, scale), LineRole::Scale)
+            }
+        }
+    }
+}
+
+/// Plays an improvised line along with the band
+#[derive(Debug, Clone, Default)]
+pub struct SoloState {
+    line: Vec<LineNote>,
+    /// Index of next note to play
+    next_note_idx: usize,
+    /// Note still sounding from the line
+    sounding: Option<u8>,
+}
+
+impl SoloState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the line being played
+    pub fn set_line(&mut self, line: Vec<LineNote>) {
+        self.line = line;
+        self.next_note_idx = 0;
+        self.sounding = None;
+    }
+
+    pub fn line(&self) -> &[LineNote] {
+        &self.line
+    }
+
+    /// Forget the note still sounding, returning it so it can be stopped
+    pub fn silence(&mut self) -> Option<u8> {
+        self.sounding.take()
+    }
+
+    /// Rewind to the start of a change
+    pub fn reset(&mut self, change: usize) {
+        self.next_note_idx = self
+            .line
+            .iter()
+            .position(|note| note.change >= change)
+            .unwrap_or(self.line.len());
+        self.sounding = None;
+    }
+
+    /// Check if the next note of the line is due at this beat of the change
+    /// Returns Some((midi_note, velocity, note_it_replaces)) if we should play
+    pub fn check_note(&mut self, change: usize, current_beat: f32) -> Option<(u8, f32, Option<u8>)> {
+        let note = self.line.get(self.next_note_idx)?;
+        if note.change != change || current_beat < note.beat {
+            return None;
+        }
+        self.next_note_idx += 1;
+        let previous = self.sounding.replace(note.midi);
+        Some((note.midi, note.velocity, previous))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::ProgressionLibrary;
+
+    fn ii_v_i() -> Progression {
+        ProgressionLibrary::new()
+            .get_by_genre("Jazz")
+            .and_then(|progs| progs.first().cloned())
+            .unwrap()
+    }
+
+    fn all_progressions() -> Vec<Progression> {
+        let library = ProgressionLibrary::new();
+        library
+            .all_genres()
+            .iter()
+            .flat_map(|genre| library.get_by_genre(genre).cloned().unwrap_or_default())
+            .collect()
+    }
+
+    #[test]
+    fn test_line_shape() {
+        let prog = ii_v_i();
+        let line = Improviser::new(1).line(&prog);
+
+        // Continuous eighths from the first change to the last
+        let eighths: f32 = prog.changes.iter().map(|c| c.duration * 2.0).sum();
+        assert_eq!(line.len(), eighths as usize);
+        for pair in line.windows(2) {
+            let step = if pair[0].change == pair[1].change { pair[1].beat - pair[0].beat } else { pair[1].beat };
+            assert!(step == 0.5 || step == 0.0, "{:?}", pair);
+        }
+
+        // Deterministic per seed
+        assert_eq!(line, Improviser::new(1).line(&prog));
+        assert_ne!(line, Improviser::new(2).line(&prog));
+    }
+
+    #[test]
+    fn test_guide_tones_on_strong_beats() {
+        for prog in all_progressions() {
+            for seed in 0..4 {
+                let improviser = Improviser::new(seed);
+                let scales = ChordScaleMatcher::progression_scales(&prog);
+                for note in improviser.line(&prog) {
+                    let chord = &prog.changes[note.change].chord;
+                    let pitch = Note::from_midi(note.midi);
+                    assert!(note.midi >= improviser.range.0 && note.midi <= improviser.range.1, "{:?}", note);
+
+                    if note.beat % 2.0 == 0.0 && note.role != LineRole::Scale {
+                        assert_eq!(note.role, LineRole::GuideTone, "{} {:?}", chord.name(), note);
+                        assert!(guide_tones(chord).contains(&pitch), "{} {:?}", chord.name(), note);
+                    }
+                    if note.role == LineRole::Scale {
+                        let next = (note.change + 1) % prog.changes.len();
+                        let in_scale = |i: usize| scales[i].scale.contains(pitch);
+                        assert!(in_scale(note.change) || in_scale(next), "{:?}", note);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_enclosures_lead_into_changes() {
+        let prog = ii_v_i();
+        let mut enclosures = 0;
+        for seed in 0..8 {
+            let line = Improviser::new(seed).line(&prog);
+            for window in line.windows(3) {
+                let [above, below, goal] = window else { unreachable!() };
+                // On-beat scale tones only come from enclosures
+                if above.role == LineRole::Scale && above.beat.fract() == 0.0 && goal.change != below.change {
+                    enclosures += 1;
+                    assert_eq!(below.role, LineRole::Chromatic);
+                    assert_eq!(below.midi + 1, goal.midi);
+                    assert!(above.midi > goal.midi && above.midi <= goal.midi + 2);
+                    assert_eq!(goal.beat, 0.0);
+                    assert_eq!(goal.role, LineRole::GuideTone);
+                }
+            }
+        }
+        assert!(enclosures > 0);
+    }
+
+    #[test]
+    fn test_solo_state_follows_changes() {
+        let prog = ii_v_i();
+        let line = Improviser::new(1).line(&prog);
+        let mut state = SoloState::new();
+        state.set_line(line.clone());
+
+        // Nothing from the next change plays early
+        state.reset(1);
+        assert_eq!(state.check_note(0, 3.9), None);
+        let first = state.check_note(1, 0.0).unwrap();
+        assert_eq!((first.0, first.2), (line[8].midi, None));
+        assert_eq!(state.check_note(1, 0.2), None);
+        assert_eq!(state.check_note(1, 0.5).unwrap().2, Some(line[8].midi));
+    }
+}
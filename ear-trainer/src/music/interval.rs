@@ -0,0 +1,272 @@
+//! Intervals
+//!
+//! Names for the distance between two notes, up to two octaves, and the
+//! sets of them the interval trainer asks about. A question places an
+//! interval inside a MIDI note range and says how it is heard: one note
+//! after the other going up or down, or both at once.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::borrow::Cow;
+
+/// Name of an interval by its size in semitones
+pub fn interval_name(semitones: u8) -> &'static str {
+    match semitones {
+        0 => "Unison",
+        1 => "Minor 2nd",
+        2 => "Major 2nd",
+        3 => "Minor 3rd",
+        4 => "Major 3rd",
+        5 => "Perfect 4th",
+        6 => "Tritone",
+        7 => "Perfect 5th",
+        8 => "Minor 6th",
+        9 => "Major 6th",
+        10 => "Minor 7th",
+        11 => "Major 7th",
+        12 => "Octave",
+        13 => "Minor 9th",
+        14 => "Major 9th",
+        15 => "Minor 10th",
+        16 => "Major 10th",
+        17 => "Perfect 11th",
+        18 => "Augmented 11th",
+        19 => "Perfect 12th",
+        20 => "Minor 13th",
+        21 => "Major 13th",
+        22 => "Minor 14th",
+        23 => "Major 14th",
+        24 => "Two octaves",
+        _ => "Beyond two octaves",
+    }
+}
+
+/// Short symbol for an interval (m3, P5, M9...)
+pub fn interval_symbol(semitones: u8) -> &'static str {
+    const SYMBOLS: [&str; 25] = [
+        "P1", "m2", "M2", "m3", "M3", "P4", "TT", "P5", "m6", "M6", "m7", "M7", "P8", "m9", "M9", "m10",
+        "M10", "P11", "#11", "P12", "m13", "M13", "m14", "M14", "P15",
+    ];
+    SYMBOLS.get(semitones as usize).copied().unwrap_or("?")
+}
+
+/// How the two notes of a question are played
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntervalDirection {
+    #[default]
+    Ascending,
+    Descending,
+    /// Both notes at once
+    Harmonic,
+    /// Any of the above, picked per question
+    Mixed,
+}
+
+impl IntervalDirection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntervalDirection::Ascending => "Ascending",
+            IntervalDirection::Descending => "Descending",
+            IntervalDirection::Harmonic => "Harmonic",
+            IntervalDirection::Mixed => "Mixed",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            IntervalDirection::Ascending => IntervalDirection::Descending,
+            IntervalDirection::Descending => IntervalDirection::Harmonic,
+            IntervalDirection::Harmonic => IntervalDirection::Mixed,
+            IntervalDirection::Mixed => IntervalDirection::Ascending,
+        }
+    }
+}
+
+/// A named group of intervals to practice
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet {
+    pub name: Cow<'static, str>,
+    /// Sizes in semitones, smallest first
+    pub semitones: Cow<'static, [u8]>,
+}
+
+impl IntervalSet {
+    pub const PERFECT: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("Perfect"),
+        semitones: Cow::Borrowed(&[5, 7, 12]),
+    };
+    pub const THIRDS_SIXTHS: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("3rds & 6ths"),
+        semitones: Cow::Borrowed(&[3, 4, 8, 9]),
+    };
+    pub const SECONDS_SEVENTHS: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("2nds & 7ths"),
+        semitones: Cow::Borrowed(&[1, 2, 10, 11]),
+    };
+    pub const SIMPLE: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("All simple"),
+        semitones: Cow::Borrowed(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+    };
+    pub const COMPOUND: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("Compound"),
+        semitones: Cow::Borrowed(&[13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24]),
+    };
+    pub const EXTENSIONS: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("9ths, 11ths & 13ths"),
+        semitones: Cow::Borrowed(&[13, 14, 17, 18, 20, 21]),
+    };
+    pub const EVERYTHING: IntervalSet = IntervalSet {
+        name: Cow::Borrowed("Everything"),
+        semitones: Cow::Borrowed(&[
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+        ]),
+    };
+
+    pub const ALL: [IntervalSet; 7] = [
+        IntervalSet::PERFECT,
+        IntervalSet::THIRDS_SIXTHS,
+        IntervalSet::SECONDS_SEVENTHS,
+        IntervalSet::SIMPLE,
+        IntervalSet::COMPOUND,
+        IntervalSet::EXTENSIONS,
+        IntervalSet::EVERYTHING,
+    ];
+
+    /// A user-defined set; sizes are sorted, repeats dropped and anything
+    /// outside 1 to 24 semitones ignored. None if nothing is left
+    pub fn custom(name: impl Into<String>, semitones: &[u8]) -> Option<Self> {
+        let mut semitones: Vec<u8> = semitones.iter().copied().filter(|s| (1..=24).contains(s)).collect();
+        semitones.sort_unstable();
+        semitones.dedup();
+        if semitones.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: Cow::Owned(name.into()),
+            semitones: Cow::Owned(semitones),
+        })
+    }
+}
+
+impl Default for IntervalSet {
+    fn default() -> Self {
+        Self::PERFECT
+    }
+}
+
+/// One interval to identify, placed in a note range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalQuestion {
+    pub semitones: u8,
+    /// Lower note (MIDI)
+    pub low: u8,
+    /// Never `Mixed`
+    pub direction: IntervalDirection,
+}
+
+impl IntervalQuestion {
+    /// Pick an interval from the set that fits between `min_note` and
+    /// `max_note`, with a random lower note
+    pub fn random(
+        rng: &mut impl Rng,
+        set: &IntervalSet,
+        direction: IntervalDirection,
+        min_note: u8,
+        max_note: u8,
+    ) -> Option<Self> {
+        let span = max_note.saturating_sub(min_note);
+        let fitting: Vec<u8> = set.semitones.iter().copied().filter(|&s| s <= span).collect();
+        let semitones = *fitting.choose(rng)?;
+        let low = rng.gen_range(min_note..=max_note - semitones);
+        let direction = match direction {
+            IntervalDirection::Mixed => *[
+                IntervalDirection::Ascending,
+                IntervalDirection::Descending,
+                IntervalDirection::Harmonic,
+            ]
+            .choose(rng)?,
+            direction => direction,
+        };
+        Some(Self {
+            semitones,
+            low,
+            direction,
+        })
+    }
+
+    pub fn high(&self) -> u8 {
+        self.low + self.semitones
+    }
+
+    /// Notes in playing order; a harmonic interval is one step of two notes
+    pub fn steps(&self) -> Vec<Vec<u8>> {
+        match self.direction {
+            IntervalDirection::Descending => vec![vec![self.high()], vec![self.low]],
+            IntervalDirection::Harmonic => vec![vec![self.low, self.high()]],
+            _ => vec![vec![self.low], vec![self.high()]],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(interval_name(7), "Perfect 5th");
+        assert_eq!(interval_name(14), "Major 9th");
+        assert_eq!(interval_symbol(18), "#11");
+        for set in IntervalSet::ALL {
+            for &s in set.semitones.iter() {
+                assert_ne!(interval_symbol(s), "?");
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_set() {
+        let set = IntervalSet::custom("Tritones and up", &[6, 0, 30, 13, 6]).unwrap();
+        assert_eq!(set.name, "Tritones and up");
+        assert_eq!(&set.semitones[..], &[6, 13]);
+        assert!(IntervalSet::custom("Nothing", &[0, 25]).is_none());
+    }
+
+    #[test]
+    fn test_questions_fit_the_range() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..200 {
+            let q = IntervalQuestion::random(
+                &mut rng,
+                &IntervalSet::EVERYTHING,
+                IntervalDirection::Mixed,
+                48,
+                72,
+            )
+            .unwrap();
+            assert!(q.low >= 48 && q.high() <= 72);
+            assert_ne!(q.direction, IntervalDirection::Mixed);
+        }
+
+        // Only intervals that fit are asked
+        let q = IntervalQuestion::random(&mut rng, &IntervalSet::COMPOUND, IntervalDirection::Ascending, 60, 73)
+            .unwrap();
+        assert_eq!(q.semitones, 13);
+        assert!(IntervalQuestion::random(&mut rng, &IntervalSet::COMPOUND, IntervalDirection::Ascending, 60, 70)
+            .is_none());
+    }
+
+    #[test]
+    fn test_steps() {
+        let mut q = IntervalQuestion {
+            semitones: 4,
+            low: 60,
+            direction: IntervalDirection::Descending,
+        };
+        assert_eq!(q.steps(), vec![vec![64], vec![60]]);
+        q.direction = IntervalDirection::Harmonic;
+        assert_eq!(q.steps(), vec![vec![60, 64]]);
+    }
+}
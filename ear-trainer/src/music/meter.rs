@@ -0,0 +1,197 @@
+//! Meter (Time Signatures)
+//!
+//! A progression's beats are counted in the meter's unit: quarter notes in
+//! 3/4, 5/4 and 7/4, eighth notes in 6/8 and 12/8, so a 6/8 bar is six beats
+//! and the tempo counts eighths. Each bar splits into groups of two or three
+//! beats, which is where the comping, bass and drum patterns put their
+//! accents: 5/4 is felt 3+2, 7/4 3+2+2, and compound meters in dotted
+//! quarters.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Meter {
+    /// Beats in a bar (the top number)
+    pub beats: u8,
+    /// Note value of a beat (the bottom number)
+    pub unit: u8,
+}
+
+impl Meter {
+    pub const COMMON: Meter = Meter::new(4, 4);
+    pub const WALTZ: Meter = Meter::new(3, 4);
+    pub const FIVE_FOUR: Meter = Meter::new(5, 4);
+    pub const SIX_EIGHT: Meter = Meter::new(6, 8);
+    pub const SEVEN_FOUR: Meter = Meter::new(7, 4);
+    pub const TWELVE_EIGHT: Meter = Meter::new(12, 8);
+
+    #[cfg(test)]
+    pub const ALL: [Meter; 6] = [
+        Meter::COMMON,
+        Meter::WALTZ,
+        Meter::FIVE_FOUR,
+        Meter::SIX_EIGHT,
+        Meter::SEVEN_FOUR,
+        Meter::TWELVE_EIGHT,
+    ];
+
+    pub const fn new(beats: u8, unit: u8) -> Self {
+        Self { beats, unit }
+    }
+
+    /// Length of a bar in beats
+    pub fn bar_beats(&self) -> f32 {
+        self.beats.max(1) as f32
+    }
+
+    /// Eighth-note meters grouped in threes (6/8, 9/8, 12/8)
+    pub fn is_compound(&self) -> bool {
+        self.unit == 8 && self.beats > 3 && self.beats.is_multiple_of(3)
+    }
+
+    /// How the bar's beats are grouped, in order
+    pub fn groups(&self) -> Vec<u8> {
+        let beats = self.beats.max(1);
+        if self.is_compound() {
+            return vec![3; beats as usize / 3];
+        }
+        match beats {
+            1..=3 => vec![beats],
+            // Odd meters lead with a group of three: 5 = 3+2, 7 = 3+2+2
+            b if !b.is_multiple_of(2) => {
+                let mut groups = vec![3];
+                groups.extend(vec![2; (b as usize - 3) / 2]);
+                groups
+            }
+            b => vec![2; b as usize / 2],
+        }
+    }
+
+    /// Start beat and length of each group in the bar
+    pub fn group_spans(&self) -> Vec<(f32, f32)> {
+        let mut start = 0.0;
+        self.groups()
+            .into_iter()
+            .map(|len| {
+                let span = (start, len as f32);
+                start += len as f32;
+                span
+            })
+            .collect()
+    }
+
+    /// Beats the pulse falls on within a bar: every beat in simple meters,
+    /// every dotted quarter in compound ones
+    pub fn pulses(&self) -> Vec<f32> {
+        if self.is_compound() {
+            self.group_spans().into_iter().map(|(start, _)| start).collect()
+        } else {
+            (0..self.beats.max(1)).map(|b| b as f32).collect()
+        }
+    }
+
+    /// Pulse positions across a span of beats, bar after bar
+    pub fn pulses_in(&self, beats: f32) -> Vec<f32> {
+        let bar = self.bar_beats();
+        let pulses = self.pulses();
+        (0..(beats / bar).ceil().max(1.0) as usize)
+            .flat_map(|n| pulses.iter().map(move |p| n as f32 * bar + p))
+            .filter(|&p| p < beats)
+            .collect()
+    }
+
+    /// Accent for a beat in the bar: the downbeat is strongest, then the
+    /// start of each group
+    pub fn accent(&self, beat: f32) -> f32 {
+        let beat = beat.rem_euclid(self.bar_beats());
+        if beat == 0.0 {
+            1.0
+        } else if self.group_spans().iter().any(|&(start, _)| start == beat) {
+            0.85
+        } else {
+            0.7
+        }
+    }
+
+    /// Where a beat written on the straight grid falls with swung eighths
+    ///
+    /// `ratio` is the share of each beat the first eighth takes: 0.5 is
+    /// straight, 0.67 a triplet feel. Sixteenths move with their eighth, and
+    /// beats themselves stay put. Only quarter-note meters swing; compound
+    /// meters already divide the pulse in three.
+    pub fn swing(&self, beat: f32, ratio: f32) -> f32 {
+        if self.unit != 4 || ratio == 0.5 {
+            return beat;
+        }
+        let start = beat.floor();
+        let offset = beat - start;
+        let swung = if offset <= 0.5 {
+            offset * ratio / 0.5
+        } else {
+            ratio + (offset - 0.5) * (1.0 - ratio) / 0.5
+        };
+        start + swung
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.beats, self.unit)
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::COMMON
+    }
+}
+
+impl fmt::Display for Meter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(Meter::COMMON.groups(), vec![2, 2]);
+        assert_eq!(Meter::WALTZ.groups(), vec![3]);
+        assert_eq!(Meter::FIVE_FOUR.groups(), vec![3, 2]);
+        assert_eq!(Meter::SEVEN_FOUR.groups(), vec![3, 2, 2]);
+        assert_eq!(Meter::SIX_EIGHT.groups(), vec![3, 3]);
+        assert_eq!(Meter::TWELVE_EIGHT.groups(), vec![3, 3, 3, 3]);
+        for meter in Meter::ALL {
+            assert_eq!(meter.groups().iter().sum::<u8>(), meter.beats);
+        }
+    }
+
+    #[test]
+    fn test_pulses() {
+        assert_eq!(Meter::WALTZ.pulses(), vec![0.0, 1.0, 2.0]);
+        assert_eq!(Meter::SIX_EIGHT.pulses(), vec![0.0, 3.0]);
+        assert_eq!(Meter::SIX_EIGHT.pulses_in(12.0), vec![0.0, 3.0, 6.0, 9.0]);
+        assert_eq!(Meter::WALTZ.pulses_in(2.0), vec![0.0, 1.0]);
+
+        assert_eq!(Meter::FIVE_FOUR.accent(0.0), 1.0);
+        assert_eq!(Meter::FIVE_FOUR.accent(3.0), 0.85);
+        assert_eq!(Meter::FIVE_FOUR.accent(4.0), 0.7);
+        assert_eq!(Meter::FIVE_FOUR.name(), "5/4");
+    }
+
+    #[test]
+    fn test_swing() {
+        let meter = Meter::COMMON;
+        // Off-beat eighths move, beats stay on the grid
+        assert_eq!(meter.swing(1.5, 0.6), 1.6);
+        assert_eq!(meter.swing(2.0, 0.6), 2.0);
+        assert_eq!(meter.swing(1.5, 0.5), 1.5);
+        // Sixteenths stay inside their swung eighth
+        assert!((meter.swing(0.25, 0.6) - 0.3).abs() < 1e-6);
+        assert!((meter.swing(0.75, 0.6) - 0.8).abs() < 1e-6);
+        // Compound meters are left alone
+        assert_eq!(Meter::SIX_EIGHT.swing(1.5, 0.6), 1.5);
+    }
+}
@@ -1,23 +1,47 @@
+pub mod analysis;
 pub mod bass;
 pub mod bricks;
 pub mod chord;
+pub mod data;
 pub mod drums;
+pub mod guitar;
+pub mod humanize;
+pub mod improv;
+pub mod interval;
 pub mod joins;
+pub mod meter;
 pub mod progression;
+pub mod recognition;
+pub mod reharm;
 pub mod rhythm;
 pub mod scale;
+pub mod segmentation;
+pub mod spelling;
 pub mod standards;
 pub mod theory;
+pub mod tune;
 pub mod voicings;
 
+pub use analysis::{FunctionalAnalysis, RomanNumeral};
 pub use bass::{BassNote, BassState, BassStyle};
 pub use bricks::{Brick, BrickCategory, BrickChord, BrickLibrary, QuizDifficulty, ScaleDegree};
 pub use chord::{Chord, ChordQuality, ChordTone, Note};
+pub use data::{DataIssue, MusicData};
 pub use drums::{DrumHit, DrumState, DrumStyle};
-pub use joins::{CircleOfFourths, Join, JoinLibrary};
+pub use guitar::{GripStyle, GuitarGrip, GuitarTuning, GuitarVoicer};
+pub use humanize::{HumanizeAmount, Humanizer};
+pub use improv::{Improviser, LineNote, LineRole, SoloState};
+pub use interval::{IntervalDirection, IntervalQuestion, IntervalSet};
+pub use joins::{Join, JoinLibrary};
+pub use meter::Meter;
 pub use progression::{ChordChange, Progression, ProgressionLibrary};
+pub use recognition::ChordRecognizer;
+pub use reharm::{Reharmonization, Reharmonizer, Substitution};
 pub use rhythm::{RhythmHit, RhythmState, RhythmStyle};
-pub use scale::{Mode, Scale, ScaleType};
+pub use scale::{Mode, Scale, ScaleTier, ScaleType};
+pub use segmentation::{BrickAnalyzer, BrickSegment};
+pub use spelling::SpelledPitch;
 pub use standards::{Standard, StandardsLibrary, StandardDifficulty};
-pub use theory::{ChordScaleMatcher, GuideTone, VoiceLeading};
-pub use voicings::{VoicedChord, VoicingType};
+pub use theory::{ChordScaleMatcher, GuideTone, ScaleChoice, VoiceLeading};
+pub use tune::{Tune, TuneBuilder, TunePart};
+pub use voicings::{ProgressionVoicer, VoicedChord, VoicingType};
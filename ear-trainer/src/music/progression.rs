@@ -1,5 +1,7 @@
 use super::bricks::BrickLibrary;
 use super::chord::{Chord, ChordQuality, Note};
+use super::meter::Meter;
+use super::spelling::SpelledPitch;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -14,8 +16,14 @@ pub struct Progression {
     pub name: String,
     pub genre: String,
     pub key: Note,
+    /// Whether the key is minor, which decides how its tonic is spelled
+    #[serde(default)]
+    pub minor: bool,
     pub changes: Vec<ChordChange>,
+    /// Beats per minute, counted in the meter's unit
     pub tempo: f32,
+    #[serde(default)]
+    pub meter: Meter,
 }
 
 impl Progression {
@@ -24,15 +32,56 @@ impl Progression {
             name,
             genre,
             key,
+            minor: false,
             changes: Vec::new(),
             tempo,
+            meter: Meter::default(),
         }
     }
 
+    /// Same progression in the minor key on its tonic
+    pub fn in_minor(mut self) -> Self {
+        self.minor = true;
+        self
+    }
+
+    /// The key's tonic as it is written: C# minor, but Db major
+    pub fn spelled_key(&self) -> SpelledPitch {
+        SpelledPitch::tonic(self.key, self.minor)
+    }
+
+    /// Same progression in another time signature
+    pub fn with_meter(mut self, meter: Meter) -> Self {
+        self.meter = meter;
+        self
+    }
+
+    /// Append a chord; roots without an explicit spelling are spelled in the key
     pub fn add_chord(&mut self, chord: Chord, duration: f32) {
+        let chord = if chord.root_spelling.is_some() {
+            chord
+        } else {
+            chord.in_key(self.spelled_key())
+        };
         self.changes.push(ChordChange { chord, duration });
     }
 
+    /// The same changes moved to another key, slash basses included
+    pub fn transpose(&self, semitones: i8) -> Progression {
+        let mut prog = Progression::new(
+            self.name.clone(),
+            self.genre.clone(),
+            self.key.transpose(semitones),
+            self.tempo,
+        )
+        .with_meter(self.meter);
+        prog.minor = self.minor;
+        for change in &self.changes {
+            prog.add_chord(change.chord.transpose(semitones), change.duration);
+        }
+        prog
+    }
+
     pub fn total_duration(&self) -> f32 {
         self.changes.iter().map(|c| c.duration).sum()
     }
@@ -44,6 +93,12 @@ pub struct ProgressionLibrary {
 
 impl ProgressionLibrary {
     pub fn new() -> Self {
+        Self::with_bricks(&BrickLibrary::new())
+    }
+
+    /// The bundled progressions, with the "LEGO Bricks" genre built from
+    /// `bricks`, such as those loaded with the user's data files
+    pub fn with_bricks(bricks: &BrickLibrary) -> Self {
         let mut library = Self {
             progressions: HashMap::new(),
         };
@@ -52,7 +107,7 @@ impl ProgressionLibrary {
         library.populate_funk();
         library.populate_smooth_jazz();
         library.populate_pop();
-        library.populate_lego_bricks();
+        library.populate_lego_bricks(bricks);
         library
     }
 
@@ -89,7 +144,8 @@ impl ProgressionLibrary {
             "Jazz".to_string(),
             Note::C,
             120.0,
-        );
+        )
+        .in_minor();
         prog.add_chord(Chord::new(Note::D, ChordQuality::HalfDiminished), 4.0);
         prog.add_chord(Chord::new(Note::G, ChordQuality::Dominant7b9), 4.0);
         prog.add_chord(Chord::new(Note::C, ChordQuality::Minor7), 8.0);
@@ -153,7 +209,8 @@ impl ProgressionLibrary {
             "Jazz".to_string(),
             Note::C,
             140.0,
-        );
+        )
+        .in_minor();
         prog.add_chord(Chord::new(Note::C, ChordQuality::Minor7), 4.0);
         prog.add_chord(Chord::new(Note::F, ChordQuality::Minor7), 4.0);
         prog.add_chord(Chord::new(Note::D, ChordQuality::HalfDiminished), 2.0);
@@ -200,6 +257,47 @@ impl ProgressionLibrary {
         prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 8.0);
         jazz.push(prog);
 
+        // Jazz waltz: a bar each for ii and V, two for the I
+        let mut prog = Progression::new(
+            "Jazz Waltz ii-V-I (Bb)".to_string(),
+            "Jazz".to_string(),
+            Note::Bb,
+            160.0,
+        )
+        .with_meter(Meter::WALTZ);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Minor7), 3.0);
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Dominant7), 3.0);
+        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major7), 6.0);
+        jazz.push(prog);
+
+        // Take Five vamp in 5/4
+        let mut prog = Progression::new(
+            "Take Five Vamp (Ebm)".to_string(),
+            "Jazz".to_string(),
+            Note::Eb,
+            170.0,
+        )
+        .with_meter(Meter::FIVE_FOUR)
+        .in_minor();
+        prog.add_chord(Chord::new(Note::Eb, ChordQuality::Minor7), 5.0);
+        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Minor7), 5.0);
+        jazz.push(prog);
+
+        // Afro Blue vamp, 6/8 Afro-Cuban feel (tempo in eighths)
+        let mut prog = Progression::new(
+            "Afro Blue Vamp (Fm)".to_string(),
+            "Jazz".to_string(),
+            Note::F,
+            270.0,
+        )
+        .with_meter(Meter::SIX_EIGHT)
+        .in_minor();
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Minor7), 6.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Minor7), 6.0);
+        prog.add_chord(Chord::new(Note::Ab, ChordQuality::Major7), 6.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Minor7), 6.0);
+        jazz.push(prog);
+
         self.progressions.insert("Jazz".to_string(), jazz);
     }
 
@@ -242,7 +340,8 @@ impl ProgressionLibrary {
         prog.add_chord(Chord::new(Note::F, ChordQuality::Major7), 4.0);
         prog.add_chord(Chord::new(Note::D, ChordQuality::Minor7), 4.0);
         prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Dominant7), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Dominant7sus4), 2.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Dominant13), 2.0);
         soul.push(prog);
 
         // Motown Style
@@ -252,9 +351,9 @@ impl ProgressionLibrary {
             Note::G,
             115.0,
         );
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 2.0);
-        prog.add_chord(Chord::new(Note::E, ChordQuality::Minor7), 2.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 2.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 2.0);
+        prog.add_chord(Chord::new(Note::E, ChordQuality::Minor), 2.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 2.0);
         prog.add_chord(Chord::new(Note::D, ChordQuality::Dominant7), 2.0);
         soul.push(prog);
 
@@ -291,10 +390,10 @@ impl ProgressionLibrary {
             Note::C,
             80.0,
         );
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 2.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor7), 2.0);
-        prog.add_chord(Chord::new(Note::F, ChordQuality::Major7), 2.0);
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Dominant7), 2.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major69), 2.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor11), 2.0);
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Add9), 2.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Dominant13sus4), 2.0);
         soul.push(prog);
 
         // Slow Jam
@@ -335,6 +434,20 @@ impl ProgressionLibrary {
         prog.add_chord(Chord::new(Note::A, ChordQuality::Dominant7), 2.0);
         soul.push(prog);
 
+        // Slow 12/8 ballad turnaround (tempo in eighths)
+        let mut prog = Progression::new(
+            "12/8 Soul Ballad (Bb)".to_string(),
+            "Soul".to_string(),
+            Note::Bb,
+            180.0,
+        )
+        .with_meter(Meter::TWELVE_EIGHT);
+        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major7), 6.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Minor7), 6.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Minor7), 6.0);
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Dominant7), 6.0);
+        soul.push(prog);
+
         self.progressions.insert("Soul".to_string(), soul);
     }
 
@@ -463,6 +576,18 @@ impl ProgressionLibrary {
         prog.add_chord(Chord::new(Note::Eb, ChordQuality::Major7), 4.0);
         funk.push(prog);
 
+        // Odd-meter funk vamp in 7/4 (3+2+2)
+        let mut prog = Progression::new(
+            "Funk in Seven (Dm)".to_string(),
+            "Funk".to_string(),
+            Note::D,
+            110.0,
+        )
+        .with_meter(Meter::SEVEN_FOUR);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Minor7), 7.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Dominant9), 7.0);
+        funk.push(prog);
+
         self.progressions.insert("Funk".to_string(), funk);
     }
 
@@ -607,10 +732,10 @@ impl ProgressionLibrary {
             Note::C,
             120.0,
         );
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::F, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // vi-IV-I-V
@@ -620,10 +745,10 @@ impl ProgressionLibrary {
             Note::G,
             115.0,
         );
-        prog.add_chord(Chord::new(Note::E, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::D, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::E, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // I-IV-V
@@ -633,10 +758,10 @@ impl ProgressionLibrary {
             Note::D,
             125.0,
         );
-        prog.add_chord(Chord::new(Note::D, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::D, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // I-vi-IV-V (50s progression)
@@ -646,10 +771,10 @@ impl ProgressionLibrary {
             Note::C,
             130.0,
         );
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::F, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // I-V-IV
@@ -659,10 +784,10 @@ impl ProgressionLibrary {
             Note::E,
             110.0,
         );
-        prog.add_chord(Chord::new(Note::E, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::B, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::E, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::E, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::B, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::E, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // IV-V-iii-vi
@@ -672,10 +797,10 @@ impl ProgressionLibrary {
             Note::F,
             100.0,
         );
-        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::D, ChordQuality::Minor7), 4.0);
+        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Minor), 4.0);
         pop.push(prog);
 
         // I-iii-IV-iv (Beatles style)
@@ -685,10 +810,10 @@ impl ProgressionLibrary {
             Note::G,
             105.0,
         );
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::B, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Minor7), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::B, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Minor), 4.0);
         pop.push(prog);
 
         // I-ii-iii-IV
@@ -698,10 +823,10 @@ impl ProgressionLibrary {
             Note::A,
             115.0,
         );
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::B, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::Db, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::D, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::B, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::Db, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // vi-V-IV-V
@@ -711,10 +836,10 @@ impl ProgressionLibrary {
             Note::D,
             108.0,
         );
-        prog.add_chord(Chord::new(Note::B, ChordQuality::Minor7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::G, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::A, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::B, ChordQuality::Minor), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::G, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::A, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         // I-bVII-IV (Modal pop)
@@ -724,17 +849,16 @@ impl ProgressionLibrary {
             Note::C,
             120.0,
         );
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::F, ChordQuality::Major7), 4.0);
-        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::Bb, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::F, ChordQuality::Major), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major), 4.0);
         pop.push(prog);
 
         self.progressions.insert("Pop".to_string(), pop);
     }
 
-    fn populate_lego_bricks(&mut self) {
-        let brick_library = BrickLibrary::new();
+    fn populate_lego_bricks(&mut self, brick_library: &BrickLibrary) {
         let mut lego = Vec::new();
 
         // Common jazz keys to generate bricks in
@@ -759,6 +883,22 @@ impl ProgressionLibrary {
         self.progressions.insert("LEGO Bricks".to_string(), lego);
     }
 
+    /// Add a progression under its genre, replacing one of the same name,
+    /// and return its index within the genre
+    pub fn insert(&mut self, progression: Progression) -> usize {
+        let progs = self.progressions.entry(progression.genre.clone()).or_default();
+        match progs.iter().position(|p| p.name == progression.name) {
+            Some(idx) => {
+                progs[idx] = progression;
+                idx
+            }
+            None => {
+                progs.push(progression);
+                progs.len() - 1
+            }
+        }
+    }
+
     pub fn get_by_genre(&self, genre: &str) -> Option<&Vec<Progression>> {
         self.progressions.get(genre)
     }
@@ -783,3 +923,55 @@ impl Default for ProgressionLibrary {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lego_genre_uses_given_bricks() {
+        let mut bricks = BrickLibrary::new();
+        let mut mine = bricks.get("Launcher").unwrap().clone();
+        mine.name = "My Launcher".to_string();
+        bricks.add(mine);
+
+        let library = ProgressionLibrary::with_bricks(&bricks);
+        let names: Vec<&str> = library.get_by_genre("LEGO Bricks").unwrap().iter().map(|p| p.name.as_str()).collect();
+        assert!(names.contains(&"My Launcher (C)"));
+        assert!(!ProgressionLibrary::new().get_by_genre("LEGO Bricks").unwrap().iter().any(|p| p.name.starts_with("My ")));
+    }
+
+    #[test]
+    fn test_transpose_keeps_slash_bass() {
+        let mut prog = Progression::new("Test".to_string(), "Jazz".to_string(), Note::C, 120.0);
+        prog.add_chord(Chord::new(Note::D, ChordQuality::Minor7), 4.0);
+        prog.add_chord(Chord::with_bass(Note::G, ChordQuality::Dominant7, Note::B), 4.0);
+        prog.add_chord(Chord::new(Note::C, ChordQuality::Major7), 8.0);
+
+        let up = prog.transpose(2);
+        assert_eq!(up.key, Note::D);
+        assert_eq!(up.total_duration(), prog.total_duration());
+        let names: Vec<String> = up.changes.iter().map(|c| c.chord.name()).collect();
+        assert_eq!(names, ["Em7", "A7/C#", "Dmaj7"]);
+
+        // Flat keys spell the roots with flats
+        let down = prog.transpose(-2);
+        assert_eq!(down.key, Note::Bb);
+        let names: Vec<String> = down.changes.iter().map(|c| c.chord.name()).collect();
+        assert_eq!(names, ["Cm7", "F7/A", "Bbmaj7"]);
+    }
+
+    #[test]
+    fn test_minor_keys_spell_their_tonic() {
+        let mut prog = Progression::new("Test".to_string(), "Jazz".to_string(), Note::Ab, 120.0).in_minor();
+        prog.add_chord(Chord::new(Note::Bb, ChordQuality::HalfDiminished), 4.0);
+        prog.add_chord(Chord::new(Note::Eb, ChordQuality::Dominant7), 4.0);
+        prog.add_chord(Chord::new(Note::Ab, ChordQuality::Minor7), 8.0);
+        assert_eq!(prog.spelled_key().name(), "G#");
+        let names: Vec<String> = prog.changes.iter().map(|c| c.chord.name()).collect();
+        assert_eq!(names, ["A#m7b5", "D#7", "G#m7"]);
+
+        // Transposing keeps the key minor
+        assert!(prog.transpose(1).minor);
+    }
+}
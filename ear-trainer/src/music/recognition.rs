@@ -0,0 +1,268 @@
+//! Chord Recognition
+//!
+//! The reverse of `Chord::notes`: given the MIDI notes of a voicing, find
+//! the chords it could be. Every root and quality is scored by how well its
+//! chord tones cover the notes, so a voicing with a missing fifth or an
+//! added tension still gets a name, and inversions come out as slash chords.
+//! Rootless voicings are recognized from the bass note played under them.
+
+use super::chord::{Chord, ChordQuality, Note};
+use super::spelling::{Letter, SpelledPitch};
+
+/// Points for each note explained by the chord
+const MATCH_SCORE: i32 = 10;
+/// Penalty for each note that is not a chord tone
+const ADDED_PENALTY: i32 = 8;
+/// Penalty for a missing perfect fifth, which voicings drop all the time
+const NO_FIFTH_PENALTY: i32 = 1;
+/// Penalty for a missing root (rootless voicings)
+const NO_ROOT_PENALTY: i32 = 5;
+/// Penalty for any other missing chord tone
+const OMITTED_PENALTY: i32 = 6;
+/// Bonus when the lowest note is the root
+const ROOT_POSITION_BONUS: i32 = 3;
+
+/// One way of naming a set of notes
+#[derive(Debug, Clone)]
+pub struct ChordCandidate {
+    /// The chord, with a slash bass when the lowest note is not the root
+    pub chord: Chord,
+    /// Higher is a better fit
+    pub score: i32,
+    /// Chord tones that were not played, as degree labels ("5", "root")
+    pub omitted: Vec<&'static str>,
+    /// Notes that are not chord tones, as interval labels above the root ("b9")
+    pub added: Vec<&'static str>,
+    /// The voicing leaves the root out (to the bass, when one is given)
+    pub rootless: bool,
+}
+
+impl ChordCandidate {
+    /// Name with any omissions and additions, e.g. "C7 (no 5, add b9)"
+    pub fn describe(&self) -> String {
+        let mut notes: Vec<String> = self.omitted.iter().map(|d| format!("no {}", d)).collect();
+        notes.extend(self.added.iter().map(|d| format!("add {}", d)));
+        if notes.is_empty() {
+            self.chord.name()
+        } else {
+            format!("{} ({})", self.chord.name(), notes.join(", "))
+        }
+    }
+}
+
+pub struct ChordRecognizer;
+
+impl ChordRecognizer {
+    /// Ranked candidates for a set of MIDI notes, best first
+    ///
+    /// The lowest note is taken as the bass.
+    pub fn recognize(notes: &[u8]) -> Vec<ChordCandidate> {
+        let Some(&lowest) = notes.iter().min() else {
+            return Vec::new();
+        };
+        Self::rank(notes, notes, lowest)
+    }
+
+    /// Candidates for an upper-structure voicing over a separate bass note
+    ///
+    /// This is how rootless voicings are named: the bass supplies the root
+    /// that the voicing leaves out.
+    pub fn recognize_with_bass(voicing: &[u8], bass: u8) -> Vec<ChordCandidate> {
+        let mut notes = voicing.to_vec();
+        notes.push(bass);
+        Self::rank(&notes, voicing, bass)
+    }
+
+    /// Score every root and quality against `notes`
+    ///
+    /// `upper` is the part of the voicing a chord counts as rootless without.
+    fn rank(notes: &[u8], upper: &[u8], bass: u8) -> Vec<ChordCandidate> {
+        let played = pitch_class_set(notes.iter().copied());
+        let upper = pitch_class_set(upper.iter().copied());
+        let bass_pc = bass % 12;
+
+        let mut candidates = Vec::new();
+        for root in 0..12u8 {
+            for quality in ChordQuality::ALL {
+                if let Some(candidate) = score(root, quality, played, upper, bass_pc) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                // Prefer the chord with fewer tones when the fit is equal
+                .then(a.chord.quality.intervals().len().cmp(&b.chord.quality.intervals().len()))
+        });
+        candidates
+    }
+}
+
+/// Bit set of pitch classes
+fn pitch_class_set(notes: impl Iterator<Item = u8>) -> u16 {
+    notes.fold(0, |set, n| set | 1 << (n % 12))
+}
+
+fn score(root: u8, quality: ChordQuality, played: u16, upper: u16, bass: u8) -> Option<ChordCandidate> {
+    let intervals = quality.intervals();
+    let tones = intervals
+        .iter()
+        .fold(0u16, |set, &i| set | 1 << ((root as i8 + i).rem_euclid(12)));
+    let matched = (played & tones).count_ones() as i32;
+    // At least the root or two other tones must be there to call it this chord
+    if matched < 2 || (played & 1 << root == 0 && matched < 3) {
+        return None;
+    }
+
+    let mut score = matched * MATCH_SCORE;
+    let mut omitted = Vec::new();
+    for &interval in &intervals {
+        let pc = (root as i8 + interval).rem_euclid(12) as u8;
+        if played & 1 << pc != 0 {
+            continue;
+        }
+        score -= match interval {
+            0 => NO_ROOT_PENALTY,
+            7 => NO_FIFTH_PENALTY,
+            _ => OMITTED_PENALTY,
+        };
+        omitted.push(degree_label(interval, quality));
+    }
+
+    let mut added = Vec::new();
+    for pc in 0..12u8 {
+        if played & 1 << pc != 0 && tones & 1 << pc == 0 {
+            score -= ADDED_PENALTY;
+            added.push(tension_label((pc + 12 - root) % 12));
+        }
+    }
+
+    if bass == root {
+        score += ROOT_POSITION_BONUS;
+    }
+
+    let spelling = best_spelling(Note::from_midi(root), quality);
+    let mut chord = Chord::spelled(spelling, quality);
+    if bass != root {
+        let bass = Note::from_midi(bass);
+        chord.bass = Some(bass);
+        chord.bass_spelling = Some(chord.spell(bass));
+    }
+
+    Some(ChordCandidate {
+        chord,
+        score,
+        omitted,
+        added,
+        rootless: upper & 1 << root == 0,
+    })
+}
+
+/// Spell a black-key root with whichever of sharp or flat needs fewer accidentals
+fn best_spelling(root: Note, quality: ChordQuality) -> SpelledPitch {
+    let flat = SpelledPitch::from_note(root);
+    if flat.accidental == 0 {
+        return flat;
+    }
+    let sharp = SpelledPitch::new(
+        Letter::ALL[(flat.letter as usize + 6) % 7],
+        flat.accidental + 2,
+    );
+    let accidentals = |spelling: SpelledPitch| -> i32 {
+        Chord::spelled(spelling, quality)
+            .notes()
+            .iter()
+            .map(|n| n.accidental.abs() as i32)
+            .sum()
+    };
+    if accidentals(sharp) < accidentals(flat) {
+        sharp
+    } else {
+        flat
+    }
+}
+
+/// Name of a chord degree, for omissions
+fn degree_label(interval: i8, quality: ChordQuality) -> &'static str {
+    match interval {
+        0 => "root",
+        2 => "2",
+        3 | 4 => "3",
+        5 => "4",
+        6..=8 => "5",
+        9 if quality == ChordQuality::Diminished7 => "7",
+        9 => "6",
+        10 | 11 => "7",
+        13..=15 => "9",
+        17 | 18 => "11",
+        _ => "13",
+    }
+}
+
+/// Name of a non-chord tone by its distance above the root
+fn tension_label(semitones: u8) -> &'static str {
+    match semitones {
+        1 => "b9",
+        2 => "9",
+        3 => "#9",
+        4 => "3",
+        5 => "11",
+        6 => "#11",
+        7 => "5",
+        8 => "b13",
+        9 => "13",
+        10 => "b7",
+        11 => "maj7",
+        _ => "root",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::VoicingType;
+
+    fn best(notes: &[u8]) -> String {
+        ChordRecognizer::recognize(notes)[0].describe()
+    }
+
+    #[test]
+    fn test_root_position_and_inversions() {
+        assert_eq!(best(&[60, 64, 67]), "C");
+        assert_eq!(best(&[60, 64, 67, 71, 74]), "Cmaj9");
+        assert_eq!(best(&[48, 64, 67, 69]), "C6");
+        assert_eq!(best(&[52, 60, 67]), "C/E");
+        assert_eq!(best(&[47, 53, 55, 62]), "G7/B");
+        assert_eq!(best(&[50, 53, 57, 60]), "Dm7");
+        assert_eq!(best(&[61, 64, 68]), "C#m");
+        assert_eq!(best(&[55, 59, 65]), "G7 (no 5)");
+        assert_eq!(best(&[48, 52, 58, 61]), "C7b9 (no 5)");
+        assert!(ChordRecognizer::recognize(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_rootless_voicings() {
+        // F A C E alone is Fmaj7; over a D bass it is a rootless Dm9
+        assert_eq!(best(&[65, 69, 72, 76]), "Fmaj7");
+        let candidates = ChordRecognizer::recognize(&[65, 69, 72, 76]);
+        assert!(candidates
+            .iter()
+            .any(|c| c.chord.root == Note::D && c.chord.quality == ChordQuality::Minor9 && c.rootless));
+
+        let dm9 = Chord::new(Note::D, ChordQuality::Minor9);
+        let voiced = VoicingType::RootlessA.voice_chord(&dm9, 2, 4, (36, 84));
+        let top = &ChordRecognizer::recognize_with_bass(&voiced.voicing, voiced.bass)[0];
+        assert_eq!(top.describe(), "Dm9");
+        assert!(top.rootless);
+
+        // Every voicing type of G13 still reads as a G chord
+        let g13 = Chord::new(Note::G, ChordQuality::Dominant13);
+        for voicing in [VoicingType::Full, VoicingType::Shell, VoicingType::RootlessA, VoicingType::RootlessB] {
+            let voiced = voicing.voice_chord(&g13, 2, 4, (36, 84));
+            let top = &ChordRecognizer::recognize_with_bass(&voiced.voicing, voiced.bass)[0];
+            assert_eq!(top.chord.root, Note::G, "{:?} gave {}", voicing, top.describe());
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! Reharmonization
+//!
+//! Applies common jazz substitutions to a progression: tritone subs,
+//! ii-V insertion, the backdoor ii-V, diminished passing chords, Coltrane
+//! changes and relative major/minor swaps. Every change is recorded so the
+//! new version can be compared with the original, by eye or by ear.
+
+use super::chord::{Chord, ChordQuality};
+use super::progression::{ChordChange, Progression};
+use serde::Serialize;
+
+/// A reharmonization technique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+pub enum Substitution {
+    /// Dominant a tritone away, resolving down a half step (G7 → Db7)
+    #[default]
+    TritoneSub,
+    /// The ii-V of a chord in the second half of the chord before it
+    IiVInsertion,
+    /// ivm7 - bVII7 in place of ii-V into a major tonic (Fm7 Bb7 → C)
+    Backdoor,
+    /// Diminished seventh between chords a whole step apart (C C#°7 Dm7)
+    DiminishedPassing,
+    /// Major-third cycle through the ii-V of a major tonic
+    Coltrane,
+    /// Major chord for its relative minor and back (Cmaj7 ↔ Am7)
+    RelativeSwap,
+}
+
+impl Substitution {
+    pub const ALL: [Substitution; 6] = [
+        Substitution::TritoneSub,
+        Substitution::IiVInsertion,
+        Substitution::Backdoor,
+        Substitution::DiminishedPassing,
+        Substitution::Coltrane,
+        Substitution::RelativeSwap,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Substitution::TritoneSub => "Tritone Sub",
+            Substitution::IiVInsertion => "ii-V Insertion",
+            Substitution::Backdoor => "Backdoor ii-V",
+            Substitution::DiminishedPassing => "Diminished Passing",
+            Substitution::Coltrane => "Coltrane",
+            Substitution::RelativeSwap => "Relative Swap",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// One change made to the progression
+#[derive(Debug, Clone, Serialize)]
+pub struct ReharmChange {
+    pub substitution: Substitution,
+    /// Beat the replaced chords started on
+    pub beat: f32,
+    /// Chords that were there before
+    pub from: Vec<String>,
+    /// Chords that replace them
+    pub to: Vec<String>,
+    pub reason: String,
+}
+
+impl ReharmChange {
+    /// One-line summary, e.g. "G7 → Db7: resolves down a half step to Cmaj7"
+    pub fn describe(&self) -> String {
+        format!("{} → {}: {}", self.from.join(" "), self.to.join(" "), self.reason)
+    }
+}
+
+/// A reharmonized progression with what was changed
+#[derive(Debug, Clone, Serialize)]
+pub struct Reharmonization {
+    pub progression: Progression,
+    pub changes: Vec<ReharmChange>,
+}
+
+pub struct Reharmonizer;
+
+impl Reharmonizer {
+    /// Apply substitutions in order, each to the result of the one before
+    pub fn apply(progression: &Progression, substitutions: &[Substitution]) -> Reharmonization {
+        let mut changes = progression.changes.clone();
+        let mut diff = Vec::new();
+        for &substitution in substitutions {
+            changes = apply_one(&changes, substitution, &mut diff);
+        }
+
+        let names: Vec<&str> = substitutions.iter().map(|s| s.name()).collect();
+        let mut reharmonized = progression.clone();
+        reharmonized.name = format!("{} ({})", progression.name, names.join(", "));
+        reharmonized.changes = changes;
+        Reharmonization {
+            progression: reharmonized,
+            changes: diff,
+        }
+    }
+}
+
+/// Chord on an interval above `from`, spelled by letter steps from its root
+fn above(from: &Chord, steps: u8, semitones: i8, quality: ChordQuality) -> Chord {
+    Chord::spelled(from.spelled_root().up(steps, semitones), quality)
+}
+
+fn semitones_up(from: &Chord, to: &Chord) -> i8 {
+    (to.root as i8 - from.root as i8).rem_euclid(12)
+}
+
+fn is_major(quality: ChordQuality) -> bool {
+    let intervals = quality.intervals();
+    !quality.is_dominant() && intervals.contains(&4) && intervals.contains(&7)
+}
+
+fn is_plain_minor(quality: ChordQuality) -> bool {
+    quality.is_minor() && quality.intervals().contains(&7)
+}
+
+fn change(chord: Chord, duration: f32) -> ChordChange {
+    ChordChange { chord, duration }
+}
+
+fn names(changes: &[ChordChange]) -> Vec<String> {
+    changes.iter().map(|c| c.chord.name()).collect()
+}
+
+fn apply_one(changes: &[ChordChange], substitution: Substitution, diff: &mut Vec<ReharmChange>) -> Vec<ChordChange> {
+    let n = changes.len();
+    // Looping progressions resolve from the last chord into the first
+    let ahead = |i: usize, steps: usize| if n > 1 { Some(&changes[(i + steps) % n]) } else { None };
+
+    let mut out: Vec<ChordChange> = Vec::with_capacity(n);
+    let mut beat = 0.0;
+    let mut i = 0;
+    while i < n {
+        let current = &changes[i];
+        let (consumed, replacement) = match substitution {
+            Substitution::TritoneSub => (1, tritone_sub(current, ahead(i, 1))),
+            Substitution::IiVInsertion => (1, ii_v_insertion(current, changes.get(i + 1))),
+            Substitution::Backdoor => backdoor(current, changes.get(i + 1), ahead(i, 1), ahead(i, 2)),
+            Substitution::DiminishedPassing => (1, diminished_passing(current, changes.get(i + 1))),
+            Substitution::Coltrane => coltrane(current, changes.get(i + 1), changes.get(i + 2)),
+            Substitution::RelativeSwap => (1, relative_swap(current, ahead(i, 1))),
+        };
+
+        let old = &changes[i..i + consumed];
+        let duration: f32 = old.iter().map(|c| c.duration).sum();
+        match replacement {
+            Some((new, reason)) => {
+                diff.push(ReharmChange {
+                    substitution,
+                    beat,
+                    from: names(old),
+                    to: names(&new),
+                    reason,
+                });
+                out.extend(new);
+            }
+            None => out.extend_from_slice(old),
+        }
+        beat += duration;
+        i += consumed;
+    }
+    out
+}
+
+/// New chords for a change, and why
+type Replacement = Option<(Vec<ChordChange>, String)>;
+
+fn tritone_sub(current: &ChordChange, next: Option<&ChordChange>) -> Replacement {
+    let next = next?;
+    if !current.chord.quality.is_dominant() || semitones_up(&current.chord, &next.chord) != 5 {
+        return None;
+    }
+    let quality = match current.chord.quality {
+        q @ (ChordQuality::Dominant9 | ChordQuality::Dominant13) => q,
+        _ => ChordQuality::Dominant7,
+    };
+    Some((
+        vec![change(above(&next.chord, 1, 1, quality), current.duration)],
+        format!("resolves down a half step to {}", next.chord.name()),
+    ))
+}
+
+fn ii_v_insertion(current: &ChordChange, target: Option<&ChordChange>) -> Replacement {
+    let target = target?;
+    let q = target.chord.quality;
+    // Skip targets the chord before already leads into (V, subV, ii or the same root)
+    let already_approached = matches!(
+        (semitones_up(&target.chord, &current.chord), current.chord.quality.is_dominant()),
+        (0, _) | (7, true) | (1, true) | (2, false)
+    );
+    if current.duration < 4.0 || !(is_major(q) || is_plain_minor(q)) || already_approached {
+        return None;
+    }
+
+    let (ii, v) = if q.is_minor() {
+        (ChordQuality::HalfDiminished, ChordQuality::Dominant7b9)
+    } else {
+        (ChordQuality::Minor7, ChordQuality::Dominant7)
+    };
+    let d = current.duration;
+    Some((
+        vec![
+            change(current.chord.clone(), d / 2.0),
+            change(above(&target.chord, 1, 2, ii), d / 4.0),
+            change(above(&target.chord, 4, 7, v), d / 4.0),
+        ],
+        format!("ii-V of {} leads into it", target.chord.name()),
+    ))
+}
+
+/// Backdoor ii-V for a ii-V or a lone V into a major chord
+fn backdoor(
+    current: &ChordChange,
+    following: Option<&ChordChange>,
+    next: Option<&ChordChange>,
+    after_next: Option<&ChordChange>,
+) -> (usize, Replacement) {
+    let resolves = |v: &ChordChange, target: &ChordChange| {
+        v.chord.quality.is_dominant() && semitones_up(&v.chord, &target.chord) == 5 && is_major(target.chord.quality)
+    };
+    let approach = |target: &Chord| {
+        (
+            above(target, 3, 5, ChordQuality::Minor7),
+            above(target, 6, 10, ChordQuality::Dominant7),
+            format!("approaches {} from the backdoor", target.name()),
+        )
+    };
+
+    // ii-V: replace both, keeping their lengths
+    if let (Some(v), Some(target)) = (following, after_next) {
+        if is_plain_minor(current.chord.quality) && semitones_up(&current.chord, &v.chord) == 5 && resolves(v, target) {
+            let (iv, bvii, reason) = approach(&target.chord);
+            return (2, Some((vec![change(iv, current.duration), change(bvii, v.duration)], reason)));
+        }
+    }
+
+    // Lone V: split it, or just swap it when short
+    let Some(target) = next.filter(|target| resolves(current, target)) else {
+        return (1, None);
+    };
+    let (iv, bvii, reason) = approach(&target.chord);
+    let new = if current.duration >= 2.0 {
+        let half = current.duration / 2.0;
+        vec![change(iv, half), change(bvii, half)]
+    } else {
+        vec![change(bvii, current.duration)]
+    };
+    (1, Some((new, reason)))
+}
+
+fn diminished_passing(current: &ChordChange, next: Option<&ChordChange>) -> Replacement {
+    let next = next?;
+    if current.duration < 2.0 || semitones_up(&current.chord, &next.chord) != 2 {
+        return None;
+    }
+    let half = current.duration / 2.0;
+    let passing = above(&current.chord, 0, 1, ChordQuality::Diminished7);
+    let reason = format!(
+        "passes chromatically from {} up to {}",
+        current.chord.spelled_root(),
+        next.chord.spelled_root()
+    );
+    Some((vec![change(current.chord.clone(), half), change(passing, half)], reason))
+}
+
+/// ii-V-I becomes ii bIII7 | bVImaj7 VII7 IIImaj7 V7 | I, as in "Countdown"
+fn coltrane(ii: &ChordChange, v: Option<&ChordChange>, target: Option<&ChordChange>) -> (usize, Replacement) {
+    let (Some(v), Some(target)) = (v, target) else {
+        return (1, None);
+    };
+    let is_ii_v_i = is_plain_minor(ii.chord.quality)
+        && v.chord.quality.is_dominant()
+        && semitones_up(&ii.chord, &v.chord) == 5
+        && semitones_up(&v.chord, &target.chord) == 5
+        && is_major(target.chord.quality);
+    if !is_ii_v_i || ii.duration < 2.0 || v.duration < 4.0 {
+        return (1, None);
+    }
+
+    let (a, b) = (ii.duration / 2.0, v.duration / 4.0);
+    let t = &target.chord;
+    let new = vec![
+        change(ii.chord.clone(), a),
+        change(above(t, 2, 3, ChordQuality::Dominant7), a),
+        change(above(t, 5, 8, ChordQuality::Major7), b),
+        change(above(t, 6, 11, ChordQuality::Dominant7), b),
+        change(above(t, 2, 4, ChordQuality::Major7), b),
+        change(above(t, 4, 7, ChordQuality::Dominant7), b),
+    ];
+    (2, Some((new, format!("major-third cycle into {}", t.name()))))
+}
+
+fn relative_swap(current: &ChordChange, next: Option<&ChordChange>) -> Replacement {
+    let q = current.chord.quality;
+    // A ii stays a ii when its V (or the tritone sub) follows
+    let is_ii = next.is_some_and(|next| {
+        next.chord.quality.is_dominant() && matches!(semitones_up(&current.chord, &next.chord), 5 | 11)
+    });
+
+    let (swapped, relation) = if is_major(q) {
+        let quality = if q == ChordQuality::Major { ChordQuality::Minor } else { ChordQuality::Minor7 };
+        (above(&current.chord, 5, 9, quality), "minor")
+    } else if is_plain_minor(q) && !is_ii {
+        let quality = if q == ChordQuality::Minor { ChordQuality::Major } else { ChordQuality::Major7 };
+        (above(&current.chord, 2, 3, quality), "major")
+    } else {
+        return None;
+    };
+    let reason = format!("relative {} of {}", relation, current.chord.name());
+    Some((vec![change(swapped, current.duration)], reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::Note;
+
+    fn progression(chords: &[(&str, f32)]) -> Progression {
+        let mut prog = Progression::new("Test".to_string(), "Jazz".to_string(), Note::C, 120.0);
+        for (chord, beats) in chords {
+            prog.add_chord(chord.parse().unwrap(), *beats);
+        }
+        prog
+    }
+
+    fn chords(reharm: &Reharmonization) -> Vec<String> {
+        reharm.progression.changes.iter().map(|c| format!("{} {}", c.chord.name(), c.duration)).collect()
+    }
+
+    #[test]
+    fn test_dominant_substitutions() {
+        let prog = progression(&[("Dm7", 4.0), ("G7", 4.0), ("Cmaj7", 8.0)]);
+
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::TritoneSub]);
+        assert_eq!(chords(&reharm), vec!["Dm7 4", "Db7 4", "Cmaj7 8"]);
+        assert_eq!(reharm.changes.len(), 1);
+        assert_eq!(reharm.changes[0].beat, 4.0);
+        assert_eq!(reharm.changes[0].describe(), "G7 → Db7: resolves down a half step to Cmaj7");
+        assert_eq!(reharm.progression.name, "Test (Tritone Sub)");
+
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::Backdoor]);
+        assert_eq!(chords(&reharm), vec!["Fm7 4", "Bb7 4", "Cmaj7 8"]);
+        assert_eq!(reharm.changes[0].from, vec!["Dm7", "G7"]);
+        assert_eq!(reharm.changes[0].beat, 0.0);
+
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::Coltrane]);
+        assert_eq!(
+            chords(&reharm),
+            vec!["Dm7 2", "Eb7 2", "Abmaj7 1", "B7 1", "Emaj7 1", "G7 1", "Cmaj7 8"]
+        );
+    }
+
+    #[test]
+    fn test_inserted_and_swapped_chords() {
+        let prog = progression(&[("Cmaj7", 8.0), ("Dm7", 4.0), ("Em7", 4.0)]);
+
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::IiVInsertion]);
+        assert_eq!(
+            chords(&reharm),
+            vec!["Cmaj7 4", "Em7b5 2", "A7b9 2", "Dm7 2", "F#m7b5 1", "B7b9 1", "Em7 4"]
+        );
+        assert_eq!(reharm.changes.len(), 2);
+        assert_eq!(reharm.changes[1].beat, 8.0);
+
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::DiminishedPassing]);
+        assert_eq!(
+            chords(&reharm),
+            vec!["Cmaj7 4", "C#dim7 4", "Dm7 2", "D#dim7 2", "Em7 4"]
+        );
+
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::RelativeSwap]);
+        assert_eq!(chords(&reharm), vec!["Am7 8", "Fmaj7 4", "Gmaj7 4"]);
+
+        // The last chord resolves into the first when the progression loops
+        let turnaround = progression(&[("Cmaj7", 4.0), ("Am7", 4.0), ("Dm7", 4.0), ("G7", 4.0)]);
+        let reharm = Reharmonizer::apply(&turnaround, &[Substitution::TritoneSub]);
+        assert_eq!(chords(&reharm), vec!["Cmaj7 4", "Am7 4", "Dm7 4", "Db7 4"]);
+
+        // Substitutions stack, each working on the last result
+        let prog = progression(&[("Dm7", 4.0), ("G7", 4.0), ("Cmaj7", 8.0)]);
+        let reharm = Reharmonizer::apply(&prog, &[Substitution::TritoneSub, Substitution::RelativeSwap]);
+        assert_eq!(chords(&reharm), vec!["Dm7 4", "Db7 4", "Am7 8"]);
+        assert_eq!(reharm.changes.len(), 2);
+    }
+}
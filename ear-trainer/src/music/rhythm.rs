@@ -3,6 +3,8 @@
 //! Provides basic rhythmic patterns for chord playback,
 //! inspired by Band-in-a-Box style accompaniment.
 
+use super::humanize::{Humanizer, Part};
+use super::meter::Meter;
 use serde::{Deserialize, Serialize};
 
 /// A single hit in a rhythm pattern
@@ -22,6 +24,10 @@ impl RhythmHit {
     }
 }
 
+/// Afro-Cuban bell pattern across a bar of 12/8, in eighths (6/8 takes the
+/// first half)
+pub(super) const BELL: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+
 /// Rhythm pattern style
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum RhythmStyle {
@@ -41,8 +47,93 @@ pub enum RhythmStyle {
 }
 
 impl RhythmStyle {
-    /// Get the pattern for one bar (4 beats)
-    pub fn pattern(&self) -> Vec<RhythmHit> {
+    /// Get the pattern for one bar of the meter
+    pub fn pattern(&self, meter: Meter) -> Vec<RhythmHit> {
+        if meter == Meter::COMMON {
+            return self.common_time();
+        }
+        let bar = meter.bar_beats();
+        let groups = meter.group_spans();
+        let accent = |beat: f32| meter.accent(beat);
+        // An eighth note is half a beat in x/4 and a whole beat in x/8
+        let eighth = if meter.unit == 8 { 1.0 } else { 0.5 };
+
+        let mut hits = match self {
+            RhythmStyle::Whole => vec![RhythmHit::new(0.0, 0.8, bar - 0.5)],
+
+            // The pulse: every beat, or every dotted quarter
+            RhythmStyle::Quarter => meter
+                .pulses()
+                .into_iter()
+                .map(|beat| RhythmHit::new(beat, 0.8 * accent(beat), 0.8))
+                .collect(),
+
+            // Downbeat, then anticipate each following group by an eighth;
+            // the waltz answers on the "and" of two
+            RhythmStyle::JazzBasic => {
+                let mut hits = vec![RhythmHit::new(0.0, 0.85, 1.0)];
+                hits.extend(groups[1..].iter().map(|&(start, _)| RhythmHit::new(start - eighth, 0.7, 0.8)));
+                if groups.len() == 1 {
+                    hits.push(RhythmHit::new(1.5, 0.7, 0.8));
+                }
+                hits
+            }
+
+            // Each group's start and an offbeat: the "and" of the next beat,
+            // or the last eighth of a dotted quarter
+            RhythmStyle::SwingComp => groups
+                .iter()
+                .flat_map(|&(start, _)| {
+                    let skip = if meter.is_compound() { start + 2.0 } else { start + 1.5 };
+                    [
+                        RhythmHit::new(start, 0.8 * accent(start), 0.5),
+                        RhythmHit::new(skip, 0.6, 0.4),
+                    ]
+                })
+                .filter(|hit| hit.beat < bar)
+                .collect(),
+
+            // Simple meters: each group start and the "and" before the next;
+            // compound meters: the 6/8 bell
+            RhythmStyle::BossaNova => {
+                if meter.is_compound() {
+                    BELL.iter()
+                        .map(|&beat| beat as f32)
+                        .filter(|&beat| beat < bar)
+                        .map(|beat| RhythmHit::new(beat, 0.7 * accent(beat), 0.4))
+                        .collect()
+                } else {
+                    groups
+                        .iter()
+                        .flat_map(|&(start, len)| {
+                            [
+                                RhythmHit::new(start, 0.75 * accent(start), 0.4),
+                                RhythmHit::new(start + len - eighth, 0.6, 0.4),
+                            ]
+                        })
+                        .collect()
+                }
+            }
+
+            // Short stabs: each group start, a push a sixteenth after the
+            // "and", and a pickup before the next group
+            RhythmStyle::Funk => groups
+                .iter()
+                .flat_map(|&(start, len)| {
+                    [
+                        RhythmHit::new(start, 0.9 * accent(start), 0.2),
+                        RhythmHit::new(start + 1.5 * eighth, 0.55, 0.2),
+                        RhythmHit::new(start + len - eighth, 0.6, 0.2),
+                    ]
+                })
+                .collect(),
+        };
+        hits.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+        hits
+    }
+
+    /// The 4/4 patterns
+    fn common_time(&self) -> Vec<RhythmHit> {
         match self {
             RhythmStyle::Whole => vec![
                 // Just one hit on beat 1, sustained
@@ -67,9 +158,9 @@ impl RhythmStyle {
             RhythmStyle::SwingComp => vec![
                 // More active swing comping
                 RhythmHit::new(0.0, 0.8, 0.5),    // Beat 1
-                RhythmHit::new(1.33, 0.6, 0.4),   // Swung "and" of 1 (triplet feel)
+                RhythmHit::new(1.5, 0.6, 0.4),    // "And" of 2
                 RhythmHit::new(2.0, 0.75, 0.5),   // Beat 3
-                RhythmHit::new(3.33, 0.65, 0.4),  // Swung "and" of 3
+                RhythmHit::new(3.5, 0.65, 0.4),   // "And" of 4
             ],
 
             RhythmStyle::BossaNova => vec![
@@ -107,6 +198,12 @@ impl RhythmStyle {
         }
     }
 
+    /// Whether the style is played with a swing feel; the others stay
+    /// straight whatever the swing setting
+    pub fn swings(&self) -> bool {
+        matches!(self, RhythmStyle::JazzBasic | RhythmStyle::SwingComp)
+    }
+
     /// Get display name
     pub fn name(&self) -> &'static str {
         match self {
@@ -125,6 +222,10 @@ impl RhythmStyle {
 pub struct RhythmState {
     /// Current rhythm style
     pub style: RhythmStyle,
+    /// Time signature the pattern is written for
+    meter: Meter,
+    /// Swing ratio applied to off-beat eighths (0.5 = straight)
+    swing: f32,
     /// Current pattern
     pattern: Vec<RhythmHit>,
     /// Index of next hit to play
@@ -136,9 +237,12 @@ pub struct RhythmState {
 impl RhythmState {
     pub fn new() -> Self {
         let style = RhythmStyle::default();
+        let meter = Meter::default();
         Self {
-            pattern: style.pattern(),
+            pattern: style.pattern(meter),
             style,
+            meter,
+            swing: 0.5,
             next_hit_idx: 0,
             last_beat: -1.0,
         }
@@ -147,10 +251,23 @@ impl RhythmState {
     /// Set the rhythm style
     pub fn set_style(&mut self, style: RhythmStyle) {
         self.style = style;
-        self.pattern = style.pattern();
+        self.pattern = style.pattern(self.meter);
         self.reset();
     }
 
+    /// Switch to the patterns for another time signature
+    pub fn set_meter(&mut self, meter: Meter) {
+        if meter != self.meter {
+            self.meter = meter;
+            self.set_style(self.style);
+        }
+    }
+
+    /// Swing the off-beat eighths of swing styles by a ratio (0.5 = straight)
+    pub fn set_swing(&mut self, ratio: f32) {
+        self.swing = ratio;
+    }
+
     /// Cycle to next style
     pub fn cycle_style(&mut self) {
         self.set_style(self.style.next());
@@ -162,6 +279,16 @@ impl RhythmState {
         self.last_beat = -1.0;
     }
 
+    /// Re-roll the pattern's timing and velocities from the style's grid
+    pub fn humanize(&mut self, humanizer: &mut Humanizer, beat_ms: f32) {
+        self.pattern = self.style.pattern(self.meter);
+        for hit in &mut self.pattern {
+            hit.beat = humanizer.shift_beat(Part::Comping, hit.beat, beat_ms);
+            hit.velocity = humanizer.velocity(Part::Comping, hit.velocity);
+        }
+        self.pattern.sort_by(|a, b| a.beat.total_cmp(&b.beat));
+    }
+
     /// Check if we should play a hit at the current beat position
     /// Returns Some((velocity, duration)) if we should play, None otherwise
     pub fn check_hit(&mut self, current_beat: f32, beats_in_bar: f32) -> Option<(f32, f32)> {
@@ -172,11 +299,13 @@ impl RhythmState {
         // Wrap beat position to bar
         let beat_in_bar = current_beat % beats_in_bar;
 
-        // Don't trigger on same beat twice
-        if (beat_in_bar - self.last_beat).abs() < 0.05 {
-            return None;
+        // Start the pattern over when a new bar begins
+        if beat_in_bar < self.last_beat {
+            self.reset();
         }
 
+        let swing = if self.style.swings() { self.swing } else { 0.5 };
+
         // Find if any hit should trigger
         for (i, hit) in self.pattern.iter().enumerate() {
             // Skip already played hits
@@ -184,8 +313,8 @@ impl RhythmState {
                 continue;
             }
 
-            // Check if we've passed this hit's beat
-            let hit_beat = hit.beat % beats_in_bar;
+            // Check if we've passed this hit's beat, once swung
+            let hit_beat = self.meter.swing(hit.beat, swing) % beats_in_bar;
             if beat_in_bar >= hit_beat && self.last_beat < hit_beat {
                 self.next_hit_idx = i + 1;
                 self.last_beat = beat_in_bar;
@@ -193,11 +322,6 @@ impl RhythmState {
             }
         }
 
-        // Reset if we've wrapped around
-        if beat_in_bar < self.last_beat {
-            self.next_hit_idx = 0;
-        }
-
         self.last_beat = beat_in_bar;
         None
     }
@@ -223,17 +347,53 @@ mod tests {
             RhythmStyle::BossaNova,
             RhythmStyle::Funk,
         ] {
-            let pattern = style.pattern();
-            assert!(!pattern.is_empty(), "{:?} pattern should not be empty", style);
-
-            // All beats should be within 0-4
-            for hit in &pattern {
-                assert!(hit.beat >= 0.0 && hit.beat < 4.0,
-                    "{:?} hit beat {} out of range", style, hit.beat);
-                assert!(hit.velocity > 0.0 && hit.velocity <= 1.0,
-                    "{:?} hit velocity {} out of range", style, hit.velocity);
+            for meter in Meter::ALL {
+                let pattern = style.pattern(meter);
+                assert!(!pattern.is_empty(), "{:?} pattern should not be empty", style);
+                assert_eq!(pattern[0].beat, 0.0, "{:?} in {} should start on the downbeat", style, meter);
+
+                // All beats should be within the bar
+                for hit in &pattern {
+                    assert!(hit.beat >= 0.0 && hit.beat < meter.bar_beats(),
+                        "{:?} in {} hit beat {} out of range", style, meter, hit.beat);
+                    assert!(hit.velocity > 0.0 && hit.velocity <= 1.0,
+                        "{:?} hit velocity {} out of range", style, hit.velocity);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_meter_patterns() {
+        let beats = |style: RhythmStyle, meter| -> Vec<f32> {
+            style.pattern(meter).iter().map(|hit| hit.beat).collect()
+        };
+        // 5/4 is comped 3+2, with the push before beat four
+        assert_eq!(beats(RhythmStyle::JazzBasic, Meter::FIVE_FOUR), vec![0.0, 2.5]);
+        assert_eq!(beats(RhythmStyle::JazzBasic, Meter::WALTZ), vec![0.0, 1.5]);
+        // Compound meters pulse in dotted quarters and play the bell
+        assert_eq!(beats(RhythmStyle::Quarter, Meter::TWELVE_EIGHT), vec![0.0, 3.0, 6.0, 9.0]);
+        assert_eq!(beats(RhythmStyle::BossaNova, Meter::SIX_EIGHT), vec![0.0, 2.0, 4.0, 5.0]);
+
+        // Group starts are accented over the beats between them
+        let quarter = RhythmStyle::Quarter.pattern(Meter::SEVEN_FOUR);
+        assert!(quarter[3].velocity > quarter[4].velocity);
+    }
+
+    #[test]
+    fn test_pattern_repeats_each_bar() {
+        let mut state = RhythmState::new();
+        state.set_meter(Meter::WALTZ);
+        state.set_style(RhythmStyle::Quarter);
+
+        // Six beats of one chord play the waltz bar twice
+        let mut hits = 0;
+        for tick in 0..60 {
+            if state.check_hit(tick as f32 * 0.1, Meter::WALTZ.bar_beats()).is_some() {
+                hits += 1;
             }
         }
+        assert_eq!(hits, 6);
     }
 
     #[test]
@@ -1,4 +1,5 @@
 use super::chord::{Chord, ChordQuality, Note};
+use super::spelling::SpelledPitch;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -23,9 +24,61 @@ pub enum ScaleType {
     MajorPentatonic,
     BluesMajor,
     BluesMinor,
+    /// Third mode of melodic minor
+    LydianAugmented,
+    /// Sixth mode of melodic minor
+    LocrianNatural2,
+    /// Second mode of melodic minor
+    DorianFlat2,
+    /// Fifth mode of melodic minor
+    MixolydianFlat6,
+    /// Fifth mode of harmonic minor
+    PhrygianDominant,
+    /// Mixolydian with a passing major 7th
+    BebopDominant,
+    /// Major with a passing #5
+    BebopMajor,
+    /// Dorian with a passing major 3rd
+    BebopMinor,
+    /// Alternating minor 3rds and half steps
+    Augmented,
+    HungarianMinor,
 }
 
 impl ScaleType {
+    /// All scale types, in declaration order
+    pub const ALL: [ScaleType; 29] = [
+        ScaleType::Major,
+        ScaleType::NaturalMinor,
+        ScaleType::HarmonicMinor,
+        ScaleType::MelodicMinor,
+        ScaleType::Dorian,
+        ScaleType::Phrygian,
+        ScaleType::Lydian,
+        ScaleType::Mixolydian,
+        ScaleType::Aeolian,
+        ScaleType::Locrian,
+        ScaleType::LydianDominant,
+        ScaleType::Altered,
+        ScaleType::WholeTone,
+        ScaleType::DiminishedHalfWhole,
+        ScaleType::DiminishedWholeHalf,
+        ScaleType::MinorPentatonic,
+        ScaleType::MajorPentatonic,
+        ScaleType::BluesMajor,
+        ScaleType::BluesMinor,
+        ScaleType::LydianAugmented,
+        ScaleType::LocrianNatural2,
+        ScaleType::DorianFlat2,
+        ScaleType::MixolydianFlat6,
+        ScaleType::PhrygianDominant,
+        ScaleType::BebopDominant,
+        ScaleType::BebopMajor,
+        ScaleType::BebopMinor,
+        ScaleType::Augmented,
+        ScaleType::HungarianMinor,
+    ];
+
     pub fn intervals(&self) -> Vec<i8> {
         match self {
             ScaleType::Major => vec![0, 2, 4, 5, 7, 9, 11],
@@ -47,6 +100,37 @@ impl ScaleType {
             ScaleType::MajorPentatonic => vec![0, 2, 4, 7, 9],
             ScaleType::BluesMajor => vec![0, 2, 3, 4, 7, 9],
             ScaleType::BluesMinor => vec![0, 3, 5, 6, 7, 10],
+            ScaleType::LydianAugmented => vec![0, 2, 4, 6, 8, 9, 11],
+            ScaleType::LocrianNatural2 => vec![0, 2, 3, 5, 6, 8, 10],
+            ScaleType::DorianFlat2 => vec![0, 1, 3, 5, 7, 9, 10],
+            ScaleType::MixolydianFlat6 => vec![0, 2, 4, 5, 7, 8, 10],
+            ScaleType::PhrygianDominant => vec![0, 1, 4, 5, 7, 8, 10],
+            ScaleType::BebopDominant => vec![0, 2, 4, 5, 7, 9, 10, 11],
+            ScaleType::BebopMajor => vec![0, 2, 4, 5, 7, 8, 9, 11],
+            ScaleType::BebopMinor => vec![0, 2, 3, 4, 5, 7, 9, 10],
+            ScaleType::Augmented => vec![0, 3, 4, 7, 8, 11],
+            ScaleType::HungarianMinor => vec![0, 2, 3, 6, 7, 8, 11],
+        }
+    }
+
+    /// Letters above the root for each scale degree
+    ///
+    /// Seven-note scales use every letter once; the others skip or repeat
+    /// letters the way they are usually written (C D E F# G# Bb, C Eb F Gb G Bb).
+    pub fn letter_steps(&self) -> Vec<u8> {
+        match self {
+            ScaleType::WholeTone => vec![0, 1, 2, 3, 4, 6],
+            ScaleType::DiminishedHalfWhole => vec![0, 1, 1, 2, 3, 4, 5, 6],
+            ScaleType::DiminishedWholeHalf => vec![0, 1, 2, 3, 4, 5, 5, 6],
+            ScaleType::MinorPentatonic => vec![0, 2, 3, 4, 6],
+            ScaleType::MajorPentatonic => vec![0, 1, 2, 4, 5],
+            ScaleType::BluesMajor => vec![0, 1, 2, 2, 4, 5],
+            ScaleType::BluesMinor => vec![0, 2, 3, 4, 4, 6],
+            ScaleType::BebopDominant => vec![0, 1, 2, 3, 4, 5, 6, 6],
+            ScaleType::BebopMajor => vec![0, 1, 2, 3, 4, 4, 5, 6],
+            ScaleType::BebopMinor => vec![0, 1, 2, 2, 3, 4, 5, 6],
+            ScaleType::Augmented => vec![0, 2, 2, 4, 5, 6],
+            _ => (0..7).collect(),
         }
     }
 
@@ -71,6 +155,27 @@ impl ScaleType {
             ScaleType::MajorPentatonic => "Major Pentatonic",
             ScaleType::BluesMajor => "Blues Major",
             ScaleType::BluesMinor => "Blues Minor",
+            ScaleType::LydianAugmented => "Lydian Augmented",
+            ScaleType::LocrianNatural2 => "Locrian Natural 2",
+            ScaleType::DorianFlat2 => "Dorian b2",
+            ScaleType::MixolydianFlat6 => "Mixolydian b6",
+            ScaleType::PhrygianDominant => "Phrygian Dominant",
+            ScaleType::BebopDominant => "Bebop Dominant",
+            ScaleType::BebopMajor => "Bebop Major",
+            ScaleType::BebopMinor => "Bebop Minor",
+            ScaleType::Augmented => "Augmented",
+            ScaleType::HungarianMinor => "Hungarian Minor",
+        }
+    }
+
+    /// Chromatic notes added to a seven-note scale so chord tones fall on
+    /// the beat in eighth-note lines, as intervals from the root
+    pub fn passing_tones(&self) -> Vec<i8> {
+        match self {
+            ScaleType::BebopDominant => vec![11],
+            ScaleType::BebopMajor => vec![8],
+            ScaleType::BebopMinor => vec![4],
+            _ => Vec::new(),
         }
     }
 }
@@ -81,6 +186,75 @@ impl fmt::Display for ScaleType {
     }
 }
 
+/// Groups of scales to learn by ear, easiest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleTier {
+    /// The seven modes of the major scale
+    #[default]
+    MajorModes,
+    /// The seven modes of melodic minor
+    MelodicMinorModes,
+    /// Scales built from a repeating pattern
+    Symmetric,
+    /// Every scale type; Natural Minor is left out as it sounds the same
+    /// as Aeolian
+    Everything,
+}
+
+impl ScaleTier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScaleTier::MajorModes => "Major modes",
+            ScaleTier::MelodicMinorModes => "Melodic minor modes",
+            ScaleTier::Symmetric => "Symmetric scales",
+            ScaleTier::Everything => "Everything",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ScaleTier::MajorModes => ScaleTier::MelodicMinorModes,
+            ScaleTier::MelodicMinorModes => ScaleTier::Symmetric,
+            ScaleTier::Symmetric => ScaleTier::Everything,
+            ScaleTier::Everything => ScaleTier::MajorModes,
+        }
+    }
+
+    /// Scales in the tier, in mode order where there is one
+    pub fn scale_types(&self) -> Vec<ScaleType> {
+        match self {
+            ScaleTier::MajorModes => vec![
+                ScaleType::Major,
+                ScaleType::Dorian,
+                ScaleType::Phrygian,
+                ScaleType::Lydian,
+                ScaleType::Mixolydian,
+                ScaleType::Aeolian,
+                ScaleType::Locrian,
+            ],
+            ScaleTier::MelodicMinorModes => vec![
+                ScaleType::MelodicMinor,
+                ScaleType::DorianFlat2,
+                ScaleType::LydianAugmented,
+                ScaleType::LydianDominant,
+                ScaleType::MixolydianFlat6,
+                ScaleType::LocrianNatural2,
+                ScaleType::Altered,
+            ],
+            ScaleTier::Symmetric => vec![
+                ScaleType::WholeTone,
+                ScaleType::DiminishedHalfWhole,
+                ScaleType::DiminishedWholeHalf,
+                ScaleType::Augmented,
+            ],
+            ScaleTier::Everything => ScaleType::ALL
+                .into_iter()
+                .filter(|&t| t != ScaleType::NaturalMinor)
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Ionian,
@@ -110,21 +284,54 @@ impl Mode {
 pub struct Scale {
     pub root: Note,
     pub scale_type: ScaleType,
+    /// How the root is spelled, when known from a chord or key
+    pub root_spelling: Option<SpelledPitch>,
 }
 
 impl Scale {
     pub fn new(root: Note, scale_type: ScaleType) -> Self {
-        Self { root, scale_type }
+        Self {
+            root,
+            scale_type,
+            root_spelling: None,
+        }
     }
 
-    pub fn notes(&self) -> Vec<Note> {
+    /// Scale on an explicitly spelled root
+    pub fn spelled(root: SpelledPitch, scale_type: ScaleType) -> Self {
+        Self {
+            root_spelling: Some(root),
+            ..Self::new(root.note(), scale_type)
+        }
+    }
+
+    pub fn spelled_root(&self) -> SpelledPitch {
+        self.root_spelling
+            .filter(|spelling| *spelling == self.root)
+            .unwrap_or_else(|| SpelledPitch::from_note(self.root))
+    }
+
+    /// Scale tones spelled from the root
+    pub fn notes(&self) -> Vec<SpelledPitch> {
+        let root = self.spelled_root();
         self.scale_type
             .intervals()
             .iter()
-            .map(|&interval| self.root.transpose(interval))
+            .zip(self.scale_type.letter_steps())
+            .map(|(&interval, steps)| root.up(steps, interval))
             .collect()
     }
 
+    /// Spell a pitch class the way this scale writes it
+    ///
+    /// Notes outside the scale are spelled as chromatic degrees of the root.
+    pub fn spell(&self, note: Note) -> SpelledPitch {
+        self.notes()
+            .into_iter()
+            .find(|spelled| *spelled == note)
+            .unwrap_or_else(|| SpelledPitch::in_key(note, self.spelled_root()))
+    }
+
     pub fn contains(&self, note: Note) -> bool {
         let interval = (note as i8 - self.root as i8).rem_euclid(12);
         self.scale_type.intervals().contains(&interval)
@@ -140,33 +347,41 @@ impl Scale {
     }
 
     pub fn name(&self) -> String {
-        format!("{} {}", self.root, self.scale_type.name())
+        format!("{} {}", self.spelled_root(), self.scale_type.name())
     }
 
     pub fn is_chord_tone(&self, chord: &Chord, note: Note) -> bool {
-        chord.notes().contains(&note)
+        chord.pitch_classes().contains(&note)
     }
 
     pub fn is_extension(&self, chord: &Chord, note: Note) -> bool {
         self.contains(note) && !self.is_chord_tone(chord, note)
     }
 
-    pub fn available_extensions(&self, chord: &Chord) -> Vec<(Note, &'static str)> {
-        let chord_notes = chord.notes();
+    pub fn available_extensions(&self, chord: &Chord) -> Vec<(SpelledPitch, &'static str)> {
+        let chord_notes = chord.pitch_classes();
         let scale_notes = self.notes();
         let mut extensions = Vec::new();
 
         for note in scale_notes {
-            if !chord_notes.contains(&note) {
-                let interval = (note as i8 - chord.root as i8).rem_euclid(12);
+            if !chord_notes.contains(&note.note()) {
+                let interval = (note.note() as i8 - chord.root as i8).rem_euclid(12);
+                if self.scale_type.passing_tones().contains(&interval) {
+                    extensions.push((note, "passing tone"));
+                    continue;
+                }
                 let label = match interval {
+                    1 => "b9th",
                     2 => "9th",
-                    3 => "b9th",
-                    4 => "#9th",
+                    3 => "#9th",
+                    4 => "3rd",
                     5 => "11th",
                     6 => "#11th/b5",
+                    7 => "5th",
                     8 => "b13th",
                     9 => "13th",
+                    10 => "b7th",
+                    11 => "7th",
                     _ => "tension",
                 };
                 extensions.push((note, label));
@@ -204,4 +419,109 @@ mod tests {
         assert!(c_major.contains(Note::E));
         assert!(!c_major.contains(Note::Eb));
     }
+
+    #[test]
+    fn test_scale_spelling() {
+        let names = |scale: &Scale| -> Vec<String> {
+            scale.notes().iter().map(|n| n.name()).collect()
+        };
+
+        assert_eq!(
+            names(&Scale::new(Note::D, ScaleType::Major)),
+            vec!["D", "E", "F#", "G", "A", "B", "C#"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::Gb, ScaleType::Major)),
+            vec!["Gb", "Ab", "Bb", "Cb", "Db", "Eb", "F"]
+        );
+        assert_eq!(
+            names(&Scale::spelled("C#".parse().unwrap(), ScaleType::Mixolydian)),
+            vec!["C#", "D#", "E#", "F#", "G#", "A#", "B"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::C, ScaleType::DiminishedHalfWhole)),
+            vec!["C", "Db", "D#", "E", "F#", "G", "A", "Bb"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::A, ScaleType::BluesMinor)),
+            vec!["A", "C", "D", "Eb", "E", "G"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::C, ScaleType::BebopDominant)),
+            vec!["C", "D", "E", "F", "G", "A", "Bb", "B"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::C, ScaleType::BebopMajor)),
+            vec!["C", "D", "E", "F", "G", "G#", "A", "B"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::D, ScaleType::BebopMinor)),
+            vec!["D", "E", "F", "F#", "G", "A", "B", "C"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::C, ScaleType::Augmented)),
+            vec!["C", "Eb", "E", "G", "Ab", "B"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::E, ScaleType::PhrygianDominant)),
+            vec!["E", "F", "G#", "A", "B", "C", "D"]
+        );
+        assert_eq!(
+            names(&Scale::new(Note::Eb, ScaleType::LydianAugmented)),
+            vec!["Eb", "F", "G", "A", "B", "C", "D"]
+        );
+    }
+
+    #[test]
+    fn test_scale_tiers() {
+        // Each tier's scales sound different from each other
+        let mut tier = ScaleTier::default();
+        loop {
+            let types = tier.scale_types();
+            for (i, a) in types.iter().enumerate() {
+                for b in &types[i + 1..] {
+                    assert_ne!(a.intervals(), b.intervals(), "{} and {}", a, b);
+                }
+            }
+            tier = tier.next();
+            if tier == ScaleTier::default() {
+                break;
+            }
+        }
+
+        // The melodic minor tier really is the modes of melodic minor
+        let melodic = ScaleType::MelodicMinor.intervals();
+        for (degree, scale_type) in ScaleTier::MelodicMinorModes.scale_types().iter().enumerate() {
+            let rotated: Vec<i8> = (0..7)
+                .map(|i| (melodic[(degree + i) % 7] - melodic[degree]).rem_euclid(12))
+                .collect();
+            assert_eq!(scale_type.intervals(), rotated, "{}", scale_type);
+        }
+    }
+
+    #[test]
+    fn test_available_extensions() {
+        let labels = |chord: &str, scale_type: ScaleType| -> Vec<(String, &'static str)> {
+            let chord: Chord = chord.parse().unwrap();
+            Scale::new(chord.root, scale_type)
+                .available_extensions(&chord)
+                .into_iter()
+                .map(|(note, label)| (note.name(), label))
+                .collect()
+        };
+
+        assert_eq!(
+            labels("Bm7b5", ScaleType::LocrianNatural2),
+            vec![("C#".to_string(), "9th"), ("E".to_string(), "11th"), ("G".to_string(), "b13th")]
+        );
+        // The bebop major 7th is a passing tone, not a tension
+        assert_eq!(
+            labels("G7", ScaleType::BebopDominant),
+            vec![("A".to_string(), "9th"), ("C".to_string(), "11th"), ("E".to_string(), "13th"), ("F#".to_string(), "passing tone")]
+        );
+        assert_eq!(
+            labels("E7", ScaleType::PhrygianDominant),
+            vec![("F".to_string(), "b9th"), ("A".to_string(), "11th"), ("C".to_string(), "b13th")]
+        );
+    }
 }
@@ -0,0 +1,422 @@
+//! LEGO Brick Segmentation
+//!
+//! Breaks any progression into bricks from the `BrickLibrary`, the way the
+//! `StandardsLibrary` breakdowns are written by hand. Each run of chords is
+//! compared with every brick template in all twelve keys. Close relatives
+//! of a template chord (Dm9 for Dm7, G7b9 for G7) and common substitutions
+//! (a tritone sub for V7, iiø7 or II7 for ii7) still match, at a lower
+//! score. The best-scoring segmentation wins, and key changes between
+//! bricks are named with `JoinLibrary::find_join`.
+
+use super::bricks::{Brick, BrickLibrary, ScaleDegree};
+use super::chord::{Chord, ChordQuality, Note};
+use super::joins::JoinLibrary;
+use super::meter::Meter;
+use super::progression::Progression;
+use super::standards::BrickRef;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+/// Points for a chord exactly as the template has it
+const EXACT_SCORE: i32 = 3;
+/// Points for a chord of the same family (Dm9 for Dm7)
+const FAMILY_SCORE: i32 = 2;
+/// Points for an accepted substitution (Db7 for G7)
+const SUBSTITUTE_SCORE: i32 = 1;
+/// Cost of each brick, so one long brick beats two short ones that score the same
+const BRICK_COST: i32 = 1;
+
+const DEGREES: [ScaleDegree; 12] = [
+    ScaleDegree::I,
+    ScaleDegree::bII,
+    ScaleDegree::II,
+    ScaleDegree::bIII,
+    ScaleDegree::III,
+    ScaleDegree::IV,
+    ScaleDegree::bV,
+    ScaleDegree::V,
+    ScaleDegree::bVI,
+    ScaleDegree::VI,
+    ScaleDegree::bVII,
+    ScaleDegree::VII,
+];
+
+/// One brick of a breakdown, or a stretch of chords no brick explains
+#[derive(Debug, Clone, Serialize)]
+pub struct BrickSegment {
+    /// Name of the matched brick, None for unexplained chords
+    pub brick: Option<String>,
+    /// Key the brick is played in
+    pub key: Note,
+    /// Beat the segment starts on, from the top of the progression
+    pub start_beat: f32,
+    pub beats: f32,
+    /// Chord names as they appear in the progression
+    pub chords: Vec<String>,
+    /// Substitutions the match accepted, e.g. "bII7 for V7"
+    pub substitutions: Vec<String>,
+    /// Join from the previous brick's key, when the key changes
+    pub join: Option<String>,
+}
+
+impl BrickSegment {
+    /// Bar the segment starts in, counting from 1, in the progression's meter
+    pub fn start_bar(&self, meter: Meter) -> u32 {
+        (self.start_beat / meter.bar_beats()) as u32 + 1
+    }
+
+    /// This segment as a `BrickRef`, like the hand-written standards use,
+    /// with bars in the progression's meter
+    ///
+    /// Fails if the segment lies past bar 255, which a `BrickRef` can't hold.
+    pub fn to_brick_ref(&self, meter: Meter) -> Result<Option<BrickRef>> {
+        let Some(brick_name) = self.brick.clone() else {
+            return Ok(None);
+        };
+        let bars = (self.beats / meter.bar_beats()).ceil().max(1.0) as u32;
+        Ok(Some(BrickRef {
+            start_bar: u8::try_from(self.start_bar(meter))
+                .map_err(|_| anyhow!("brick \"{}\" starts past bar 255", brick_name))?,
+            duration_bars: u8::try_from(bars)
+                .map_err(|_| anyhow!("brick \"{}\" lasts more than 255 bars", brick_name))?,
+            brick_name,
+            key: self.key,
+            notes: (!self.substitutions.is_empty()).then(|| self.substitutions.join(", ")),
+        }))
+    }
+}
+
+/// A chord held for one or more consecutive changes
+struct Run {
+    chord: Chord,
+    start_beat: f32,
+    beats: f32,
+}
+
+/// A template chord, standing for any repeats of it that follow
+struct TemplateRun {
+    degree: ScaleDegree,
+    quality: ChordQuality,
+}
+
+struct Match<'a> {
+    brick: &'a Brick,
+    key: Note,
+    len: usize,
+    score: i32,
+    substitutions: Vec<String>,
+}
+
+pub struct BrickAnalyzer {
+    bricks: BrickLibrary,
+    joins: JoinLibrary,
+}
+
+impl BrickAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            bricks: BrickLibrary::new(),
+            joins: JoinLibrary::new(),
+        }
+    }
+
+    /// Detect bricks and joins from other libraries, e.g. with user data
+    pub fn with_libraries(bricks: BrickLibrary, joins: JoinLibrary) -> Self {
+        Self { bricks, joins }
+    }
+
+    /// Segment a progression into bricks, in order
+    pub fn analyze(&self, progression: &Progression) -> Vec<BrickSegment> {
+        let runs = collapse_changes(progression);
+        let mut bricks = self.bricks.all();
+        bricks.sort_by(|a, b| a.name.cmp(&b.name));
+        let templates: Vec<(&Brick, Vec<TemplateRun>)> =
+            bricks.into_iter().map(|b| (b, collapse_template(b))).collect();
+
+        // best[i] is the top score for runs[i..], with the match taken at i
+        let mut best: Vec<(i32, Option<Match>)> = Vec::with_capacity(runs.len() + 1);
+        best.resize_with(runs.len() + 1, || (0, None));
+        for start in (0..runs.len()).rev() {
+            let mut top = (best[start + 1].0, None);
+            for (brick, template) in &templates {
+                for key in 0..12 {
+                    let key = Note::from_midi(key);
+                    if let Some(m) = match_template(brick, template, &runs[start..], key) {
+                        let total = m.score - BRICK_COST + best[start + m.len].0;
+                        if total > top.0 {
+                            top = (total, Some(m));
+                        }
+                    }
+                }
+            }
+            best[start] = top;
+        }
+
+        let mut segments: Vec<BrickSegment> = Vec::new();
+        let mut last_key: Option<Note> = None;
+        let mut i = 0;
+        while i < runs.len() {
+            let (len, segment) = match best[i].1.take() {
+                Some(m) => {
+                    let join = last_key
+                        .filter(|&k| k != m.key)
+                        .and_then(|k| self.joins.find_join(k, m.key))
+                        .map(|j| j.name.clone());
+                    last_key = Some(m.key);
+                    (
+                        m.len,
+                        BrickSegment {
+                            brick: Some(m.brick.name.clone()),
+                            key: m.key,
+                            start_beat: runs[i].start_beat,
+                            beats: 0.0,
+                            chords: Vec::new(),
+                            substitutions: m.substitutions,
+                            join,
+                        },
+                    )
+                }
+                None => (
+                    1,
+                    BrickSegment {
+                        brick: None,
+                        key: progression.key,
+                        start_beat: runs[i].start_beat,
+                        beats: 0.0,
+                        chords: Vec::new(),
+                        substitutions: Vec::new(),
+                        join: None,
+                    },
+                ),
+            };
+            // Consecutive unexplained chords share one segment
+            let segment = match segments.last_mut() {
+                Some(prev) if prev.brick.is_none() && segment.brick.is_none() => prev,
+                _ => {
+                    segments.push(segment);
+                    segments.last_mut().unwrap()
+                }
+            };
+            for run in &runs[i..i + len] {
+                segment.beats += run.beats;
+                segment.chords.push(run.chord.name());
+            }
+            i += len;
+        }
+        segments
+    }
+}
+
+impl Default for BrickAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn same_chord(a: &Chord, b: &Chord) -> bool {
+    a.root == b.root && a.quality == b.quality && a.bass == b.bass
+}
+
+fn collapse_changes(progression: &Progression) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut beat = 0.0;
+    for change in &progression.changes {
+        match runs.last_mut() {
+            Some(run) if same_chord(&run.chord, &change.chord) => run.beats += change.duration,
+            _ => runs.push(Run {
+                chord: change.chord.clone(),
+                start_beat: beat,
+                beats: change.duration,
+            }),
+        }
+        beat += change.duration;
+    }
+    runs
+}
+
+fn collapse_template(brick: &Brick) -> Vec<TemplateRun> {
+    let mut runs: Vec<TemplateRun> = Vec::new();
+    for bc in &brick.template {
+        match runs.last_mut() {
+            Some(run) if run.degree == bc.degree && run.quality == bc.quality => {}
+            _ => runs.push(TemplateRun {
+                degree: bc.degree,
+                quality: bc.quality,
+            }),
+        }
+    }
+    runs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    Major,
+    Minor,
+    Dominant,
+    HalfDiminished,
+    Diminished,
+}
+
+fn family(quality: ChordQuality) -> Family {
+    let intervals = quality.intervals();
+    if matches!(quality, ChordQuality::Diminished | ChordQuality::Diminished7) {
+        Family::Diminished
+    } else if intervals.contains(&3) && intervals.contains(&6) && !intervals.contains(&7) {
+        Family::HalfDiminished
+    } else if quality.is_dominant() {
+        Family::Dominant
+    } else if quality.is_minor() {
+        Family::Minor
+    } else {
+        Family::Major
+    }
+}
+
+fn degree_above(note: Note, key: Note) -> ScaleDegree {
+    DEGREES[(note as i8 - key as i8).rem_euclid(12) as usize]
+}
+
+/// Score one chord against a template chord, with the substitution it took
+fn score_chord(chord: &Chord, template: &TemplateRun, key: Note) -> Option<(i32, Option<String>)> {
+    let expected = template.degree.to_note(key);
+    let (actual, wanted) = (family(chord.quality), family(template.quality));
+    let substitution = || {
+        format!(
+            "{}{} for {}{}",
+            degree_above(chord.root, key).symbol(),
+            chord.quality.symbol(),
+            template.degree.symbol(),
+            template.quality.symbol()
+        )
+    };
+
+    if chord.root == expected {
+        if chord.quality == template.quality {
+            return Some((EXACT_SCORE, None));
+        }
+        if actual == wanted {
+            return Some((FAMILY_SCORE, None));
+        }
+        // iiø7 or II7 in place of ii7
+        if wanted == Family::Minor && matches!(actual, Family::HalfDiminished | Family::Dominant) {
+            return Some((SUBSTITUTE_SCORE, Some(substitution())));
+        }
+        return None;
+    }
+
+    // Tritone substitution of a dominant
+    if wanted == Family::Dominant && actual == Family::Dominant && chord.root == expected.transpose(6) {
+        return Some((SUBSTITUTE_SCORE, Some(substitution())));
+    }
+    None
+}
+
+fn match_template<'a>(brick: &'a Brick, template: &[TemplateRun], runs: &[Run], key: Note) -> Option<Match<'a>> {
+    if template.len() > runs.len() {
+        return None;
+    }
+    // A single held chord (Overrun, So What) only counts when held as long as the brick
+    if template.len() == 1 && runs[0].beats < brick.duration_beats {
+        return None;
+    }
+
+    let mut score = 0;
+    let mut substitutions = Vec::new();
+    for (run, expected) in runs.iter().zip(template) {
+        let (points, substitution) = score_chord(&run.chord, expected, key)?;
+        score += points;
+        substitutions.extend(substitution);
+    }
+    // Mostly substitutions is a different brick
+    if substitutions.len() * 2 > template.len() {
+        return None;
+    }
+
+    Some(Match {
+        brick,
+        key,
+        len: template.len(),
+        score,
+        substitutions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progression(chords: &str, key: Note) -> Progression {
+        let mut prog = Progression::new("Test".to_string(), "Jazz".to_string(), key, 120.0);
+        for chord in chords.split_whitespace() {
+            prog.add_chord(chord.parse().unwrap(), 4.0);
+        }
+        prog
+    }
+
+    fn summary(segments: &[BrickSegment]) -> Vec<String> {
+        segments
+            .iter()
+            .map(|s| match &s.brick {
+                Some(name) => format!("{} in {}", name, s.key),
+                None => format!("? {}", s.chords.join(" ")),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bricks_in_any_key() {
+        let analyzer = BrickAnalyzer::new();
+        let segments = analyzer.analyze(&progression("Em7 A7 Dm7 G7 Cmaj7 Cmaj7 Cmaj7 Cmaj7", Note::C));
+        assert_eq!(summary(&segments), vec!["Long Approach in C", "Overrun in C"]);
+        assert!(segments[0].join.is_none());
+
+        // Same shape a fourth up, joined by a High Jump
+        let segments = analyzer.analyze(&progression("Dm7 G7 Cmaj7 Gm7 C7 Fmaj7", Note::C));
+        assert_eq!(summary(&segments), vec!["Launcher in C", "Launcher in F"]);
+        assert_eq!(segments[1].join.as_deref(), Some("High Jump"));
+        assert_eq!(segments[1].start_beat, 12.0);
+
+        let brick_ref = segments[1].to_brick_ref(Meter::COMMON).unwrap().unwrap();
+        assert_eq!((brick_ref.start_bar, brick_ref.duration_bars), (4, 3));
+    }
+
+    #[test]
+    fn test_bars_follow_the_meter() {
+        let mut prog = Progression::new("Waltz".to_string(), "Jazz".to_string(), Note::C, 120.0)
+            .with_meter(Meter::WALTZ);
+        for chord in "C7 F#7 Dm7 G7 Cmaj7 Cmaj7".split_whitespace() {
+            prog.add_chord(chord.parse().unwrap(), 3.0);
+        }
+        let segments = BrickAnalyzer::new().analyze(&prog);
+        assert_eq!(summary(&segments), vec!["? C7 F#7", "Launcher in C"]);
+        assert_eq!(segments[0].start_bar(prog.meter), 1);
+
+        // Four chords of one 3/4 bar each, from bar 3
+        let brick_ref = segments[1].to_brick_ref(prog.meter).unwrap().unwrap();
+        assert_eq!((brick_ref.start_bar, brick_ref.duration_bars), (3, 4));
+
+        // Bar numbers past 255 don't fit a BrickRef
+        let mut late = segments[1].clone();
+        late.start_beat = 255.0 * 3.0;
+        assert!(late.to_brick_ref(prog.meter).is_err());
+    }
+
+    #[test]
+    fn test_substitutions_and_gaps() {
+        let analyzer = BrickAnalyzer::new();
+
+        // Extended chords still match; the tritone sub is its own brick
+        let segments = analyzer.analyze(&progression("Dm9 G13 Cmaj9 Dm7 Db7 Cmaj7", Note::C));
+        assert_eq!(summary(&segments), vec!["Launcher in C", "Dizzy in C"]);
+        assert!(segments[1].substitutions.is_empty());
+
+        // A tritone sub inside a longer brick is noted
+        let segments = analyzer.analyze(&progression("Cmaj7 Am7 Dm7 Db7", Note::C));
+        assert_eq!(summary(&segments), vec!["Rhythm in C"]);
+        assert_eq!(segments[0].substitutions, vec!["bII7 for V7"]);
+
+        let segments = analyzer.analyze(&progression("C7 F#7 Dm7 G7 Cmaj7", Note::C));
+        assert_eq!(summary(&segments), vec!["? C7 F#7", "Launcher in C"]);
+        assert!(segments[0].to_brick_ref(Meter::COMMON).unwrap().is_none());
+    }
+}
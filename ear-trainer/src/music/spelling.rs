@@ -0,0 +1,273 @@
+//! Enharmonic Spelling
+//!
+//! `Note` is a pitch class and always prints with flats. A `SpelledPitch`
+//! keeps the letter name as well, so the third of D major is F# rather than
+//! Gb and the seventh of C#maj7 is B# rather than C. Chords and scales spell
+//! their tones by counting letters up from a spelled root; roots themselves
+//! are spelled from the key they appear in.
+
+use super::chord::Note;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Letter names in scale order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Letter {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl Letter {
+    pub const ALL: [Letter; 7] = [
+        Letter::C,
+        Letter::D,
+        Letter::E,
+        Letter::F,
+        Letter::G,
+        Letter::A,
+        Letter::B,
+    ];
+
+    /// Pitch class of the natural note
+    pub fn natural(self) -> i8 {
+        match self {
+            Letter::C => 0,
+            Letter::D => 2,
+            Letter::E => 4,
+            Letter::F => 5,
+            Letter::G => 7,
+            Letter::A => 9,
+            Letter::B => 11,
+        }
+    }
+
+    /// Letter a number of steps higher (wrapping from B to C)
+    pub fn up(self, steps: u8) -> Self {
+        Self::ALL[(self as usize + steps as usize) % 7]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Letter::C => "C",
+            Letter::D => "D",
+            Letter::E => "E",
+            Letter::F => "F",
+            Letter::G => "G",
+            Letter::A => "A",
+            Letter::B => "B",
+        }
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'C' => Some(Letter::C),
+            'D' => Some(Letter::D),
+            'E' => Some(Letter::E),
+            'F' => Some(Letter::F),
+            'G' => Some(Letter::G),
+            'A' => Some(Letter::A),
+            'B' => Some(Letter::B),
+            _ => None,
+        }
+    }
+}
+
+/// Letter steps for each chromatic degree above a key or chord root
+///
+/// Chromatic degrees are spelled I bII II bIII III IV #IV V bVI VI bVII VII.
+const CHROMATIC_STEPS: [u8; 12] = [0, 1, 1, 2, 2, 3, 3, 4, 5, 5, 6, 6];
+
+/// A pitch with its letter name: F# and Gb sound the same but spell differently
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SpelledPitch {
+    pub letter: Letter,
+    /// Sharps (positive) or flats (negative); ±2 for double accidentals
+    pub accidental: i8,
+}
+
+impl SpelledPitch {
+    pub fn new(letter: Letter, accidental: i8) -> Self {
+        Self { letter, accidental }
+    }
+
+    /// Default spelling, matching `Note::name` (flats for black keys)
+    pub fn from_note(note: Note) -> Self {
+        match note {
+            Note::C => Self::new(Letter::C, 0),
+            Note::Db => Self::new(Letter::D, -1),
+            Note::D => Self::new(Letter::D, 0),
+            Note::Eb => Self::new(Letter::E, -1),
+            Note::E => Self::new(Letter::E, 0),
+            Note::F => Self::new(Letter::F, 0),
+            Note::Gb => Self::new(Letter::G, -1),
+            Note::G => Self::new(Letter::G, 0),
+            Note::Ab => Self::new(Letter::A, -1),
+            Note::A => Self::new(Letter::A, 0),
+            Note::Bb => Self::new(Letter::B, -1),
+            Note::B => Self::new(Letter::B, 0),
+        }
+    }
+
+    /// Spelling of a key's tonic with the fewest accidentals in its scale
+    ///
+    /// Db major but C# minor, G# minor rather than Ab minor. The two even
+    /// splits go the usual way, to F# major and Eb minor.
+    pub fn tonic(note: Note, minor: bool) -> Self {
+        let flat = Self::from_note(note);
+        if flat.accidental == 0 {
+            return flat;
+        }
+        let sharp = Self::new(flat.letter.up(6), 1);
+        let intervals: [i8; 7] = if minor {
+            [0, 2, 3, 5, 7, 8, 10]
+        } else {
+            [0, 2, 4, 5, 7, 9, 11]
+        };
+        let accidentals = |tonic: SpelledPitch| -> i32 {
+            (0..7)
+                .map(|step| tonic.up(step, intervals[step as usize]).accidental.unsigned_abs() as i32)
+                .sum()
+        };
+        match accidentals(sharp).cmp(&accidentals(flat)) {
+            Ordering::Less => sharp,
+            Ordering::Equal if !minor => sharp,
+            _ => flat,
+        }
+    }
+
+    /// Spell a pitch class as a chromatic degree of a key
+    ///
+    /// The degrees are those of the major scale with bII, bIII, #IV, bVI
+    /// and bVII between them, which also covers the minor scale.
+    pub fn in_key(note: Note, key: SpelledPitch) -> Self {
+        let semitones = (note as i8 - key.note() as i8).rem_euclid(12);
+        key.up(CHROMATIC_STEPS[semitones as usize], semitones)
+    }
+
+    /// Pitch class, forgetting the spelling
+    pub fn note(&self) -> Note {
+        Note::from_midi((self.letter.natural() + self.accidental).rem_euclid(12) as u8)
+    }
+
+    /// Interval above this pitch, `steps` letters and `semitones` higher
+    ///
+    /// A major third is 2 steps and 4 semitones: E above C, E# above C#.
+    pub fn up(&self, steps: u8, semitones: i8) -> Self {
+        let letter = self.letter.up(steps);
+        let target = self.letter.natural() + self.accidental + semitones;
+        // Nearest accidental that reaches the target pitch class
+        let mut accidental = (target - letter.natural()).rem_euclid(12);
+        if accidental > 6 {
+            accidental -= 12;
+        }
+        Self::new(letter, accidental)
+    }
+
+    pub fn name(&self) -> String {
+        let accidental = if self.accidental >= 0 { "#" } else { "b" };
+        format!(
+            "{}{}",
+            self.letter.name(),
+            accidental.repeat(self.accidental.unsigned_abs() as usize)
+        )
+    }
+}
+
+impl From<Note> for SpelledPitch {
+    fn from(note: Note) -> Self {
+        Self::from_note(note)
+    }
+}
+
+impl PartialEq<Note> for SpelledPitch {
+    fn eq(&self, other: &Note) -> bool {
+        self.note() == *other
+    }
+}
+
+impl fmt::Display for SpelledPitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SpelledPitch {
+    type Err = anyhow::Error;
+
+    /// Parse a note name like "F#", "Bb" or "Cbb", keeping its spelling
+    fn from_str(s: &str) -> Result<Self> {
+        let mut chars = s.trim().chars();
+        let letter = chars
+            .next()
+            .and_then(Letter::from_char)
+            .ok_or_else(|| anyhow!("invalid note name: {}", s))?;
+        let mut accidental = 0;
+        for c in chars {
+            match c {
+                '#' | '♯' => accidental += 1,
+                'b' | '♭' => accidental -= 1,
+                _ => return Err(anyhow!("invalid note name: {}", s)),
+            }
+        }
+        Ok(Self::new(letter, accidental))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spelled(s: &str) -> SpelledPitch {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_intervals_and_names() {
+        let c_sharp = spelled("C#");
+        assert_eq!(c_sharp.up(2, 4).name(), "E#");
+        assert_eq!(c_sharp.up(6, 11).name(), "B#");
+        assert_eq!(spelled("C").up(6, 9).name(), "Bbb");
+        assert_eq!(spelled("D#").up(6, 11).name(), "C##");
+        assert_eq!(spelled("B#").note(), Note::C);
+        assert_eq!(spelled("Cb"), Note::B);
+        assert!("H".parse::<SpelledPitch>().is_err());
+    }
+
+    #[test]
+    fn test_spelled_in_key() {
+        let d = SpelledPitch::from_note(Note::D);
+        assert_eq!(SpelledPitch::in_key(Note::Gb, d).name(), "F#");
+        assert_eq!(SpelledPitch::in_key(Note::C, d).name(), "C");
+
+        let ab = SpelledPitch::from_note(Note::Ab);
+        assert_eq!(SpelledPitch::in_key(Note::Db, ab).name(), "Db");
+        assert_eq!(SpelledPitch::in_key(Note::E, ab).name(), "Fb");
+    }
+
+    #[test]
+    fn test_key_tonics() {
+        let tonic = |note, minor| SpelledPitch::tonic(note, minor).name();
+        assert_eq!(tonic(Note::Gb, false), "F#");
+        assert_eq!(tonic(Note::Db, false), "Db");
+        assert_eq!(tonic(Note::Db, true), "C#");
+        assert_eq!(tonic(Note::Ab, true), "G#");
+        assert_eq!(tonic(Note::Eb, true), "Eb");
+        assert_eq!(tonic(Note::Bb, true), "Bb");
+        assert_eq!(tonic(Note::B, false), "B");
+
+        // Scale tones follow the tonic: A# in F# major, E in C# minor
+        let f_sharp = SpelledPitch::tonic(Note::Gb, false);
+        assert_eq!(SpelledPitch::in_key(Note::Bb, f_sharp).name(), "A#");
+        let c_sharp_minor = SpelledPitch::tonic(Note::Db, true);
+        assert_eq!(SpelledPitch::in_key(Note::E, c_sharp_minor).name(), "E");
+        assert_eq!(SpelledPitch::in_key(Note::A, c_sharp_minor).name(), "A");
+    }
+}
@@ -57,8 +57,10 @@ pub struct Standard {
     /// Section breakdowns
     pub sections: Vec<StandardSection>,
     /// Overall brick usage summary
+    #[serde(default)]
     pub brick_summary: Vec<String>,
-    /// Join patterns used
+    /// Join patterns used, as "Name (from -> to)"
+    #[serde(default)]
     pub joins_used: Vec<String>,
 }
 
@@ -89,6 +91,9 @@ impl Standard {
     }
 }
 
+/// Standards shipped in `data/standards.json`
+const BUNDLED_STANDARDS: &str = include_str!("../../data/standards.json");
+
 /// Library of jazz standard breakdowns
 #[derive(Debug)]
 pub struct StandardsLibrary {
@@ -96,360 +101,25 @@ pub struct StandardsLibrary {
 }
 
 impl StandardsLibrary {
+    /// The standards bundled with the app
     pub fn new() -> Self {
-        let mut library = Self {
-            standards: Vec::new(),
-        };
-        library.populate();
-        library
+        let standards: Vec<Standard> =
+            serde_json::from_str(BUNDLED_STANDARDS).expect("bundled standards.json is valid");
+        Self { standards }
     }
 
-    fn populate(&mut self) {
-        // Autumn Leaves - Classic ii-V-I study piece
-        self.standards.push(Standard {
-            name: "Autumn Leaves".to_string(),
-            composer: "Joseph Kosma".to_string(),
-            year: Some(1945),
-            total_bars: 32,
-            form: "AABA".to_string(),
-            key: Note::G, // Minor, but we use relative major
-            tempo_range: (100, 180),
-            style: "Medium swing".to_string(),
-            sections: vec![
-                StandardSection {
-                    label: "A1".to_string(),
-                    bars: 8,
-                    key: Note::G,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 1,
-                            duration_bars: 4,
-                            key: Note::Bb,
-                            notes: Some("Relative major ii-V-I".to_string()),
-                        },
-                        BrickRef {
-                            brick_name: "Sad Launcher".to_string(),
-                            start_bar: 5,
-                            duration_bars: 4,
-                            key: Note::G,
-                            notes: Some("Minor ii-V-i to tonic".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "A2".to_string(),
-                    bars: 8,
-                    key: Note::G,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 9,
-                            duration_bars: 4,
-                            key: Note::Bb,
-                            notes: None,
-                        },
-                        BrickRef {
-                            brick_name: "Sad Launcher".to_string(),
-                            start_bar: 13,
-                            duration_bars: 4,
-                            key: Note::G,
-                            notes: None,
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "B".to_string(),
-                    bars: 8,
-                    key: Note::Bb,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Long Approach".to_string(),
-                            start_bar: 17,
-                            duration_bars: 8,
-                            key: Note::Bb,
-                            notes: Some("Extended approach to Bb".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "A3".to_string(),
-                    bars: 8,
-                    key: Note::G,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 25,
-                            duration_bars: 4,
-                            key: Note::Bb,
-                            notes: None,
-                        },
-                        BrickRef {
-                            brick_name: "Sad Launcher".to_string(),
-                            start_bar: 29,
-                            duration_bars: 4,
-                            key: Note::G,
-                            notes: Some("Final resolution".to_string()),
-                        },
-                    ],
-                },
-            ],
-            brick_summary: vec![
-                "Launcher x3".to_string(),
-                "Sad Launcher x3".to_string(),
-                "Long Approach x1".to_string(),
-            ],
-            joins_used: vec!["High Jump (Bb -> G minor)".to_string()],
-        });
-
-        // All The Things You Are - Modulation showcase
-        self.standards.push(Standard {
-            name: "All The Things You Are".to_string(),
-            composer: "Jerome Kern".to_string(),
-            year: Some(1939),
-            total_bars: 36,
-            form: "AABA".to_string(),
-            key: Note::Ab,
-            tempo_range: (120, 200),
-            style: "Medium up swing".to_string(),
-            sections: vec![
-                StandardSection {
-                    label: "A1".to_string(),
-                    bars: 8,
-                    key: Note::Ab,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 1,
-                            duration_bars: 4,
-                            key: Note::Ab,
-                            notes: Some("Opens in Ab".to_string()),
-                        },
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 5,
-                            duration_bars: 4,
-                            key: Note::C,
-                            notes: Some("Modulates to C".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "A2".to_string(),
-                    bars: 8,
-                    key: Note::Eb,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 9,
-                            duration_bars: 4,
-                            key: Note::Eb,
-                            notes: Some("Modulates to Eb".to_string()),
-                        },
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 13,
-                            duration_bars: 4,
-                            key: Note::G,
-                            notes: Some("Sets up bridge".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "B".to_string(),
-                    bars: 8,
-                    key: Note::E,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 17,
-                            duration_bars: 4,
-                            key: Note::E,
-                            notes: Some("Distant key center".to_string()),
-                        },
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 21,
-                            duration_bars: 4,
-                            key: Note::Ab,
-                            notes: Some("Returns to tonic".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "A3".to_string(),
-                    bars: 12,
-                    key: Note::Ab,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 25,
-                            duration_bars: 4,
-                            key: Note::Ab,
-                            notes: None,
-                        },
-                        BrickRef {
-                            brick_name: "Dizzy".to_string(),
-                            start_bar: 29,
-                            duration_bars: 4,
-                            key: Note::Ab,
-                            notes: Some("Tritone sub turnaround".to_string()),
-                        },
-                        BrickRef {
-                            brick_name: "Overrun".to_string(),
-                            start_bar: 33,
-                            duration_bars: 4,
-                            key: Note::Ab,
-                            notes: Some("Extended ending".to_string()),
-                        },
-                    ],
-                },
-            ],
-            brick_summary: vec![
-                "Launcher x7".to_string(),
-                "Dizzy x1".to_string(),
-                "Overrun x1".to_string(),
-            ],
-            joins_used: vec![
-                "High Jump (Ab -> C)".to_string(),
-                "Cherokee (C -> Eb)".to_string(),
-                "High Jump (Eb -> G)".to_string(),
-                "Ladybird (G -> E)".to_string(),
-                "Cherokee (E -> Ab)".to_string(),
-            ],
-        });
-
-        // Blue Bossa - Latin jazz standard
-        self.standards.push(Standard {
-            name: "Blue Bossa".to_string(),
-            composer: "Kenny Dorham".to_string(),
-            year: Some(1963),
-            total_bars: 16,
-            form: "AB".to_string(),
-            key: Note::C, // C minor
-            tempo_range: (120, 160),
-            style: "Bossa nova".to_string(),
-            sections: vec![
-                StandardSection {
-                    label: "A".to_string(),
-                    bars: 8,
-                    key: Note::C,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Sad Launcher".to_string(),
-                            start_bar: 1,
-                            duration_bars: 8,
-                            key: Note::C,
-                            notes: Some("Minor ii-V-i in C minor".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "B".to_string(),
-                    bars: 8,
-                    key: Note::Db,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Launcher".to_string(),
-                            start_bar: 9,
-                            duration_bars: 4,
-                            key: Note::Db,
-                            notes: Some("Brief modulation to Db".to_string()),
-                        },
-                        BrickRef {
-                            brick_name: "Sad Launcher".to_string(),
-                            start_bar: 13,
-                            duration_bars: 4,
-                            key: Note::C,
-                            notes: Some("Return to C minor".to_string()),
-                        },
-                    ],
-                },
-            ],
-            brick_summary: vec![
-                "Sad Launcher x2".to_string(),
-                "Launcher x1".to_string(),
-            ],
-            joins_used: vec![
-                "Sidewinder (C -> Db)".to_string(),
-                "Sidewinder (Db -> C)".to_string(),
-            ],
-        });
+    pub fn empty() -> Self {
+        Self {
+            standards: Vec::new(),
+        }
+    }
 
-        // Rhythm Changes - Essential standard form
-        self.standards.push(Standard {
-            name: "Rhythm Changes (I Got Rhythm)".to_string(),
-            composer: "George Gershwin".to_string(),
-            year: Some(1930),
-            total_bars: 32,
-            form: "AABA".to_string(),
-            key: Note::Bb,
-            tempo_range: (160, 320),
-            style: "Up-tempo swing".to_string(),
-            sections: vec![
-                StandardSection {
-                    label: "A1".to_string(),
-                    bars: 8,
-                    key: Note::Bb,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Rhythm".to_string(),
-                            start_bar: 1,
-                            duration_bars: 8,
-                            key: Note::Bb,
-                            notes: Some("I-vi-ii-V turnaround pattern".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "A2".to_string(),
-                    bars: 8,
-                    key: Note::Bb,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Rhythm".to_string(),
-                            start_bar: 9,
-                            duration_bars: 8,
-                            key: Note::Bb,
-                            notes: None,
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "B".to_string(),
-                    bars: 8,
-                    key: Note::Bb,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Long Approach".to_string(),
-                            start_bar: 17,
-                            duration_bars: 8,
-                            key: Note::Bb,
-                            notes: Some("III7-VI7-II7-V7 sequence".to_string()),
-                        },
-                    ],
-                },
-                StandardSection {
-                    label: "A3".to_string(),
-                    bars: 8,
-                    key: Note::Bb,
-                    bricks: vec![
-                        BrickRef {
-                            brick_name: "Rhythm".to_string(),
-                            start_bar: 25,
-                            duration_bars: 8,
-                            key: Note::Bb,
-                            notes: Some("Final A section".to_string()),
-                        },
-                    ],
-                },
-            ],
-            brick_summary: vec![
-                "Rhythm x3".to_string(),
-                "Long Approach x1".to_string(),
-            ],
-            joins_used: vec![],
-        });
+    /// Add a standard, replacing any with the same name
+    pub fn add(&mut self, standard: Standard) {
+        match self.standards.iter_mut().find(|s| s.name == standard.name) {
+            Some(existing) => *existing = standard,
+            None => self.standards.push(standard),
+        }
     }
 
     /// Get all standards
@@ -1,9 +1,12 @@
+use super::analysis::{FunctionalAnalysis, MAJOR, NATURAL_MINOR};
 use super::chord::{Chord, ChordQuality, Note};
+use super::progression::Progression;
 use super::scale::{Scale, ScaleType};
+use super::spelling::SpelledPitch;
 
 #[derive(Debug, Clone)]
 pub struct GuideTone {
-    pub note: Note,
+    pub note: SpelledPitch,
     pub from_chord: String,
     pub to_chord: String,
     pub movement: i8,
@@ -27,7 +30,7 @@ impl VoiceLeading {
                 };
 
                 movements.push(GuideTone {
-                    note: from_note,
+                    note: from.spell(from_note),
                     from_chord: from.name(),
                     to_chord: to.name(),
                     movement,
@@ -39,8 +42,8 @@ impl VoiceLeading {
     }
 
     pub fn smooth_voice_leading(from: &Chord, to: &Chord) -> Vec<(Note, Note, i8)> {
-        let from_notes = from.notes();
-        let to_notes = to.notes();
+        let from_notes = from.pitch_classes();
+        let to_notes = to.pitch_classes();
         let mut voice_pairs = Vec::new();
 
         for &from_note in &from_notes {
@@ -69,6 +72,66 @@ impl VoiceLeading {
 
         voice_pairs
     }
+
+    /// Where each voice of one voicing goes in the next, as (from, to, semitones)
+    ///
+    /// The MIDI counterpart of `smooth_voice_leading`: voicings of the same
+    /// size move voice for voice, otherwise each note goes to the nearest
+    /// note of the next voicing.
+    pub fn voice_movements(from: &[u8], to: &[u8]) -> Vec<(u8, u8, i8)> {
+        let mut from = from.to_vec();
+        let mut to = to.to_vec();
+        from.sort_unstable();
+        to.sort_unstable();
+
+        if from.len() == to.len() {
+            return from
+                .iter()
+                .zip(&to)
+                .map(|(&f, &t)| (f, t, t as i8 - f as i8))
+                .collect();
+        }
+        from.iter()
+            .filter_map(|&f| {
+                to.iter()
+                    .min_by_key(|&&t| (t as i8 - f as i8).abs())
+                    .map(|&t| (f, t, t as i8 - f as i8))
+            })
+            .collect()
+    }
+
+    /// Total semitones the voices move between two voicings
+    ///
+    /// Notes of the new voicing that no voice moved to count from the
+    /// nearest old note, so thinning out or adding voices isn't free.
+    pub fn total_motion(from: &[u8], to: &[u8]) -> u32 {
+        let movements = Self::voice_movements(from, to);
+        let moved: u32 = movements.iter().map(|m| m.2.unsigned_abs() as u32).sum();
+        let added: u32 = to
+            .iter()
+            .filter(|t| !movements.iter().any(|m| m.1 == **t))
+            .filter_map(|&t| from.iter().map(|&f| (t as i8 - f as i8).unsigned_abs() as u32).min())
+            .sum();
+        moved + added
+    }
+}
+
+/// Modes of the major scale, in order from its first degree
+const MAJOR_MODES: [ScaleType; 7] = [
+    ScaleType::Major,
+    ScaleType::Dorian,
+    ScaleType::Phrygian,
+    ScaleType::Lydian,
+    ScaleType::Mixolydian,
+    ScaleType::Aeolian,
+    ScaleType::Locrian,
+];
+
+/// A scale picked for a chord in context, with the reason it was picked
+#[derive(Debug, Clone)]
+pub struct ScaleChoice {
+    pub scale: Scale,
+    pub reason: String,
 }
 
 pub struct ChordScaleMatcher;
@@ -83,9 +146,9 @@ impl ChordScaleMatcher {
             ChordQuality::Dominant9 => ScaleType::Mixolydian,
             ChordQuality::Dominant7b9 => ScaleType::DiminishedHalfWhole,
             ChordQuality::Dominant7sharp9 => ScaleType::Altered,
-            ChordQuality::Dominant7b13 => ScaleType::Altered,
+            ChordQuality::Dominant7b13 => ScaleType::MixolydianFlat6,
             ChordQuality::Altered => ScaleType::Altered,
-            ChordQuality::HalfDiminished => ScaleType::Locrian,
+            ChordQuality::HalfDiminished => ScaleType::LocrianNatural2,
             ChordQuality::Diminished7 => ScaleType::DiminishedWholeHalf,
             ChordQuality::MinorMajor7 => ScaleType::MelodicMinor,
             ChordQuality::MinorMajor9 => ScaleType::MelodicMinor,
@@ -94,70 +157,196 @@ impl ChordScaleMatcher {
             ChordQuality::Dominant7sus4 => ScaleType::Mixolydian,
             ChordQuality::Major9 => ScaleType::Major,
             ChordQuality::Minor9 => ScaleType::Dorian,
+            ChordQuality::Major => ScaleType::Major,
+            ChordQuality::Minor => ScaleType::Dorian,
+            ChordQuality::Diminished => ScaleType::DiminishedWholeHalf,
+            ChordQuality::Augmented => ScaleType::WholeTone,
+            ChordQuality::Sus2 => ScaleType::Mixolydian,
+            ChordQuality::Sus4 => ScaleType::Mixolydian,
+            ChordQuality::Add9 => ScaleType::Major,
+            ChordQuality::Major69 => ScaleType::Major,
+            ChordQuality::Dominant11 => ScaleType::Mixolydian,
+            ChordQuality::Minor11 => ScaleType::Dorian,
+            ChordQuality::Dominant13 => ScaleType::Mixolydian,
+            ChordQuality::Dominant7sharp11 => ScaleType::LydianDominant,
+            ChordQuality::Dominant7sharp5 => ScaleType::WholeTone,
+            ChordQuality::Dominant7b5 => ScaleType::WholeTone,
+            ChordQuality::Dominant13sus4 => ScaleType::Mixolydian,
+            ChordQuality::Major7sharp5 => ScaleType::LydianAugmented,
         };
 
-        Scale::new(chord.root, scale_type)
+        Scale::spelled(chord.spelled_root(), scale_type)
     }
 
-    pub fn get_alternate_scales(chord: &Chord) -> Vec<Scale> {
-        let mut scales = vec![Self::get_primary_scale(chord)];
+    /// Scale for a chord given the chord it moves to and the key
+    ///
+    /// `key` is the tonic and whether the key is minor. Dominants follow
+    /// their resolution (Lydian dominant for a tritone sub, Phrygian
+    /// dominant into a minor chord, Lydian dominant for a backdoor bVII7);
+    /// chords made of key tones take the key's own mode on their root (iii is
+    /// Phrygian, IV Lydian); anything else falls back to `get_primary_scale`.
+    pub fn get_contextual_scale(chord: &Chord, next: Option<&Chord>, key: Option<(Note, bool)>) -> ScaleChoice {
+        let primary = Self::get_primary_scale(chord);
+        let root = chord.spelled_root();
+        let intervals = chord.quality.intervals();
+        let choice = |scale_type, reason: String| ScaleChoice {
+            scale: Scale::spelled(root, scale_type),
+            reason,
+        };
 
-        match chord.quality {
-            ChordQuality::Major7 => {
-                scales.push(Scale::new(chord.root, ScaleType::Lydian));
+        // Plain dominants take their colour from where they go
+        let plain_dominant = primary.scale_type == ScaleType::Mixolydian && intervals.contains(&4);
+        let resolution = next.map(|n| ((n.root as i8 - chord.root as i8).rem_euclid(12), n));
+        if plain_dominant {
+            match resolution {
+                Some((11, next)) => {
+                    return choice(ScaleType::LydianDominant, format!("Tritone sub resolving down to {}", next.name()));
+                }
+                Some((5, next)) if next.quality.is_minor() => {
+                    return choice(ScaleType::PhrygianDominant, format!("V7 resolving to a minor chord ({})", next.name()));
+                }
+                _ => {}
             }
-            ChordQuality::Minor7 => {
-                scales.push(Scale::new(chord.root, ScaleType::Aeolian));
-                scales.push(Scale::new(chord.root, ScaleType::Phrygian));
+            if let Some((tonic, false)) = key {
+                let up_a_fourth = matches!(resolution, Some((5, _)));
+                if (chord.root as i8 - tonic as i8).rem_euclid(12) == 10 && !up_a_fourth {
+                    return choice(ScaleType::LydianDominant, format!("Backdoor bVII7 in {}", tonic.name()));
+                }
             }
-            ChordQuality::Dominant7 | ChordQuality::Dominant9 => {
-                scales.push(Scale::new(chord.root, ScaleType::LydianDominant));
-                scales.push(Scale::new(chord.root, ScaleType::Altered));
-                scales.push(Scale::new(chord.root, ScaleType::WholeTone));
-                scales.push(Scale::new(chord.root, ScaleType::DiminishedHalfWhole));
+        }
+
+        // Chords made of key tones use the key's mode on their root
+        if let Some((tonic, minor)) = key {
+            let degree = (chord.root as i8 - tonic as i8).rem_euclid(12);
+            let key_scale = if minor { NATURAL_MINOR } else { MAJOR };
+            let in_key = intervals.iter().all(|&i| key_scale.contains(&((degree + i).rem_euclid(12))));
+            if let Some(position) = key_scale.iter().position(|&d| d == degree).filter(|_| in_key) {
+                // Natural minor is the sixth mode of its relative major
+                let mode = MAJOR_MODES[(position + if minor { 5 } else { 0 }) % 7];
+                let key_name = format!("{} {}", tonic.name(), if minor { "minor" } else { "major" });
+                return choice(mode, format!("Diatonic to {}", key_name));
             }
-            _ => {}
         }
 
-        scales
+        if let Some((5, next)) = resolution.filter(|_| plain_dominant) {
+            return choice(primary.scale_type, format!("V7 resolving to {}", next.name()));
+        }
+        ScaleChoice {
+            scale: primary,
+            reason: "Default for the chord quality".to_string(),
+        }
     }
 
-    pub fn get_avoid_notes(chord: &Chord, scale: &Scale) -> Vec<Note> {
-        let mut avoid_notes = Vec::new();
-        let chord_notes = chord.notes();
-
-        match chord.quality {
-            ChordQuality::Major7 => {
-                let fourth = chord.root.transpose(5);
-                if !chord_notes.contains(&fourth) {
-                    avoid_notes.push(fourth);
-                }
-            }
-            ChordQuality::Minor7 => {
-                let sixth = chord.root.transpose(8);
-                if scale.contains(sixth) && !chord_notes.contains(&sixth) {
-                    avoid_notes.push(sixth);
-                }
+    /// Contextual scale for each chord of a sequence in a key
+    ///
+    /// The last chord resolves into the first, as when looping.
+    pub fn scales_in_key(chords: &[Chord], key: Note) -> Vec<ScaleChoice> {
+        let minor = FunctionalAnalysis::is_minor_key(chords, key);
+        chords
+            .iter()
+            .enumerate()
+            .map(|(i, chord)| {
+                let next = chords.get(i + 1).or(chords.first());
+                Self::get_contextual_scale(chord, next, Some((key, minor)))
+            })
+            .collect()
+    }
+
+    /// Contextual scale for each change of a progression, in its key
+    pub fn progression_scales(progression: &Progression) -> Vec<ScaleChoice> {
+        let chords: Vec<Chord> = progression.changes.iter().map(|c| c.chord.clone()).collect();
+        Self::scales_in_key(&chords, progression.key)
+    }
+
+    /// Scales to cycle through for a chord: the contextual choice, then the
+    /// alternates for its quality
+    pub fn scale_options(chord: &Chord, choice: &ScaleChoice) -> Vec<Scale> {
+        let mut scales = vec![choice.scale.clone()];
+        for scale in Self::get_alternate_scales(chord) {
+            if scales.iter().all(|s| s.scale_type != scale.scale_type) {
+                scales.push(scale);
             }
-            _ => {}
         }
+        scales
+    }
 
-        avoid_notes
+    pub fn get_alternate_scales(chord: &Chord) -> Vec<Scale> {
+        let mut scales = vec![Self::get_primary_scale(chord)];
+        let root = chord.spelled_root();
+
+        let alternates: &[ScaleType] = match chord.quality {
+            ChordQuality::Major7 => &[ScaleType::Lydian, ScaleType::BebopMajor],
+            ChordQuality::Minor7 => &[
+                ScaleType::Aeolian,
+                ScaleType::Phrygian,
+                ScaleType::BebopMinor,
+                ScaleType::DorianFlat2,
+            ],
+            ChordQuality::Dominant7 | ChordQuality::Dominant9 => &[
+                ScaleType::LydianDominant,
+                ScaleType::Altered,
+                ScaleType::WholeTone,
+                ScaleType::DiminishedHalfWhole,
+                ScaleType::BebopDominant,
+                ScaleType::PhrygianDominant,
+                ScaleType::MixolydianFlat6,
+            ],
+            ChordQuality::Dominant7b9 => &[ScaleType::PhrygianDominant, ScaleType::Altered],
+            ChordQuality::Dominant7b13 => &[ScaleType::PhrygianDominant, ScaleType::Altered],
+            ChordQuality::HalfDiminished => &[ScaleType::Locrian],
+            ChordQuality::MinorMajor7 => &[ScaleType::HarmonicMinor, ScaleType::HungarianMinor],
+            ChordQuality::Major7sharp5 => &[ScaleType::Augmented],
+            ChordQuality::Dominant7sharp5 | ChordQuality::Dominant7b5 => &[ScaleType::Altered],
+            _ => &[],
+        };
+        scales.extend(alternates.iter().map(|&scale_type| Scale::spelled(root, scale_type)));
+
+        scales
     }
 
-    pub fn chord_function(chord: &Chord, key: Note) -> &'static str {
-        let interval = (chord.root as i8 - key as i8).rem_euclid(12);
+    /// A chord to sound under a scale, made only of scale tones: the first
+    /// quality whose primary scale it is, else the first that lists it as
+    /// an alternate
+    pub fn chord_for_scale(scale: &Scale) -> Option<Chord> {
+        let chords: Vec<Chord> = ChordQuality::ALL
+            .into_iter()
+            .map(|quality| Chord::new(scale.root, quality))
+            .filter(|chord| chord.pitch_classes().iter().all(|&note| scale.contains(note)))
+            .collect();
+        chords
+            .iter()
+            .find(|chord| Self::get_primary_scale(chord).scale_type == scale.scale_type)
+            .or_else(|| {
+                chords.iter().find(|chord| {
+                    Self::get_alternate_scales(chord)
+                        .iter()
+                        .any(|s| s.scale_type == scale.scale_type)
+                })
+            })
+            .cloned()
+    }
 
-        match interval {
-            0 => "Tonic (I)",
-            2 => "Supertonic (ii)",
-            4 => "Mediant (iii)",
-            5 => "Subdominant (IV)",
-            7 => "Dominant (V)",
-            9 => "Submediant (vi)",
-            11 => "Leading Tone (vii)",
-            _ => "Unknown",
-        }
+    /// Scale tones that clash when held over the chord
+    ///
+    /// A scale tone a half step above a chord tone rubs against it. The b9
+    /// and b13 of a dominant are tensions rather than avoid notes, and bebop
+    /// passing tones are only passed through, so neither counts.
+    pub fn get_avoid_notes(chord: &Chord, scale: &Scale) -> Vec<Note> {
+        let chord_notes = chord.pitch_classes();
+        let passing = scale.scale_type.passing_tones();
+
+        scale
+            .scale_type
+            .intervals()
+            .iter()
+            .filter(|interval| !passing.contains(interval))
+            .map(|&interval| scale.root.transpose(interval))
+            .filter(|note| !chord_notes.contains(note) && chord_notes.contains(&note.transpose(-1)))
+            .filter(|&note| {
+                let above_root = (note as i8 - chord.root as i8).rem_euclid(12);
+                !(chord.quality.is_dominant() && matches!(above_root, 1 | 8))
+            })
+            .collect()
     }
 }
 
@@ -173,6 +362,167 @@ mod tests {
         assert_eq!(scale.root, Note::C);
     }
 
+    #[test]
+    fn test_primary_scales_contain_chord_tones() {
+        let qualities = [
+            ChordQuality::Major,
+            ChordQuality::Minor,
+            ChordQuality::Diminished,
+            ChordQuality::Augmented,
+            ChordQuality::Sus2,
+            ChordQuality::Sus4,
+            ChordQuality::Add9,
+            ChordQuality::Major69,
+            ChordQuality::Dominant11,
+            ChordQuality::Minor11,
+            ChordQuality::Dominant13,
+            ChordQuality::Dominant7sharp11,
+            ChordQuality::Dominant7sharp5,
+            ChordQuality::Dominant7b5,
+            ChordQuality::Dominant13sus4,
+            ChordQuality::HalfDiminished,
+            ChordQuality::Dominant7b13,
+            ChordQuality::Major7sharp5,
+        ];
+        for quality in qualities {
+            let chord = Chord::new(Note::Eb, quality);
+            let scale = ChordScaleMatcher::get_primary_scale(&chord);
+            for note in chord.pitch_classes() {
+                assert!(scale.contains(note), "{} not in {}", note, scale.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_contextual_scales() {
+        let chords = |names: &str| -> Vec<Chord> { names.split(' ').map(|n| n.parse().unwrap()).collect() };
+        let types = |choices: Vec<ScaleChoice>| -> Vec<ScaleType> { choices.iter().map(|c| c.scale.scale_type).collect() };
+
+        // iii-vi-ii-V-I in C: the minor chords follow the key
+        let scales = ChordScaleMatcher::scales_in_key(&chords("Em7 Am7 Dm7 G7 Cmaj7 Fmaj7"), Note::C);
+        assert_eq!(
+            types(scales.clone()),
+            vec![
+                ScaleType::Phrygian,
+                ScaleType::Aeolian,
+                ScaleType::Dorian,
+                ScaleType::Mixolydian,
+                ScaleType::Major,
+                ScaleType::Lydian,
+            ]
+        );
+        assert_eq!(scales[0].reason, "Diatonic to C major");
+
+        // Minor ii-V-i: the V7 resolves to minor, the iiø7 is from the key
+        let scales = ChordScaleMatcher::scales_in_key(&chords("Dm7b5 G7 Cm7"), Note::C);
+        assert_eq!(
+            types(scales.clone()),
+            vec![ScaleType::Locrian, ScaleType::PhrygianDominant, ScaleType::Aeolian]
+        );
+        assert!(scales[1].reason.contains("minor"));
+
+        // Secondary dominant into a minor chord, tritone sub and backdoor dominant
+        let scales = ChordScaleMatcher::scales_in_key(&chords("A7 Dm7 Db7 Cmaj7 Bb7 Cmaj7"), Note::C);
+        assert_eq!(scales[0].scale.scale_type, ScaleType::PhrygianDominant);
+        assert_eq!(scales[2].scale.scale_type, ScaleType::LydianDominant);
+        assert_eq!(scales[4].scale.scale_type, ScaleType::LydianDominant);
+        assert!(scales[4].reason.contains("Backdoor"));
+
+        // Without a key only the resolution counts; chromatic chords keep their default
+        let em7 = Chord::new(Note::E, ChordQuality::Minor7);
+        let choice = ChordScaleMatcher::get_contextual_scale(&em7, None, None);
+        assert_eq!(choice.scale.scale_type, ScaleType::Dorian);
+        let choice = ChordScaleMatcher::get_contextual_scale(&em7, None, Some((Note::Bb, false)));
+        assert_eq!(choice.reason, "Default for the chord quality");
+    }
+
+    #[test]
+    fn test_contextual_scales_contain_chord_tones() {
+        let library = crate::music::ProgressionLibrary::new();
+        for genre in library.all_genres() {
+            for prog in library.get_by_genre(&genre).into_iter().flatten() {
+                let options = ChordScaleMatcher::progression_scales(prog);
+                for (change, choice) in prog.changes.iter().zip(&options) {
+                    let scales = ChordScaleMatcher::scale_options(&change.chord, choice);
+                    assert_eq!(scales[0].scale_type, choice.scale.scale_type);
+
+                    // Only check scales context picked; some defaults leave out the 5th
+                    if choice.scale.scale_type == ChordScaleMatcher::get_primary_scale(&change.chord).scale_type {
+                        continue;
+                    }
+                    for note in change.chord.pitch_classes() {
+                        assert!(choice.scale.contains(note), "{} not in {} ({})", note, choice.scale.name(), prog.name);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_new_scale_suggestions() {
+        let primary = |symbol: &str| ChordScaleMatcher::get_primary_scale(&symbol.parse().unwrap()).scale_type;
+        assert_eq!(primary("Bm7b5"), ScaleType::LocrianNatural2);
+        assert_eq!(primary("Ebmaj7#5"), ScaleType::LydianAugmented);
+        assert_eq!(primary("G7b13"), ScaleType::MixolydianFlat6);
+
+        // Every alternate keeps the root and guide tones; altered scales may drop the 5th
+        for quality in ChordQuality::ALL {
+            let chord = Chord::new(Note::F, quality);
+            for scale in ChordScaleMatcher::get_alternate_scales(&chord).iter().skip(1) {
+                for note in chord.guide_tones().into_iter().chain([chord.root]) {
+                    assert!(scale.contains(note), "{} not in {} for {}", note, scale.name(), chord.name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_chord_for_scale() {
+        let chord = |scale_type| {
+            ChordScaleMatcher::chord_for_scale(&Scale::new(Note::D, scale_type)).map(|c| c.name())
+        };
+        assert_eq!(chord(ScaleType::Major).as_deref(), Some("Dmaj7"));
+        assert_eq!(chord(ScaleType::Phrygian).as_deref(), Some("Dm7"));
+        assert_eq!(chord(ScaleType::Locrian).as_deref(), Some("Dm7b5"));
+        // The altered scale has no perfect 5th
+        assert_eq!(chord(ScaleType::Altered).as_deref(), Some("D7#5"));
+        assert_eq!(chord(ScaleType::MajorPentatonic), None);
+
+        // Every tone of the chord is in the scale
+        for scale_type in ScaleType::ALL {
+            let scale = Scale::new(Note::D, scale_type);
+            if let Some(chord) = ChordScaleMatcher::chord_for_scale(&scale) {
+                for note in chord.pitch_classes() {
+                    assert!(scale.contains(note), "{} not in {}", note, scale.name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_avoid_notes() {
+        let avoid = |symbol: &str, scale_type: ScaleType| -> Vec<Note> {
+            let chord: Chord = symbol.parse().unwrap();
+            ChordScaleMatcher::get_avoid_notes(&chord, &Scale::new(chord.root, scale_type))
+        };
+
+        // A half step above a chord tone
+        assert_eq!(avoid("Cmaj7", ScaleType::Major), vec![Note::F]);
+        assert_eq!(avoid("G7", ScaleType::Mixolydian), vec![Note::C]);
+        assert_eq!(avoid("Am7", ScaleType::Aeolian), vec![Note::F]);
+        assert_eq!(avoid("Em7", ScaleType::Phrygian), vec![Note::F, Note::C]);
+        assert!(avoid("Dm7", ScaleType::Dorian).is_empty());
+        assert!(avoid("Cmaj7", ScaleType::Lydian).is_empty());
+
+        // Locrian natural 2 drops the clashing b9 of Locrian
+        assert_eq!(avoid("Bm7b5", ScaleType::Locrian), vec![Note::C]);
+        assert!(avoid("Bm7b5", ScaleType::LocrianNatural2).is_empty());
+
+        // b9 and b13 are tensions on a dominant, and passing tones pass
+        assert_eq!(avoid("E7", ScaleType::PhrygianDominant), vec![Note::A]);
+        assert_eq!(avoid("G7", ScaleType::BebopDominant), vec![Note::C]);
+    }
+
     #[test]
     fn test_guide_tone_movement() {
         let dm7 = Chord::new(Note::D, ChordQuality::Minor7);
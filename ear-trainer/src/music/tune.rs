@@ -0,0 +1,390 @@
+//! LEGO Tune Builder
+//!
+//! Chains bricks and joins into a full form. A tune is a set of labelled
+//! sections (A, B, C...) played in the order its form gives, so an AABA
+//! tune only spells out its A and B sections once. Each section starts in
+//! the home key (or a key of its own), and every join moves the key for
+//! the bricks after it. Building yields a playable `Progression` and a
+//! `Standard`-shaped breakdown of where each brick lands.
+
+use super::bricks::BrickLibrary;
+use super::chord::Note;
+use super::joins::JoinLibrary;
+use super::meter::Meter;
+use super::progression::Progression;
+use super::standards::{BrickRef, Standard, StandardSection};
+use anyhow::{anyhow, bail, Result};
+
+/// Genre built tunes are filed under in the progression library
+const TUNE_GENRE: &str = "LEGO Tunes";
+
+/// One step within a section
+#[derive(Debug, Clone, PartialEq)]
+pub enum TunePart {
+    /// A brick in the current key, or moving to a key of its own, optionally
+    /// stretched to fill a number of bars
+    Brick {
+        name: String,
+        key: Option<Note>,
+        bars: Option<u8>,
+    },
+    /// A join moving the current key
+    Join(String),
+}
+
+/// A labelled section and the parts it is made of
+#[derive(Debug, Clone)]
+pub struct TuneSection {
+    pub label: String,
+    /// Key the section starts in (the home key when None)
+    pub key: Option<Note>,
+    pub parts: Vec<TunePart>,
+}
+
+/// The result of building: something to play and its breakdown
+#[derive(Debug, Clone)]
+pub struct Tune {
+    pub progression: Progression,
+    pub standard: Standard,
+}
+
+#[derive(Debug, Clone)]
+pub struct TuneBuilder {
+    pub name: String,
+    pub key: Note,
+    pub tempo: f32,
+    pub meter: Meter,
+    pub sections: Vec<TuneSection>,
+    /// Section labels in playing order, one letter each; empty plays the
+    /// sections as written
+    pub form: String,
+}
+
+impl TuneBuilder {
+    pub fn new(name: impl Into<String>, key: Note, tempo: f32) -> Self {
+        Self {
+            name: name.into(),
+            key,
+            tempo,
+            meter: Meter::default(),
+            sections: Vec::new(),
+            form: String::new(),
+        }
+    }
+
+    /// Start a section in the home key
+    pub fn section(mut self, label: impl Into<String>) -> Self {
+        self.add_section(label, None);
+        self
+    }
+
+    /// Add a brick in the current key
+    pub fn brick(mut self, name: &str) -> Self {
+        self.add_brick(name, None);
+        self
+    }
+
+    /// Move the current key with a join
+    pub fn join(mut self, name: &str) -> Self {
+        self.add_join(name);
+        self
+    }
+
+    pub fn form(mut self, form: &str) -> Self {
+        self.form = form.to_string();
+        self
+    }
+
+    /// Rebuild a standard from its LEGO breakdown
+    ///
+    /// Bricks are laid end to end, each stretched over the bars the
+    /// breakdown gives it.
+    pub fn from_standard(standard: &Standard) -> Self {
+        let tempo = (standard.tempo_range.0 + standard.tempo_range.1) as f32 / 2.0;
+        let mut builder = Self::new(standard.name.clone(), standard.key, tempo);
+        for section in &standard.sections {
+            builder.add_section(section.label.clone(), Some(section.key));
+            for brick in &section.bricks {
+                builder.push(TunePart::Brick {
+                    name: brick.brick_name.clone(),
+                    key: Some(brick.key),
+                    bars: Some(brick.duration_bars),
+                });
+            }
+        }
+        builder
+    }
+
+    pub fn add_section(&mut self, label: impl Into<String>, key: Option<Note>) {
+        self.sections.push(TuneSection {
+            label: label.into(),
+            key,
+            parts: Vec::new(),
+        });
+    }
+
+    pub fn add_brick(&mut self, name: &str, key: Option<Note>) {
+        self.push(TunePart::Brick {
+            name: name.to_string(),
+            key,
+            bars: None,
+        });
+    }
+
+    pub fn add_join(&mut self, name: &str) {
+        self.push(TunePart::Join(name.to_string()));
+    }
+
+    /// Append to the last section, opening an A section if there is none
+    fn push(&mut self, part: TunePart) {
+        if self.sections.is_empty() {
+            self.add_section("A", None);
+        }
+        if let Some(section) = self.sections.last_mut() {
+            section.parts.push(part);
+        }
+    }
+
+    /// Label for the next new section: the first unused letter
+    pub fn next_label(&self) -> String {
+        ('A'..='Z')
+            .map(String::from)
+            .find(|label| self.sections.iter().all(|s| s.label != *label))
+            .unwrap_or_else(|| "Z".to_string())
+    }
+
+    /// Remove the last part, or the last section once it is empty
+    pub fn undo(&mut self) {
+        if let Some(section) = self.sections.last_mut() {
+            if section.parts.pop().is_none() {
+                self.sections.pop();
+            }
+        }
+    }
+
+    /// Sections in playing order
+    fn ordered_sections(&self) -> Result<Vec<&TuneSection>> {
+        if self.form.is_empty() {
+            return Ok(self.sections.iter().collect());
+        }
+        self.form
+            .chars()
+            .map(|c| {
+                self.sections
+                    .iter()
+                    .find(|s| s.label == c.to_string())
+                    .ok_or_else(|| anyhow!("form {} has no {} section", self.form, c))
+            })
+            .collect()
+    }
+
+    pub fn build(&self, bricks: &BrickLibrary, joins: &JoinLibrary) -> Result<Tune> {
+        let order = self.ordered_sections()?;
+        let bar = self.meter.bar_beats();
+
+        let mut progression = Progression::new(
+            self.name.clone(),
+            TUNE_GENRE.to_string(),
+            self.key,
+            self.tempo,
+        )
+        .with_meter(self.meter);
+        let mut sections = Vec::new();
+        let mut joins_used: Vec<String> = Vec::new();
+        let mut beat = 0.0;
+
+        for (i, section) in order.iter().enumerate() {
+            // Repeated sections are numbered: A1, A2, B, A3
+            let repeats = order.iter().filter(|s| s.label == section.label).count();
+            let label = if repeats > 1 {
+                let nth = order[..=i].iter().filter(|s| s.label == section.label).count();
+                format!("{}{}", section.label, nth)
+            } else {
+                section.label.clone()
+            };
+
+            let start = beat;
+            let section_key = section.key.unwrap_or(self.key);
+            let mut key = section_key;
+            let mut refs = Vec::new();
+
+            for part in &section.parts {
+                match part {
+                    TunePart::Join(name) => {
+                        let join = joins
+                            .get(name)
+                            .ok_or_else(|| anyhow!("unknown join: {}", name))?;
+                        let to = join.apply(key);
+                        let used = format!("{} ({} -> {})", join.name, key.name(), to.name());
+                        if !joins_used.contains(&used) {
+                            joins_used.push(used);
+                        }
+                        key = to;
+                    }
+                    TunePart::Brick {
+                        name,
+                        key: brick_key,
+                        bars,
+                    } => {
+                        let brick = bricks
+                            .get(name)
+                            .ok_or_else(|| anyhow!("unknown brick: {}", name))?;
+                        if let Some(brick_key) = brick_key {
+                            key = *brick_key;
+                        }
+                        let length = bars.map_or(brick.duration_beats, |n| n as f32 * bar);
+                        // Breakdowns number their bars in a u8
+                        let end_bar = ((beat + length) / bar).ceil();
+                        if end_bar > u8::MAX as f32 {
+                            bail!("tune is over {} bars long", u8::MAX);
+                        }
+                        let stretch = length / brick.duration_beats;
+                        let tonic = brick.tonic(key);
+                        for bc in &brick.template {
+                            progression.add_chord(bc.to_chord(tonic), bc.duration * stretch);
+                        }
+                        refs.push(BrickRef {
+                            brick_name: brick.name.clone(),
+                            start_bar: (beat / bar) as u8 + 1,
+                            duration_bars: (length / bar).ceil() as u8,
+                            key,
+                            notes: None,
+                        });
+                        beat += length;
+                    }
+                }
+            }
+
+            sections.push(StandardSection {
+                label,
+                bars: ((beat - start) / bar).ceil() as u8,
+                bricks: refs,
+                key: section_key,
+            });
+        }
+
+        if progression.changes.is_empty() {
+            bail!("tune has no bricks");
+        }
+
+        // Numbered labels from a breakdown (A1, A2) count as their letter
+        let form = order
+            .iter()
+            .map(|s| s.label.trim_end_matches(|c: char| c.is_ascii_digit()))
+            .collect::<String>();
+        let mut standard = Standard {
+            name: self.name.clone(),
+            composer: "LEGO Builder".to_string(),
+            year: None,
+            total_bars: (beat / bar).ceil() as u8,
+            form,
+            key: self.key,
+            tempo_range: (self.tempo as u16, self.tempo as u16),
+            style: format!("{} practice tune", self.meter),
+            sections,
+            brick_summary: Vec::new(),
+            joins_used,
+        };
+        standard.brick_summary = standard
+            .brick_frequency()
+            .into_iter()
+            .map(|(name, count)| format!("{} x{}", name, count))
+            .collect();
+
+        Ok(Tune {
+            progression,
+            standard,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::StandardsLibrary;
+
+    fn build(builder: &TuneBuilder) -> Result<Tune> {
+        builder.build(&BrickLibrary::new(), &JoinLibrary::new())
+    }
+
+    #[test]
+    fn test_aaba_form() {
+        let builder = TuneBuilder::new("Practice AABA", Note::C, 160.0)
+            .section("A")
+            .brick("Launcher")
+            .brick("Sad Launcher")
+            .section("B")
+            .join("High Jump")
+            .brick("Launcher")
+            .brick("Launcher")
+            .form("AABA");
+        let tune = build(&builder).unwrap();
+
+        let labels: Vec<_> = tune.standard.sections.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, vec!["A1", "A2", "B", "A3"]);
+        assert_eq!(tune.standard.form, "AABA");
+        assert_eq!(tune.standard.total_bars, 32);
+        assert_eq!(tune.progression.total_duration(), 128.0);
+        assert_eq!(tune.progression.changes.len(), 24);
+
+        // The bridge moves up a fourth; the last A is back home
+        let bridge = &tune.standard.sections[2];
+        assert_eq!(bridge.bricks[0].key, Note::F);
+        assert_eq!(bridge.bricks[1].start_bar, 21);
+        assert_eq!(tune.standard.sections[3].bricks[0].key, Note::C);
+        assert_eq!(tune.progression.changes[12].chord.name(), "Gm7");
+        assert_eq!(tune.standard.joins_used, vec!["High Jump (C -> F)"]);
+        assert_eq!(tune.standard.brick_frequency()[0], ("Launcher", 5));
+    }
+
+    #[test]
+    fn test_form_needs_its_sections() {
+        let builder = TuneBuilder::new("ABAC", Note::F, 140.0)
+            .section("A")
+            .brick("Launcher")
+            .section("B")
+            .brick("Rhythm")
+            .form("ABAC");
+        assert!(build(&builder).is_err());
+        assert!(build(&TuneBuilder::new("Empty", Note::C, 120.0)).is_err());
+        assert!(build(&TuneBuilder::new("Typo", Note::C, 120.0).brick("Lancher")).is_err());
+
+        // Longer than a breakdown can number
+        let long = (0..200).fold(TuneBuilder::new("Long", Note::C, 120.0), |b, _| b.brick("Launcher"));
+        let err = build(&long).unwrap_err().to_string();
+        assert!(err.contains("bars long"), "{}", err);
+    }
+
+    #[test]
+    fn test_rebuild_standard() {
+        let library = StandardsLibrary::new();
+        let standard = library.get("Autumn Leaves").unwrap();
+        let tune = build(&TuneBuilder::from_standard(standard)).unwrap();
+
+        assert_eq!(tune.standard.total_bars, standard.total_bars);
+        assert_eq!(tune.standard.form, standard.form);
+        for (rebuilt, original) in tune.standard.sections.iter().zip(&standard.sections) {
+            assert_eq!(rebuilt.label, original.label);
+            for (a, b) in rebuilt.bricks.iter().zip(&original.bricks) {
+                assert_eq!(a.brick_name, b.brick_name);
+                assert_eq!(a.start_bar, b.start_bar);
+                assert_eq!(a.key, b.key);
+            }
+        }
+        // Opens on the relative major's ii: Cm7 in Bb
+        assert_eq!(tune.progression.changes[0].chord.name(), "Cm7");
+    }
+
+    #[test]
+    fn test_undo_and_labels() {
+        let mut builder = TuneBuilder::new("Scratch", Note::C, 120.0);
+        builder.add_brick("Launcher", None);
+        assert_eq!(builder.sections[0].label, "A");
+        assert_eq!(builder.next_label(), "B");
+        builder.add_section("B", None);
+        builder.undo();
+        assert_eq!(builder.sections.len(), 1);
+        builder.undo();
+        assert!(builder.sections[0].parts.is_empty());
+    }
+}
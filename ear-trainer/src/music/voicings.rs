@@ -4,6 +4,7 @@
 //! to sophisticated rootless voicings used by jazz pianists.
 
 use super::chord::{Chord, ChordQuality};
+use super::theory::{ChordScaleMatcher, VoiceLeading};
 use serde::{Deserialize, Serialize};
 
 /// Types of chord voicings
@@ -20,6 +21,18 @@ pub enum VoicingType {
     RootlessB,
     /// Drop 2 voicing - second voice from top dropped an octave
     Drop2,
+    /// Drop 3 voicing - third voice from top dropped an octave
+    Drop3,
+    /// Drop 2&4 voicing - second and fourth voices from top dropped an octave
+    Drop24,
+    /// Four notes stacked in fourths from the chord scale (McCoy Tyner)
+    Quartal,
+    /// Three fourths and a major third (Bill Evans on "So What")
+    SoWhat,
+    /// A triad over the 3rd and 7th of a dominant
+    UpperStructure,
+    /// Open two-hand voicing: root and 7th below, 3rd, 5th and 9th above
+    Spread,
 }
 
 impl VoicingType {
@@ -30,6 +43,12 @@ impl VoicingType {
             VoicingType::RootlessA => "Rootless A",
             VoicingType::RootlessB => "Rootless B",
             VoicingType::Drop2 => "Drop 2",
+            VoicingType::Drop3 => "Drop 3",
+            VoicingType::Drop24 => "Drop 2&4",
+            VoicingType::Quartal => "Quartal",
+            VoicingType::SoWhat => "So What",
+            VoicingType::UpperStructure => "Upper Structure",
+            VoicingType::Spread => "Spread",
         }
     }
 
@@ -40,6 +59,12 @@ impl VoicingType {
             VoicingType::RootlessA => "3-5-7-9 (Bill Evans style, no root)",
             VoicingType::RootlessB => "7-9-3-5 (inverted rootless)",
             VoicingType::Drop2 => "Second voice from top dropped an octave",
+            VoicingType::Drop3 => "Third voice from top dropped an octave",
+            VoicingType::Drop24 => "Second and fourth voices from top dropped an octave",
+            VoicingType::Quartal => "Stacked fourths from the chord scale, starting on the 3rd",
+            VoicingType::SoWhat => "Three fourths and a major third (Bill Evans, \"So What\")",
+            VoicingType::UpperStructure => "Triad over the 3rd and 7th (e.g. D over C7 for C13#11)",
+            VoicingType::Spread => "Root and 7th in the left hand, 3-5-9 in the right",
         }
     }
 
@@ -49,7 +74,13 @@ impl VoicingType {
             VoicingType::Shell => VoicingType::RootlessA,
             VoicingType::RootlessA => VoicingType::RootlessB,
             VoicingType::RootlessB => VoicingType::Drop2,
-            VoicingType::Drop2 => VoicingType::Full,
+            VoicingType::Drop2 => VoicingType::Drop3,
+            VoicingType::Drop3 => VoicingType::Drop24,
+            VoicingType::Drop24 => VoicingType::Quartal,
+            VoicingType::Quartal => VoicingType::SoWhat,
+            VoicingType::SoWhat => VoicingType::UpperStructure,
+            VoicingType::UpperStructure => VoicingType::Spread,
+            VoicingType::Spread => VoicingType::Full,
         }
     }
 
@@ -81,6 +112,12 @@ impl VoicingType {
             VoicingType::RootlessA => self.rootless_a(chord, voicing_octave),
             VoicingType::RootlessB => self.rootless_b(chord, voicing_octave),
             VoicingType::Drop2 => self.drop2_voicing(chord, voicing_octave),
+            VoicingType::Drop3 => self.drop_voicing(chord, voicing_octave, &[2]),
+            VoicingType::Drop24 => self.drop_voicing(chord, voicing_octave, &[1, 3]),
+            VoicingType::Quartal => self.quartal_voicing(chord, voicing_octave),
+            VoicingType::SoWhat => self.so_what_voicing(chord, voicing_octave),
+            VoicingType::UpperStructure => self.upper_structure(chord, voicing_octave),
+            VoicingType::Spread => self.spread_voicing(chord, voicing_octave),
         };
 
         // Filter to range
@@ -109,13 +146,13 @@ impl VoicingType {
         let intervals = chord.quality.intervals();
         let mut notes = Vec::new();
 
-        // Find 3rd (interval 3 or 4)
-        if let Some(&third_int) = intervals.iter().find(|&&i| i == 3 || i == 4) {
+        // Find 3rd (interval 3 or 4, or the suspended note)
+        if let Some(third_int) = third(&intervals) {
             notes.push(chord.root.transpose(third_int).to_midi(octave));
         }
 
-        // Find 7th (interval 10, 11, or 9 for 6th chords)
-        if let Some(&seventh_int) = intervals.iter().find(|&&i| i == 10 || i == 11 || i == 9) {
+        // Find 7th (interval 10, 11, or 9 for 6th chords); triads use the 5th
+        if let Some(seventh_int) = seventh(&intervals).or_else(|| fifth(&intervals)) {
             notes.push(chord.root.transpose(seventh_int).to_midi(octave));
         }
 
@@ -129,24 +166,18 @@ impl VoicingType {
         let mut notes = Vec::new();
 
         // 3rd
-        if let Some(&third) = intervals.iter().find(|&&i| i == 3 || i == 4) {
+        if let Some(third) = third(&intervals) {
             notes.push(chord.root.transpose(third).to_midi(octave));
         }
 
-        // 5th
-        if intervals.contains(&7) {
-            notes.push(chord.root.transpose(7).to_midi(octave));
-        } else if intervals.contains(&6) {
-            // Diminished 5th
-            notes.push(chord.root.transpose(6).to_midi(octave));
+        // 5th (or the 13th on thirteenth chords)
+        if let Some(fifth) = thirteenth(&intervals).or_else(|| fifth(&intervals)) {
+            notes.push(chord.root.transpose(fifth).to_midi(octave));
         }
 
-        // 7th
-        if let Some(&seventh) = intervals.iter().find(|&&i| i == 10 || i == 11) {
+        // 7th (or 6th on 6th chords)
+        if let Some(seventh) = seventh(&intervals) {
             notes.push(chord.root.transpose(seventh).to_midi(octave));
-        } else if intervals.contains(&9) {
-            // 6th chord
-            notes.push(chord.root.transpose(9).to_midi(octave));
         }
 
         // 9th (add if available, one octave up)
@@ -167,10 +198,8 @@ impl VoicingType {
         let mut notes = Vec::new();
 
         // 7th at bottom
-        if let Some(&seventh) = intervals.iter().find(|&&i| i == 10 || i == 11) {
+        if let Some(seventh) = seventh(&intervals) {
             notes.push(chord.root.transpose(seventh).to_midi(octave));
-        } else if intervals.contains(&9) {
-            notes.push(chord.root.transpose(9).to_midi(octave));
         }
 
         // 9th
@@ -181,15 +210,13 @@ impl VoicingType {
         }
 
         // 3rd (one octave up)
-        if let Some(&third) = intervals.iter().find(|&&i| i == 3 || i == 4) {
+        if let Some(third) = third(&intervals) {
             notes.push(chord.root.transpose(third).to_midi(octave + 1));
         }
 
-        // 5th (one octave up)
-        if intervals.contains(&7) {
-            notes.push(chord.root.transpose(7).to_midi(octave + 1));
-        } else if intervals.contains(&6) {
-            notes.push(chord.root.transpose(6).to_midi(octave + 1));
+        // 5th or 13th (one octave up)
+        if let Some(fifth) = thirteenth(&intervals).or_else(|| fifth(&intervals)) {
+            notes.push(chord.root.transpose(fifth).to_midi(octave + 1));
         }
 
         notes.sort_unstable();
@@ -212,6 +239,206 @@ impl VoicingType {
         close.sort_unstable();
         close
     }
+
+    /// Drop the given voices (counted from the top, 0 = top) of a four-part
+    /// close voicing by an octave
+    fn drop_voicing(&self, chord: &Chord, octave: i8, drops: &[usize]) -> Vec<u8> {
+        let mut close = stack(chord, &four_part(&chord.quality.intervals()), octave);
+        let top = close.len().saturating_sub(1);
+        for &drop in drops {
+            if let Some(note) = top.checked_sub(drop).and_then(|i| close.get_mut(i)) {
+                *note = note.saturating_sub(12);
+            }
+        }
+        close.sort_unstable();
+        close
+    }
+
+    fn quartal_voicing(&self, chord: &Chord, octave: i8) -> Vec<u8> {
+        // Fourths up from the 3rd: a perfect 4th, or an augmented one where
+        // the chord scale has no perfect 4th, so the stack stays inside the
+        // scale whether it has six, seven or eight notes
+        let intervals = chord.quality.intervals();
+        let scale: Vec<i8> = ChordScaleMatcher::get_primary_scale(chord)
+            .scale_type
+            .intervals()
+            .iter()
+            .map(|i| i.rem_euclid(12))
+            .collect();
+
+        let mut fourths = vec![third(&intervals).unwrap_or(0) % 12];
+        while fourths.len() < 4 {
+            let below = fourths[fourths.len() - 1];
+            let fourth = if scale.contains(&((below + 5) % 12)) || !scale.contains(&((below + 6) % 12)) {
+                below + 5
+            } else {
+                below + 6
+            };
+            fourths.push(fourth % 12);
+        }
+        stack(chord, &fourths, octave - 1)
+    }
+
+    fn so_what_voicing(&self, chord: &Chord, octave: i8) -> Vec<u8> {
+        // The shape starts on the root of a minor chord (m11), the 3rd of a
+        // major one (maj13) and the 5th of a dominant (the sus sound)
+        let quality = chord.quality;
+        let intervals = quality.intervals();
+        let unaltered = intervals.iter().all(|i| [0, 2, 4, 5, 7, 9, 10].contains(&(i % 12)));
+        let bottom = if quality.is_minor() && fifth(&intervals) == Some(7) && seventh(&intervals) != Some(11) {
+            0
+        } else if unaltered && (quality.is_dominant() || intervals.contains(&5)) {
+            7
+        } else if intervals.contains(&4) && fifth(&intervals) == Some(7) {
+            4
+        } else {
+            // Diminished and augmented chords have no So What; stack fourths instead
+            return self.quartal_voicing(chord, octave);
+        };
+        let shape: Vec<i8> = [0, 5, 10, 15, 19].iter().map(|i| (bottom + i) % 12).collect();
+        stack(chord, &shape, octave - 1)
+    }
+
+    fn upper_structure(&self, chord: &Chord, octave: i8) -> Vec<u8> {
+        let intervals = chord.quality.intervals();
+        // (triad root above the chord root, triad intervals)
+        let triad: (i8, [i8; 3]) = match chord.quality {
+            ChordQuality::Dominant7b9 => (9, [0, 4, 7]), // VI: 13, b9, 3
+            ChordQuality::Dominant7sharp9 | ChordQuality::Altered | ChordQuality::Dominant7sharp5 => {
+                (8, [0, 4, 7]) // bVI: b13, root, #9
+            }
+            ChordQuality::Dominant7b13 => (8, [0, 4, 8]), // bVI+: b13, root, 3
+            ChordQuality::Major7sharp5 => (4, [0, 4, 7]), // III: 3, #5, 7
+            ChordQuality::Dominant7sus4 | ChordQuality::Dominant11 | ChordQuality::Dominant13sus4 => {
+                (10, [0, 4, 7]) // bVII: 7, 9, 11
+            }
+            q if q.is_dominant() => (2, [0, 4, 7]), // II: 9, #11, 13
+            q if q.is_minor() && seventh(&intervals) == Some(10) => (10, [0, 4, 7]), // bVII: m11
+            _ if intervals.contains(&4) && seventh(&intervals) == Some(11) => (7, [0, 4, 7]), // V: maj9
+            _ => return self.rootless_a(chord, octave),
+        };
+
+        // Guide tones in the left hand, the triad in root position above them
+        let guide: Vec<i8> = third(&intervals).into_iter().chain(seventh(&intervals)).collect();
+        let mut notes = stack(chord, &guide, octave - 1);
+        let upper: Vec<i8> = triad.1.iter().map(|i| (triad.0 + i) % 12).collect();
+        let floor = notes.last().copied().unwrap_or(0);
+        let mut triad_notes = stack(chord, &upper, octave);
+        while triad_notes.first().is_some_and(|&n| n <= floor) {
+            triad_notes.iter_mut().for_each(|n| *n += 12);
+        }
+        notes.extend(triad_notes);
+        notes
+    }
+
+    fn spread_voicing(&self, chord: &Chord, octave: i8) -> Vec<u8> {
+        let intervals = chord.quality.intervals();
+        // Left hand: root and 7th (the 5th on triads)
+        let left: Vec<i8> = std::iter::once(0)
+            .chain(seventh(&intervals).or_else(|| fifth(&intervals)))
+            .collect();
+
+        // Right hand: 3rd, 5th (or 13th) and 9th
+        let mut right: Vec<i8> = third(&intervals).into_iter().collect();
+        if seventh(&intervals).is_some() {
+            right.extend(
+                intervals
+                    .iter()
+                    .find(|&&i| i == 20 || i == 21)
+                    .copied()
+                    .or_else(|| fifth(&intervals)),
+            );
+        }
+        right.push(intervals.iter().find(|&&i| (13..=15).contains(&i)).copied().unwrap_or(14));
+
+        let mut notes = stack(chord, &left, octave - 1);
+        let floor = notes.last().copied().unwrap_or(0);
+        let mut upper = stack(chord, &right, octave);
+        while upper.first().is_some_and(|&n| n <= floor) {
+            upper.iter_mut().for_each(|n| *n += 12);
+        }
+        notes.extend(upper);
+        notes
+    }
+
+    /// Voicings with a set shape, which a progression voicer may move by
+    /// octaves but not invert
+    fn fixed_shape(&self) -> bool {
+        matches!(
+            self,
+            VoicingType::Drop3
+                | VoicingType::Drop24
+                | VoicingType::Quartal
+                | VoicingType::SoWhat
+                | VoicingType::UpperStructure
+                | VoicingType::Spread
+        )
+    }
+}
+
+/// Stack chord tones upwards in the given order, each above the last,
+/// starting at the root's octave
+fn stack(chord: &Chord, intervals: &[i8], octave: i8) -> Vec<u8> {
+    let mut notes: Vec<u8> = Vec::with_capacity(intervals.len());
+    for &interval in intervals {
+        let mut midi = chord.root.transpose(interval).to_midi(octave);
+        while notes.last().is_some_and(|&prev| midi <= prev) {
+            midi += 12;
+        }
+        notes.push(midi);
+    }
+    notes
+}
+
+/// Four voices for drop voicings in close position: the 9th replaces the
+/// root, and a 13th, b13th, #11 or 11th replaces the 5th, when the chord has them
+pub(super) fn four_part(intervals: &[i8]) -> Vec<i8> {
+    let bottom = intervals.iter().find(|&&i| (13..=15).contains(&i)).copied().unwrap_or(0);
+    let middle = intervals
+        .iter()
+        .find(|&&i| matches!(i, 20 | 21 | 18))
+        .or_else(|| intervals.iter().find(|&&i| i == 17 && intervals.contains(&3)))
+        .copied()
+        .or_else(|| fifth(intervals));
+    // Triads double the root on top
+    let top = seventh(intervals).unwrap_or(if bottom == 0 { 12 } else { 0 });
+
+    let mut voices: Vec<i8> = std::iter::once(bottom % 12)
+        .chain(third(intervals).map(|i| i % 12))
+        .chain(middle.map(|i| i % 12))
+        .collect();
+    voices.sort_unstable();
+    voices.push(top);
+    voices
+}
+
+/// The 3rd of a chord, or the suspended 4th/2nd that replaces it
+pub(super) fn third(intervals: &[i8]) -> Option<i8> {
+    intervals
+        .iter()
+        .find(|&&i| i == 3 || i == 4)
+        .or_else(|| intervals.iter().find(|&&i| i % 12 == 5))
+        .or_else(|| intervals.iter().find(|&&i| i % 12 == 2))
+        .copied()
+}
+
+/// The 5th, including diminished and augmented fifths
+pub(super) fn fifth(intervals: &[i8]) -> Option<i8> {
+    [7, 6, 8].into_iter().find(|i| intervals.contains(i))
+}
+
+/// The 7th, or the 6th on 6th chords; triads have none
+pub(super) fn seventh(intervals: &[i8]) -> Option<i8> {
+    intervals
+        .iter()
+        .find(|&&i| i == 10 || i == 11)
+        .or_else(|| intervals.iter().find(|&&i| i == 9))
+        .copied()
+}
+
+/// A natural 13th, played in place of the 5th in rootless voicings
+fn thirteenth(intervals: &[i8]) -> Option<i8> {
+    intervals.iter().find(|&&i| i == 21).copied()
 }
 
 /// A chord that has been voiced with specific MIDI notes
@@ -232,29 +459,129 @@ impl VoicedChord {
         notes.dedup();
         notes
     }
+}
+
+/// Cost of moving the comping hand one semitone, relative to drifting from the centre
+const MOTION_WEIGHT: u32 = 2;
+
+/// Voices a whole progression so the comping hand moves as little as possible
+///
+/// Each chord can be played in any inversion of its voicing, an octave either
+/// way, within `range`; rootless voicings may also switch between the A and B
+/// forms. The cheapest path through those choices minimizes the total
+/// semitone motion, with a small pull towards the middle of the range so the
+/// voicing doesn't creep up or down the keyboard.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressionVoicer {
+    pub voicing: VoicingType,
+    /// (min_midi, max_midi) every voicing note must fall in
+    pub range: (u8, u8),
+}
+
+impl ProgressionVoicer {
+    pub fn new(voicing: VoicingType) -> Self {
+        Self {
+            voicing,
+            range: (48, 84), // C3 to C6
+        }
+    }
+
+    /// Voice every chord, bass in octave 2 as `voice_chord` does
+    pub fn voice(&self, chords: &[Chord]) -> Vec<VoicedChord> {
+        let candidates: Vec<Vec<Vec<u8>>> = chords.iter().map(|c| self.candidates(c)).collect();
+        let centre = (self.range.0 as u32 + self.range.1 as u32) / 2;
+        let drift = |notes: &[u8]| {
+            let mean = notes.iter().map(|&n| n as u32).sum::<u32>() / notes.len().max(1) as u32;
+            mean.abs_diff(centre)
+        };
 
-    /// Check if this voicing would sound good (no collisions, reasonable spread)
-    pub fn is_playable(&self) -> bool {
-        let all = self.all_notes();
-        if all.len() < 2 {
-            return false;
+        // costs[i][c] is the cheapest way to reach candidate c of chord i,
+        // from[i][c] the candidate of chord i - 1 it came from
+        let mut costs: Vec<Vec<u32>> = Vec::with_capacity(chords.len());
+        let mut from: Vec<Vec<usize>> = Vec::with_capacity(chords.len());
+        for (i, options) in candidates.iter().enumerate() {
+            let mut row_costs = Vec::with_capacity(options.len());
+            let mut row_from = Vec::with_capacity(options.len());
+            for option in options {
+                let (best, prev) = match i.checked_sub(1) {
+                    Some(p) => candidates[p]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, prev)| {
+                            let motion = VoiceLeading::total_motion(prev, option);
+                            (costs[p][j] + motion * MOTION_WEIGHT, j)
+                        })
+                        .min()
+                        .unwrap_or((0, 0)),
+                    None => (0, 0),
+                };
+                row_costs.push(best + drift(option));
+                row_from.push(prev);
+            }
+            costs.push(row_costs);
+            from.push(row_from);
         }
 
-        // Check for no very close notes (less than 2 semitones except bass)
-        for window in all.windows(2) {
-            if window[1] - window[0] < 2 && window[0] > self.bass {
-                return false;
+        // Walk back from the cheapest final voicing
+        let mut picks = vec![0; chords.len()];
+        if let Some(last) = costs.last() {
+            let mut pick = (0..last.len()).min_by_key(|&c| last[c]).unwrap_or(0);
+            for i in (0..chords.len()).rev() {
+                picks[i] = pick;
+                pick = from[i].get(pick).copied().unwrap_or(0);
             }
         }
 
-        // Check total spread isn't too wide (max 2 octaves for voicing)
-        if let (Some(&lowest), Some(&highest)) = (self.voicing.first(), self.voicing.last()) {
-            if highest - lowest > 24 {
-                return false;
+        chords
+            .iter()
+            .zip(candidates)
+            .zip(picks)
+            .map(|((chord, mut options), pick)| VoicedChord {
+                bass: chord.bass.unwrap_or(chord.root).to_midi(2),
+                voicing: options.swap_remove(pick),
+            })
+            .collect()
+    }
+
+    /// Every inversion and octave placement of the chord's voicing that fits the range
+    fn candidates(&self, chord: &Chord) -> Vec<Vec<u8>> {
+        let types: &[VoicingType] = match self.voicing {
+            VoicingType::RootlessA | VoicingType::RootlessB => {
+                &[VoicingType::RootlessA, VoicingType::RootlessB]
+            }
+            _ => std::slice::from_ref(&self.voicing),
+        };
+        let inversions = |len: usize| if self.voicing.fixed_shape() { 1 } else { len.max(1) };
+
+        let mut candidates: Vec<Vec<u8>> = Vec::new();
+        for voicing in types {
+            let notes = voicing.voice_chord(chord, 2, 4, (0, 127)).voicing;
+            for inversion in 0..inversions(notes.len()) {
+                // Move the lowest notes up an octave
+                let mut inverted: Vec<u8> = notes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &n)| if i < inversion { n + 12 } else { n })
+                    .collect();
+                inverted.sort_unstable();
+
+                for shift in [-12i16, 0, 12] {
+                    let shifted: Vec<u8> = inverted
+                        .iter()
+                        .map(|&n| (n as i16 + shift).clamp(0, 127) as u8)
+                        .collect();
+                    let fits = shifted.iter().all(|&n| n >= self.range.0 && n <= self.range.1);
+                    if fits && !candidates.contains(&shifted) {
+                        candidates.push(shifted);
+                    }
+                }
             }
         }
 
-        true
+        if candidates.is_empty() {
+            candidates.push(self.voicing.voice_chord(chord, 2, 4, self.range).voicing);
+        }
+        candidates
     }
 }
 
@@ -286,21 +613,193 @@ mod tests {
         assert!(!all.contains(&50)); // D3 should not be in voicing
     }
 
+    #[test]
+    fn test_extended_qualities_voiced() {
+        let qualities = [
+            ChordQuality::Major,
+            ChordQuality::Minor,
+            ChordQuality::Diminished,
+            ChordQuality::Augmented,
+            ChordQuality::Sus2,
+            ChordQuality::Sus4,
+            ChordQuality::Add9,
+            ChordQuality::Major69,
+            ChordQuality::Dominant11,
+            ChordQuality::Minor11,
+            ChordQuality::Dominant13,
+            ChordQuality::Dominant7sharp11,
+            ChordQuality::Dominant7sharp5,
+            ChordQuality::Dominant7b5,
+            ChordQuality::Dominant13sus4,
+        ];
+        let voicings = [
+            VoicingType::Full,
+            VoicingType::Shell,
+            VoicingType::RootlessA,
+            VoicingType::RootlessB,
+            VoicingType::Drop2,
+        ];
+        for quality in qualities {
+            let chord = Chord::new(Note::F, quality);
+            let tones = chord.pitch_classes();
+            for voicing in voicings {
+                let voiced = voicing.voice_chord(&chord, 2, 4, (36, 84));
+                assert!(voiced.voicing.len() >= 2, "{:?} {:?}", voicing, quality);
+                for &midi in &voiced.voicing {
+                    let note = Note::from_midi(midi);
+                    // Rootless voicings may add a natural 9th
+                    assert!(
+                        tones.contains(&note) || note == Note::G,
+                        "{:?} {:?} has {}",
+                        voicing,
+                        quality,
+                        note
+                    );
+                }
+            }
+        }
+
+        // Sus chords keep the 4th in place of the 3rd
+        let sus = Chord::new(Note::C, ChordQuality::Sus4);
+        let voiced = VoicingType::Shell.voice_chord(&sus, 2, 4, (36, 84));
+        assert_eq!(voiced.voicing, vec![65, 67]);
+
+        // Thirteenth chords put the 13th where the 5th would be
+        let c13 = Chord::new(Note::C, ChordQuality::Dominant13);
+        let voiced = VoicingType::RootlessA.voice_chord(&c13, 2, 4, (36, 84));
+        assert_eq!(voiced.voicing, vec![64, 69, 70, 74]);
+    }
+
     #[test]
     fn test_voicing_cycle() {
         let v = VoicingType::Full;
         assert_eq!(v.next(), VoicingType::Shell);
         assert_eq!(v.next().next(), VoicingType::RootlessA);
-        assert_eq!(
-            v.next().next().next().next().next(),
-            VoicingType::Full
-        );
+        let cycle: Vec<VoicingType> = std::iter::successors(Some(v), |s| Some(s.next()))
+            .take(12)
+            .collect();
+        assert_eq!(cycle[11], VoicingType::Full);
+        assert!(cycle[1..11].iter().all(|s| *s != VoicingType::Full));
     }
 
     #[test]
-    fn test_voiced_chord_playable() {
+    fn test_drop_voicings() {
         let cmaj7 = Chord::new(Note::C, ChordQuality::Major7);
-        let voiced = VoicingType::Full.voice_chord(&cmaj7, 2, 4, (36, 84));
-        assert!(voiced.is_playable());
+        // Close C E G B: drop 3 takes E down, drop 2&4 takes G and C down
+        assert_eq!(VoicingType::Drop3.voice_chord(&cmaj7, 2, 4, (36, 84)).voicing, vec![52, 60, 67, 71]);
+        assert_eq!(VoicingType::Drop24.voice_chord(&cmaj7, 2, 4, (36, 84)).voicing, vec![48, 55, 64, 71]);
+
+        // The 9th replaces the root and the 13th the 5th
+        let g13 = Chord::new(Note::G, ChordQuality::Dominant13);
+        let notes = VoicingType::Drop3.voice_chord(&g13, 2, 4, (36, 84)).voicing;
+        let pcs: Vec<u8> = notes.iter().map(|n| n % 12).collect();
+        assert_eq!(notes.len(), 4);
+        for pc in [9, 11, 4, 5] {
+            assert!(pcs.contains(&pc), "{:?}", notes);
+        }
+    }
+
+    #[test]
+    fn test_quartal_and_so_what() {
+        // G7: B E A D, fourths from the 3rd in mixolydian
+        let g7 = Chord::new(Note::G, ChordQuality::Dominant7);
+        assert_eq!(VoicingType::Quartal.voice_chord(&g7, 2, 4, (36, 84)).voicing, vec![59, 64, 69, 74]);
+
+        // Dm7: D G C F A, the So What chord itself
+        let dm7 = Chord::new(Note::D, ChordQuality::Minor7);
+        assert_eq!(VoicingType::SoWhat.voice_chord(&dm7, 2, 4, (36, 84)).voicing, vec![50, 55, 60, 65, 69]);
+
+        // Cmaj7: E A D G B from the 3rd
+        let cmaj7 = Chord::new(Note::C, ChordQuality::Major7);
+        assert_eq!(VoicingType::SoWhat.voice_chord(&cmaj7, 2, 4, (36, 84)).voicing, vec![52, 57, 62, 67, 71]);
+    }
+
+    #[test]
+    fn test_upper_structure_and_spread() {
+        // C7: E Bb under a D triad (C13#11)
+        let c7 = Chord::new(Note::C, ChordQuality::Dominant7);
+        assert_eq!(VoicingType::UpperStructure.voice_chord(&c7, 2, 4, (36, 84)).voicing, vec![52, 58, 62, 66, 69]);
+
+        // C7alt: E Bb under an Ab triad
+        let alt = Chord::new(Note::C, ChordQuality::Altered);
+        assert_eq!(VoicingType::UpperStructure.voice_chord(&alt, 2, 4, (36, 84)).voicing, vec![52, 58, 68, 72, 75]);
+
+        // Cmaj7: C B below, E G D above
+        let cmaj7 = Chord::new(Note::C, ChordQuality::Major7);
+        assert_eq!(VoicingType::Spread.voice_chord(&cmaj7, 2, 4, (36, 84)).voicing, vec![48, 59, 64, 67, 74]);
+    }
+
+    #[test]
+    fn test_new_voicings_cover_every_quality() {
+        let voicings = [
+            VoicingType::Drop3,
+            VoicingType::Drop24,
+            VoicingType::Quartal,
+            VoicingType::SoWhat,
+            VoicingType::UpperStructure,
+            VoicingType::Spread,
+        ];
+        for quality in ChordQuality::ALL {
+            let chord = Chord::new(Note::F, quality);
+            for voicing in voicings {
+                let notes = voicing.voice_chord(&chord, 2, 4, (36, 96)).voicing;
+                assert!(notes.len() >= 3, "{} {:?}", voicing.name(), quality);
+                assert!(notes.windows(2).all(|w| w[0] < w[1]), "{} {:?}", voicing.name(), quality);
+            }
+            // Quartal stacks only perfect and augmented 4ths
+            let quartal = VoicingType::Quartal.voice_chord(&chord, 2, 4, (36, 96)).voicing;
+            assert!(
+                quartal.windows(2).all(|w| matches!(w[1] - w[0], 5 | 6)),
+                "{:?} {:?}",
+                quality,
+                quartal
+            );
+        }
+    }
+
+    #[test]
+    fn test_progression_voicer_moves_less() {
+        let chords = vec![
+            Chord::new(Note::D, ChordQuality::Minor7),
+            Chord::new(Note::G, ChordQuality::Dominant7),
+            Chord::new(Note::C, ChordQuality::Major7),
+            Chord::new(Note::A, ChordQuality::Dominant7),
+        ];
+        let motion = |voiced: &[VoicedChord]| -> u32 {
+            voiced
+                .windows(2)
+                .map(|w| VoiceLeading::total_motion(&w[0].voicing, &w[1].voicing))
+                .sum()
+        };
+
+        for voicing in [VoicingType::Full, VoicingType::RootlessA, VoicingType::Drop2] {
+            let independent: Vec<VoicedChord> = chords
+                .iter()
+                .map(|c| voicing.voice_chord(c, 2, 4, (36, 84)))
+                .collect();
+            let led = ProgressionVoicer::new(voicing).voice(&chords);
+
+            assert_eq!(led.len(), chords.len());
+            assert!(motion(&led) < motion(&independent), "{}", voicing.name());
+            for (voiced, chord) in led.iter().zip(&chords) {
+                assert_eq!(voiced.bass, chord.root.to_midi(2));
+                assert!(voiced.voicing.iter().all(|&n| (48..=84).contains(&n)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rootless_voicer_alternates() {
+        // ii-V-I with rootless voicings: the classic A-B-A keeps the hand in place
+        let chords = vec![
+            Chord::new(Note::D, ChordQuality::Minor9),
+            Chord::new(Note::G, ChordQuality::Dominant13),
+            Chord::new(Note::C, ChordQuality::Major9),
+        ];
+        let led = ProgressionVoicer::new(VoicingType::RootlessA).voice(&chords);
+        for pair in led.windows(2) {
+            let moved = VoiceLeading::voice_movements(&pair[0].voicing, &pair[1].voicing);
+            assert!(moved.iter().all(|m| m.2.abs() <= 2), "{:?}", moved);
+        }
     }
 }
@@ -31,6 +31,12 @@ pub struct UserProgress {
     pub difficulty: QuizDifficulty,
     /// Mastered bricks (consistently identified correctly)
     pub mastered_bricks: Vec<String>,
+    /// Per-interval statistics from the interval trainer, by interval name
+    #[serde(default)]
+    pub interval_stats: HashMap<String, BrickStats>,
+    /// Per-scale statistics from the scale trainer, by scale name
+    #[serde(default)]
+    pub scale_stats: HashMap<String, BrickStats>,
 }
 
 /// Statistics for a single brick
@@ -168,6 +174,22 @@ impl UserProgress {
         }
     }
 
+    /// Record a single interval quiz result
+    pub fn record_interval_result(&mut self, interval_name: &str, correct: bool) {
+        self.interval_stats
+            .entry(interval_name.to_string())
+            .or_default()
+            .record(correct);
+    }
+
+    /// Record a single scale quiz result
+    pub fn record_scale_result(&mut self, scale_name: &str, correct: bool) {
+        self.scale_stats
+            .entry(scale_name.to_string())
+            .or_default()
+            .record(correct);
+    }
+
     /// Get weak bricks (low accuracy, need more practice)
     pub fn weak_bricks(&self) -> Vec<(&String, &BrickStats)> {
         let mut weak: Vec<_> = self
@@ -256,6 +278,32 @@ mod tests {
         assert!(stats.is_mastered());
     }
 
+    #[test]
+    fn test_ear_training_stats_persist() {
+        let path = std::env::temp_dir().join(format!("ear-trainer-progress-{}.json", std::process::id()));
+        let mut progress = UserProgress::default();
+        progress.record_interval_result("Perfect 5th", true);
+        progress.record_interval_result("Perfect 5th", false);
+        progress.record_scale_result("Dorian", true);
+        progress.save_to(path.clone()).unwrap();
+
+        let loaded = UserProgress::load_from(path.clone());
+        let stats = &loaded.interval_stats["Perfect 5th"];
+        assert_eq!((stats.times_seen, stats.times_correct), (2, 1));
+        assert_eq!(loaded.scale_stats["Dorian"].times_correct, 1);
+        let _ = fs::remove_file(path);
+
+        // Progress saved before interval stats existed still loads
+        let old: UserProgress = serde_json::from_str(
+            r#"{"total_sessions": 1, "total_questions": 4, "total_correct": 3, "best_streak_ever": 2,
+                "daily_streak": 1, "last_practice_date": null, "brick_stats": {},
+                "difficulty": "Beginner", "mastered_bricks": []}"#,
+        )
+        .unwrap();
+        assert_eq!(old.total_correct, 3);
+        assert!(old.interval_stats.is_empty() && old.scale_stats.is_empty());
+    }
+
     #[test]
     fn test_progress_accuracy() {
         let mut progress = UserProgress::default();
@@ -1,8 +1,31 @@
 use crate::audio::{ActiveBackend, AudioManager, BleStatus};
-use crate::music::{BassState, BassStyle, Chord, ChordScaleMatcher, DrumState, DrumStyle, Progression, ProgressionLibrary, RhythmState, RhythmStyle, Scale, VoicingType};
+use crate::clock::{Clock, SystemClock};
+use crate::config::Config;
+use crate::music::{BassState, BassStyle, BrickAnalyzer, BrickSegment, Chord, ChordScaleMatcher, DrumState, DrumStyle, FunctionalAnalysis, GripStyle, GuitarGrip, GuitarTuning, GuitarVoicer, Humanizer, Improviser, MusicData, Progression, ProgressionLibrary, ProgressionVoicer, Reharmonization, Reharmonizer, RhythmState, RomanNumeral, Scale, ScaleChoice, SoloState, Substitution, VoicedChord, VoicingType};
+use crate::music::humanize::DEFAULT_SEED;
+use crate::music::interval::interval_name;
+use crate::storage::UserProgress;
+use super::calibration::CalibrationState;
+use super::interval_mode::IntervalModeState;
 use super::lego_mode::LegoModeState;
+use super::phrase::{Phrase, PhraseEvent, PhrasePlayback};
+use super::scale_mode::ScaleModeState;
 use super::timeline::TimelineState;
-use std::time::Instant;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Note used for the calibration click (C6)
+const CLICK_NOTE: u8 = 84;
+
+/// How long each note of a melodic interval sounds; harmonic intervals
+/// hold both notes for twice as long
+const INTERVAL_NOTE_MS: u64 = 900;
+
+/// How long each note of a scale question sounds
+const SCALE_NOTE_MS: u64 = 350;
+
+/// Scale notes' worth of backing heard before the scale starts
+const SCALE_LEAD_IN: u32 = 3;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
@@ -13,6 +36,14 @@ pub enum AppMode {
     LegoListen,
     /// LEGO Bricks: Quiz - identify which brick is playing
     LegoQuiz,
+    /// LEGO Bricks: Chain bricks and joins into a tune
+    LegoBuild,
+    /// Measure output latency of the active backend
+    Calibrate,
+    /// Identify intervals by ear
+    IntervalQuiz,
+    /// Identify scales and modes by ear
+    ScaleQuiz,
 }
 
 pub struct App {
@@ -37,6 +68,23 @@ pub struct App {
     pub lego_state: LegoModeState,
     /// Current voicing type for chord playback
     pub current_voicing: VoicingType,
+    /// Voice-led voicing of each chord in the current progression
+    pub voiced: Vec<VoicedChord>,
+    /// Scale picked for each chord from the key and the chord it moves to
+    pub scales: Vec<ScaleChoice>,
+    /// Roman numeral analysis of each chord in the current progression
+    pub analysis: Vec<RomanNumeral>,
+    /// LEGO bricks the current progression breaks into
+    pub bricks: Vec<BrickSegment>,
+    /// Finds the bricks, with the same bricks and joins as LEGO mode
+    brick_analyzer: BrickAnalyzer,
+    /// Which of the current chord's scale options is shown, 0 for the
+    /// contextual choice; lasts until the chord changes
+    scale_pick: usize,
+    /// Show a guitar fretboard instead of the keyboard reference
+    pub show_fretboard: bool,
+    /// Tuning of the fretboard view
+    pub guitar_tuning: GuitarTuning,
     /// Swing enabled for jazz feel
     pub swing_enabled: bool,
     /// Swing ratio: 0.5 = straight, 0.58 = light swing, 0.67 = hard swing
@@ -47,18 +95,70 @@ pub struct App {
     pub bass_state: BassState,
     /// Drum state for drum patterns
     pub drum_state: DrumState,
+    /// Play an improvised line over the changes in Listen mode
+    pub soloist: bool,
+    /// Soloist state for the improvised line
+    pub solo_state: SoloState,
+    /// Timing and velocity variation for the backing parts
+    pub humanizer: Humanizer,
+    /// Semitones the library progressions are moved for this session
+    pub transpose: i8,
+    /// The current library progression in the transposed key
+    transposed: Option<Progression>,
+    /// Substitution applied to the current progression, with the result
+    pub reharm: Option<(Substitution, Reharmonization)>,
+    /// Latency calibration state (while in Calibrate mode)
+    pub calibration: Option<CalibrationState>,
+    /// Interval trainer state
+    pub interval_state: IntervalModeState,
+    /// Scale trainer state
+    pub scale_state: ScaleModeState,
+    /// Quiz notes being played outside the progression scheduler
+    phrase: Option<PhrasePlayback>,
+    pub config: Config,
+    /// Quiz results kept between sessions
+    pub progress: UserProgress,
+    /// Where progress is saved; None keeps it in memory only
+    progress_path: Option<PathBuf>,
+    /// Chord that was playing before the current one, with its start time
+    previous_chord: Option<(usize, Instant)>,
+    /// Passes through the progression since playback started
+    chorus: u32,
+    /// Time source for playback scheduling
+    clock: Box<dyn Clock>,
 }
 
 impl App {
     pub fn new() -> Self {
-        let library = ProgressionLibrary::new();
-        let genres = library.all_genres();
-        let current_genre = genres.first().cloned().unwrap_or_else(|| "Jazz".to_string());
-
         let mut audio_manager = AudioManager::default();
         // Start BLE scanning in background
         audio_manager.start_ble_scan();
 
+        let mut app = Self::with_audio(audio_manager, Box::new(SystemClock));
+        let data = MusicData::load();
+        app.library = ProgressionLibrary::with_bricks(&data.bricks);
+        app.brick_analyzer = BrickAnalyzer::with_libraries(data.bricks.clone(), data.joins.clone());
+        app.lego_state = LegoModeState::with_data(data);
+        app.config = Config::load();
+        app.interval_state = IntervalModeState::new(&app.config);
+        app.scale_state = ScaleModeState::new(&app.config);
+        let progress_path = UserProgress::default_path();
+        app.progress = UserProgress::load_from(progress_path.clone());
+        app.progress_path = Some(progress_path);
+        app.refresh_timeline();
+        app
+    }
+
+    /// Create an app with a specific audio manager and time source
+    ///
+    /// Used with `AudioManager::with_backend` and a `VirtualClock` to run
+    /// playback headlessly and deterministically.
+    pub fn with_audio(audio_manager: AudioManager, clock: Box<dyn Clock>) -> Self {
+        let library = ProgressionLibrary::new();
+        let genres = library.all_genres();
+        let current_genre = genres.first().cloned().unwrap_or_else(|| "Jazz".to_string());
+        let config = Config::default();
+
         let mut app = Self {
             mode: AppMode::Listen,
             audio_manager,
@@ -78,11 +178,35 @@ impl App {
             timeline_state: TimelineState::new(),
             lego_state: LegoModeState::new(),
             current_voicing: VoicingType::Full,
-            swing_enabled: false,
-            swing_ratio: 0.5, // Straight timing by default
+            voiced: Vec::new(),
+            scales: Vec::new(),
+            analysis: Vec::new(),
+            bricks: Vec::new(),
+            brick_analyzer: BrickAnalyzer::new(),
+            scale_pick: 0,
+            show_fretboard: false,
+            guitar_tuning: GuitarTuning::default(),
+            swing_enabled: true,
+            swing_ratio: 0.67, // Hard swing on the swing styles by default
             rhythm_state: RhythmState::new(),
             bass_state: BassState::new(),
             drum_state: DrumState::new(),
+            soloist: false,
+            solo_state: SoloState::new(),
+            humanizer: Humanizer::default(),
+            transpose: 0,
+            transposed: None,
+            reharm: None,
+            calibration: None,
+            interval_state: IntervalModeState::new(&config),
+            scale_state: ScaleModeState::new(&config),
+            phrase: None,
+            config,
+            progress: UserProgress::default(),
+            progress_path: None,
+            previous_chord: None,
+            chorus: 0,
+            clock,
         };
         app.refresh_timeline();
         app
@@ -90,21 +214,99 @@ impl App {
 
     /// Refresh the timeline state from the current progression
     pub fn refresh_timeline(&mut self) {
+        self.transposed = match self.transpose {
+            0 => None,
+            offset => self.library_progression().map(|prog| prog.transpose(offset)),
+        };
         if let Some(prog) = self.current_progression() {
+            let chords: Vec<Chord> = prog.changes.iter().map(|c| c.chord.clone()).collect();
+            let voiced = ProgressionVoicer::new(self.current_voicing).voice(&chords);
+            let scales = ChordScaleMatcher::progression_scales(prog);
+            let analysis = FunctionalAnalysis::analyze(prog);
+            let bricks = self.brick_analyzer.analyze(prog);
+            let meter = prog.meter;
             self.timeline_state = TimelineState::from_progression(
                 prog,
                 self.current_chord_idx,
                 self.current_beat,
-            );
+            )
+            .with_voicings(&voiced);
+            self.voiced = voiced;
+            self.scales = scales;
+            self.analysis = analysis;
+            self.bricks = bricks;
+            self.scale_pick = 0;
+            self.rhythm_state.set_meter(meter);
+            self.bass_state.set_meter(meter);
+            self.drum_state.set_meter(meter);
+            self.refresh_solo();
+        }
+    }
+
+    /// Write a new improvised line for the current chorus
+    fn refresh_solo(&mut self) {
+        let seed = DEFAULT_SEED + self.chorus as u64;
+        if let Some(line) = self.current_progression().map(|prog| Improviser::new(seed).line(prog)) {
+            self.solo_state.set_line(line);
+            self.sync_solo_line();
+        }
+    }
+
+    /// Show the soloist's line on the timeline while the soloist is on
+    fn sync_solo_line(&mut self) {
+        if self.soloist {
+            self.timeline_state.set_line(self.solo_state.line());
+        } else {
+            self.timeline_state.set_line(&[]);
         }
     }
 
     pub fn current_progression(&self) -> Option<&Progression> {
+        if let Some((_, reharm)) = &self.reharm {
+            return Some(&reharm.progression);
+        }
+        self.original_progression()
+    }
+
+    /// The current progression in the session key, before any reharmonization
+    fn original_progression(&self) -> Option<&Progression> {
+        self.transposed.as_ref().or_else(|| self.library_progression())
+    }
+
+    /// The current progression as it is in the library
+    fn library_progression(&self) -> Option<&Progression> {
         self.library
             .get_by_genre(&self.current_genre)
             .and_then(|progs| progs.get(self.current_progression_idx))
     }
 
+    /// Move the session key up or down a number of semitones
+    ///
+    /// The offset carries over to other progressions; any reharmonization
+    /// is reapplied in the new key.
+    pub fn transpose_by(&mut self, semitones: i8) {
+        // Keep the offset within a tritone either way
+        self.transpose = (self.transpose + semitones + 6).rem_euclid(12) - 6;
+        self.current_chord_idx = 0;
+        self.stop();
+        self.refresh_timeline();
+        if let Some((substitution, _)) = self.reharm.take() {
+            self.reharm = self
+                .original_progression()
+                .map(|prog| (substitution, Reharmonizer::apply(prog, &[substitution])));
+            self.refresh_timeline();
+        }
+    }
+
+    /// Key of the current progression, with the session offset
+    pub fn key_name(&self) -> Option<String> {
+        let key = self.current_progression()?.spelled_key().name();
+        Some(match self.transpose {
+            0 => key.to_string(),
+            offset => format!("{} ({:+})", key, offset),
+        })
+    }
+
     pub fn current_chord(&self) -> Option<&Chord> {
         self.current_progression()
             .and_then(|prog| prog.changes.get(self.current_chord_idx))
@@ -112,14 +314,58 @@ impl App {
     }
 
     pub fn current_scale(&self) -> Option<Scale> {
-        self.current_chord()
-            .map(|chord| ChordScaleMatcher::get_primary_scale(chord))
+        let options = self.scale_options();
+        options.get(self.scale_pick % options.len().max(1)).cloned()
+    }
+
+    /// Scales the current chord can be shown with, the contextual choice first
+    pub fn scale_options(&self) -> Vec<Scale> {
+        let Some(chord) = self.current_chord() else {
+            return Vec::new();
+        };
+        match self.scales.get(self.current_chord_idx) {
+            Some(choice) => ChordScaleMatcher::scale_options(chord, choice),
+            None => ChordScaleMatcher::get_alternate_scales(chord),
+        }
+    }
+
+    /// The LEGO brick the current chord belongs to, if one explains it
+    pub fn current_brick(&self) -> Option<&BrickSegment> {
+        let beat = self.timeline_state.chords.get(self.current_chord_idx)?.start_beat;
+        self.bricks
+            .iter()
+            .find(|s| s.start_beat <= beat && beat < s.start_beat + s.beats)
+            .filter(|s| s.brick.is_some())
+    }
+
+    /// Why the current scale is shown
+    pub fn scale_reason(&self) -> Option<String> {
+        let options = self.scale_options().len().max(1);
+        let pick = self.scale_pick % options;
+        if pick > 0 {
+            return Some(format!("Alternate {} of {}", pick + 1, options));
+        }
+        self.scales.get(self.current_chord_idx).map(|choice| choice.reason.clone())
+    }
+
+    /// Show the current chord's next scale option
+    pub fn cycle_scale(&mut self) {
+        self.set_scale_pick(self.scale_pick + 1);
+    }
+
+    fn set_scale_pick(&mut self, pick: usize) {
+        self.scale_pick = pick;
+        let idx = self.current_chord_idx;
+        if let (Some(scale), Some(chord)) = (self.current_scale(), self.timeline_state.chords.get_mut(idx)) {
+            chord.scale = scale;
+        }
     }
 
     pub fn next_progression(&mut self) {
         if let Some(progs) = self.library.get_by_genre(&self.current_genre) {
             self.current_progression_idx = (self.current_progression_idx + 1) % progs.len();
             self.current_chord_idx = 0;
+            self.reharm = None;
             self.stop();
             self.refresh_timeline();
         }
@@ -133,6 +379,7 @@ impl App {
                 self.current_progression_idx -= 1;
             }
             self.current_chord_idx = 0;
+            self.reharm = None;
             self.stop();
             self.refresh_timeline();
         }
@@ -144,6 +391,24 @@ impl App {
         self.current_genre = genres[self.selected_genre_idx].clone();
         self.current_progression_idx = 0;
         self.current_chord_idx = 0;
+        self.reharm = None;
+        self.stop();
+        self.refresh_timeline();
+    }
+
+    /// Add a progression to the library and make it the current one
+    pub fn load_progression(&mut self, progression: Progression) {
+        let genre = progression.genre.clone();
+        self.current_progression_idx = self.library.insert(progression);
+        self.selected_genre_idx = self
+            .library
+            .all_genres()
+            .iter()
+            .position(|g| *g == genre)
+            .unwrap_or(0);
+        self.current_genre = genre;
+        self.current_chord_idx = 0;
+        self.reharm = None;
         self.stop();
         self.refresh_timeline();
     }
@@ -158,23 +423,32 @@ impl App {
         self.current_genre = genres[self.selected_genre_idx].clone();
         self.current_progression_idx = 0;
         self.current_chord_idx = 0;
+        self.reharm = None;
         self.stop();
         self.refresh_timeline();
     }
 
     pub fn play(&mut self) {
         self.is_playing = true;
-        self.last_chord_change = Some(Instant::now());
-
-        if let Some(chord) = self.current_chord().cloned() {
-            self.play_current_chord(&chord);
+        self.last_chord_change = Some(self.schedule_now());
+        self.previous_chord = None;
+        self.chorus = 0;
+        if let Some(key) = self.current_progression().map(|prog| prog.key) {
+            self.audio_manager.set_tuning_tonic(key as u8);
         }
+        self.humanizer.restart();
+        self.sync_swing();
+        self.reset_parts();
+
+        // Sound the downbeat right away instead of waiting for the next tick
+        self.advance_playback();
     }
 
     pub fn stop(&mut self) {
         self.is_playing = false;
         self.last_chord_change = None;
         self.current_beat = 0.0;
+        self.phrase = None;
         self.audio_manager.stop_all();
     }
 
@@ -211,107 +485,203 @@ impl App {
         // Poll BLE events
         self.audio_manager.poll_ble_events();
 
-        if !self.is_playing {
+        if self.mode == AppMode::Calibrate {
+            self.update_calibration();
             return;
         }
 
-        if let Some(last_change) = self.last_chord_change {
-            let elapsed = last_change.elapsed();
-
-            // Extract values from progression without holding borrow
-            let (beat_duration_ms, chord_duration_ms, change_duration) = if let Some(prog) = self.current_progression() {
-                let current_change = &prog.changes[self.current_chord_idx];
-                let beat_duration_ms = (60000.0 / prog.tempo) as u64;
-
-                // Apply swing timing if enabled
-                let swing_factor = if self.swing_enabled {
-                    if self.current_chord_idx % 2 == 0 {
-                        self.swing_ratio * 2.0
-                    } else {
-                        (1.0 - self.swing_ratio) * 2.0
-                    }
-                } else {
-                    1.0
-                };
-
-                let chord_duration_ms =
-                    (current_change.duration * beat_duration_ms as f32 * swing_factor) as u64;
-
-                (beat_duration_ms, chord_duration_ms, current_change.duration)
-            } else {
-                return;
-            };
+        self.update_phrase();
 
-            // Update current beat position
-            self.current_beat =
-                (elapsed.as_millis() as f32 / beat_duration_ms as f32) % change_duration;
+        // Loop so that a chord change also triggers the new chord's downbeat
+        while self.is_playing && self.advance_playback() {}
 
-            // Check if rhythm pattern should trigger a chord hit
-            if let Some((velocity, _duration)) = self.rhythm_state.check_hit(self.current_beat, change_duration) {
-                if let Some(chord) = self.current_chord().cloned() {
-                    self.play_chord_hit(&chord, velocity);
-                }
-            }
+        if self.is_playing {
+            self.update_playhead();
+        }
+    }
+
+    /// Point in the progression the scheduler is sending now
+    ///
+    /// Notes reach the ear after the backend's latency, so the scheduler runs
+    /// that far ahead of the clock and they are heard on the beat.
+    fn schedule_now(&self) -> Instant {
+        self.clock.now() + self.audio_manager.output_latency()
+    }
+
+    /// Move the timeline playhead to what is being heard right now
+    ///
+    /// The scheduler runs ahead by the backend's latency, so the display
+    /// follows the plain clock and lags it by that much.
+    fn update_playhead(&mut self) {
+        let Some(last_change) = self.last_chord_change else {
+            return;
+        };
+        let Some((beat_duration_ms, current_duration)) = self.current_progression().map(|prog| {
+            (
+                (60000.0 / prog.tempo) as u64,
+                prog.changes[self.current_chord_idx].duration,
+            )
+        }) else {
+            return;
+        };
+
+        let heard_at = self.clock.now();
+
+        let (chord_idx, started_at) = match self.previous_chord {
+            Some(previous) if heard_at < last_change => previous,
+            _ => (self.current_chord_idx, last_change),
+        };
+        let duration = if chord_idx == self.current_chord_idx {
+            current_duration
+        } else {
+            self.current_progression()
+                .and_then(|prog| prog.changes.get(chord_idx))
+                .map(|change| change.duration)
+                .unwrap_or(current_duration)
+        };
+
+        let elapsed = heard_at.saturating_duration_since(started_at);
+        let beat = (elapsed.as_millis() as f32 / beat_duration_ms as f32) % duration;
+        self.timeline_state.update(chord_idx, beat);
+    }
+
+    /// Trigger everything due at the current time
+    ///
+    /// Returns true if playback moved on to the next chord, in which case the
+    /// new chord has not been processed yet.
+    fn advance_playback(&mut self) -> bool {
+        let Some(last_change) = self.last_chord_change else {
+            return false;
+        };
+        let elapsed = self.schedule_now().saturating_duration_since(last_change);
 
-            // Check if bass should trigger a note
+        // Extract values from progression without holding borrow
+        let (beat_duration_ms, chord_duration_ms, change_duration, bar) = if let Some(prog) = self.current_progression() {
+            let current_change = &prog.changes[self.current_chord_idx];
+            let beat_duration_ms = (60000.0 / prog.tempo) as u64;
+            let chord_duration_ms = (current_change.duration * beat_duration_ms as f32) as u64;
+
+            (beat_duration_ms, chord_duration_ms, current_change.duration, prog.meter.bar_beats())
+        } else {
+            return false;
+        };
+
+        // Move to the next chord before triggering anything, so the old
+        // chord's pattern doesn't wrap around and replay its downbeat
+        if chord_duration_ms > 0 && elapsed.as_millis() >= chord_duration_ms as u128 {
+            // Measure from the exact boundary so timing doesn't drift by a tick per chord
+            self.next_chord(last_change + Duration::from_millis(chord_duration_ms));
+            return true;
+        }
+
+        // Update current beat position
+        self.current_beat =
+            (elapsed.as_millis() as f32 / beat_duration_ms as f32) % change_duration;
+
+        // Check if rhythm pattern should trigger a chord hit
+        // Comping and drums repeat their bar; the bass line is written out for
+        // the whole change
+        if let Some((velocity, _duration)) = self.rhythm_state.check_hit(self.current_beat, bar) {
             if let Some(chord) = self.current_chord().cloned() {
-                if let Some((midi_note, velocity)) = self.bass_state.check_note(self.current_beat, change_duration, &chord) {
-                    self.play_single_note(midi_note, velocity);
-                }
+                self.play_chord_hit(&chord, velocity);
             }
+        }
 
-            // Check if drums should trigger hits
-            let drum_hits = self.drum_state.check_hits(self.current_beat, change_duration);
-            for (midi_note, velocity) in drum_hits {
-                self.play_drum_hit(midi_note, velocity);
+        // Check if bass should trigger a note
+        if let Some(chord) = self.current_chord().cloned() {
+            if let Some((midi_note, velocity)) = self.bass_state.check_note(self.current_beat, change_duration, &chord) {
+                self.play_single_note(midi_note, velocity);
             }
+        }
 
-            // Check if we should move to next chord
-            let should_change = elapsed.as_millis() >= chord_duration_ms as u128;
-
-            // Update timeline state with current playback position
-            self.timeline_state.update(self.current_chord_idx, self.current_beat);
-
-            if should_change {
-                self.next_chord();
+        // The soloist plays over the changes in Listen mode
+        if self.soloist && self.mode == AppMode::Listen {
+            if let Some((midi_note, velocity, previous)) =
+                self.solo_state.check_note(self.current_chord_idx, self.current_beat)
+            {
+                self.play_solo_note(midi_note, velocity, previous);
             }
         }
+
+        // Check if drums should trigger hits
+        let drum_hits = self.drum_state.check_hits(self.current_beat, bar);
+        for (midi_note, velocity) in drum_hits {
+            self.play_drum_hit(midi_note, velocity);
+        }
+
+        false
     }
 
-    fn next_chord(&mut self) {
+    fn next_chord(&mut self, started_at: Instant) {
         self.audio_manager.stop_all();
 
+        if let Some(last_change) = self.last_chord_change {
+            self.previous_chord = Some((self.current_chord_idx, last_change));
+        }
+
         let num_changes = self.current_progression().map(|p| p.changes.len()).unwrap_or(1);
+        self.set_scale_pick(0);
         self.current_chord_idx = (self.current_chord_idx + 1) % num_changes;
-        self.last_chord_change = Some(Instant::now());
+        if self.current_chord_idx == 0 {
+            self.chorus += 1;
+        }
+        self.last_chord_change = Some(started_at);
 
-        // Reset rhythm, bass, and drum states for new chord
-        self.rhythm_state.reset();
-        self.bass_state.reset();
-        self.drum_state.reset();
+        // Reset rhythm, bass, and drum states for new chord; the first hit
+        // of each pattern is triggered by update()
+        self.reset_parts();
+    }
 
-        // For Whole style, play immediately on chord change
-        // For rhythm patterns, the first hit will be triggered by update()
-        if self.rhythm_state.style == RhythmStyle::Whole {
-            if let Some(chord) = self.current_chord().cloned() {
-                self.play_current_chord(&chord);
-            }
+    /// Rewind the backing patterns and give them a fresh humanized feel
+    fn reset_parts(&mut self) {
+        let beat_ms = self
+            .current_progression()
+            .map(|prog| (60000.0 / prog.tempo) as u64 as f32)
+            .unwrap_or(500.0);
+
+        // The bass walks towards the next change
+        let idx = self.current_chord_idx;
+        let changes = self.current_progression().map(|prog| {
+            let next = prog.changes.get((idx + 1) % prog.changes.len()).cloned();
+            (prog.changes.get(idx).cloned(), next)
+        });
+
+        // Every chorus gets a fresh solo
+        if idx == 0 {
+            self.refresh_solo();
         }
+
+        self.rhythm_state.reset();
+        match changes {
+            Some((Some(change), next)) => self.bass_state.prepare(&change, next.as_ref(), idx, self.chorus),
+            _ => self.bass_state.reset(),
+        }
+        self.drum_state.reset();
+        self.solo_state.reset(idx);
+        self.rhythm_state.humanize(&mut self.humanizer, beat_ms);
+        self.bass_state.humanize(&mut self.humanizer, beat_ms);
+        self.drum_state.humanize(&mut self.humanizer, beat_ms);
     }
 
-    fn play_current_chord(&mut self, chord: &Chord) {
-        self.play_chord_hit(chord, 0.8);
+    /// Voice the chord at `idx` the way comping plays it
+    ///
+    /// Uses the voice-led voicing of the progression, falling back to voicing
+    /// the chord on its own.
+    pub fn voice(&self, idx: usize, chord: &Chord) -> VoicedChord {
+        self.voiced.get(idx).cloned().unwrap_or_else(|| {
+            self.current_voicing.voice_chord(
+                chord,
+                2,        // Bass octave (C2 = MIDI 36)
+                4,        // Voicing octave (C4 = MIDI 60)
+                (36, 84), // Range: C2 to C6
+            )
+        })
     }
 
     /// Play a chord hit with specified velocity (0.0-1.0)
     fn play_chord_hit(&mut self, chord: &Chord, velocity: f32) {
         // Use voicing system to get properly voiced notes
-        let voiced = self.current_voicing.voice_chord(
-            chord,
-            2,        // Bass octave (C2 = MIDI 36)
-            4,        // Voicing octave (C4 = MIDI 60)
-            (36, 84), // Range: C2 to C6
-        );
+        let voiced = self.voice(self.current_chord_idx, chord);
 
         // Combine bass and voicing notes
         let mut notes = voiced.all_notes();
@@ -330,12 +700,29 @@ impl App {
         let _ = self.audio_manager.play_chord(&[midi_note], midi_velocity);
     }
 
+    /// Play the next note of the solo line, releasing the one before it
+    ///
+    /// A released note that comping is also holding keeps ringing.
+    fn play_solo_note(&mut self, midi_note: u8, velocity: f32, previous: Option<u8>) {
+        if let Some(previous) = previous.filter(|&p| p != midi_note) {
+            self.stop_solo_note(previous);
+        }
+        let midi_velocity = (velocity * 127.0).clamp(1.0, 127.0) as u8;
+        let _ = self.audio_manager.play_note(midi_note, midi_velocity);
+    }
+
+    /// Release a solo note, unless the comping is holding the same note
+    fn stop_solo_note(&mut self, midi_note: u8) {
+        let comped = self.voiced.get(self.current_chord_idx).is_some_and(|v| v.all_notes().contains(&midi_note));
+        if !comped {
+            let _ = self.audio_manager.stop_note(midi_note);
+        }
+    }
+
     /// Play a drum hit (uses GM drum channel)
     fn play_drum_hit(&mut self, midi_note: u8, velocity: f32) {
         let midi_velocity = (velocity * 127.0).clamp(1.0, 127.0) as u8;
-        // For now, use the same play_chord method - synth will handle it
-        // In a full implementation, this would use MIDI channel 10 for drums
-        let _ = self.audio_manager.play_chord(&[midi_note], midi_velocity);
+        let _ = self.audio_manager.play_drum(midi_note, midi_velocity);
     }
 
     pub fn increase_tempo(&mut self) {
@@ -353,11 +740,27 @@ impl App {
     /// Cycle to the next voicing type
     pub fn cycle_voicing(&mut self) {
         self.current_voicing = self.current_voicing.next();
+        self.refresh_timeline();
+    }
+
+    /// Cycle the fretboard tuning
+    pub fn cycle_guitar_tuning(&mut self) {
+        self.guitar_tuning = self.guitar_tuning.next();
+    }
+
+    /// Guitar grip for the current chord in the style nearest the current voicing
+    pub fn guitar_grip(&self) -> Option<GuitarGrip> {
+        let chord = self.current_chord()?;
+        GuitarVoicer::new(self.guitar_tuning)
+            .grips(chord, GripStyle::for_voicing(self.current_voicing))
+            .into_iter()
+            .next()
     }
 
     /// Toggle swing feel
     pub fn toggle_swing(&mut self) {
         self.swing_enabled = !self.swing_enabled;
+        self.sync_swing();
     }
 
     /// Cycle swing ratio: 0.5 (straight) -> 0.58 (light) -> 0.67 (hard) -> 0.5
@@ -367,6 +770,32 @@ impl App {
             r if r < 0.63 => 0.67,
             _ => 0.5,
         };
+        self.sync_swing();
+    }
+
+    /// Hand the swing setting to the pattern schedulers; chord changes stay
+    /// on the beat and only the off-beat eighths of swing styles move
+    fn sync_swing(&mut self) {
+        let ratio = if self.swing_enabled { self.swing_ratio } else { 0.5 };
+        self.rhythm_state.set_swing(ratio);
+        self.bass_state.set_swing(ratio);
+        self.drum_state.set_swing(ratio);
+    }
+
+    /// Cycle the reharmonization of the current progression, ending back at the original
+    pub fn cycle_reharm(&mut self) {
+        let substitution = match &self.reharm {
+            None => Some(Substitution::default()),
+            Some((s, _)) if s.next() == Substitution::default() => None,
+            Some((s, _)) => Some(s.next()),
+        };
+        self.reharm = substitution.and_then(|s| {
+            self.original_progression()
+                .map(|prog| (s, Reharmonizer::apply(prog, &[s])))
+        });
+        self.current_chord_idx = 0;
+        self.stop();
+        self.refresh_timeline();
     }
 
     /// Cycle rhythm style
@@ -389,6 +818,18 @@ impl App {
         self.bass_state.style.name()
     }
 
+    /// Turn the improvised solo line on or off
+    pub fn toggle_soloist(&mut self) {
+        self.soloist = !self.soloist;
+        if !self.soloist {
+            // Let the band play on; only the solo note is stopped
+            if let Some(note) = self.solo_state.silence() {
+                self.stop_solo_note(note);
+            }
+        }
+        self.sync_solo_line();
+    }
+
     /// Cycle drum style
     pub fn cycle_drums(&mut self) {
         self.drum_state.cycle_style();
@@ -399,6 +840,26 @@ impl App {
         self.drum_state.style.name()
     }
 
+    /// Cycle humanize amount
+    pub fn cycle_humanize(&mut self) {
+        self.humanizer.cycle_amount();
+    }
+
+    /// Get current humanize amount name
+    pub fn humanize_name(&self) -> &'static str {
+        self.humanizer.amount.name()
+    }
+
+    /// Cycle tuning system (equal, just, Pythagorean, meantone, Scala)
+    pub fn cycle_temperament(&mut self) {
+        self.audio_manager.cycle_temperament();
+    }
+
+    /// Cycle reference pitch for A4
+    pub fn cycle_reference_pitch(&mut self) {
+        self.audio_manager.cycle_reference_pitch();
+    }
+
     /// Enter LEGO Listen mode
     pub fn enter_lego_listen(&mut self) {
         self.stop();
@@ -419,16 +880,306 @@ impl App {
         self.lego_state.generate_quiz();
     }
 
-    /// Submit quiz answer (1-4)
+    /// Enter LEGO Build mode
+    pub fn enter_lego_build(&mut self) {
+        self.stop();
+        self.mode = AppMode::LegoBuild;
+        if self.lego_state.current_brick_name.is_none() {
+            let bricks = self.lego_state.brick_library.for_difficulty(self.lego_state.difficulty);
+            if let Some(brick) = bricks.first() {
+                self.lego_state.current_brick_name = Some(brick.name.clone());
+            }
+        }
+    }
+
+    /// Build the tune and make it the current progression
+    ///
+    /// Returns false if it doesn't build; Build mode shows why.
+    pub fn load_tune(&mut self) -> bool {
+        match self.lego_state.build_tune() {
+            Ok(tune) => {
+                self.load_progression(tune.progression);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Load the tune and switch to Listen mode to follow it
+    pub fn open_tune(&mut self) {
+        if self.load_tune() {
+            self.mode = AppMode::Listen;
+        }
+    }
+
+    /// Play the tune being built, or stop it
+    pub fn audition_tune(&mut self) {
+        if self.is_playing {
+            self.stop();
+        } else if self.load_tune() {
+            self.play();
+        }
+    }
+
+    /// Enter latency calibration for the active backend
+    pub fn enter_calibration(&mut self) {
+        self.stop();
+        self.mode = AppMode::Calibrate;
+        self.calibration = Some(CalibrationState::new(
+            self.audio_manager.active_backend(),
+            self.clock.now(),
+        ));
+    }
+
+    /// Register a tap along with the calibration click
+    pub fn calibration_tap(&mut self) {
+        let now = self.clock.now();
+        if let Some(calibration) = self.calibration.as_mut() {
+            if calibration.is_finished() {
+                calibration.restart(now);
+            } else {
+                calibration.tap(now);
+            }
+        }
+    }
+
+    /// Measure MIDI round-trip latency in the background
+    ///
+    /// Only the MIDI backend can be measured this way, over the connection
+    /// it sends notes on; the others rely on tapping.
+    pub fn start_loopback(&mut self) {
+        let connection = self.audio_manager.midi_connection();
+        if let Some(calibration) = self.calibration.as_mut() {
+            if calibration.loopback.is_some() {
+                return;
+            }
+            if calibration.backend != ActiveBackend::Midi {
+                calibration.loopback_status = Some("Only MIDI output can be measured by loopback".to_string());
+                return;
+            }
+            let Some(connection) = connection else {
+                calibration.loopback_status = Some("No MIDI output port connected".to_string());
+                return;
+            };
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let _ = tx.send(crate::audio::loopback::measure_round_trip(&connection, 8));
+            });
+            calibration.loopback = Some(rx);
+            calibration.loopback_status = Some("Measuring...".to_string());
+        }
+    }
+
+    /// Save the measured latency and leave calibration
+    pub fn save_calibration(&mut self) {
+        if let Some(calibration) = self.calibration.take() {
+            if let Some(ms) = calibration.estimate_ms() {
+                self.audio_manager.set_latency_ms(calibration.backend, ms);
+            }
+        }
+        self.exit_calibration();
+    }
+
+    pub fn exit_calibration(&mut self) {
+        self.calibration = None;
+        self.audio_manager.stop_all();
+        self.mode = AppMode::Listen;
+    }
+
+    /// Play due clicks and collect loopback results
+    fn update_calibration(&mut self) {
+        let now = self.clock.now();
+        let Some(calibration) = self.calibration.as_mut() else {
+            return;
+        };
+
+        if let Some(rx) = calibration.loopback.as_ref() {
+            if let Ok(result) = rx.try_recv() {
+                calibration.loopback = None;
+                calibration.set_loopback_result(result);
+            }
+        }
+
+        if let Some(click) = calibration.due_click(now) {
+            // Accent the first beat of each bar
+            let velocity = if click % 4 == 0 { 120 } else { 90 };
+            let _ = self.audio_manager.stop_note(CLICK_NOTE);
+            let _ = self.audio_manager.play_note(CLICK_NOTE, velocity);
+        }
+    }
+
+    /// Play a phrase from now, cutting off any that is still sounding
+    fn play_phrase(&mut self, phrase: &Phrase) {
+        self.stop_phrase();
+        self.phrase = Some(PhrasePlayback::new(phrase, self.clock.now()));
+        self.update_phrase();
+    }
+
+    fn stop_phrase(&mut self) {
+        if let Some(playback) = self.phrase.take() {
+            for note in playback.sounding() {
+                let _ = self.audio_manager.stop_note(note);
+            }
+        }
+    }
+
+    /// Send the phrase notes that have fallen due
+    fn update_phrase(&mut self) {
+        let now = self.clock.now();
+        let Some(playback) = self.phrase.as_mut() else {
+            return;
+        };
+        for event in playback.due(now) {
+            let _ = match event {
+                PhraseEvent::On(note) => self.audio_manager.play_note(note, self.config.default_velocity),
+                PhraseEvent::Off(note) => self.audio_manager.stop_note(note),
+            };
+        }
+        if playback.is_finished() {
+            self.phrase = None;
+        }
+    }
+
+    /// Enter the interval trainer and play the first question
+    pub fn enter_interval_quiz(&mut self) {
+        self.stop();
+        self.mode = AppMode::IntervalQuiz;
+        if self.interval_state.quiz.current.is_none() {
+            self.interval_state.generate_quiz();
+        }
+        self.play_question();
+    }
+
+    /// Enter the scale trainer and play the first question
+    pub fn enter_scale_quiz(&mut self) {
+        self.stop();
+        self.mode = AppMode::ScaleQuiz;
+        if self.scale_state.quiz.current.is_none() {
+            self.scale_state.generate_quiz();
+        }
+        self.play_question();
+    }
+
+    /// Play the current interval or scale question again
+    pub fn play_question(&mut self) {
+        match self.mode {
+            AppMode::IntervalQuiz => self.play_interval(),
+            AppMode::ScaleQuiz => self.play_scale(),
+            _ => {}
+        }
+    }
+
+    /// Replay the question, or move on once it has been answered
+    pub fn quiz_space(&mut self) {
+        let active = match self.mode {
+            AppMode::IntervalQuiz => self.interval_state.quiz.has_active_quiz(),
+            AppMode::ScaleQuiz => self.scale_state.quiz.has_active_quiz(),
+            _ => return,
+        };
+        if active {
+            self.play_question();
+        } else {
+            self.next_question();
+        }
+    }
+
+    /// Ask the next interval or scale question and play it
+    pub fn next_question(&mut self) {
+        match self.mode {
+            AppMode::IntervalQuiz => self.interval_state.generate_quiz(),
+            AppMode::ScaleQuiz => self.scale_state.generate_quiz(),
+            _ => return,
+        }
+        self.play_question();
+    }
+
+    /// Answer the current quiz question (0-3); interval and scale results
+    /// are kept
     pub fn submit_quiz_answer(&mut self, answer: usize) {
-        if self.mode == AppMode::LegoQuiz {
-            self.lego_state.submit_answer(answer);
+        match self.mode {
+            AppMode::LegoQuiz => {
+                self.lego_state.quiz.submit(answer);
+            }
+            AppMode::IntervalQuiz => {
+                if let Some((semitones, correct)) = self.interval_state.quiz.submit(answer) {
+                    self.progress.record_interval_result(interval_name(semitones), correct);
+                    self.save_progress();
+                }
+            }
+            AppMode::ScaleQuiz => {
+                if let Some((scale_type, correct)) = self.scale_state.quiz.submit(answer) {
+                    self.progress.record_scale_result(scale_type.name(), correct);
+                    self.save_progress();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn play_interval(&mut self) {
+        let Some(quiz) = self.interval_state.quiz.current.as_ref() else {
+            return;
+        };
+        let steps = quiz.question.steps();
+        let length = Duration::from_millis(INTERVAL_NOTE_MS * (3 - steps.len() as u64));
+        self.play_phrase(&Phrase::sequence(&steps, length));
+    }
+
+    pub fn cycle_interval_set(&mut self) {
+        self.interval_state.cycle_set();
+        self.play_interval();
+    }
+
+    pub fn cycle_interval_direction(&mut self) {
+        self.interval_state.cycle_direction();
+        self.play_interval();
+    }
+
+    /// Play the scale question, with its backing
+    fn play_scale(&mut self) {
+        let Some(quiz) = self.scale_state.quiz.current.as_ref() else {
+            return;
+        };
+        let question = &quiz.question;
+        let step = Duration::from_millis(SCALE_NOTE_MS);
+        let backing = question.backing_notes(self.scale_state.backing);
+        let lead_in = if backing.is_empty() { Duration::ZERO } else { step * SCALE_LEAD_IN };
+
+        let mut phrase = Phrase::default();
+        let melody = question.melody();
+        for (i, &note) in melody.iter().enumerate() {
+            phrase.add(vec![note], lead_in + step * i as u32, step);
+        }
+        if !backing.is_empty() {
+            phrase.add(backing, Duration::ZERO, lead_in + step * melody.len() as u32);
+        }
+        self.play_phrase(&phrase);
+    }
+
+    pub fn cycle_scale_tier(&mut self) {
+        self.scale_state.cycle_tier();
+        self.play_scale();
+    }
+
+    /// Change the backing and hear the question with it
+    pub fn cycle_scale_backing(&mut self) {
+        self.scale_state.cycle_backing();
+        self.play_scale();
+    }
+
+    /// Write progress to disk; a failed save only loses this answer's stats
+    fn save_progress(&mut self) {
+        if let Some(path) = self.progress_path.clone() {
+            let _ = self.progress.save_to(path);
         }
     }
 
     /// Check if in a LEGO mode
     pub fn is_lego_mode(&self) -> bool {
-        matches!(self.mode, AppMode::LegoListen | AppMode::LegoQuiz)
+        matches!(
+            self.mode,
+            AppMode::LegoListen | AppMode::LegoQuiz | AppMode::LegoBuild
+        )
     }
 }
 
@@ -437,3 +1188,482 @@ impl Default for App {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::recording::{RecordedKind, RecordingBackend};
+    use crate::clock::VirtualClock;
+    use crate::music::drums::gm_drums;
+    use crate::music::analysis::ChordRole;
+    use crate::music::{BassStyle, DrumStyle, Note, RhythmStyle, VoiceLeading};
+    use std::sync::Arc;
+
+    /// App on the jazz ii-V-I in C (Dm7 4, G7 4, Cmaj7 8 at 120 bpm)
+    fn headless_app() -> (App, VirtualClock, RecordingBackend) {
+        let clock = VirtualClock::new();
+        let recorder = RecordingBackend::new(Arc::new(clock.clone()));
+        let audio = AudioManager::with_backend(Box::new(recorder.clone()));
+        let mut app = App::with_audio(audio, Box::new(clock.clone()));
+        app.current_genre = "Jazz".to_string();
+        app.current_progression_idx = 0;
+        app.refresh_timeline();
+        (app, clock, recorder)
+    }
+
+    /// Play for `ms` milliseconds, ticking every 50ms like the UI loop
+    fn run_for(app: &mut App, clock: &VirtualClock, ms: u64) {
+        app.play();
+        for _ in 0..ms / 50 {
+            clock.advance(Duration::from_millis(50));
+            app.update();
+        }
+    }
+
+    /// Times at which a given note started
+    fn onsets(recorder: &RecordingBackend, note: u8) -> Vec<u64> {
+        recorder
+            .note_ons()
+            .into_iter()
+            .filter(|&(_, n, _)| n == note)
+            .map(|(t, _, _)| t)
+            .collect()
+    }
+
+    #[test]
+    fn test_whole_notes_follow_chord_changes() {
+        let (mut app, clock, recorder) = headless_app();
+        run_for(&mut app, &clock, 8_500);
+
+        let ons = recorder.note_ons();
+        // Dm7, G7 and Cmaj7 in close position over the root, then back to
+        // Dm7; Dm7 is inverted to sit next to G7. Cmaj7 lasts two bars and
+        // is struck again on the second
+        let chords: Vec<(u64, Vec<u8>)> = vec![
+            (0, vec![38, 60, 65, 69, 74]),
+            (2_000, vec![43, 62, 65, 67, 71]),
+            (4_000, vec![36, 60, 64, 67, 71]),
+            (6_000, vec![36, 60, 64, 67, 71]),
+            (8_000, vec![38, 60, 65, 69, 74]),
+        ];
+        let expected: Vec<(u64, u8)> = chords
+            .into_iter()
+            .flat_map(|(t, notes)| notes.into_iter().map(move |n| (t, n)))
+            .collect();
+        let actual: Vec<(u64, u8)> = ons.iter().map(|&(t, n, _)| (t, n)).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_quarter_comping() {
+        let (mut app, clock, recorder) = headless_app();
+        app.rhythm_state.set_style(RhythmStyle::Quarter);
+        run_for(&mut app, &clock, 3_900);
+
+        // The fifth of Dm7 (A4) then the third of G7 (B4) on every beat
+        assert_eq!(onsets(&recorder, 69), vec![0, 500, 1_000, 1_500]);
+        assert_eq!(onsets(&recorder, 71), vec![2_000, 2_500, 3_000, 3_500]);
+    }
+
+    #[test]
+    fn test_soloist_plays_line_in_listen_mode() {
+        let (mut app, clock, recorder) = headless_app();
+        app.soloist = true;
+        run_for(&mut app, &clock, 7_900);
+
+        // Every eighth of the line on the piano roll sounds on time: Dm7
+        // and G7 last 2s each
+        let line: Vec<_> = app.timeline_state.chords.iter().flat_map(|c| c.line.clone()).collect();
+        assert_eq!(line.len(), 32);
+        let ons = recorder.note_ons();
+        for note in &line {
+            let at = note.change.min(2) as u64 * 2_000 + (note.beat * 500.0) as u64;
+            assert!(ons.iter().any(|&(t, n, _)| t == at && n == note.midi), "{:?} at {}", note, at);
+        }
+
+        // Nothing plays off the beat without the soloist, or outside Listen mode
+        for (soloist, mode) in [(false, AppMode::Listen), (true, AppMode::Practice)] {
+            let (mut app, clock, recorder) = headless_app();
+            app.soloist = soloist;
+            app.mode = mode;
+            run_for(&mut app, &clock, 1_900);
+            assert!(recorder.note_ons().iter().all(|&(t, _, _)| t % 500 == 0));
+        }
+    }
+
+    #[test]
+    fn test_soloist_off_stops_only_the_solo() {
+        let (mut app, clock, recorder) = headless_app();
+        assert!(app.timeline_state.chords.iter().all(|c| c.line.is_empty()));
+        app.toggle_soloist();
+        assert!(app.timeline_state.chords.iter().any(|c| !c.line.is_empty()));
+
+        run_for(&mut app, &clock, 1_250);
+        let playing = recorder.note_ons().last().unwrap().1;
+        let before = recorder.events().len();
+        app.toggle_soloist();
+
+        // The solo note is released, the band keeps going and the line
+        // leaves the piano roll
+        let after = recorder.events();
+        assert_eq!(
+            after[before..].iter().map(|e| e.kind).collect::<Vec<_>>(),
+            vec![RecordedKind::NoteOff { note: playing }]
+        );
+        assert!(app.timeline_state.chords.iter().all(|c| c.line.is_empty()));
+    }
+
+    #[test]
+    fn test_cycle_scale() {
+        let (mut app, clock, _recorder) = headless_app();
+        use crate::music::ScaleType;
+
+        // Dm7 is the ii of C major
+        let scale = |app: &App| app.current_scale().unwrap().scale_type;
+        assert_eq!(scale(&app), ScaleType::Dorian);
+        assert_eq!(app.scale_reason().as_deref(), Some("Diatonic to C major"));
+
+        // The alternates cycle back round, and the piano roll follows
+        app.cycle_scale();
+        assert_eq!(scale(&app), ScaleType::Aeolian);
+        assert_eq!(app.scale_reason().as_deref(), Some("Alternate 2 of 5"));
+        assert_eq!(app.timeline_state.chords[0].scale.scale_type, ScaleType::Aeolian);
+        for _ in 0..4 {
+            app.cycle_scale();
+        }
+        assert_eq!(scale(&app), ScaleType::Dorian);
+
+        // A pick only lasts for its chord
+        app.cycle_scale();
+        run_for(&mut app, &clock, 2_100);
+        assert_eq!(app.current_chord_idx, 1);
+        assert_eq!(scale(&app), ScaleType::Mixolydian);
+        assert_eq!(app.timeline_state.chords[0].scale.scale_type, ScaleType::Dorian);
+    }
+
+    #[test]
+    fn test_walking_bass() {
+        let (mut app, clock, recorder) = headless_app();
+        app.bass_state.set_style(BassStyle::Walking);
+        run_for(&mut app, &clock, 3_900);
+
+        // Each chord walks up from its root, under the whole-note comping
+        // that doubles the root on the downbeat, and beat 4 leads into the
+        // next chord: F# a half step under G, then G as the dominant of C
+        let low: Vec<(u64, u8)> = recorder
+            .note_ons()
+            .into_iter()
+            .filter(|&(_, n, _)| n < 60)
+            .map(|(t, n, _)| (t, n))
+            .collect();
+        assert_eq!(
+            low,
+            vec![
+                (0, 38),
+                (0, 38),
+                (500, 41),
+                (1_000, 45),
+                (1_500, 42),
+                (2_000, 43),
+                (2_000, 43),
+                (2_500, 45),
+                (3_000, 47),
+                (3_500, 43),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_swing_moves_offbeats_not_changes() {
+        let (mut app, clock, recorder) = headless_app();
+        app.swing_enabled = true;
+        app.swing_ratio = 0.6;
+        app.rhythm_state.set_style(RhythmStyle::JazzBasic);
+        app.drum_state.set_style(DrumStyle::JazzRide);
+        run_for(&mut app, &clock, 4_500);
+
+        // Chords change on the beat: Dm7 at 0, G7 at 2s, Cmaj7 at 4s
+        assert_eq!(onsets(&recorder, 38), vec![0, 800, 1_500]);
+        assert_eq!(onsets(&recorder, 43), vec![2_000, 2_800, 3_500]);
+        assert_eq!(onsets(&recorder, 36), vec![4_000]);
+
+        // The ride's skip notes land on the swung "and" of 2 and 4
+        let ride = onsets(&recorder, gm_drums::RIDE);
+        assert_eq!(&ride[..6], &[0, 500, 800, 1_000, 1_500, 1_800]);
+    }
+
+    #[test]
+    fn test_jazz_styles_swing_by_default() {
+        let (mut app, clock, recorder) = headless_app();
+        app.drum_state.set_style(DrumStyle::JazzRide);
+        run_for(&mut app, &clock, 1_900);
+
+        // The skip note lands two thirds of the way through beat two, at
+        // 833ms, heard on the next 50ms tick
+        let ride = onsets(&recorder, gm_drums::RIDE);
+        assert_eq!(&ride[..4], &[0, 500, 850, 1_000]);
+
+        // Bossa stays straight
+        let (mut app, clock, recorder) = headless_app();
+        app.drum_state.set_style(DrumStyle::BossaNova);
+        run_for(&mut app, &clock, 1_000);
+        assert!(onsets(&recorder, gm_drums::CLOSED_HH).contains(&250));
+    }
+
+    #[test]
+    fn test_playhead_follows_latency() {
+        let (mut app, clock, recorder) = headless_app();
+        app.audio_manager.set_latency_ms(ActiveBackend::Synth, 100.0);
+        run_for(&mut app, &clock, 2_050);
+
+        // The scheduler runs 100ms ahead of the clock: the downbeat sent at
+        // once is heard at 100ms, and G7 is sent at 2000ms to land at 2100ms
+        assert!(recorder.note_ons().iter().any(|&(t, _, _)| t == 2_000));
+        assert!((app.current_beat - 0.1).abs() < 0.01);
+
+        // G7 has been sent, but what we hear is still the end of Dm7
+        assert_eq!(app.current_chord_idx, 1);
+        assert_eq!(app.timeline_state.current_chord_idx, 0);
+        assert!((app.timeline_state.current_beat - 3.9).abs() < 0.01);
+
+        clock.advance(Duration::from_millis(100));
+        app.update();
+        assert_eq!(app.timeline_state.current_chord_idx, 1);
+        assert!((app.timeline_state.current_beat - 4.1).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_calibration_clicks() {
+        let (mut app, clock, recorder) = headless_app();
+        app.enter_calibration();
+        for _ in 0..20 {
+            clock.advance(Duration::from_millis(50));
+            app.update();
+        }
+
+        let clicks = onsets(&recorder, CLICK_NOTE);
+        assert_eq!(clicks, vec![600]);
+        assert!(app.calibration.as_ref().unwrap().clicks_played() == 1);
+    }
+
+    #[test]
+    fn test_loopback_only_measures_midi() {
+        let (mut app, _clock, _recorder) = headless_app();
+        app.enter_calibration();
+        app.start_loopback();
+
+        // A MIDI round trip would otherwise be saved as the synth's latency
+        let calibration = app.calibration.as_ref().unwrap();
+        assert_eq!(calibration.backend, ActiveBackend::Synth);
+        assert!(calibration.loopback.is_none());
+        assert!(calibration.estimate_ms().is_none());
+    }
+
+    #[test]
+    fn test_interval_quiz_plays_and_records() {
+        use crate::music::IntervalDirection;
+
+        let (mut app, clock, recorder) = headless_app();
+        app.interval_state.direction = IntervalDirection::Descending;
+        app.enter_interval_quiz();
+        for _ in 0..40 {
+            clock.advance(Duration::from_millis(50));
+            app.update();
+        }
+
+        // High note first, then the low one a note length later
+        let question = app.interval_state.quiz.current.as_ref().unwrap().question;
+        let ons: Vec<(u64, u8)> = recorder.note_ons().into_iter().map(|(t, n, _)| (t, n)).collect();
+        assert_eq!(ons, vec![(0, question.high()), (INTERVAL_NOTE_MS, question.low)]);
+        assert!(question.low >= app.config.min_note && question.high() <= app.config.max_note);
+        let offs = recorder
+            .events()
+            .iter()
+            .filter(|e| matches!(e.kind, RecordedKind::NoteOff { .. }))
+            .count();
+        assert_eq!(offs, 2);
+
+        let correct_idx = app.interval_state.quiz.current.as_ref().unwrap().correct_idx;
+        app.submit_quiz_answer(correct_idx);
+        let name = interval_name(question.semitones);
+        assert_eq!(app.progress.interval_stats[name].times_correct, 1);
+        assert!(app.interval_state.quiz.waiting_for_next());
+    }
+
+    #[test]
+    fn test_scale_quiz_plays_over_its_chord() {
+        use super::super::scale_mode::ScaleBacking;
+
+        let (mut app, clock, recorder) = headless_app();
+        app.scale_state.backing = ScaleBacking::Chord;
+        app.enter_scale_quiz();
+        for _ in 0..200 {
+            clock.advance(Duration::from_millis(50));
+            app.update();
+        }
+
+        let quiz = app.scale_state.quiz.current.clone().unwrap();
+        let question = &quiz.question;
+        let chord = question.backing_notes(ScaleBacking::Chord);
+        let ons = recorder.note_ons();
+        // The chord sounds first, then the scale after the lead-in
+        assert!(ons[..chord.len()].iter().all(|&(t, n, _)| t == 0 && chord.contains(&n)));
+        let melody: Vec<(u64, u8)> = ons[chord.len()..].iter().map(|&(t, n, _)| (t, n)).collect();
+        assert_eq!(melody.len(), question.melody().len());
+        assert_eq!(melody[0], (SCALE_NOTE_MS * SCALE_LEAD_IN as u64, question.root));
+        assert_eq!(melody[7].1, question.root + 12);
+
+        app.submit_quiz_answer((quiz.correct_idx + 1) % 4);
+        let name = question.scale_type.name();
+        assert_eq!(app.progress.scale_stats[name].times_correct, 0);
+        assert_eq!(app.progress.scale_stats[name].times_seen, 1);
+    }
+
+    #[test]
+    fn test_stop_silences_output() {
+        let (mut app, clock, recorder) = headless_app();
+        run_for(&mut app, &clock, 1_000);
+        app.stop();
+        clock.advance(Duration::from_millis(5_000));
+        app.update();
+
+        let last = recorder.events().last().copied().unwrap();
+        assert_eq!(last.kind, RecordedKind::AllNotesOff);
+        assert_eq!(last.at_ms(), 1_000);
+    }
+
+    #[test]
+    fn test_reharm_plays_substitute() {
+        let (mut app, clock, recorder) = headless_app();
+        app.cycle_reharm();
+        assert_eq!(app.current_progression().unwrap().changes[1].chord.name(), "Db7");
+        assert_eq!(app.analysis[1].role, ChordRole::TritoneSub { target: "I".to_string() });
+
+        // The second chord is heard as Db7 at beat 4
+        run_for(&mut app, &clock, 4_500);
+        let at_change: Vec<u8> = recorder
+            .note_ons()
+            .into_iter()
+            .filter(|&(t, _, _)| t == 2_000)
+            .map(|(_, n, _)| n % 12)
+            .collect();
+        assert!(at_change.contains(&1) && !at_change.contains(&7));
+
+        // Cycling through every substitution comes back to the original
+        for _ in 1..Substitution::ALL.len() {
+            app.cycle_reharm();
+            assert!(app.reharm.is_some());
+        }
+        app.cycle_reharm();
+        assert!(app.reharm.is_none());
+        assert_eq!(app.current_progression().unwrap().changes[1].chord.name(), "G7");
+    }
+
+    #[test]
+    fn test_bricks_follow_the_progression() {
+        let (mut app, _clock, _recorder) = headless_app();
+        let brick = app.current_brick().unwrap();
+        assert_eq!(brick.brick.as_deref(), Some("Launcher"));
+        assert_eq!(brick.key, Note::C);
+
+        // Recomputed when the key changes, and found for every chord
+        app.transpose_by(2);
+        app.current_chord_idx = 2;
+        let brick = app.current_brick().unwrap();
+        assert_eq!(brick.brick.as_deref(), Some("Launcher"));
+        assert_eq!(brick.key, Note::D);
+    }
+
+    #[test]
+    fn test_transpose_session_key() {
+        let (mut app, clock, recorder) = headless_app();
+        app.transpose_by(2);
+        assert_eq!(app.key_name().as_deref(), Some("D (+2)"));
+        assert_eq!(app.timeline_state.chords[0].chord.name(), "Em7");
+        assert_eq!(app.voiced[1].bass % 12, Note::A as u8);
+        assert_eq!(app.current_scale().unwrap().root, Note::E);
+
+        // The second chord is heard as A7 at beat 4
+        run_for(&mut app, &clock, 4_500);
+        let at_change: Vec<u8> = recorder
+            .note_ons()
+            .into_iter()
+            .filter(|&(t, _, _)| t == 2_000)
+            .map(|(_, n, _)| n % 12)
+            .collect();
+        assert!(at_change.contains(&1) && !at_change.contains(&11));
+
+        // The offset carries over to the next progression and wraps around
+        app.next_progression();
+        assert_eq!(app.transpose, 2);
+        app.transpose_by(-8);
+        assert_eq!(app.transpose, 6 - 12);
+        app.transpose_by(6);
+        assert_eq!(app.transpose, 0);
+        app.prev_progression();
+        assert_eq!(app.current_progression().unwrap().changes[0].chord.name(), "Dm7");
+    }
+
+    #[test]
+    fn test_play_built_tune() {
+        let (mut app, _clock, _recorder) = headless_app();
+        app.enter_lego_build();
+        assert!(!app.load_tune());
+
+        app.lego_state.set_current_brick("Sad Launcher", Note::C);
+        app.lego_state.add_brick_to_tune();
+        app.lego_state.add_join_to_tune();
+        app.lego_state.add_brick_to_tune();
+        app.audition_tune();
+        assert!(app.is_playing);
+        assert_eq!(app.current_genre, "LEGO Tunes");
+        assert_eq!(app.current_progression().unwrap().changes.len(), 6);
+        assert_eq!(app.timeline_state.chords[0].chord.name(), "Dm7b5");
+
+        // Rebuilding replaces the tune instead of adding another
+        app.audition_tune();
+        assert!(!app.is_playing);
+        app.open_tune();
+        assert_eq!(app.mode, AppMode::Listen);
+        assert_eq!(app.library.get_by_genre("LEGO Tunes").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_comping_follows_voice_leading() {
+        let (mut app, clock, recorder) = headless_app();
+        app.cycle_voicing(); // Shell
+        app.cycle_voicing(); // Rootless A
+        assert_eq!(app.voiced.len(), app.current_progression().unwrap().changes.len());
+        assert!(app.timeline_state.chords.iter().all(|c| c.voiced.is_some()));
+
+        // Each change sounds the voice-led voicing, not the chord voiced on its own
+        run_for(&mut app, &clock, 4_500);
+        for (idx, at) in [(0, 0), (1, 2_000), (2, 4_000)] {
+            let heard: Vec<u8> = recorder
+                .note_ons()
+                .into_iter()
+                .filter(|&(t, _, _)| t == at)
+                .map(|(_, n, _)| n)
+                .collect();
+            assert!(app.voiced[idx].voicing.iter().all(|n| heard.contains(n)));
+        }
+
+        let led: u32 = app
+            .voiced
+            .windows(2)
+            .map(|w| VoiceLeading::total_motion(&w[0].voicing, &w[1].voicing))
+            .sum();
+        let independent: Vec<VoicedChord> = app
+            .current_progression()
+            .unwrap()
+            .changes
+            .iter()
+            .map(|c| VoicingType::RootlessA.voice_chord(&c.chord, 2, 4, (36, 84)))
+            .collect();
+        let jumpy: u32 = independent
+            .windows(2)
+            .map(|w| VoiceLeading::total_motion(&w[0].voicing, &w[1].voicing))
+            .sum();
+        assert!(led < jumpy);
+    }
+}
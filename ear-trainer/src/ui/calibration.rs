@@ -0,0 +1,190 @@
+//! Latency Calibration Mode
+//!
+//! Plays a steady click and collects taps on the space bar. Since people tap
+//! on what they hear, the typical delay between sending a click and the tap
+//! that answers it is the output latency of the backend.
+
+use crate::audio::ActiveBackend;
+use anyhow::Result;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+/// Clicks played per calibration run
+pub const TOTAL_CLICKS: usize = 16;
+
+/// Clicks to let the user settle into the pulse before taps count
+const WARMUP_CLICKS: usize = 4;
+
+/// Fewest matched taps needed for an estimate
+const MIN_TAPS: usize = 4;
+
+/// State for the calibration screen
+#[derive(Debug)]
+pub struct CalibrationState {
+    /// Backend being calibrated
+    pub backend: ActiveBackend,
+    /// Time between clicks (100 bpm)
+    pub interval: Duration,
+    /// When each click was sent
+    clicks: Vec<Instant>,
+    /// When each tap arrived
+    taps: Vec<Instant>,
+    next_click: Instant,
+    /// Running MIDI loopback measurement, if any
+    pub loopback: Option<Receiver<Result<Duration>>>,
+    /// Result or error of the last loopback run
+    pub loopback_status: Option<String>,
+    /// Latency measured by loopback, which takes priority over taps
+    loopback_latency: Option<Duration>,
+}
+
+impl CalibrationState {
+    pub fn new(backend: ActiveBackend, now: Instant) -> Self {
+        let interval = Duration::from_millis(600);
+        Self {
+            backend,
+            interval,
+            clicks: Vec::new(),
+            taps: Vec::new(),
+            next_click: now + interval,
+            loopback: None,
+            loopback_status: None,
+            loopback_latency: None,
+        }
+    }
+
+    /// Whether a click is due; records it as sent if so
+    ///
+    /// Returns the click number (0-based) so the caller can accent downbeats.
+    pub fn due_click(&mut self, now: Instant) -> Option<usize> {
+        if self.clicks.len() >= TOTAL_CLICKS || now < self.next_click {
+            return None;
+        }
+        self.clicks.push(now);
+        self.next_click += self.interval;
+        Some(self.clicks.len() - 1)
+    }
+
+    pub fn tap(&mut self, now: Instant) {
+        self.taps.push(now);
+    }
+
+    pub fn clicks_played(&self) -> usize {
+        self.clicks.len()
+    }
+
+    pub fn tap_count(&self) -> usize {
+        self.taps.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.clicks.len() >= TOTAL_CLICKS
+    }
+
+    /// Start over with a fresh set of clicks
+    pub fn restart(&mut self, now: Instant) {
+        self.clicks.clear();
+        self.taps.clear();
+        self.next_click = now + self.interval;
+    }
+
+    /// Record the outcome of a MIDI loopback round trip
+    pub fn set_loopback_result(&mut self, result: Result<Duration>) {
+        match result {
+            Ok(round_trip) => {
+                let latency = round_trip / 2;
+                self.loopback_status = Some(format!(
+                    "Round trip {:.1} ms",
+                    round_trip.as_secs_f32() * 1000.0
+                ));
+                self.loopback_latency = Some(latency);
+            }
+            Err(e) => self.loopback_status = Some(e.to_string()),
+        }
+    }
+
+    /// Estimated latency in milliseconds
+    ///
+    /// Each tap after the warm-up is matched to the nearest click within half
+    /// an interval; the median offset ignores the odd early or late tap.
+    pub fn estimate_ms(&self) -> Option<f32> {
+        if let Some(latency) = self.loopback_latency {
+            return Some(latency.as_secs_f32() * 1000.0);
+        }
+
+        let half_interval = self.interval.as_secs_f32() * 500.0;
+        let mut offsets: Vec<f32> = self
+            .taps
+            .iter()
+            .filter_map(|&tap| {
+                self.clicks
+                    .iter()
+                    .skip(WARMUP_CLICKS)
+                    .map(|&click| signed_ms(tap, click))
+                    .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+            })
+            .filter(|offset| offset.abs() <= half_interval)
+            .collect();
+
+        if offsets.len() < MIN_TAPS {
+            return None;
+        }
+        offsets.sort_by(|a, b| a.total_cmp(b));
+        Some(offsets[offsets.len() / 2].max(0.0))
+    }
+}
+
+/// `a - b` in milliseconds
+fn signed_ms(a: Instant, b: Instant) -> f32 {
+    if a >= b {
+        a.duration_since(b).as_secs_f32() * 1000.0
+    } else {
+        -(b.duration_since(a).as_secs_f32() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_clicks(state: &mut CalibrationState, start: Instant) -> Vec<Instant> {
+        let mut sent = Vec::new();
+        let mut now = start;
+        while !state.is_finished() {
+            now += Duration::from_millis(10);
+            if state.due_click(now).is_some() {
+                sent.push(now);
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn test_estimate_from_taps() {
+        let start = Instant::now();
+        let mut state = CalibrationState::new(ActiveBackend::BleMidi, start);
+        let clicks = run_clicks(&mut state, start);
+        assert_eq!(clicks.len(), TOTAL_CLICKS);
+
+        // Taps land 40ms after each click, give or take, with one stray
+        let jitter = [-6i64, 3, 0, 5, -2, 4, -4, 1, 2, -1, 6, -3, 0, 2, -5, 3];
+        for (click, j) in clicks.iter().zip(jitter) {
+            state.tap(*click + Duration::from_millis((40 + j) as u64));
+        }
+        state.tap(clicks[8] + Duration::from_millis(250));
+
+        let estimate = state.estimate_ms().unwrap();
+        assert!((estimate - 40.0).abs() <= 3.0, "estimate {}", estimate);
+    }
+
+    #[test]
+    fn test_no_estimate_without_taps() {
+        let start = Instant::now();
+        let mut state = CalibrationState::new(ActiveBackend::Synth, start);
+        run_clicks(&mut state, start);
+        assert_eq!(state.estimate_ms(), None);
+
+        state.set_loopback_result(Ok(Duration::from_millis(12)));
+        assert_eq!(state.estimate_ms(), Some(6.0));
+    }
+}
@@ -1,37 +1,78 @@
 use super::app::{App, AppMode};
 use crossterm::event::{KeyCode, KeyEvent};
 
+/// Keys every quiz mode shares: the answers, help, quit and back
+///
+/// Returns None for keys left to the mode, otherwise whether to keep running.
+fn handle_quiz_input(app: &mut App, key: KeyEvent) -> Option<bool> {
+    match key.code {
+        KeyCode::Char('q') => return Some(false),
+        KeyCode::Char('h') => app.show_help = !app.show_help,
+        // Answer selection (1-4)
+        KeyCode::Char(c @ '1'..='4') => app.submit_quiz_answer(c as usize - '1' as usize),
+        // Escape to go back to normal mode
+        KeyCode::Esc => {
+            app.stop();
+            app.mode = AppMode::Listen;
+        }
+        _ => return None,
+    }
+    Some(true)
+}
+
 pub fn handle_input(app: &mut App, key: KeyEvent) -> bool {
     // Handle LEGO Quiz mode inputs separately
     if app.mode == AppMode::LegoQuiz {
+        if let Some(running) = handle_quiz_input(app, key) {
+            return running;
+        }
         match key.code {
-            KeyCode::Char('q') => return false,
-            KeyCode::Char('h') => app.show_help = !app.show_help,
-            // Quiz answer selection (1-4)
-            KeyCode::Char('1') => app.submit_quiz_answer(0),
-            KeyCode::Char('2') => app.submit_quiz_answer(1),
-            KeyCode::Char('3') => app.submit_quiz_answer(2),
-            KeyCode::Char('4') => app.submit_quiz_answer(3),
             // Space to play current quiz brick or next question
             KeyCode::Char(' ') => {
-                if app.lego_state.waiting_for_next() {
+                if app.lego_state.quiz.waiting_for_next() {
                     app.lego_state.next_question();
                 }
                 app.toggle_play();
             }
             // Enter for next question after answering
             KeyCode::Enter => {
-                if app.lego_state.waiting_for_next() {
+                if app.lego_state.quiz.waiting_for_next() {
                     app.lego_state.next_question();
                 }
             }
             // d to cycle difficulty
             KeyCode::Char('d') => app.lego_state.cycle_difficulty(),
-            // Escape to go back to normal mode
-            KeyCode::Esc => {
-                app.stop();
-                app.mode = AppMode::Listen;
-            }
+            _ => {}
+        }
+        return true;
+    }
+
+    // Handle latency calibration
+    if app.mode == AppMode::Calibrate {
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char(' ') => app.calibration_tap(),
+            KeyCode::Char('l') => app.start_loopback(),
+            KeyCode::Enter => app.save_calibration(),
+            KeyCode::Esc => app.exit_calibration(),
+            _ => {}
+        }
+        return true;
+    }
+
+    // Handle the interval and scale trainers
+    if matches!(app.mode, AppMode::IntervalQuiz | AppMode::ScaleQuiz) {
+        if let Some(running) = handle_quiz_input(app, key) {
+            return running;
+        }
+        match (app.mode, key.code) {
+            // Space replays, or moves on once answered
+            (_, KeyCode::Char(' ')) => app.quiz_space(),
+            (_, KeyCode::Enter) => app.next_question(),
+            (AppMode::IntervalQuiz, KeyCode::Char('s')) => app.cycle_interval_set(),
+            (AppMode::IntervalQuiz, KeyCode::Char('d')) => app.cycle_interval_direction(),
+            (AppMode::ScaleQuiz, KeyCode::Char('d')) => app.cycle_scale_tier(),
+            (AppMode::ScaleQuiz, KeyCode::Char('b')) => app.cycle_scale_backing(),
             _ => {}
         }
         return true;
@@ -76,6 +117,40 @@ pub fn handle_input(app: &mut App, key: KeyEvent) -> bool {
         return true;
     }
 
+    // Handle LEGO Build mode
+    if app.mode == AppMode::LegoBuild {
+        match key.code {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('h') => app.show_help = !app.show_help,
+            KeyCode::Char(' ') => app.audition_tune(),
+            // Enter to take the tune to Listen mode
+            KeyCode::Enter => app.open_tune(),
+            // n/p to pick a brick, a to add it
+            KeyCode::Char('n') => app.lego_state.next_brick(),
+            KeyCode::Char('p') => app.lego_state.prev_brick(),
+            KeyCode::Char('a') => app.lego_state.add_brick_to_tune(),
+            // j/J to pick a join, c to add it
+            KeyCode::Char('j') => app.lego_state.next_join(),
+            KeyCode::Char('J') => app.lego_state.prev_join(),
+            KeyCode::Char('c') => app.lego_state.add_join_to_tune(),
+            // Sections and form
+            KeyCode::Char('s') => app.lego_state.new_section(),
+            KeyCode::Char('f') => app.lego_state.cycle_form(),
+            // k/K to move the home key
+            KeyCode::Char('k') => app.lego_state.transpose_tune(1),
+            KeyCode::Char('K') => app.lego_state.transpose_tune(-1),
+            KeyCode::Char('u') | KeyCode::Backspace => app.lego_state.builder.undo(),
+            KeyCode::Char('x') => app.lego_state.clear_tune(),
+            KeyCode::Char('d') => app.lego_state.cycle_difficulty(),
+            KeyCode::Esc => {
+                app.stop();
+                app.mode = AppMode::Listen;
+            }
+            _ => {}
+        }
+        return true;
+    }
+
     // Normal mode controls
     match key.code {
         KeyCode::Char('q') => return false,
@@ -86,9 +161,13 @@ pub fn handle_input(app: &mut App, key: KeyEvent) -> bool {
         KeyCode::Char('G') => app.prev_genre(),
         KeyCode::Char('m') => app.toggle_audio_mode(),
         KeyCode::Char('b') => app.force_ble_rescan(),
+        KeyCode::Char('L') => app.enter_calibration(),
         KeyCode::Char('h') => app.show_help = !app.show_help,
         KeyCode::Char('s') => app.show_scales = !app.show_scales,
+        KeyCode::Char('S') => app.cycle_scale(),
         KeyCode::Char('v') => app.show_voice_leading = !app.show_voice_leading,
+        KeyCode::Char('f') => app.show_fretboard = !app.show_fretboard,
+        KeyCode::Char('F') => app.cycle_guitar_tuning(),
         KeyCode::Char('+') | KeyCode::Char('=') => app.increase_tempo(),
         KeyCode::Char('-') | KeyCode::Char('_') => app.decrease_tempo(),
         // Mode selection
@@ -97,17 +176,30 @@ pub fn handle_input(app: &mut App, key: KeyEvent) -> bool {
         KeyCode::Char('3') => app.mode = AppMode::Quiz,
         KeyCode::Char('4') => app.enter_lego_listen(),
         KeyCode::Char('5') => app.enter_lego_quiz(),
+        KeyCode::Char('6') => app.enter_lego_build(),
+        KeyCode::Char('7') => app.enter_interval_quiz(),
+        KeyCode::Char('8') => app.enter_scale_quiz(),
         // Timeline scroll controls
-        KeyCode::Char('[') | KeyCode::Left => app.timeline_state.scroll(-4.0),
-        KeyCode::Char(']') | KeyCode::Right => app.timeline_state.scroll(4.0),
+        KeyCode::Char('[') | KeyCode::Left => app.timeline_state.scroll_bars(-1),
+        KeyCode::Char(']') | KeyCode::Right => app.timeline_state.scroll_bars(1),
         // Voicing and swing controls
         KeyCode::Char('V') => app.cycle_voicing(),
         KeyCode::Char('w') => app.toggle_swing(),
         KeyCode::Char('W') => app.cycle_swing_ratio(),
+        // Reharmonize the current progression
+        KeyCode::Char('x') => app.cycle_reharm(),
+        // Transpose the session key
+        KeyCode::Char('k') => app.transpose_by(1),
+        KeyCode::Char('K') => app.transpose_by(-1),
         // Rhythm, bass, and drums controls
         KeyCode::Char('r') => app.cycle_rhythm(),
         KeyCode::Char('B') => app.cycle_bass(),
         KeyCode::Char('D') => app.cycle_drums(),
+        KeyCode::Char('i') => app.toggle_soloist(),
+        KeyCode::Char('H') => app.cycle_humanize(),
+        // Tuning controls
+        KeyCode::Char('t') => app.cycle_temperament(),
+        KeyCode::Char('A') => app.cycle_reference_pitch(),
         _ => {}
     }
     true
@@ -1,6 +1,6 @@
 //! Enhanced piano roll with vertical timeline visualization
 
-use crate::music::{Chord, ChordScaleMatcher, Note, Scale, VoiceLeading};
+use crate::music::{Chord, ChordScaleMatcher, LineRole, Note, Scale, VoiceLeading};
 use crate::music::chord::ChordTone;
 use ratatui::{
     buffer::Buffer,
@@ -17,6 +17,8 @@ pub struct EnhancedPianoRoll<'a> {
     pub timeline: &'a TimelineState,
     /// Show voice leading arrows between chords
     pub show_voice_leading: bool,
+    /// Show the soloist's line
+    pub show_line: bool,
     /// MIDI range to display
     pub midi_low: u8,
     pub midi_high: u8,
@@ -27,6 +29,7 @@ impl<'a> EnhancedPianoRoll<'a> {
         Self {
             timeline,
             show_voice_leading: true,
+            show_line: false,
             midi_low: 48,  // C3
             midi_high: 84, // C6
         }
@@ -37,6 +40,11 @@ impl<'a> EnhancedPianoRoll<'a> {
         self
     }
 
+    pub fn with_line(mut self, show: bool) -> Self {
+        self.show_line = show;
+        self
+    }
+
     pub fn with_range(mut self, low: u8, high: u8) -> Self {
         self.midi_low = low;
         self.midi_high = high;
@@ -183,6 +191,39 @@ impl<'a> EnhancedPianoRoll<'a> {
             }
         }
 
+        // Draw the soloist's line across the column, one mark per eighth,
+        // with the guide tones in bold
+        let line: &[_] = if self.show_line { &timeline_chord.line } else { &[] };
+        for note in line {
+            if let Some(y) = self.midi_to_y(note.midi, inner) {
+                if y > inner.y && y < inner.y + inner.height - 1 {
+                    let progress = note.beat / timeline_chord.duration_beats;
+                    let note_x = col_x + ((col_width as f32 - 2.0) * progress.clamp(0.0, 1.0)) as u16;
+                    let mut style = Style::default().fg(colors::SOLO_NOTE);
+                    if note.role == LineRole::GuideTone {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    if !timeline_chord.is_current {
+                        style = style.add_modifier(Modifier::DIM);
+                    }
+                    buf.set_string(note_x, y, symbols::NOTE_DIAMOND, style);
+                }
+            }
+        }
+
+        // Beat grid along the bottom row, heavier at each bar line
+        let grid_y = inner.y + inner.height - 1;
+        for beat in 0..timeline_chord.duration_beats.ceil() as usize {
+            let progress = beat as f32 / timeline_chord.duration_beats;
+            let grid_x = col_x + ((col_width as f32 - 2.0) * progress) as u16;
+            let (marker, color) = if self.timeline.is_bar_line(timeline_chord.start_beat + beat as f32) {
+                (symbols::MEASURE_MARKER, Color::Gray)
+            } else {
+                (symbols::BEAT_MARKER, Color::DarkGray)
+            };
+            buf.set_string(grid_x, grid_y, marker, Style::default().fg(color));
+        }
+
         // Draw playhead if this is the current chord
         if timeline_chord.is_current {
             // Calculate playhead position within the chord column
@@ -204,6 +245,33 @@ impl<'a> EnhancedPianoRoll<'a> {
         to_chord: &TimelineChord,
         arrow_x: u16,
     ) {
+        // Follow the voices comping actually plays, when the progression has been voiced
+        if let (Some(from_voiced), Some(to_voiced)) = (&from_chord.voiced, &to_chord.voiced) {
+            for (from_midi, _, semitones) in
+                VoiceLeading::voice_movements(&from_voiced.voicing, &to_voiced.voicing)
+            {
+                if let Some(fy) = self.midi_to_y(from_midi, inner) {
+                    if fy > inner.y && fy < inner.y + inner.height - 1 {
+                        let arrow = styles::voice_leading_arrow(semitones);
+                        let style = styles::voice_leading(semitones);
+                        buf.set_string(arrow_x, fy, arrow, style);
+                    }
+                }
+            }
+
+            // Total motion for the change along the bottom row
+            let total = VoiceLeading::total_motion(&from_voiced.voicing, &to_voiced.voicing);
+            let label = format!("Δ{}", total);
+            let label_x = (arrow_x + 1).saturating_sub(label.chars().count() as u16);
+            buf.set_string(
+                label_x,
+                inner.y + inner.height - 1,
+                &label,
+                Style::default().fg(Color::DarkGray),
+            );
+            return;
+        }
+
         let movements = VoiceLeading::smooth_voice_leading(&from_chord.chord, &to_chord.chord);
 
         for (from_note, to_note, semitones) in movements {
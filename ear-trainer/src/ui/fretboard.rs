@@ -0,0 +1,180 @@
+//! Guitar fretboard widget showing current chord analysis
+
+use crate::music::{Chord, GuitarGrip, GuitarTuning, Note, Scale};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Widget},
+};
+use super::horizontal_keyboard::note_marker;
+use super::symbols::{styles, symbols};
+
+/// Frets that get a position dot in the header row
+const INLAY_FRETS: [u8; 9] = [3, 5, 7, 9, 12, 15, 17, 19, 21];
+
+/// Fretboard with one row per string, high string on top as in tab
+pub struct Fretboard<'a> {
+    /// The chord being displayed
+    pub chord: &'a Chord,
+    /// The scale associated with this chord
+    pub scale: &'a Scale,
+    /// Open-string tuning
+    pub tuning: GuitarTuning,
+    /// First fret shown (0 includes the open strings)
+    pub start_fret: u8,
+    /// Number of frets to display
+    pub frets: u8,
+    /// Grip to highlight, if any
+    pub grip: Option<GuitarGrip>,
+}
+
+impl<'a> Fretboard<'a> {
+    pub fn new(chord: &'a Chord, scale: &'a Scale) -> Self {
+        Self {
+            chord,
+            scale,
+            tuning: GuitarTuning::default(),
+            start_fret: 0,
+            frets: 13, // Open strings through the 12th fret
+            grip: None,
+        }
+    }
+
+    pub fn with_tuning(mut self, tuning: GuitarTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    pub fn with_frets(mut self, start_fret: u8, frets: u8) -> Self {
+        self.start_fret = start_fret;
+        self.frets = frets.max(1);
+        self
+    }
+
+    pub fn with_grip(mut self, grip: GuitarGrip) -> Self {
+        self.grip = Some(grip);
+        self
+    }
+
+    fn in_grip(&self, string: usize, fret: u8) -> bool {
+        self.grip
+            .as_ref()
+            .and_then(|g| g.frets.get(string).copied().flatten())
+            == Some(fret)
+    }
+}
+
+impl<'a> Widget for Fretboard<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let grip_text = self
+            .grip
+            .as_ref()
+            .map(|g| format!(" · {}", g.diagram()))
+            .unwrap_or_default();
+        let block = Block::default()
+            .title(format!(
+                " {} ({}) · {}{} ",
+                self.chord.name(),
+                self.scale.name(),
+                self.tuning.name(),
+                grip_text
+            ))
+            .title_style(styles::title())
+            .borders(Borders::ALL)
+            .border_style(styles::border());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let strings = self.tuning.strings();
+        if inner.height < 2 || inner.width < 12 {
+            return;
+        }
+
+        // String names on the left, then one cell per fret ending in a fret wire
+        let label_width = 3u16;
+        let cell_width = ((inner.width.saturating_sub(label_width)) / self.frets as u16).max(2);
+        let fret_x = |i: u16| inner.x + label_width + i * cell_width;
+
+        // Header row: fret numbers at the inlay positions
+        for i in 0..self.frets as u16 {
+            let fret = self.start_fret + i as u8;
+            if fret_x(i) + cell_width > inner.x + inner.width {
+                break;
+            }
+            if fret == 0 || INLAY_FRETS.contains(&fret) {
+                buf.set_string(fret_x(i), inner.y, fret.to_string(), styles::label());
+            }
+        }
+
+        // High string on the top row
+        for (row, (string, &open)) in strings.iter().enumerate().rev().enumerate() {
+            let y = inner.y + 1 + row as u16;
+            if y >= inner.y + inner.height {
+                break;
+            }
+
+            let name = Note::from_midi(open).name();
+            buf.set_string(inner.x, y, name, Style::default().fg(Color::White));
+
+            for i in 0..self.frets as u16 {
+                let fret = self.start_fret + i as u8;
+                let x = fret_x(i);
+                if x + cell_width > inner.x + inner.width {
+                    break;
+                }
+
+                // String line with the fret wire (or the nut) at the end of the cell
+                let wire = if fret == 0 { "║" } else { symbols::BOX_V };
+                let line = symbols::BOX_H.repeat(cell_width as usize - 1);
+                buf.set_string(x, y, &line, Style::default().fg(Color::DarkGray));
+                buf.set_string(x + cell_width - 1, y, wire, Style::default().fg(Color::Gray));
+
+                if let Some((marker, style)) = note_marker(self.chord, self.scale, open + fret) {
+                    let style = if self.in_grip(string, fret) {
+                        style.add_modifier(Modifier::REVERSED)
+                    } else {
+                        style
+                    };
+                    let marker: String = marker.chars().take(cell_width as usize - 1).collect();
+                    buf.set_string(x + (cell_width - 1) / 2, y, &marker, style);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::{ChordQuality, ChordScaleMatcher, GripStyle, GuitarVoicer};
+
+    #[test]
+    fn test_render_fretboard() {
+        let chord = Chord::new(Note::C, ChordQuality::Major7);
+        let scale = ChordScaleMatcher::get_primary_scale(&chord);
+        let grip = GuitarVoicer::new(GuitarTuning::Standard)
+            .grips(&chord, GripStyle::Drop2)
+            .into_iter()
+            .find(|g| g.diagram() == "x 3 5 4 5 x")
+            .unwrap();
+
+        let area = Rect::new(0, 0, 60, 9);
+        let mut buf = Buffer::empty(area);
+        Fretboard::new(&chord, &scale)
+            .with_frets(0, 13)
+            .with_grip(grip)
+            .render(area, &mut buf);
+
+        // Cells are 4 wide after the 3-column labels: the A string (row 6)
+        // has the root on fret 3, in the grip
+        let cell = |fret: u16, row: u16| buf.get(1 + 3 + fret * 4 + 1, row).clone();
+        assert_eq!(buf.get(1, 6).symbol(), "A");
+        assert_eq!(cell(3, 6).symbol(), "R");
+        assert!(cell(3, 6).modifier.contains(Modifier::REVERSED));
+        // The open high E is the 3rd, not part of the grip
+        assert_eq!(cell(0, 2).symbol(), "3");
+        assert!(!cell(0, 2).modifier.contains(Modifier::REVERSED));
+    }
+}
@@ -41,38 +41,11 @@ impl<'a> HorizontalKeyboard<'a> {
 
     /// Get the display character and style for a note
     fn note_display(&self, midi: u8) -> (&'static str, Style) {
-        let note = Note::from_midi(midi);
-        let chord_notes = self.chord.notes_in_range(midi, midi + 1);
-        let is_chord_tone = !chord_notes.is_empty();
-        let guide_tones = self.chord.guide_tones();
-        let is_guide = guide_tones.contains(&note);
-        let avoid_notes = ChordScaleMatcher::get_avoid_notes(self.chord, self.scale);
-        let is_avoid = avoid_notes.contains(&note);
-        let is_scale = self.scale.contains(note);
-
-        if is_chord_tone {
-            match self.chord.chord_tone(note) {
-                Some(ChordTone::Root) => (symbols::ROOT_MARKER, styles::root()),
-                Some(ChordTone::Third) => ("3", styles::third()),
-                Some(ChordTone::Fifth) => ("5", styles::fifth()),
-                Some(ChordTone::Seventh) => ("7", styles::seventh()),
-                Some(ChordTone::Ninth) => ("9", styles::extension()),
-                Some(ChordTone::Eleventh) => ("11", styles::extension()),
-                Some(ChordTone::Thirteenth) => ("13", styles::extension()),
-                None => (symbols::NOTE_FILLED, styles::fifth()),
-            }
-        } else if is_guide {
-            // Guide tone not in chord (unusual but handle it)
-            (symbols::NOTE_FILLED, styles::seventh())
-        } else if is_avoid {
-            (symbols::AVOID_NOTE, styles::avoid_note())
-        } else if is_scale {
-            (symbols::NOTE_SMALL, styles::scale_note())
-        } else if is_black_key(midi) {
+        note_marker(self.chord, self.scale, midi).unwrap_or(if is_black_key(midi) {
             (symbols::BLACK_KEY, styles::black_key())
         } else {
             (symbols::WHITE_KEY, styles::white_key())
-        }
+        })
     }
 
     /// Get the extension label for a note if it's an available extension
@@ -85,6 +58,41 @@ impl<'a> HorizontalKeyboard<'a> {
     }
 }
 
+/// Analysis marker for a note: chord tones by function, then guide tones,
+/// avoid notes and scale notes; None for notes outside the scale
+pub(super) fn note_marker(chord: &Chord, scale: &Scale, midi: u8) -> Option<(&'static str, Style)> {
+    let note = Note::from_midi(midi);
+    let chord_notes = chord.notes_in_range(midi, midi + 1);
+    let is_chord_tone = !chord_notes.is_empty();
+    let guide_tones = chord.guide_tones();
+    let is_guide = guide_tones.contains(&note);
+    let avoid_notes = ChordScaleMatcher::get_avoid_notes(chord, scale);
+    let is_avoid = avoid_notes.contains(&note);
+    let is_scale = scale.contains(note);
+
+    if is_chord_tone {
+        Some(match chord.chord_tone(note) {
+            Some(ChordTone::Root) => (symbols::ROOT_MARKER, styles::root()),
+            Some(ChordTone::Third) => ("3", styles::third()),
+            Some(ChordTone::Fifth) => ("5", styles::fifth()),
+            Some(ChordTone::Seventh) => ("7", styles::seventh()),
+            Some(ChordTone::Ninth) => ("9", styles::extension()),
+            Some(ChordTone::Eleventh) => ("11", styles::extension()),
+            Some(ChordTone::Thirteenth) => ("13", styles::extension()),
+            None => (symbols::NOTE_FILLED, styles::fifth()),
+        })
+    } else if is_guide {
+        // Guide tone not in chord (unusual but handle it)
+        Some((symbols::NOTE_FILLED, styles::seventh()))
+    } else if is_avoid {
+        Some((symbols::AVOID_NOTE, styles::avoid_note()))
+    } else if is_scale {
+        Some((symbols::NOTE_SMALL, styles::scale_note()))
+    } else {
+        None
+    }
+}
+
 impl<'a> Widget for HorizontalKeyboard<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let block = Block::default()
@@ -123,7 +131,7 @@ impl<'a> Widget for HorizontalKeyboard<'a> {
             // Row 1: Note name (only for white keys to save space, or all if wide enough)
             if !is_black || key_width >= 2 {
                 let note_name = if is_black {
-                    note.name().to_string()
+                    self.scale.spell(note).name()
                 } else {
                     format!("{}", note.name().chars().next().unwrap_or(' '))
                 };
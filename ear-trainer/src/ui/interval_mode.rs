@@ -0,0 +1,140 @@
+//! Interval Training Mode
+//!
+//! Plays two notes, one after the other or together, and asks which
+//! interval they make. Questions come from the selected interval set and
+//! stay within the configured note range.
+
+use super::quiz::{Question, Quiz, QuizSession};
+use crate::config::Config;
+use crate::music::{IntervalDirection, IntervalQuestion, IntervalSet};
+
+/// State for the interval trainer
+#[derive(Debug)]
+pub struct IntervalModeState {
+    /// The built-in sets followed by any from the config
+    pub sets: Vec<IntervalSet>,
+    /// Index into `sets`
+    pub set_idx: usize,
+    pub direction: IntervalDirection,
+    /// Lowest and highest notes a question may use (MIDI)
+    pub min_note: u8,
+    pub max_note: u8,
+    /// Answers are interval sizes in semitones
+    pub quiz: QuizSession<IntervalQuestion>,
+}
+
+impl Question for IntervalQuestion {
+    type Answer = u8;
+
+    fn answer(&self) -> u8 {
+        self.semitones
+    }
+}
+
+impl IntervalModeState {
+    pub fn new(config: &Config) -> Self {
+        let custom = config
+            .interval_sets
+            .iter()
+            .filter_map(|set| IntervalSet::custom(set.name.clone(), &set.semitones));
+        Self {
+            sets: IntervalSet::ALL.into_iter().chain(custom).collect(),
+            set_idx: 0,
+            direction: IntervalDirection::default(),
+            min_note: config.min_note,
+            max_note: config.max_note,
+            quiz: QuizSession::default(),
+        }
+    }
+
+    pub fn set(&self) -> &IntervalSet {
+        &self.sets[self.set_idx % self.sets.len()]
+    }
+
+    /// Switch to the next interval set and start a fresh question
+    pub fn cycle_set(&mut self) {
+        self.set_idx = (self.set_idx + 1) % self.sets.len();
+        self.generate_quiz();
+    }
+
+    pub fn cycle_direction(&mut self) {
+        self.direction = self.direction.next();
+        self.generate_quiz();
+    }
+
+    /// Generate a new question; none if no interval in the set fits the
+    /// note range. Answers are offered smallest first
+    pub fn generate_quiz(&mut self) {
+        let mut rng = rand::thread_rng();
+        let set = self.set();
+        let quiz = IntervalQuestion::random(&mut rng, set, self.direction, self.min_note, self.max_note)
+            .map(|question| Quiz::new(question, &set.semitones, &mut rng).sorted());
+        self.quiz.current = quiz;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CustomIntervalSet;
+
+    #[test]
+    fn test_interval_quiz() {
+        let mut state = IntervalModeState::new(&Config::default());
+        for _ in 0..50 {
+            state.generate_quiz();
+            let quiz = state.quiz.current.clone().unwrap();
+            // Perfect intervals: all three are offered
+            assert_eq!(quiz.options, vec![5, 7, 12]);
+            assert_eq!(quiz.correct_answer(), &quiz.question.semitones);
+        }
+
+        state.cycle_set();
+        let quiz = state.quiz.current.clone().unwrap();
+        assert_eq!(quiz.options.len(), 4);
+        assert!(quiz.options.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_range_too_small() {
+        let config = Config {
+            min_note: 60,
+            max_note: 70,
+            ..Config::default()
+        };
+        let mut state = IntervalModeState::new(&config);
+        state.set_idx = 4; // Compound
+        assert_eq!(state.set().name, "Compound");
+        state.generate_quiz();
+        assert!(state.quiz.current.is_none());
+        assert!(state.quiz.submit(0).is_none());
+    }
+
+    #[test]
+    fn test_sets_from_config() {
+        let config = Config {
+            interval_sets: vec![
+                CustomIntervalSet {
+                    name: "Tritones".to_string(),
+                    semitones: vec![18, 6],
+                },
+                CustomIntervalSet {
+                    name: "Too wide".to_string(),
+                    semitones: vec![30],
+                },
+            ],
+            ..Config::default()
+        };
+        let mut state = IntervalModeState::new(&config);
+        assert_eq!(state.sets.len(), IntervalSet::ALL.len() + 1);
+
+        state.set_idx = IntervalSet::ALL.len() - 1;
+        state.cycle_set();
+        assert_eq!(state.set().name, "Tritones");
+        let quiz = state.quiz.current.clone().unwrap();
+        assert_eq!(quiz.options, vec![6, 18]);
+
+        state.cycle_set();
+        assert_eq!(state.set_idx, 0);
+    }
+}
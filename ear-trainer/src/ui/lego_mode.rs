@@ -1,99 +1,77 @@
 //! LEGO Bricks Training Mode
 //!
-//! Quiz system for identifying brick patterns by ear.
-
-use crate::music::{Brick, BrickLibrary, JoinLibrary, Note, QuizDifficulty};
+//! Quiz system for identifying brick patterns by ear, and a builder for
+//! chaining bricks and joins into whole tunes.
+
+use crate::music::{
+    Brick, BrickLibrary, DataIssue, Join, JoinLibrary, MusicData, Note, QuizDifficulty, Tune,
+    TuneBuilder,
+};
+use super::quiz::{Question, Quiz, QuizSession, MAX_OPTIONS};
+use anyhow::Result;
 use rand::seq::SliceRandom;
-use rand::Rng;
 
 /// State for the LEGO training mode
 #[derive(Debug)]
 pub struct LegoModeState {
     pub brick_library: BrickLibrary,
     pub join_library: JoinLibrary,
-    pub current_quiz: Option<BrickQuiz>,
-    pub session_score: QuizScore,
+    pub quiz: QuizSession<BrickQuestion>,
     pub difficulty: QuizDifficulty,
     /// Current brick being played in Listen mode
     pub current_brick_name: Option<String>,
     pub current_key: Note,
+    /// Tune being assembled in Build mode
+    pub builder: TuneBuilder,
+    /// Join selected in Build mode
+    pub join_idx: usize,
+    /// User data entries that were left out when loading
+    pub data_issues: Vec<DataIssue>,
 }
 
-/// A brick identification quiz question
+/// Forms the builder cycles through; empty plays the sections as written
+pub const TUNE_FORMS: [&str; 4] = ["", "AABA", "ABAC", "AB"];
+
+/// A brick played for identification
 #[derive(Debug, Clone)]
-pub struct BrickQuiz {
+pub struct BrickQuestion {
     /// The brick being played
-    pub target_brick: Brick,
+    pub brick: Brick,
     /// Key it's being played in
-    pub target_key: Note,
-    /// Multiple choice options (brick names)
-    pub options: Vec<String>,
-    /// Index of the correct answer in options
-    pub correct_idx: usize,
-    /// User's answer (None if not answered yet)
-    pub user_answer: Option<usize>,
-    /// Whether the answer has been revealed
-    pub revealed: bool,
+    pub key: Note,
 }
 
-impl BrickQuiz {
-    /// Check if the user's answer is correct
-    pub fn is_correct(&self) -> bool {
-        self.user_answer == Some(self.correct_idx)
-    }
+impl Question for BrickQuestion {
+    type Answer = String;
 
-    /// Get the correct answer name
-    pub fn correct_answer(&self) -> &str {
-        &self.options[self.correct_idx]
+    fn answer(&self) -> String {
+        self.brick.name.clone()
     }
 }
 
-/// Score tracking for the current session
-#[derive(Debug, Clone, Default)]
-pub struct QuizScore {
-    pub correct: u32,
-    pub total: u32,
-    pub streak: u32,
-    pub best_streak: u32,
-}
-
-impl QuizScore {
-    pub fn accuracy(&self) -> f32 {
-        if self.total == 0 {
-            0.0
-        } else {
-            (self.correct as f32 / self.total as f32) * 100.0
-        }
-    }
-
-    pub fn record(&mut self, correct: bool) {
-        self.total += 1;
-        if correct {
-            self.correct += 1;
-            self.streak += 1;
-            if self.streak > self.best_streak {
-                self.best_streak = self.streak;
-            }
-        } else {
-            self.streak = 0;
-        }
+impl LegoModeState {
+    pub fn new() -> Self {
+        Self::with_libraries(BrickLibrary::new(), JoinLibrary::new())
     }
 
-    pub fn reset(&mut self) {
-        *self = Self::default();
+    /// Bricks and joins including the user's data files
+    pub fn with_data(data: MusicData) -> Self {
+        let mut state = Self::with_libraries(data.bricks, data.joins);
+        state.data_issues = data.issues;
+        state
     }
-}
 
-impl LegoModeState {
-    pub fn new() -> Self {
+    fn with_libraries(brick_library: BrickLibrary, join_library: JoinLibrary) -> Self {
         Self {
-            brick_library: BrickLibrary::new(),
-            join_library: JoinLibrary::new(),
-            current_quiz: None,
-            session_score: QuizScore::default(),
+            brick_library,
+            join_library,
+            quiz: QuizSession::default(),
             difficulty: QuizDifficulty::Beginner,
             current_brick_name: None,
             current_key: Note::C,
+            builder: TuneBuilder::new("LEGO Tune", Note::C, 140.0),
+            join_idx: 0,
+            data_issues: Vec::new(),
         }
     }
 
@@ -103,64 +81,25 @@ impl LegoModeState {
 
         // Get bricks for current difficulty
         let available_bricks = self.brick_library.for_difficulty(self.difficulty);
-        if available_bricks.is_empty() {
+        let Some(&target) = available_bricks.choose(&mut rng) else {
             return;
-        }
-
-        // Pick a random brick
-        let target = (*available_bricks.choose(&mut rng).unwrap()).clone();
+        };
 
         // Pick a random key from common jazz keys
         let common_keys = [Note::C, Note::F, Note::Bb, Note::Eb, Note::G];
-        let target_key = *common_keys.choose(&mut rng).unwrap();
-
-        // Generate 3 wrong options + correct
-        let mut wrong_options: Vec<String> = available_bricks
-            .iter()
-            .filter(|b| b.name != target.name)
-            .map(|b| b.name.clone())
-            .collect();
-        wrong_options.shuffle(&mut rng);
-        wrong_options.truncate(3);
-
-        // Insert correct answer at random position
-        let correct_idx = rng.gen_range(0..=wrong_options.len().min(3));
-        let mut options = wrong_options;
-        options.insert(correct_idx, target.name.clone());
-
-        // Ensure we have exactly 4 options (pad with more wrong answers if needed)
-        while options.len() < 4 {
-            let all_names = self.brick_library.names();
-            if let Some(&name) = all_names.choose(&mut rng) {
-                if !options.contains(&name.to_string()) {
-                    options.push(name.to_string());
-                }
-            }
-        }
-        options.truncate(4);
-
-        self.current_quiz = Some(BrickQuiz {
-            target_brick: target.clone(),
-            target_key,
-            options,
-            correct_idx,
-            user_answer: None,
-            revealed: false,
-        });
-    }
-
-    /// Submit an answer for the current quiz
-    pub fn submit_answer(&mut self, answer_idx: usize) {
-        if let Some(quiz) = &mut self.current_quiz {
-            if quiz.revealed {
-                return; // Already answered
-            }
+        let key = *common_keys.choose(&mut rng).unwrap();
 
-            quiz.user_answer = Some(answer_idx);
-            quiz.revealed = true;
-
-            self.session_score.record(answer_idx == quiz.correct_idx);
+        // Wrong answers from the same difficulty, or any brick if there
+        // aren't enough there
+        let mut choices: Vec<String> = available_bricks.iter().map(|b| b.name.clone()).collect();
+        if choices.len() < MAX_OPTIONS {
+            choices = self.brick_library.names().into_iter().map(String::from).collect();
         }
+        let question = BrickQuestion {
+            brick: target.clone(),
+            key,
+        };
+        self.quiz.current = Some(Quiz::new(question, &choices, &mut rng));
     }
 
     /// Move to the next question
@@ -168,21 +107,11 @@ impl LegoModeState {
         self.generate_quiz();
     }
 
-    /// Check if there's an active unanswered quiz
-    pub fn has_active_quiz(&self) -> bool {
-        self.current_quiz.as_ref().is_some_and(|q| !q.revealed)
-    }
-
-    /// Check if waiting for user to proceed to next question
-    pub fn waiting_for_next(&self) -> bool {
-        self.current_quiz.as_ref().is_some_and(|q| q.revealed)
-    }
-
     /// Cycle to the next difficulty level
     pub fn cycle_difficulty(&mut self) {
         self.difficulty = self.difficulty.next();
         // Reset quiz on difficulty change
-        self.current_quiz = None;
+        self.quiz.current = None;
     }
 
     /// Set a specific brick for listen mode
@@ -200,8 +129,8 @@ impl LegoModeState {
 
     /// Get progression for current quiz (for playback)
     pub fn quiz_progression(&self) -> Option<crate::music::Progression> {
-        self.current_quiz.as_ref().map(|quiz| {
-            quiz.target_brick.to_progression(quiz.target_key, 140.0)
+        self.quiz.current.as_ref().map(|quiz| {
+            quiz.question.brick.to_progression(quiz.question.key, 140.0)
         })
     }
 
@@ -258,6 +187,70 @@ impl LegoModeState {
     pub fn prev_key(&mut self) {
         self.current_key = self.current_key.transpose(-1);
     }
+
+    /// Joins in a stable order for Build mode
+    fn sorted_joins(&self) -> Vec<&Join> {
+        let mut joins = self.join_library.all();
+        joins.sort_by_key(|j| (j.key_shift, j.name.clone()));
+        joins
+    }
+
+    /// The join Build mode would add next
+    pub fn selected_join(&self) -> Option<&Join> {
+        let joins = self.sorted_joins();
+        joins.get(self.join_idx % joins.len().max(1)).copied()
+    }
+
+    pub fn next_join(&mut self) {
+        self.join_idx = (self.join_idx + 1) % self.join_library.all().len().max(1);
+    }
+
+    pub fn prev_join(&mut self) {
+        let count = self.join_library.all().len().max(1);
+        self.join_idx = (self.join_idx + count - 1) % count;
+    }
+
+    /// Append the selected brick to the tune
+    pub fn add_brick_to_tune(&mut self) {
+        if let Some(name) = self.current_brick_name.clone() {
+            self.builder.add_brick(&name, None);
+        }
+    }
+
+    /// Append the selected join to the tune
+    pub fn add_join_to_tune(&mut self) {
+        if let Some(name) = self.selected_join().map(|j| j.name.clone()) {
+            self.builder.add_join(&name);
+        }
+    }
+
+    /// Start the next section (A, B, C...)
+    pub fn new_section(&mut self) {
+        let label = self.builder.next_label();
+        self.builder.add_section(label, None);
+    }
+
+    pub fn cycle_form(&mut self) {
+        let idx = TUNE_FORMS
+            .iter()
+            .position(|f| *f == self.builder.form)
+            .unwrap_or(0);
+        self.builder.form = TUNE_FORMS[(idx + 1) % TUNE_FORMS.len()].to_string();
+    }
+
+    /// Move the tune's home key
+    pub fn transpose_tune(&mut self, semitones: i8) {
+        self.builder.key = self.builder.key.transpose(semitones);
+    }
+
+    /// Start over with an empty tune in the same key
+    pub fn clear_tune(&mut self) {
+        self.builder = TuneBuilder::new(self.builder.name.clone(), self.builder.key, self.builder.tempo);
+    }
+
+    pub fn build_tune(&self) -> Result<Tune> {
+        self.builder.build(&self.brick_library, &self.join_library)
+    }
 }
 
 impl Default for LegoModeState {
@@ -275,31 +268,10 @@ mod tests {
         let mut state = LegoModeState::new();
         state.generate_quiz();
 
-        let quiz = state.current_quiz.as_ref().unwrap();
+        let quiz = state.quiz.current.as_ref().unwrap();
         assert_eq!(quiz.options.len(), 4);
-        assert!(quiz.options.contains(&quiz.target_brick.name));
-        assert!(!quiz.revealed);
-        assert!(quiz.user_answer.is_none());
-    }
-
-    #[test]
-    fn test_quiz_scoring() {
-        let mut score = QuizScore::default();
-
-        score.record(true);
-        assert_eq!(score.correct, 1);
-        assert_eq!(score.streak, 1);
-
-        score.record(true);
-        assert_eq!(score.correct, 2);
-        assert_eq!(score.streak, 2);
-        assert_eq!(score.best_streak, 2);
-
-        score.record(false);
-        assert_eq!(score.correct, 2);
-        assert_eq!(score.total, 3);
-        assert_eq!(score.streak, 0);
-        assert_eq!(score.best_streak, 2);
+        assert_eq!(quiz.correct_answer(), &quiz.question.brick.name);
+        assert!(state.quiz.has_active_quiz());
     }
 
     #[test]
@@ -316,4 +288,25 @@ mod tests {
         state.cycle_difficulty();
         assert_eq!(state.difficulty, QuizDifficulty::Beginner);
     }
+
+    #[test]
+    fn test_build_tune() {
+        let mut state = LegoModeState::new();
+        assert!(state.build_tune().is_err());
+
+        state.set_current_brick("Launcher", Note::C);
+        state.add_brick_to_tune();
+        state.new_section();
+        state.add_join_to_tune();
+        state.add_brick_to_tune();
+        state.cycle_form();
+        assert_eq!(state.builder.form, "AABA");
+
+        let tune = state.build_tune().unwrap();
+        assert_eq!(tune.standard.sections.len(), 4);
+        assert_eq!(tune.standard.joins_used.len(), 1);
+
+        state.clear_tune();
+        assert!(state.builder.sections.is_empty());
+    }
 }
@@ -1,16 +1,23 @@
 pub mod app;
+pub mod calibration;
 pub mod controls;
 pub mod enhanced_piano_roll;
+pub mod fretboard;
 pub mod horizontal_keyboard;
+pub mod interval_mode;
 pub mod lego_mode;
 pub mod notation;
+pub mod phrase;
 pub mod piano_roll;
+pub mod quiz;
+pub mod scale_mode;
 pub mod symbols;
 pub mod timeline;
 
 pub use app::{App, AppMode};
 pub use controls::handle_input;
 pub use enhanced_piano_roll::EnhancedPianoRoll;
+pub use fretboard::Fretboard;
 pub use horizontal_keyboard::HorizontalKeyboard;
 pub use lego_mode::LegoModeState;
 pub use notation::render_notation;
@@ -1,4 +1,4 @@
-use crate::music::{Chord, Scale, VoiceLeading};
+use crate::music::{BrickSegment, Chord, ChordRecognizer, Note, RomanNumeral, Scale, VoiceLeading, VoicedChord, VoicingType};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -12,6 +12,14 @@ pub struct NotationView<'a> {
     pub scale: &'a Scale,
     pub next_chord: Option<&'a Chord>,
     pub show_voice_leading: bool,
+    /// Voicing being played, named by what its notes actually spell
+    pub voicing: Option<(VoicingType, VoicedChord)>,
+    /// Roman numeral of the chord in the progression's key
+    pub analysis: Option<RomanNumeral>,
+    /// LEGO brick the chord is part of
+    pub brick: Option<&'a BrickSegment>,
+    /// Why the scale was chosen
+    pub scale_reason: Option<String>,
 }
 
 impl<'a> NotationView<'a> {
@@ -21,9 +29,33 @@ impl<'a> NotationView<'a> {
             scale,
             next_chord: None,
             show_voice_leading: false,
+            voicing: None,
+            analysis: None,
+            brick: None,
+            scale_reason: None,
         }
     }
 
+    pub fn with_analysis(mut self, analysis: RomanNumeral) -> Self {
+        self.analysis = Some(analysis);
+        self
+    }
+
+    pub fn with_brick(mut self, brick: &'a BrickSegment) -> Self {
+        self.brick = Some(brick);
+        self
+    }
+
+    pub fn with_scale_reason(mut self, reason: String) -> Self {
+        self.scale_reason = Some(reason);
+        self
+    }
+
+    pub fn with_voicing(mut self, voicing: VoicingType, voiced: VoicedChord) -> Self {
+        self.voicing = Some((voicing, voiced));
+        self
+    }
+
     pub fn with_next_chord(mut self, next: &'a Chord) -> Self {
         self.next_chord = Some(next);
         self.show_voice_leading = true;
@@ -55,6 +87,41 @@ impl<'a> Widget for NotationView<'a> {
             ),
         ]));
 
+        if let Some(analysis) = &self.analysis {
+            lines.push(Line::from(vec![
+                Span::styled("Function: ", Style::default().fg(Color::Gray)),
+                Span::styled(
+                    analysis.numeral.clone(),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("  {} · {}", analysis.function.name(), analysis.role.name()),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]));
+        }
+
+        if let Some(segment) = self.brick {
+            let mut spans = vec![
+                Span::styled("Brick: ", Style::default().fg(Color::Gray)),
+                Span::styled(
+                    segment.brick.clone().unwrap_or_default(),
+                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" in {}", segment.key.name()),
+                    Style::default().fg(Color::Gray),
+                ),
+            ];
+            if let Some(join) = &segment.join {
+                spans.push(Span::styled(
+                    format!("  after a {} join", join),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            lines.push(Line::from(spans));
+        }
+
         lines.push(Line::from(vec![
             Span::styled("Scale/Mode: ", Style::default().fg(Color::Gray)),
             Span::styled(
@@ -62,11 +129,17 @@ impl<'a> Widget for NotationView<'a> {
                 Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
             ),
         ]));
+        if let Some(reason) = &self.scale_reason {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", reason),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
 
         lines.push(Line::from(""));
 
         let chord_notes = self.chord.notes();
-        let note_names: Vec<String> = chord_notes.iter().map(|n| n.name().to_string()).collect();
+        let note_names: Vec<String> = chord_notes.iter().map(|n| n.name()).collect();
         lines.push(Line::from(vec![
             Span::styled("Chord Tones: ", Style::default().fg(Color::Gray)),
             Span::styled(
@@ -76,7 +149,7 @@ impl<'a> Widget for NotationView<'a> {
         ]));
 
         let guide_tones = self.chord.guide_tones();
-        let guide_names: Vec<String> = guide_tones.iter().map(|n| n.name().to_string()).collect();
+        let guide_names: Vec<String> = guide_tones.iter().map(|&n| self.chord.spell(n).name()).collect();
         lines.push(Line::from(vec![
             Span::styled("Guide Tones: ", Style::default().fg(Color::Gray)),
             Span::styled(
@@ -85,6 +158,28 @@ impl<'a> Widget for NotationView<'a> {
             ),
         ]));
 
+        if let Some((voicing, voiced)) = &self.voicing {
+            let notes: Vec<String> = voiced
+                .voicing
+                .iter()
+                .map(|&n| self.chord.spell(Note::from_midi(n)).name())
+                .collect();
+            let heard = ChordRecognizer::recognize_with_bass(&voiced.voicing, voiced.bass)
+                .first()
+                .map(|c| c.describe())
+                .unwrap_or_default();
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}: ", voicing.name()), Style::default().fg(Color::Gray)),
+                Span::styled(notes.join(" "), Style::default().fg(Color::White)),
+                Span::styled(" = ", Style::default().fg(Color::Gray)),
+                Span::styled(heard, Style::default().fg(Color::Green)),
+            ]));
+            lines.push(Line::from(Span::styled(
+                format!("  {}", voicing.description()),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
         let extensions = self.scale.available_extensions(self.chord);
         if !extensions.is_empty() {
             lines.push(Line::from(""));
@@ -0,0 +1,124 @@
+//! Short Phrase Playback
+//!
+//! The ear training quizzes play a handful of notes rather than a chord
+//! progression: the two notes of an interval, the steps of a scale, a drone
+//! under it. A phrase lists each group of notes with when it starts and how
+//! long it sounds; playback turns that into note on and off events that
+//! `App::update` sends once they fall due.
+
+use std::time::{Duration, Instant};
+
+/// Notes struck together, with their timing from the start of the phrase
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhraseStep {
+    pub notes: Vec<u8>,
+    pub at: Duration,
+    pub length: Duration,
+}
+
+/// A note on or off due at an offset into the phrase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhraseEvent {
+    On(u8),
+    Off(u8),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Phrase {
+    pub steps: Vec<PhraseStep>,
+}
+
+impl Phrase {
+    /// Groups of notes one after another, each sounding for `length`
+    pub fn sequence(groups: &[Vec<u8>], length: Duration) -> Self {
+        let mut phrase = Self::default();
+        for (i, notes) in groups.iter().enumerate() {
+            phrase.add(notes.clone(), length * i as u32, length);
+        }
+        phrase
+    }
+
+    pub fn add(&mut self, notes: Vec<u8>, at: Duration, length: Duration) {
+        self.steps.push(PhraseStep { notes, at, length });
+    }
+}
+
+/// A phrase being played
+#[derive(Debug)]
+pub struct PhrasePlayback {
+    started: Instant,
+    /// Events in time order; at the same time, offs come first so a
+    /// repeated note is struck again
+    events: Vec<(Duration, PhraseEvent)>,
+    next: usize,
+}
+
+impl PhrasePlayback {
+    pub fn new(phrase: &Phrase, now: Instant) -> Self {
+        let mut events = Vec::new();
+        for step in &phrase.steps {
+            for &note in &step.notes {
+                events.push((step.at, PhraseEvent::On(note)));
+                events.push((step.at + step.length, PhraseEvent::Off(note)));
+            }
+        }
+        events.sort_by_key(|&(at, event)| (at, matches!(event, PhraseEvent::On(_))));
+        Self {
+            started: now,
+            events,
+            next: 0,
+        }
+    }
+
+    /// Events that have fallen due since the last call
+    pub fn due(&mut self, now: Instant) -> Vec<PhraseEvent> {
+        let elapsed = now.saturating_duration_since(self.started);
+        let start = self.next;
+        while self.next < self.events.len() && self.events[self.next].0 <= elapsed {
+            self.next += 1;
+        }
+        self.events[start..self.next].iter().map(|&(_, e)| e).collect()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+
+    /// Notes switched on and not yet off, to silence when cut short
+    pub fn sounding(&self) -> Vec<u8> {
+        let mut notes = Vec::new();
+        for &(_, event) in &self.events[..self.next] {
+            match event {
+                PhraseEvent::On(note) => notes.push(note),
+                PhraseEvent::Off(note) => notes.retain(|&n| n != note),
+            }
+        }
+        notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playback_events() {
+        let ms = Duration::from_millis;
+        let mut phrase = Phrase::sequence(&[vec![60], vec![60], vec![64]], ms(500));
+        phrase.add(vec![48], ms(0), ms(1500));
+
+        let start = Instant::now();
+        let mut playback = PhrasePlayback::new(&phrase, start);
+        assert_eq!(playback.due(start), vec![PhraseEvent::On(60), PhraseEvent::On(48)]);
+        assert!(playback.due(start + ms(100)).is_empty());
+        // The repeated note is released before it is struck again
+        assert_eq!(
+            playback.due(start + ms(500)),
+            vec![PhraseEvent::Off(60), PhraseEvent::On(60)]
+        );
+        assert_eq!(playback.sounding(), vec![48, 60]);
+        assert_eq!(playback.due(start + ms(2000)).len(), 4);
+        assert!(playback.is_finished());
+        assert!(playback.sounding().is_empty());
+    }
+}
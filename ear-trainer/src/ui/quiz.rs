@@ -0,0 +1,225 @@
+//! Multiple Choice Quizzes
+//!
+//! The brick, interval and scale trainers all play something and offer a
+//! few answers, one of them right. A `Quiz` is one such question with its
+//! answers; a `QuizSession` asks them one after another and keeps score.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+/// Most answers offered per question
+pub const MAX_OPTIONS: usize = 4;
+
+/// Something played for a quiz, and the answer it expects
+pub trait Question {
+    type Answer: Clone + PartialEq + fmt::Debug;
+
+    fn answer(&self) -> Self::Answer;
+}
+
+/// A question with its multiple choice answers
+#[derive(Debug, Clone)]
+pub struct Quiz<T: Question> {
+    pub question: T,
+    pub options: Vec<T::Answer>,
+    /// Index of the correct answer in options
+    pub correct_idx: usize,
+    /// User's answer (None if not answered yet)
+    pub user_answer: Option<usize>,
+    /// Whether the answer has been revealed
+    pub revealed: bool,
+}
+
+impl<T: Question> Quiz<T> {
+    /// Offer the question's answer among up to `MAX_OPTIONS - 1` others
+    /// picked from `choices`, in random order
+    pub fn new(question: T, choices: &[T::Answer], rng: &mut impl Rng) -> Self {
+        let answer = question.answer();
+        let mut options: Vec<T::Answer> = choices.iter().filter(|&c| *c != answer).cloned().collect();
+        options.shuffle(rng);
+        options.truncate(MAX_OPTIONS - 1);
+        let correct_idx = rng.gen_range(0..=options.len());
+        options.insert(correct_idx, answer);
+        Self {
+            question,
+            options,
+            correct_idx,
+            user_answer: None,
+            revealed: false,
+        }
+    }
+
+    /// The same options in ascending order
+    pub fn sorted(mut self) -> Self
+    where
+        T::Answer: Ord,
+    {
+        let answer = self.question.answer();
+        self.options.sort();
+        self.correct_idx = self.options.iter().position(|o| *o == answer).unwrap_or(0);
+        self
+    }
+
+    pub fn correct_answer(&self) -> &T::Answer {
+        &self.options[self.correct_idx]
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.user_answer == Some(self.correct_idx)
+    }
+
+    /// Answer the question; None if it was already answered or there is no
+    /// such option
+    pub fn submit(&mut self, answer_idx: usize) -> Option<bool> {
+        if self.revealed || answer_idx >= self.options.len() {
+            return None;
+        }
+        self.user_answer = Some(answer_idx);
+        self.revealed = true;
+        Some(self.is_correct())
+    }
+}
+
+/// The question being asked, and the score so far
+#[derive(Debug)]
+pub struct QuizSession<T: Question> {
+    pub current: Option<Quiz<T>>,
+    pub score: QuizScore,
+}
+
+impl<T: Question> QuizSession<T> {
+    /// Answer the current question and score it; returns the right answer
+    /// and whether it was given, or None if there was nothing to answer
+    pub fn submit(&mut self, answer_idx: usize) -> Option<(T::Answer, bool)> {
+        let quiz = self.current.as_mut()?;
+        let correct = quiz.submit(answer_idx)?;
+        self.score.record(correct);
+        Some((quiz.correct_answer().clone(), correct))
+    }
+
+    /// Check if there's an unanswered question
+    pub fn has_active_quiz(&self) -> bool {
+        self.current.as_ref().is_some_and(|q| !q.revealed)
+    }
+
+    /// Check if waiting for the user to move on to the next question
+    pub fn waiting_for_next(&self) -> bool {
+        self.current.as_ref().is_some_and(|q| q.revealed)
+    }
+}
+
+impl<T: Question> Default for QuizSession<T> {
+    fn default() -> Self {
+        Self {
+            current: None,
+            score: QuizScore::default(),
+        }
+    }
+}
+
+/// Score tracking for the current session
+#[derive(Debug, Clone, Default)]
+pub struct QuizScore {
+    pub correct: u32,
+    pub total: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl QuizScore {
+    pub fn accuracy(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.correct as f32 / self.total as f32) * 100.0
+        }
+    }
+
+    pub fn record(&mut self, correct: bool) {
+        self.total += 1;
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+            if self.streak > self.best_streak {
+                self.best_streak = self.streak;
+            }
+        } else {
+            self.streak = 0;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct Number(u8);
+
+    impl Question for Number {
+        type Answer = u8;
+
+        fn answer(&self) -> u8 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_options() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let quiz = Quiz::new(Number(3), &[1, 2, 3, 4, 5, 6], &mut rng);
+            assert_eq!(quiz.options.len(), MAX_OPTIONS);
+            assert_eq!(quiz.correct_answer(), &3);
+            assert_eq!(quiz.options.iter().filter(|&&o| o == 3).count(), 1);
+
+            let sorted = quiz.sorted();
+            assert!(sorted.options.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(sorted.correct_answer(), &3);
+        }
+
+        // Fewer choices than options: all of them are offered
+        let quiz = Quiz::new(Number(2), &[1, 2], &mut rng).sorted();
+        assert_eq!(quiz.options, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_session_answers_once() {
+        let mut session = QuizSession::default();
+        assert!(session.submit(0).is_none());
+
+        session.current = Some(Quiz::new(Number(3), &[1, 2, 3], &mut rand::thread_rng()));
+        assert!(session.has_active_quiz());
+        assert!(session.submit(MAX_OPTIONS).is_none());
+        let correct_idx = session.current.as_ref().unwrap().correct_idx;
+        assert_eq!(session.submit(correct_idx), Some((3, true)));
+        assert!(session.waiting_for_next());
+        assert!(session.submit(0).is_none());
+        assert_eq!((session.score.correct, session.score.total), (1, 1));
+    }
+
+    #[test]
+    fn test_quiz_scoring() {
+        let mut score = QuizScore::default();
+
+        score.record(true);
+        assert_eq!(score.correct, 1);
+        assert_eq!(score.streak, 1);
+
+        score.record(true);
+        assert_eq!(score.correct, 2);
+        assert_eq!(score.streak, 2);
+        assert_eq!(score.best_streak, 2);
+
+        score.record(false);
+        assert_eq!(score.correct, 2);
+        assert_eq!(score.total, 3);
+        assert_eq!(score.streak, 0);
+        assert_eq!(score.best_streak, 2);
+    }
+}
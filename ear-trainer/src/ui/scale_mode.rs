@@ -0,0 +1,187 @@
+//! Scale Training Mode
+//!
+//! Plays a scale or mode up an octave and back down, on its own, over a
+//! drone on the root, or over the chord it is usually played on, and asks
+//! which scale it was. Tiers take the scales a family at a time: the major
+//! modes, then the melodic minor modes and the symmetric scales.
+
+use super::quiz::{Question, Quiz, QuizSession};
+use crate::config::Config;
+use crate::music::{ChordScaleMatcher, Note, Scale, ScaleTier, ScaleType};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// What sounds under the scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleBacking {
+    #[default]
+    None,
+    /// The root an octave below
+    Drone,
+    /// The scale's chord from `ChordScaleMatcher`, or the drone for
+    /// scales without one
+    Chord,
+}
+
+impl ScaleBacking {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScaleBacking::None => "No backing",
+            ScaleBacking::Drone => "Drone",
+            ScaleBacking::Chord => "Chord",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ScaleBacking::None => ScaleBacking::Drone,
+            ScaleBacking::Drone => ScaleBacking::Chord,
+            ScaleBacking::Chord => ScaleBacking::None,
+        }
+    }
+}
+
+/// State for the scale trainer
+#[derive(Debug)]
+pub struct ScaleModeState {
+    pub tier: ScaleTier,
+    pub backing: ScaleBacking,
+    /// Lowest and highest notes the scale may use (MIDI)
+    pub min_note: u8,
+    pub max_note: u8,
+    pub quiz: QuizSession<ScaleQuestion>,
+}
+
+/// A scale played for identification
+#[derive(Debug, Clone)]
+pub struct ScaleQuestion {
+    pub scale_type: ScaleType,
+    /// Root of the scale (MIDI)
+    pub root: u8,
+}
+
+impl Question for ScaleQuestion {
+    type Answer = ScaleType;
+
+    fn answer(&self) -> ScaleType {
+        self.scale_type
+    }
+}
+
+impl ScaleQuestion {
+    pub fn scale(&self) -> Scale {
+        Scale::new(Note::from_midi(self.root), self.scale_type)
+    }
+
+    /// Scale tones from the root up to its octave and back down
+    pub fn melody(&self) -> Vec<u8> {
+        let mut up: Vec<u8> = self
+            .scale_type
+            .intervals()
+            .iter()
+            .map(|&i| self.root + i as u8)
+            .collect();
+        up.push(self.root + 12);
+        let down: Vec<u8> = up.iter().rev().skip(1).copied().collect();
+        up.extend(down);
+        up
+    }
+
+    /// Notes held under the melody, in the octave below the root; a chord
+    /// keeps its root, 3rd, 5th and 7th and leaves the tensions to the scale
+    pub fn backing_notes(&self, backing: ScaleBacking) -> Vec<u8> {
+        let bass = self.root.saturating_sub(12);
+        match backing {
+            ScaleBacking::None => Vec::new(),
+            ScaleBacking::Chord => match ChordScaleMatcher::chord_for_scale(&self.scale()) {
+                Some(chord) => chord
+                    .quality
+                    .intervals()
+                    .iter()
+                    .filter(|&&i| i < 12)
+                    .map(|&i| bass + i as u8)
+                    .collect(),
+                None => vec![bass],
+            },
+            ScaleBacking::Drone => vec![bass],
+        }
+    }
+}
+
+impl ScaleModeState {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            tier: ScaleTier::default(),
+            backing: ScaleBacking::default(),
+            min_note: config.min_note,
+            max_note: config.max_note,
+            quiz: QuizSession::default(),
+        }
+    }
+
+    pub fn cycle_tier(&mut self) {
+        self.tier = self.tier.next();
+        self.generate_quiz();
+    }
+
+    pub fn cycle_backing(&mut self) {
+        self.backing = self.backing.next();
+    }
+
+    /// Generate a new question; none if an octave doesn't fit the note
+    /// range
+    pub fn generate_quiz(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.quiz.current = None;
+        if self.max_note < self.min_note.saturating_add(12) {
+            return;
+        }
+        let types = self.tier.scale_types();
+        let Some(&scale_type) = types.choose(&mut rng) else {
+            return;
+        };
+        let root = rng.gen_range(self.min_note..=self.max_note - 12);
+        self.quiz.current = Some(Quiz::new(ScaleQuestion { scale_type, root }, &types, &mut rng));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_quiz() {
+        let mut state = ScaleModeState::new(&Config::default());
+        for _ in 0..50 {
+            state.generate_quiz();
+            let quiz = state.quiz.current.clone().unwrap();
+            assert_eq!(quiz.options.len(), 4);
+            assert_eq!(quiz.correct_answer(), &quiz.question.scale_type);
+            assert!(quiz.options.iter().all(|t| ScaleTier::MajorModes.scale_types().contains(t)));
+            assert!(quiz.question.root >= 48 && quiz.question.root + 12 <= 84);
+        }
+    }
+
+    #[test]
+    fn test_melody_and_backing() {
+        let question = ScaleQuestion {
+            scale_type: ScaleType::Locrian,
+            root: 62,
+        };
+        let melody = question.melody();
+        assert_eq!(melody.len(), 15);
+        assert_eq!(&melody[..8], &[62, 63, 65, 67, 68, 70, 72, 74]);
+        assert_eq!(melody.last(), Some(&62));
+
+        assert!(question.backing_notes(ScaleBacking::None).is_empty());
+        assert_eq!(question.backing_notes(ScaleBacking::Drone), vec![50]);
+        // Dm7b5 under D Locrian
+        assert_eq!(question.backing_notes(ScaleBacking::Chord), vec![50, 53, 56, 60]);
+
+        // Tensions are left out, so the chord stays below the root
+        for scale_type in ScaleType::ALL {
+            let question = ScaleQuestion { scale_type, root: 62 };
+            assert!(question.backing_notes(ScaleBacking::Chord).iter().all(|&n| n < 62), "{:?}", scale_type);
+        }
+    }
+}
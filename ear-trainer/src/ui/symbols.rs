@@ -62,6 +62,7 @@ pub mod colors {
     pub const EXTENSION: Color = Color::Cyan;
     pub const SCALE_NOTE: Color = Color::Blue;
     pub const AVOID_NOTE: Color = Color::DarkGray;
+    pub const SOLO_NOTE: Color = Color::LightMagenta;
 
     // Key colors
     pub const BLACK_KEY: Color = Color::DarkGray;
@@ -1,13 +1,13 @@
 //! Timeline state management for chord progression visualization
 
-use crate::music::{Chord, ChordScaleMatcher, Progression, Scale};
+use crate::music::{Chord, ChordScaleMatcher, LineNote, Progression, Scale, VoicedChord};
 
 /// A chord positioned on the timeline with its visual state
 #[derive(Debug, Clone)]
 pub struct TimelineChord {
     /// The chord to display
     pub chord: Chord,
-    /// The scale associated with this chord
+    /// The scale for this chord in the progression's key
     pub scale: Scale,
     /// Starting beat position in the progression
     pub start_beat: f32,
@@ -19,6 +19,10 @@ pub struct TimelineChord {
     pub is_past: bool,
     /// Index in the progression
     pub index: usize,
+    /// Notes comping plays for this chord, once the progression is voiced
+    pub voiced: Option<VoicedChord>,
+    /// Notes the soloist plays over this chord
+    pub line: Vec<LineNote>,
 }
 
 /// State for the timeline visualization
@@ -32,7 +36,7 @@ pub struct TimelineState {
     pub visible_beats: f32,
     /// Scroll offset for viewing different parts of the progression
     pub scroll_offset: f32,
-    /// Beats per measure (typically 4)
+    /// Beats per measure, from the progression's meter
     pub beats_per_measure: u8,
     /// Current chord index
     pub current_chord_idx: usize,
@@ -64,19 +68,23 @@ impl TimelineState {
         current_beat_in_chord: f32,
     ) -> Self {
         let mut accumulated_beat = 0.0;
+        let scales = ChordScaleMatcher::progression_scales(progression);
         let chords: Vec<TimelineChord> = progression
             .changes
             .iter()
+            .zip(scales)
             .enumerate()
-            .map(|(i, change)| {
+            .map(|(i, (change, choice))| {
                 let tc = TimelineChord {
                     chord: change.chord.clone(),
-                    scale: ChordScaleMatcher::get_primary_scale(&change.chord),
+                    scale: choice.scale,
                     start_beat: accumulated_beat,
                     duration_beats: change.duration,
                     is_current: i == current_chord_idx,
                     is_past: i < current_chord_idx,
                     index: i,
+                    voiced: None,
+                    line: Vec::new(),
                 };
                 accumulated_beat += change.duration;
                 tc
@@ -92,16 +100,34 @@ impl TimelineState {
         // Center the view on the current position
         let scroll_offset = (global_beat - 4.0).max(0.0);
 
+        // Show four bars at a time
+        let beats_per_measure = progression.meter.beats.max(1);
+
         Self {
             chords,
             current_beat: global_beat,
-            visible_beats: 16.0,
+            visible_beats: beats_per_measure as f32 * 4.0,
             scroll_offset,
-            beats_per_measure: 4,
+            beats_per_measure,
             current_chord_idx,
         }
     }
 
+    /// Attach the voicing played for each chord
+    pub fn with_voicings(mut self, voicings: &[VoicedChord]) -> Self {
+        for (chord, voiced) in self.chords.iter_mut().zip(voicings) {
+            chord.voiced = Some(voiced.clone());
+        }
+        self
+    }
+
+    /// Attach the soloist's line, splitting it between the chords
+    pub fn set_line(&mut self, line: &[LineNote]) {
+        for chord in &mut self.chords {
+            chord.line = line.iter().filter(|note| note.change == chord.index).copied().collect();
+        }
+    }
+
     /// Update the timeline state with new playback position
     pub fn update(&mut self, current_chord_idx: usize, current_beat_in_chord: f32) {
         self.current_chord_idx = current_chord_idx;
@@ -183,6 +209,16 @@ impl TimelineState {
         }
     }
 
+    /// Whether a beat (from the start of the progression) begins a bar
+    pub fn is_bar_line(&self, beat: f32) -> bool {
+        beat.rem_euclid(self.beats_per_measure.max(1) as f32) == 0.0
+    }
+
+    /// Scroll the timeline by whole bars
+    pub fn scroll_bars(&mut self, bars: i32) {
+        self.scroll(bars as f32 * self.beats_per_measure as f32);
+    }
+
     /// Calculate the x position (0.0 to 1.0) for a given beat in the visible window
     pub fn beat_to_position(&self, beat: f32) -> f32 {
         (beat - self.scroll_offset) / self.visible_beats
@@ -197,7 +233,7 @@ impl TimelineState {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::music::{ChordQuality, Note};
+    use crate::music::{ChordQuality, Meter, Note};
 
     fn create_test_progression() -> Progression {
         let mut prog = Progression::new(
@@ -245,4 +281,15 @@ mod tests {
         assert!(!timeline.chords[2].is_past);
         assert!(!timeline.chords[2].is_current);
     }
+
+    #[test]
+    fn test_bar_lines_follow_meter() {
+        let prog = create_test_progression().with_meter(Meter::WALTZ);
+        let timeline = TimelineState::from_progression(&prog, 0, 0.0);
+
+        assert_eq!(timeline.beats_per_measure, 3);
+        assert_eq!(timeline.visible_beats, 12.0);
+        let bars: Vec<f32> = (0..12).map(|b| b as f32).filter(|&b| timeline.is_bar_line(b)).collect();
+        assert_eq!(bars, vec![0.0, 3.0, 6.0, 9.0]);
+    }
 }